@@ -46,7 +46,7 @@ fn main() {
     let mut color_index = 0;
     window
         .render_loop(move |frame_input| {
-            camera.set_aspect(frame_input.viewport.aspect()).unwrap();
+            camera.set_viewport(frame_input.viewport).unwrap();
 
             for event in frame_input.events.iter() {
                 match event {
@@ -49,6 +49,7 @@ fn main() {
                 specular_intensity: 0.2,
                 specular_power: 5.0,
                 color_source: ColorSource::Color(vec4(0.9, 0.2, 0.2, 1.0)),
+                ..Default::default()
             };
             let edges = PhongInstancedMesh::new(
                 &gl,
@@ -4,6 +4,7 @@ use crate::math::*;
 ///
 /// Either orthographic or perspective projection.
 ///
+#[derive(Clone, Copy)]
 pub enum ProjectionType {
     Orthographic {
         width: f32,
@@ -22,6 +23,7 @@ pub enum ProjectionType {
 /// Used in a render call to define how to view the 3D world.
 ///
 pub struct Camera {
+    context: Context,
     projection_type: ProjectionType,
     position: Vec3,
     target: Vec3,
@@ -31,6 +33,7 @@ pub struct Camera {
     screen2ray: Mat4,
     matrix_buffer: UniformBuffer,
     frustrum: [Vec4; 6],
+    previous_view_projection: Mat4,
 }
 
 impl Camera {
@@ -51,6 +54,7 @@ impl Camera {
         let mut camera = Camera::new(context);
         camera.set_view(position, target, up)?;
         camera.set_orthographic_projection(width, height, depth)?;
+        camera.store_previous_view_projection();
         Ok(camera)
     }
 
@@ -70,9 +74,44 @@ impl Camera {
         let mut camera = Camera::new(context);
         camera.set_view(position, target, up)?;
         camera.set_perspective_projection(field_of_view_y, aspect, z_near, z_far)?;
+        camera.store_previous_view_projection();
         Ok(camera)
     }
 
+    ///
+    /// New camera which projects the world with a perspective projection where the far plane is at infinity,
+    /// so nothing in front of the camera is clipped by the far plane, which is useful for huge scenes, for example space scenes.
+    /// The [z_far](Self::z_far) distance is infinite, so the far plane can also be moved to infinity for an existing camera
+    /// by calling [set_near_far](Self::set_near_far) with `f32::INFINITY`.
+    ///
+    /// **Note:** Objects infinitely far away are mapped to depth 1, which is also where the [Skybox](crate::Skybox) is rendered,
+    /// so very distant objects may get the exact same depth as the skybox. Since the skybox is rendered with a
+    /// [LessOrEqual](crate::DepthTestType::LessOrEqual) depth test, render the skybox before those objects to keep them visible.
+    /// Effects which use the distance to the far plane, for example [LinearDepthEffect](crate::LinearDepthEffect), need a finite far plane,
+    /// [logarithmic depth](crate::MeshProgram::new_with_logarithmic_depth) uses the largest finite distance instead,
+    /// and the frustum has no far corners (see [frustum_corners](Self::frustum_corners)).
+    ///
+    pub fn new_perspective_infinite(
+        context: &Context,
+        position: Vec3,
+        target: Vec3,
+        up: Vec3,
+        field_of_view_y: Degrees,
+        aspect: f32,
+        z_near: f32,
+    ) -> Result<Camera, Error> {
+        Self::new_perspective(
+            context,
+            position,
+            target,
+            up,
+            field_of_view_y,
+            aspect,
+            z_near,
+            f32::INFINITY,
+        )
+    }
+
     ///
     /// Specify the camera to use perspective projection with the given field of view in the y-direction, aspect and near and far plane.
     ///
@@ -92,11 +131,7 @@ impl Camera {
             z_near,
             z_far,
         };
-        self.projection = perspective(field_of_view_y, aspect, z_near, z_far);
-        self.update_screen2ray();
-        self.update_matrix_buffer()?;
-        self.update_frustrum();
-        Ok(())
+        self.update_projection()
     }
 
     ///
@@ -114,47 +149,109 @@ impl Camera {
             height,
             depth,
         };
-        self.projection = ortho(
-            -0.5 * width,
-            0.5 * width,
-            -0.5 * height,
-            0.5 * height,
-            0.0,
-            depth,
-        );
-        self.update_screen2ray();
-        self.update_matrix_buffer()?;
-        self.update_frustrum();
-        Ok(())
+        self.update_projection()
     }
 
     ///
     /// Change the current projection to abide to the given aspect ratio.
+    /// Returns whether or not the projection changed. The camera uniform block is uploaded to the GPU once if it changed and not at all otherwise.
     ///
     pub fn set_aspect(&mut self, value: f32) -> Result<bool, Error> {
-        let mut change = false;
-        match self.projection_type {
+        let change = match self.projection_type {
             ProjectionType::Orthographic {
-                width,
+                ref mut width,
                 height,
-                depth,
+                ..
+            } => {
+                if (*width / height - value).abs() > 0.001 {
+                    *width = height * value;
+                    true
+                } else {
+                    false
+                }
+            }
+            ProjectionType::Perspective { ref mut aspect, .. } => {
+                if (*aspect - value).abs() > 0.001 {
+                    *aspect = value;
+                    true
+                } else {
+                    false
+                }
+            }
+        };
+        if change {
+            self.update_projection()?;
+        }
+        Ok(change)
+    }
+
+    ///
+    /// Change the current projection to abide to the aspect ratio of the given viewport.
+    /// Call this every frame with [FrameInput::viewport](crate::FrameInput::viewport), or at least whenever the window is resized,
+    /// to avoid a stretched image. Nothing happens if the viewport is empty, for example when the window is minimized.
+    /// Returns whether or not the projection changed. The camera uniform block is uploaded to the GPU once if it changed and not at all otherwise.
+    ///
+    pub fn set_viewport(&mut self, viewport: Viewport) -> Result<bool, Error> {
+        if viewport.width == 0 || viewport.height == 0 {
+            return Ok(false);
+        }
+        self.set_aspect(viewport.aspect())
+    }
+
+    ///
+    /// Change the field of view in the y-direction of the current perspective projection.
+    /// Returns whether or not the projection changed. The camera uniform block is uploaded to the GPU once if it changed and not at all otherwise.
+    /// Has no effect if the camera uses an orthographic projection.
+    ///
+    pub fn set_fov(&mut self, value: Degrees) -> Result<bool, Error> {
+        let change = match self.projection_type {
+            ProjectionType::Orthographic { .. } => false,
+            ProjectionType::Perspective {
+                ref mut field_of_view_y,
+                ..
             } => {
-                if (width / height - value).abs() > 0.001 {
-                    self.set_orthographic_projection(height * value, height, depth)?;
-                    change = true;
+                if *field_of_view_y != value {
+                    *field_of_view_y = value;
+                    true
+                } else {
+                    false
                 }
             }
+        };
+        if change {
+            self.update_projection()?;
+        }
+        Ok(change)
+    }
+
+    ///
+    /// Change the near and far plane of the current perspective projection.
+    /// The far plane can be at infinity, see [new_perspective_infinite](Self::new_perspective_infinite) for the consequences.
+    /// Returns whether or not the projection changed. The camera uniform block is uploaded to the GPU once if it changed and not at all otherwise.
+    /// Has no effect if the camera uses an orthographic projection, use [set_orthographic_projection](Self::set_orthographic_projection) instead.
+    ///
+    pub fn set_near_far(&mut self, near: f32, far: f32) -> Result<bool, Error> {
+        if near < 0.0 || near > far {
+            panic!("Wrong perspective camera parameters")
+        };
+        let change = match self.projection_type {
+            ProjectionType::Orthographic { .. } => false,
             ProjectionType::Perspective {
-                aspect,
-                field_of_view_y,
-                z_near,
-                z_far,
+                ref mut z_near,
+                ref mut z_far,
+                ..
             } => {
-                if (aspect - value).abs() > 0.001 {
-                    self.set_perspective_projection(field_of_view_y, value, z_near, z_far)?;
-                    change = true;
+                if *z_near != near || *z_far != far {
+                    *z_near = near;
+                    *z_far = far;
+                    true
+                } else {
+                    false
                 }
             }
+        };
+        if change {
+            self.update_projection()?;
         }
         Ok(change)
     }
@@ -188,6 +285,38 @@ impl Camera {
         Ok(())
     }
 
+    ///
+    /// Returns a new camera which is this camera reflected across the given plane,
+    /// which is useful for rendering planar reflections, for example in combination with a [StencilMask](crate::StencilMask).
+    /// The plane is given as `(a, b, c, d)`, ie. the points `(x, y, z)` in the plane satisfy `a*x + b*y + c*z + d = 0`.
+    ///
+    /// **Note:** The reflection reverses the winding order of the triangles, so back face culling must be replaced
+    /// by front face culling when rendering with the reflected camera. Also, the geometry behind the plane should be clipped,
+    /// for example by using the plane as one of the [clip planes](crate::RenderStates::clip_planes).
+    /// Changing the view of the returned camera, for example using [set_view](Self::set_view), removes the reflection.
+    ///
+    pub fn reflected_across(&self, plane: Vec4) -> Result<Camera, Error> {
+        let length = plane.truncate().magnitude();
+        let normal = plane.truncate() / length;
+        let d = plane.w / length;
+        let reflection = Mat4::from_cols(
+            (vec3(1.0, 0.0, 0.0) - 2.0 * normal.x * normal).extend(0.0),
+            (vec3(0.0, 1.0, 0.0) - 2.0 * normal.y * normal).extend(0.0),
+            (vec3(0.0, 0.0, 1.0) - 2.0 * normal.z * normal).extend(0.0),
+            (-2.0 * d * normal).extend(1.0),
+        );
+        let mut camera = Camera::new(&self.context);
+        camera.projection_type = self.projection_type;
+        camera.position = (reflection * self.position.extend(1.0)).truncate();
+        camera.target = (reflection * self.target.extend(1.0)).truncate();
+        camera.up = (reflection * self.up.extend(0.0)).truncate();
+        camera.view = self.view * reflection;
+        camera.update_projection()?;
+        camera.update_matrix_buffer()?;
+        camera.store_previous_view_projection();
+        Ok(camera)
+    }
+
     ///
     /// Returns whether or not the given bounding box is within the camera frustum.
     /// It returns false if it is fully outside and true if it is inside or intersects.
@@ -229,6 +358,38 @@ impl Camera {
         return true;
     }
 
+    ///
+    /// Returns the corners of the camera frustum in world space, which is for example useful for
+    /// visualizing the frustum with [Lines](crate::Lines) when debugging shadows or culling.
+    /// The first four corners are on the near plane and the last four on the far plane, both in the order
+    /// bottom left, bottom right, top right and top left as seen from the camera.
+    /// The far corners are `None` if the far plane is at infinity (see [new_perspective_infinite](Self::new_perspective_infinite)),
+    /// since the frustum is then unbounded.
+    ///
+    pub fn frustum_corners(&self) -> ([Vec3; 4], Option<[Vec3; 4]>) {
+        let inverse_view_projection = (self.projection * self.view).invert().unwrap();
+        let corners = |z: f32| {
+            let mut corners = [vec3(0.0, 0.0, 0.0); 4];
+            for (i, corner) in corners.iter_mut().enumerate() {
+                let (x, y) = match i {
+                    0 => (-1.0, -1.0),
+                    1 => (1.0, -1.0),
+                    2 => (1.0, 1.0),
+                    _ => (-1.0, 1.0),
+                };
+                let p = inverse_view_projection * vec4(x, y, z, 1.0);
+                *corner = p.truncate() / p.w;
+            }
+            corners
+        };
+        let far_corners = if self.z_far().is_infinite() {
+            None
+        } else {
+            Some(corners(1.0))
+        };
+        (corners(-1.0), far_corners)
+    }
+
     ///
     /// Returns the view direction at the given screen/image plane coordinates.
     /// The coordinates must be between 0 and 1, where (0, 0) indicate the top left corner of the screen
@@ -244,6 +405,48 @@ impl Camera {
         (self.screen2ray * screen_pos).truncate().normalize()
     }
 
+    ///
+    /// Returns the field of view in the y-direction or `None` if the camera uses an orthographic projection.
+    ///
+    pub fn fov(&self) -> Option<Degrees> {
+        match self.projection_type {
+            ProjectionType::Orthographic { .. } => None,
+            ProjectionType::Perspective {
+                field_of_view_y, ..
+            } => Some(field_of_view_y),
+        }
+    }
+
+    ///
+    /// Returns the aspect ratio (width divided by height) of the projection.
+    ///
+    pub fn aspect(&self) -> f32 {
+        match self.projection_type {
+            ProjectionType::Orthographic { width, height, .. } => width / height,
+            ProjectionType::Perspective { aspect, .. } => aspect,
+        }
+    }
+
+    ///
+    /// Returns the distance to the near plane. Always 0 for an orthographic projection.
+    ///
+    pub fn z_near(&self) -> f32 {
+        match self.projection_type {
+            ProjectionType::Orthographic { .. } => 0.0,
+            ProjectionType::Perspective { z_near, .. } => z_near,
+        }
+    }
+
+    ///
+    /// Returns the distance to the far plane, ie. the depth for an orthographic projection.
+    ///
+    pub fn z_far(&self) -> f32 {
+        match self.projection_type {
+            ProjectionType::Orthographic { depth, .. } => depth,
+            ProjectionType::Perspective { z_far, .. } => z_far,
+        }
+    }
+
     pub fn projection_type(&self) -> &ProjectionType {
         &self.projection_type
     }
@@ -272,8 +475,25 @@ impl Camera {
         &self.matrix_buffer
     }
 
+    ///
+    /// Stores the current view-projection matrix as the previous view-projection matrix.
+    /// Call this once per frame before the view or projection is changed, if the camera motion is needed,
+    /// for example when rendering the velocities used by the [motion blur effect](crate::MotionBlurEffect).
+    ///
+    pub fn store_previous_view_projection(&mut self) {
+        self.previous_view_projection = self.projection * self.view;
+    }
+
+    ///
+    /// Returns the view-projection matrix stored by the last call to [store_previous_view_projection](Self::store_previous_view_projection).
+    ///
+    pub fn previous_view_projection(&self) -> &Mat4 {
+        &self.previous_view_projection
+    }
+
     fn new(context: &Context) -> Camera {
         Camera {
+            context: context.clone(),
             projection_type: ProjectionType::Orthographic {
                 width: 1.0,
                 height: 1.0,
@@ -287,9 +507,41 @@ impl Camera {
             view: Mat4::identity(),
             projection: Mat4::identity(),
             screen2ray: Mat4::identity(),
+            previous_view_projection: Mat4::identity(),
         }
     }
 
+    fn update_projection(&mut self) -> Result<(), Error> {
+        self.projection = match self.projection_type {
+            ProjectionType::Orthographic {
+                width,
+                height,
+                depth,
+            } => ortho(
+                -0.5 * width,
+                0.5 * width,
+                -0.5 * height,
+                0.5 * height,
+                0.0,
+                depth,
+            ),
+            ProjectionType::Perspective {
+                field_of_view_y,
+                aspect,
+                z_near,
+                z_far,
+            } => perspective_projection(field_of_view_y, aspect, z_near, z_far),
+        };
+        self.update_screen2ray();
+        // Only the projection dependent part of the uniform block is changed, but the whole block is uploaded once
+        self.matrix_buffer.update_multiple(&[
+            (0, &(self.projection * self.view).to_slice()),
+            (2, &self.projection.to_slice()),
+        ])?;
+        self.update_frustrum();
+        Ok(())
+    }
+
     fn update_screen2ray(&mut self) {
         let mut v = self.view.clone();
         v[3] = vec4(0.0, 0.0, 0.0, 1.0);
@@ -297,12 +549,12 @@ impl Camera {
     }
 
     fn update_matrix_buffer(&mut self) -> Result<(), Error> {
-        self.matrix_buffer
-            .update(0, &(self.projection * self.view).to_slice())?;
-        self.matrix_buffer.update(1, &self.view.to_slice())?;
-        self.matrix_buffer.update(2, &self.projection.to_slice())?;
-        self.matrix_buffer.update(3, &self.position.to_slice())?;
-        Ok(())
+        self.matrix_buffer.update_multiple(&[
+            (0, &(self.projection * self.view).to_slice()),
+            (1, &self.view.to_slice()),
+            (2, &self.projection.to_slice()),
+            (3, &self.position.to_slice()),
+        ])
     }
 
     fn update_frustrum(&mut self) {
@@ -317,3 +569,30 @@ impl Camera {
         ];
     }
 }
+
+// Same as cgmath::perspective, except that an infinite far plane is supported.
+fn perspective_projection(field_of_view_y: Degrees, aspect: f32, z_near: f32, z_far: f32) -> Mat4 {
+    if z_far.is_infinite() {
+        let f = 1.0 / (0.5 * field_of_view_y.0.to_radians()).tan();
+        Mat4::new(
+            f / aspect,
+            0.0,
+            0.0,
+            0.0,
+            0.0,
+            f,
+            0.0,
+            0.0,
+            0.0,
+            0.0,
+            -1.0,
+            -1.0,
+            0.0,
+            0.0,
+            -2.0 * z_near,
+            0.0,
+        )
+    } else {
+        perspective(field_of_view_y, aspect, z_near, z_far)
+    }
+}
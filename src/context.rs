@@ -4,6 +4,19 @@
 //! Can be used in combination with more high-level features or be ignored entirely.
 //!
 
+#[doc(hidden)]
+pub mod capabilities;
+#[doc(inline)]
+pub use capabilities::*;
+
+#[doc(hidden)]
+pub mod render_stats;
+#[doc(inline)]
+pub use render_stats::*;
+
+mod state_cache;
+pub(crate) use state_cache::*;
+
 // GL
 #[doc(hidden)]
 #[cfg(not(target_arch = "wasm32"))]
@@ -0,0 +1,59 @@
+///
+/// The hardware and driver limits of a [context](crate::context::Context), queried once when the context is created
+/// (see [capabilities](crate::context::Glstruct::capabilities)).
+///
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Capabilities {
+    ///
+    /// The maximum width and height of a texture.
+    ///
+    pub max_texture_size: u32,
+
+    ///
+    /// The maximum width and height of the faces of a [cube map](crate::TextureCubeMap).
+    ///
+    pub max_cube_map_texture_size: u32,
+
+    ///
+    /// The maximum number of samples for multisampling.
+    ///
+    pub max_samples: u32,
+
+    ///
+    /// The maximum number of color textures that can be written to in a single render call.
+    ///
+    pub max_color_attachments: u32,
+
+    ///
+    /// The maximum number of layers in a texture array.
+    ///
+    pub max_array_texture_layers: u32,
+
+    ///
+    /// The maximum number of textures that can be used in a single shader program.
+    ///
+    pub max_texture_image_units: u32,
+
+    ///
+    /// The maximum anisotropy for texture filtering, or `None` if anisotropic filtering is not supported.
+    ///
+    pub max_anisotropy: Option<f32>,
+
+    ///
+    /// Whether or not shader programs can be compiled in parallel without blocking,
+    /// see [Program::from_source_parallel](crate::Program::from_source_parallel).
+    ///
+    pub parallel_shader_compile: bool,
+
+    ///
+    /// Whether or not it is possible to render into color textures with a float format, for example [RGBA16F](crate::Format::RGBA16F).
+    /// Always true on desktop, on web it requires the `EXT_color_buffer_float` extension.
+    ///
+    pub color_buffer_float: bool,
+
+    ///
+    /// Whether or not [sample shading](crate::RenderStates::sample_shading) is supported,
+    /// ie. if the OpenGL version is 4.0 or newer or the `GL_ARB_sample_shading` extension is available. Always false on web.
+    ///
+    pub sample_shading: bool,
+}
@@ -2,6 +2,7 @@ pub mod consts {
     include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
 }
 
+use crate::context::{Capabilities, RenderStats, SavedState, StateCache, SAVED_TEXTURE_TARGETS};
 use consts::Gl as InnerGl;
 
 pub type AttributeLocation = u32;
@@ -12,6 +13,7 @@ pub type Buffer = u32;
 pub type Framebuffer = u32;
 pub type Texture = u32;
 pub type VertexArrayObject = u32;
+pub type TransformFeedback = u32;
 pub type Sync = consts::types::GLsync;
 pub struct ActiveInfo {
     size: u32,
@@ -35,6 +37,12 @@ impl ActiveInfo {
 
 pub struct Glstruct {
     inner: InnerGl,
+    capabilities: Capabilities,
+    state_cache: std::cell::RefCell<StateCache>,
+    render_stats: std::cell::Cell<RenderStats>,
+    vertex_array: VertexArrayObject,
+    saved_states: std::cell::RefCell<Vec<SavedState>>,
+    framebuffer_srgb: std::cell::Cell<bool>,
 }
 
 ///
@@ -51,13 +59,428 @@ impl Glstruct {
     where
         for<'r> F: FnMut(&'r str) -> *const consts::types::GLvoid,
     {
-        let gl = Glstruct {
+        let mut gl = Glstruct {
             inner: InnerGl::load_with(loadfn),
+            capabilities: Capabilities {
+                max_texture_size: 0,
+                max_cube_map_texture_size: 0,
+                max_samples: 0,
+                max_color_attachments: 0,
+                max_array_texture_layers: 0,
+                max_texture_image_units: 0,
+                max_anisotropy: None,
+                parallel_shader_compile: false,
+                color_buffer_float: true,
+                sample_shading: false,
+            },
+            state_cache: std::cell::RefCell::new(StateCache::default()),
+            render_stats: std::cell::Cell::new(RenderStats::default()),
+            vertex_array: 0,
+            saved_states: std::cell::RefCell::new(Vec::new()),
+            framebuffer_srgb: std::cell::Cell::new(false),
         };
-        gl.bind_vertex_array(&gl.create_vertex_array().unwrap());
+        gl.capabilities = Capabilities {
+            max_texture_size: gl.get_integer(consts::MAX_TEXTURE_SIZE) as u32,
+            max_cube_map_texture_size: gl.get_integer(consts::MAX_CUBE_MAP_TEXTURE_SIZE) as u32,
+            max_samples: gl.get_integer(consts::MAX_SAMPLES) as u32,
+            max_color_attachments: gl.get_integer(consts::MAX_COLOR_ATTACHMENTS) as u32,
+            max_array_texture_layers: gl.get_integer(consts::MAX_ARRAY_TEXTURE_LAYERS) as u32,
+            max_texture_image_units: gl.get_integer(consts::MAX_TEXTURE_IMAGE_UNITS) as u32,
+            max_anisotropy: if gl.supports_extension("GL_EXT_texture_filter_anisotropic") {
+                Some(gl.get_float(MAX_TEXTURE_MAX_ANISOTROPY))
+            } else {
+                None
+            },
+            parallel_shader_compile: gl.supports_extension("GL_KHR_parallel_shader_compile")
+                || gl.supports_extension("GL_ARB_parallel_shader_compile"),
+            color_buffer_float: true,
+            sample_shading: gl.get_integer(consts::MAJOR_VERSION) >= 4
+                || gl.supports_extension("GL_ARB_sample_shading"),
+        };
+        gl.vertex_array = gl.create_vertex_array().unwrap();
+        gl.bind_vertex_array(&gl.vertex_array);
+        gl.enable_seamless_cubemaps(true);
+        // The point size is always given by gl_PointSize in the vertex shader, like on web
+        gl.enable(consts::PROGRAM_POINT_SIZE);
         std::rc::Rc::new(gl)
     }
 
+    ///
+    /// Returns the hardware and driver limits of this context.
+    ///
+    pub fn capabilities(&self) -> Capabilities {
+        self.capabilities
+    }
+
+    pub(crate) fn state_cache(&self) -> std::cell::RefMut<'_, StateCache> {
+        self.state_cache.borrow_mut()
+    }
+
+    ///
+    /// Returns the number of draw calls, triangles, program binds, framebuffer binds and state changes since the last call to
+    /// [reset_render_stats](Self::reset_render_stats) or since the context was created.
+    ///
+    pub fn render_stats(&self) -> RenderStats {
+        self.render_stats.get()
+    }
+
+    ///
+    /// Resets all of the [render stats](Self::render_stats) to zero, typically at the beginning of each frame.
+    ///
+    pub fn reset_render_stats(&self) {
+        self.render_stats.set(RenderStats::default());
+    }
+
+    ///
+    /// Sets the size of the [screen](crate::Screen) in physical pixels, which is used for example by
+    /// [ImageEffect::apply_fullscreen](crate::ImageEffect::apply_fullscreen).
+    /// This is done by the window before each frame, so it is only needed when the context is not created by a window.
+    ///
+    pub fn set_screen_size(&self, width: usize, height: usize) {
+        self.state_cache().screen_size = Some((width, height));
+    }
+
+    ///
+    /// Enables or disables filtering across the faces of cube maps, which removes visible seams at the face boundaries,
+    /// for example in a [skybox](crate::Skybox) or in reflections.
+    /// Seamless filtering is enabled when the context is created.
+    /// On web, seamless filtering is always enabled and this function does nothing.
+    ///
+    pub fn enable_seamless_cubemaps(&self, enabled: bool) {
+        if enabled {
+            self.enable(consts::TEXTURE_CUBE_MAP_SEAMLESS);
+        } else {
+            self.disable(consts::TEXTURE_CUBE_MAP_SEAMLESS);
+        }
+    }
+
+    ///
+    /// Enables or disables conversion of the colors written to the [screen](crate::Screen) from linear to sRGB color space,
+    /// which gives correct gamma when rendering in linear color space, for example with the forward renderer, without a gamma correction effect.
+    /// The conversion also applies to render targets with a [SRGBA8](crate::Format::SRGBA8) color texture, in which case blending is done in linear color space.
+    /// On the other hand, the colors written to render targets with any other format are not converted,
+    /// so do not enable this when the colors are already converted to sRGB, for example by a custom gamma correction shader,
+    /// since that will apply the conversion twice. Note that sampling a [SRGBA8](crate::Format::SRGBA8) texture always converts from sRGB to linear.
+    /// The conversion is disabled when the context is created and requires an sRGB capable default framebuffer (see [WindowSettings::srgb](crate::WindowSettings::srgb)).
+    ///
+    pub fn set_framebuffer_srgb(&self, enabled: bool) {
+        if enabled {
+            self.enable(consts::FRAMEBUFFER_SRGB);
+        } else {
+            self.disable(consts::FRAMEBUFFER_SRGB);
+        }
+        self.framebuffer_srgb.set(enabled);
+    }
+
+    ///
+    /// Returns whether the conversion from linear to sRGB color space is enabled, see [set_framebuffer_srgb](Self::set_framebuffer_srgb).
+    ///
+    pub fn is_framebuffer_srgb(&self) -> bool {
+        self.framebuffer_srgb.get()
+    }
+
+    fn update_render_stats(&self, update: impl FnOnce(&mut RenderStats)) {
+        let mut stats = self.render_stats.get();
+        update(&mut stats);
+        self.render_stats.set(stats);
+    }
+
+    ///
+    /// Saves the GL state which is changed by this crate on a stack, so that it can be restored by [pop_state](Self::pop_state).
+    /// Use this to combine rendering with this crate and rendering with other GL code, for example a user interface or the renderer of a host application,
+    /// by calling this function before and [pop_state](Self::pop_state) after rendering with this crate.
+    /// The saved state is the bound framebuffers, program, vertex array and array buffer, the active texture unit,
+    /// the 2D, 2D array, 3D and cube map textures bound to each texture unit, the generic and indexed uniform buffer bindings,
+    /// the viewport, the cull, blend, write mask, depth, front and back face stencil, alpha to coverage, clip distance,
+    /// primitive restart, sample shading and program point size states.
+    /// The indexed uniform buffers are restored with [glBindBufferBase](https://www.khronos.org/registry/OpenGL-Refpages/gl4/html/glBindBufferBase.xhtml),
+    /// so a buffer bound to only a range of an index is bound to the whole buffer after [pop_state](Self::pop_state).
+    /// Any other state, for example the texture parameters, the scissor test or the buffer contents, is not saved.
+    ///
+    /// Since the other GL code might have changed the state, this crate forgets the state it has previously set (see [RenderStates](crate::RenderStates)),
+    /// binds its own vertex array, unbinds the generic uniform buffer binding and enables the program point size.
+    ///
+    pub fn push_state(&self) {
+        let state = SavedState {
+            draw_framebuffer: self.get_id(consts::DRAW_FRAMEBUFFER_BINDING),
+            read_framebuffer: self.get_id(consts::READ_FRAMEBUFFER_BINDING),
+            program: self.get_id(consts::CURRENT_PROGRAM),
+            vertex_array: self.get_id(consts::VERTEX_ARRAY_BINDING),
+            array_buffer: self.get_id(consts::ARRAY_BUFFER_BINDING),
+            active_texture: self.get_integer(consts::ACTIVE_TEXTURE) as u32,
+            textures: (0..self.capabilities.max_texture_image_units)
+                .map(|unit| {
+                    unsafe {
+                        self.inner.ActiveTexture(consts::TEXTURE0 + unit);
+                    }
+                    let mut textures = [None; 4];
+                    for (texture, (_, binding)) in textures.iter_mut().zip(&SAVED_TEXTURE_TARGETS) {
+                        *texture = self.get_id(*binding);
+                    }
+                    textures
+                })
+                .collect(),
+            uniform_buffer: self.get_id(consts::UNIFORM_BUFFER_BINDING),
+            uniform_buffers: (0..self.get_integer(consts::MAX_UNIFORM_BUFFER_BINDINGS) as u32)
+                .map(|index| {
+                    let mut id = 0;
+                    unsafe {
+                        self.inner
+                            .GetIntegeri_v(consts::UNIFORM_BUFFER_BINDING, index, &mut id);
+                    }
+                    if id == 0 {
+                        None
+                    } else {
+                        Some(id as u32)
+                    }
+                })
+                .collect(),
+            viewport: {
+                let mut viewport = [0; 4];
+                unsafe {
+                    self.inner
+                        .GetIntegerv(consts::VIEWPORT, viewport.as_mut_ptr());
+                }
+                viewport
+            },
+            cull_face_enabled: self.is_enabled(consts::CULL_FACE),
+            cull_face: self.get_integer(consts::CULL_FACE_MODE) as u32,
+            blend_enabled: self.is_enabled(consts::BLEND),
+            blend_func: (
+                self.get_integer(consts::BLEND_SRC_RGB) as u32,
+                self.get_integer(consts::BLEND_DST_RGB) as u32,
+                self.get_integer(consts::BLEND_SRC_ALPHA) as u32,
+                self.get_integer(consts::BLEND_DST_ALPHA) as u32,
+            ),
+            blend_equation: (
+                self.get_integer(consts::BLEND_EQUATION_RGB) as u32,
+                self.get_integer(consts::BLEND_EQUATION_ALPHA) as u32,
+            ),
+            color_mask: {
+                let mut color_mask = [consts::FALSE; 4];
+                unsafe {
+                    self.inner
+                        .GetBooleanv(consts::COLOR_WRITEMASK, color_mask.as_mut_ptr());
+                }
+                [
+                    color_mask[0] == consts::TRUE,
+                    color_mask[1] == consts::TRUE,
+                    color_mask[2] == consts::TRUE,
+                    color_mask[3] == consts::TRUE,
+                ]
+            },
+            depth_test_enabled: self.is_enabled(consts::DEPTH_TEST),
+            depth_mask: {
+                let mut depth_mask = consts::FALSE;
+                unsafe {
+                    self.inner
+                        .GetBooleanv(consts::DEPTH_WRITEMASK, &mut depth_mask);
+                }
+                depth_mask == consts::TRUE
+            },
+            depth_func: self.get_integer(consts::DEPTH_FUNC) as u32,
+            stencil_test_enabled: self.is_enabled(consts::STENCIL_TEST),
+            stencil_func: [
+                (
+                    self.get_integer(consts::STENCIL_FUNC) as u32,
+                    self.get_integer(consts::STENCIL_REF),
+                    self.get_integer(consts::STENCIL_VALUE_MASK) as u32,
+                ),
+                (
+                    self.get_integer(consts::STENCIL_BACK_FUNC) as u32,
+                    self.get_integer(consts::STENCIL_BACK_REF),
+                    self.get_integer(consts::STENCIL_BACK_VALUE_MASK) as u32,
+                ),
+            ],
+            stencil_op: [
+                (
+                    self.get_integer(consts::STENCIL_FAIL) as u32,
+                    self.get_integer(consts::STENCIL_PASS_DEPTH_FAIL) as u32,
+                    self.get_integer(consts::STENCIL_PASS_DEPTH_PASS) as u32,
+                ),
+                (
+                    self.get_integer(consts::STENCIL_BACK_FAIL) as u32,
+                    self.get_integer(consts::STENCIL_BACK_PASS_DEPTH_FAIL) as u32,
+                    self.get_integer(consts::STENCIL_BACK_PASS_DEPTH_PASS) as u32,
+                ),
+            ],
+            stencil_mask: [
+                self.get_integer(consts::STENCIL_WRITEMASK) as u32,
+                self.get_integer(consts::STENCIL_BACK_WRITEMASK) as u32,
+            ],
+            alpha_to_coverage_enabled: self.is_enabled(consts::SAMPLE_ALPHA_TO_COVERAGE),
+            clip_distance_enabled: {
+                let mut enabled = [false; crate::MAX_CLIP_PLANES];
+                for (i, enabled) in enabled.iter_mut().enumerate() {
+                    *enabled = self.is_enabled(consts::CLIP_DISTANCE0 + i as u32);
+                }
+                enabled
+            },
+            primitive_restart_enabled: self.is_enabled(consts::PRIMITIVE_RESTART),
+            primitive_restart_index: self.get_integer(consts::PRIMITIVE_RESTART_INDEX) as u32,
+            sample_shading: if self.capabilities.sample_shading {
+                Some((
+                    self.is_enabled(consts::SAMPLE_SHADING),
+                    self.get_float(consts::MIN_SAMPLE_SHADING_VALUE),
+                ))
+            } else {
+                None
+            },
+            program_point_size_enabled: self.is_enabled(consts::PROGRAM_POINT_SIZE),
+        };
+        unsafe {
+            self.inner.ActiveTexture(state.active_texture);
+        }
+        self.saved_states.borrow_mut().push(state);
+        self.state_cache().invalidate();
+        self.bind_vertex_array(&self.vertex_array);
+        self.unbind_buffer(consts::UNIFORM_BUFFER);
+        self.enable(consts::PROGRAM_POINT_SIZE);
+    }
+
+    ///
+    /// Restores the GL state saved by the last call to [push_state](Self::push_state) and removes it from the stack.
+    /// Does nothing if there is no saved state.
+    ///
+    pub fn pop_state(&self) {
+        let state = match self.saved_states.borrow_mut().pop() {
+            Some(state) => state,
+            None => return,
+        };
+        unsafe {
+            self.inner.BindFramebuffer(
+                consts::DRAW_FRAMEBUFFER,
+                state.draw_framebuffer.unwrap_or(0),
+            );
+            self.inner.BindFramebuffer(
+                consts::READ_FRAMEBUFFER,
+                state.read_framebuffer.unwrap_or(0),
+            );
+            self.inner.UseProgram(state.program.unwrap_or(0));
+            self.inner.BindVertexArray(state.vertex_array.unwrap_or(0));
+            self.inner
+                .BindBuffer(consts::ARRAY_BUFFER, state.array_buffer.unwrap_or(0));
+            for (unit, textures) in state.textures.iter().enumerate() {
+                self.inner.ActiveTexture(consts::TEXTURE0 + unit as u32);
+                for (texture, (target, _)) in textures.iter().zip(&SAVED_TEXTURE_TARGETS) {
+                    self.inner.BindTexture(*target, texture.unwrap_or(0));
+                }
+            }
+            self.inner.ActiveTexture(state.active_texture);
+            for (index, buffer) in state.uniform_buffers.iter().enumerate() {
+                self.inner.BindBufferBase(
+                    consts::UNIFORM_BUFFER,
+                    index as u32,
+                    buffer.unwrap_or(0),
+                );
+            }
+            self.inner
+                .BindBuffer(consts::UNIFORM_BUFFER, state.uniform_buffer.unwrap_or(0));
+        }
+        let [x, y, width, height] = state.viewport;
+        self.viewport(x, y, width as usize, height as usize);
+        self.set_enabled(consts::CULL_FACE, state.cull_face_enabled);
+        self.cull_face(state.cull_face);
+        self.set_enabled(consts::BLEND, state.blend_enabled);
+        let (src_rgb, dst_rgb, src_alpha, dst_alpha) = state.blend_func;
+        self.blend_func_separate(src_rgb, dst_rgb, src_alpha, dst_alpha);
+        self.blend_equation_separate(state.blend_equation.0, state.blend_equation.1);
+        let [red, green, blue, alpha] = state.color_mask;
+        self.color_mask(red, green, blue, alpha);
+        self.set_enabled(consts::DEPTH_TEST, state.depth_test_enabled);
+        self.depth_mask(state.depth_mask);
+        self.depth_func(state.depth_func);
+        self.set_enabled(consts::STENCIL_TEST, state.stencil_test_enabled);
+        for (i, face) in [consts::FRONT, consts::BACK].iter().enumerate() {
+            let (func, reference, mask) = state.stencil_func[i];
+            let (fail, depth_fail, pass) = state.stencil_op[i];
+            unsafe {
+                self.inner.StencilFuncSeparate(*face, func, reference, mask);
+                self.inner.StencilOpSeparate(*face, fail, depth_fail, pass);
+                self.inner.StencilMaskSeparate(*face, state.stencil_mask[i]);
+            }
+        }
+        self.set_enabled(
+            consts::SAMPLE_ALPHA_TO_COVERAGE,
+            state.alpha_to_coverage_enabled,
+        );
+        for (i, enabled) in state.clip_distance_enabled.iter().enumerate() {
+            self.set_enabled(consts::CLIP_DISTANCE0 + i as u32, *enabled);
+        }
+        self.set_enabled(consts::PRIMITIVE_RESTART, state.primitive_restart_enabled);
+        self.primitive_restart_index(state.primitive_restart_index);
+        if let Some((enabled, value)) = state.sample_shading {
+            self.set_enabled(consts::SAMPLE_SHADING, enabled);
+            self.min_sample_shading(value);
+        }
+        self.set_enabled(consts::PROGRAM_POINT_SIZE, state.program_point_size_enabled);
+        self.state_cache().invalidate();
+    }
+
+    fn get_id(&self, pname: u32) -> Option<u32> {
+        let id = self.get_integer(pname) as u32;
+        if id == 0 {
+            None
+        } else {
+            Some(id)
+        }
+    }
+
+    fn is_enabled(&self, cap: u32) -> bool {
+        unsafe { self.inner.IsEnabled(cap) == consts::TRUE }
+    }
+
+    fn set_enabled(&self, cap: u32, enabled: bool) {
+        if enabled {
+            self.enable(cap);
+        } else {
+            self.disable(cap);
+        }
+    }
+
+    ///
+    /// Returns true if the context is lost and all GPU resources are invalid.
+    /// Always false on desktop.
+    ///
+    pub fn is_lost(&self) -> bool {
+        false
+    }
+
+    pub fn supports_extension(&self, name: &str) -> bool {
+        let count = self.get_integer(consts::NUM_EXTENSIONS);
+        (0..count as u32).any(|i| {
+            let extension = unsafe {
+                std::ffi::CStr::from_ptr(self.inner.GetStringi(consts::EXTENSIONS, i) as *const _)
+            };
+            extension.to_bytes() == name.as_bytes()
+        })
+    }
+
+    pub fn get_float(&self, pname: u32) -> f32 {
+        let mut value = 0.0;
+        unsafe {
+            self.inner.GetFloatv(pname, &mut value);
+        }
+        value
+    }
+
+    pub fn get_integer(&self, pname: u32) -> i32 {
+        let mut value = 0;
+        unsafe {
+            self.inner.GetIntegerv(pname, &mut value);
+        }
+        value
+    }
+
+    ///
+    /// Returns true if the context supports compute shaders, ie. if the OpenGL version is 4.3 or newer.
+    ///
+    pub fn supports_compute_shader(&self) -> bool {
+        let major = self.get_integer(consts::MAJOR_VERSION);
+        let minor = self.get_integer(consts::MINOR_VERSION);
+        major > 4 || (major == 4 && minor >= 3)
+    }
+
     pub fn finish(&self) {
         unsafe {
             self.inner.Finish();
@@ -70,7 +493,16 @@ impl Glstruct {
     }
 
     pub fn compile_shader(&self, source: &str, shader: &Shader) {
-        let header = "#version 330 core\n";
+        let mut shader_type = 0;
+        unsafe {
+            self.inner
+                .GetShaderiv(*shader, consts::SHADER_TYPE, &mut shader_type);
+        }
+        let header = if shader_type as u32 == consts::COMPUTE_SHADER {
+            "#version 430 core\n"
+        } else {
+            "#version 330 core\n"
+        };
         let s: &str = &[header, source].concat();
 
         use std::ffi::{CStr, CString};
@@ -195,6 +627,8 @@ impl Glstruct {
             consts::ARRAY_BUFFER => consts::ARRAY_BUFFER_BINDING,
             consts::ELEMENT_ARRAY_BUFFER => consts::ELEMENT_ARRAY_BUFFER_BINDING,
             consts::UNIFORM_BUFFER => consts::UNIFORM_BUFFER_BINDING,
+            consts::SHADER_STORAGE_BUFFER => consts::SHADER_STORAGE_BUFFER_BINDING,
+            consts::TRANSFORM_FEEDBACK_BUFFER => consts::TRANSFORM_FEEDBACK_BUFFER_BINDING,
             _ => unreachable!(),
         };
 
@@ -209,6 +643,12 @@ impl Glstruct {
         }
     }
 
+    pub fn unbind_buffer_base(&self, target: u32, index: u32) {
+        unsafe {
+            self.inner.BindBufferBase(target, index, 0);
+        }
+    }
+
     pub fn bind_buffer(&self, target: u32, buffer: &Buffer) {
         unsafe {
             self.inner.BindBuffer(target, *buffer);
@@ -232,6 +672,26 @@ impl Glstruct {
         }
     }
 
+    pub fn get_program_resource_index(
+        &self,
+        program: &Program,
+        program_interface: u32,
+        name: &str,
+    ) -> u32 {
+        let c_str = std::ffi::CString::new(name).unwrap();
+        unsafe {
+            self.inner
+                .GetProgramResourceIndex(*program, program_interface, c_str.as_ptr())
+        }
+    }
+
+    pub fn shader_storage_block_binding(&self, program: &Program, location: u32, index: u32) {
+        unsafe {
+            self.inner
+                .ShaderStorageBlockBinding(*program, location, index);
+        }
+    }
+
     pub fn buffer_data(&self, target: u32, size_in_bytes: u32, usage: u32) {
         unsafe {
             self.inner.BufferData(
@@ -276,6 +736,17 @@ impl Glstruct {
         }
     }
 
+    pub fn get_buffer_sub_data_f32(&self, target: u32, offset: u32, dst_data: &mut [f32]) {
+        unsafe {
+            self.inner.GetBufferSubData(
+                target,
+                byte_size_for_type(consts::FLOAT, offset) as consts::types::GLintptr,
+                (dst_data.len() * std::mem::size_of::<f32>()) as consts::types::GLsizeiptr,
+                dst_data.as_mut_ptr() as *mut consts::types::GLvoid,
+            );
+        }
+    }
+
     pub fn create_vertex_array(&self) -> Option<VertexArrayObject> {
         let mut id: u32 = 0;
         unsafe {
@@ -295,10 +766,22 @@ impl Glstruct {
     }
 
     pub fn link_program(&self, program: &Program) -> bool {
+        self.start_link_program(program);
+        self.get_program_link_status(program)
+    }
+
+    pub fn start_link_program(&self, program: &Program) {
         unsafe {
             self.inner.LinkProgram(*program);
         }
+    }
 
+    pub fn is_program_link_completed(&self, program: &Program) -> bool {
+        !self.capabilities.parallel_shader_compile
+            || self.get_program_parameter(program, COMPLETION_STATUS) != 0
+    }
+
+    pub fn get_program_link_status(&self, program: &Program) -> bool {
         let mut success: consts::types::GLint = 1;
         unsafe {
             self.inner
@@ -330,7 +813,70 @@ impl Glstruct {
         }
     }
 
+    pub fn transform_feedback_varyings(
+        &self,
+        program: &Program,
+        varyings: &[&str],
+        buffer_mode: u32,
+    ) {
+        let c_strs: Vec<std::ffi::CString> = varyings
+            .iter()
+            .map(|name| std::ffi::CString::new(*name).unwrap())
+            .collect();
+        let pointers: Vec<*const consts::types::GLchar> =
+            c_strs.iter().map(|c_str| c_str.as_ptr()).collect();
+        unsafe {
+            self.inner.TransformFeedbackVaryings(
+                *program,
+                pointers.len() as i32,
+                pointers.as_ptr(),
+                buffer_mode,
+            );
+        }
+    }
+
+    pub fn create_transform_feedback(&self) -> Option<TransformFeedback> {
+        let mut id: u32 = 0;
+        unsafe {
+            self.inner.GenTransformFeedbacks(1, &mut id);
+        }
+        Some(id)
+    }
+
+    pub fn bind_transform_feedback(
+        &self,
+        target: u32,
+        transform_feedback: Option<&TransformFeedback>,
+    ) {
+        let id = match transform_feedback {
+            Some(tf) => *tf,
+            None => 0,
+        };
+        unsafe {
+            self.inner.BindTransformFeedback(target, id);
+        }
+    }
+
+    pub fn delete_transform_feedback(&self, transform_feedback: &TransformFeedback) {
+        unsafe {
+            self.inner.DeleteTransformFeedbacks(1, transform_feedback);
+        }
+    }
+
+    pub fn begin_transform_feedback(&self, primitive_mode: u32) {
+        unsafe {
+            self.inner.BeginTransformFeedback(primitive_mode);
+        }
+    }
+
+    pub fn end_transform_feedback(&self) {
+        unsafe {
+            self.inner.EndTransformFeedback();
+        }
+    }
+
     pub fn use_program(&self, program: &Program) {
+        self.update_render_stats(|stats| stats.program_binds += 1);
         unsafe {
             self.inner.UseProgram(*program);
         }
@@ -477,6 +1023,7 @@ impl Glstruct {
     }
 
     pub fn bind_framebuffer(&self, target: u32, framebuffer: Option<&Framebuffer>) {
+        self.update_render_stats(|stats| stats.framebuffer_binds += 1);
         let id = match framebuffer {
             Some(fb) => *fb,
             None => 0,
@@ -555,6 +1102,7 @@ impl Glstruct {
     }
 
     pub fn viewport(&self, x: i32, y: i32, width: usize, height: usize) {
+        self.update_render_stats(|stats| stats.state_changes += 1);
         unsafe {
             self.inner.Viewport(x, y, width as i32, height as i32);
         }
@@ -572,6 +1120,20 @@ impl Glstruct {
         }
     }
 
+    pub fn clear_bufferfv(&self, buffer: u32, draw_buffer: i32, values: &[f32]) {
+        unsafe {
+            self.inner
+                .ClearBufferfv(buffer, draw_buffer, values.as_ptr());
+        }
+    }
+
+    pub fn clear_bufferuiv(&self, buffer: u32, draw_buffer: i32, values: &[u32]) {
+        unsafe {
+            self.inner
+                .ClearBufferuiv(buffer, draw_buffer, values.as_ptr());
+        }
+    }
+
     pub fn clear(&self, mask: u32) {
         unsafe {
             self.inner.Clear(mask);
@@ -579,24 +1141,28 @@ impl Glstruct {
     }
 
     pub fn enable(&self, cap: u32) {
+        self.update_render_stats(|stats| stats.state_changes += 1);
         unsafe {
             self.inner.Enable(cap);
         }
     }
 
     pub fn disable(&self, cap: u32) {
+        self.update_render_stats(|stats| stats.state_changes += 1);
         unsafe {
             self.inner.Disable(cap);
         }
     }
 
     pub fn blend_func(&self, sfactor: u32, dfactor: u32) {
+        self.update_render_stats(|stats| stats.state_changes += 1);
         unsafe {
             self.inner.BlendFunc(sfactor, dfactor);
         }
     }
 
     pub fn blend_func_separate(&self, src_rgb: u32, dst_rgb: u32, src_alpha: u32, dst_alpha: u32) {
+        self.update_render_stats(|stats| stats.state_changes += 1);
         unsafe {
             self.inner
                 .BlendFuncSeparate(src_rgb, dst_rgb, src_alpha, dst_alpha);
@@ -604,30 +1170,35 @@ impl Glstruct {
     }
 
     pub fn blend_equation(&self, mode: u32) {
+        self.update_render_stats(|stats| stats.state_changes += 1);
         unsafe {
             self.inner.BlendEquation(mode);
         }
     }
 
     pub fn blend_equation_separate(&self, mode_rgb: u32, mode_alpha: u32) {
+        self.update_render_stats(|stats| stats.state_changes += 1);
         unsafe {
             self.inner.BlendEquationSeparate(mode_rgb, mode_alpha);
         }
     }
 
     pub fn cull_face(&self, mode: u32) {
+        self.update_render_stats(|stats| stats.state_changes += 1);
         unsafe {
             self.inner.CullFace(mode);
         }
     }
 
     pub fn depth_func(&self, func: u32) {
+        self.update_render_stats(|stats| stats.state_changes += 1);
         unsafe {
             self.inner.DepthFunc(func);
         }
     }
 
     pub fn color_mask(&self, red: bool, green: bool, blue: bool, alpha: bool) {
+        self.update_render_stats(|stats| stats.state_changes += 1);
         unsafe {
             self.inner.ColorMask(
                 if red { consts::TRUE } else { consts::FALSE },
@@ -639,6 +1210,7 @@ impl Glstruct {
     }
 
     pub fn depth_mask(&self, flag: bool) {
+        self.update_render_stats(|stats| stats.state_changes += 1);
         unsafe {
             if flag {
                 self.inner.DepthMask(consts::TRUE);
@@ -648,6 +1220,47 @@ impl Glstruct {
         }
     }
 
+    pub fn stencil_func(&self, func: u32, reference: i32, mask: u32) {
+        self.update_render_stats(|stats| stats.state_changes += 1);
+        unsafe {
+            self.inner.StencilFunc(func, reference, mask);
+        }
+    }
+
+    pub fn stencil_op(&self, fail: u32, depth_fail: u32, pass: u32) {
+        self.update_render_stats(|stats| stats.state_changes += 1);
+        unsafe {
+            self.inner.StencilOp(fail, depth_fail, pass);
+        }
+    }
+
+    pub fn stencil_mask(&self, mask: u32) {
+        self.update_render_stats(|stats| stats.state_changes += 1);
+        unsafe {
+            self.inner.StencilMask(mask);
+        }
+    }
+
+    pub fn primitive_restart_index(&self, index: u32) {
+        self.update_render_stats(|stats| stats.state_changes += 1);
+        unsafe {
+            self.inner.PrimitiveRestartIndex(index);
+        }
+    }
+
+    pub fn min_sample_shading(&self, value: f32) {
+        self.update_render_stats(|stats| stats.state_changes += 1);
+        unsafe {
+            self.inner.MinSampleShading(value);
+        }
+    }
+
+    pub fn clear_stencil(&self, stencil: i32) {
+        unsafe {
+            self.inner.ClearStencil(stencil);
+        }
+    }
+
     pub fn create_texture(&self) -> Option<Texture> {
         let mut id: u32 = 0;
         unsafe {
@@ -910,6 +1523,12 @@ impl Glstruct {
         }
     }
 
+    pub fn tex_parameterfv(&self, target: u32, pname: u32, params: &[f32]) {
+        unsafe {
+            self.inner.TexParameterfv(target, pname, params.as_ptr());
+        }
+    }
+
     pub fn delete_texture(&self, texture: &Texture) {
         unsafe {
             self.inner.DeleteTextures(1, texture);
@@ -921,12 +1540,24 @@ impl Glstruct {
         target: u32,
         attachment: u32,
         textarget: u32,
-        texture: &Texture,
+        texture: Option<&Texture>,
         level: u32,
     ) {
+        unsafe {
+            self.inner.FramebufferTexture2D(
+                target,
+                attachment,
+                textarget,
+                texture.copied().unwrap_or(0),
+                level as i32,
+            );
+        }
+    }
+
+    pub fn framebuffer_texture(&self, target: u32, attachment: u32, texture: &Texture, level: u32) {
         unsafe {
             self.inner
-                .FramebufferTexture2D(target, attachment, textarget, *texture, level as i32);
+                .FramebufferTexture(target, attachment, *texture, level as i32);
         }
     }
 
@@ -950,6 +1581,7 @@ impl Glstruct {
     }
 
     pub fn draw_arrays(&self, mode: u32, first: u32, count: u32) {
+        self.update_render_stats(|stats| stats.add_draw_call(mode, count, 1));
         unsafe {
             self.inner.DrawArrays(
                 mode as consts::types::GLenum,
@@ -960,6 +1592,7 @@ impl Glstruct {
     }
 
     pub fn draw_arrays_instanced(&self, mode: u32, first: u32, count: u32, instance_count: u32) {
+        self.update_render_stats(|stats| stats.add_draw_call(mode, count, instance_count));
         unsafe {
             self.inner.DrawArraysInstanced(
                 mode as consts::types::GLenum,
@@ -971,6 +1604,7 @@ impl Glstruct {
     }
 
     pub fn draw_elements(&self, mode: u32, count: u32, data_type: u32, offset: u32) {
+        self.update_render_stats(|stats| stats.add_draw_call(mode, count, 1));
         unsafe {
             self.inner.DrawElements(
                 mode as consts::types::GLenum,
@@ -989,6 +1623,7 @@ impl Glstruct {
         offset: u32,
         instance_count: u32,
     ) {
+        self.update_render_stats(|stats| stats.add_draw_call(mode, count, instance_count));
         unsafe {
             self.inner.DrawElementsInstanced(
                 mode as consts::types::GLenum,
@@ -1000,6 +1635,19 @@ impl Glstruct {
         }
     }
 
+    pub fn dispatch_compute(&self, num_groups_x: u32, num_groups_y: u32, num_groups_z: u32) {
+        unsafe {
+            self.inner
+                .DispatchCompute(num_groups_x, num_groups_y, num_groups_z);
+        }
+    }
+
+    pub fn memory_barrier(&self, barriers: u32) {
+        unsafe {
+            self.inner.MemoryBarrier(barriers);
+        }
+    }
+
     pub fn read_pixels(
         &self,
         x: u32,
@@ -1068,6 +1716,29 @@ impl Glstruct {
         }
     }
 
+    pub fn read_pixels_with_u32_data(
+        &self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        format: u32,
+        data_type: u32,
+        dst_data: &mut [u32],
+    ) {
+        unsafe {
+            self.inner.ReadPixels(
+                x as i32,
+                y as i32,
+                width as i32,
+                height as i32,
+                format,
+                data_type,
+                dst_data.as_ptr() as *mut consts::types::GLvoid,
+            )
+        }
+    }
+
     pub fn flush(&self) {
         unsafe {
             self.inner.Flush();
@@ -1089,6 +1760,12 @@ impl Glstruct {
     }
 }
 
+// From the EXT_texture_filter_anisotropic extension which is not part of the generated bindings.
+const MAX_TEXTURE_MAX_ANISOTROPY: u32 = 0x84FF;
+
+// From the KHR_parallel_shader_compile extension which is not part of the generated bindings.
+const COMPLETION_STATUS: u32 = 0x91B1;
+
 fn create_whitespace_cstring_with_len(len: usize) -> std::ffi::CString {
     // allocate buffer of correct size
     let mut buffer: Vec<u8> = Vec::with_capacity(len + 1);
@@ -1105,3 +1782,82 @@ fn byte_size_for_type(data_type: u32, count: u32) -> u32 {
         _ => 0,
     }
 }
+
+#[cfg(all(test, feature = "headless"))]
+mod tests {
+    use super::*;
+    use crate::test_util::*;
+
+    #[test]
+    fn pop_state_restores_pushed_state() {
+        let test_context = TestContext::new();
+        let context = test_context.gl();
+        let texture = context.create_texture().unwrap();
+        let buffer = context.create_buffer().unwrap();
+        let uniform_buffer_binding = |index| {
+            let mut id = 0;
+            unsafe {
+                context
+                    .inner
+                    .GetIntegeri_v(consts::UNIFORM_BUFFER_BINDING, index, &mut id);
+            }
+            id as u32
+        };
+
+        // The state set by other GL code
+        unsafe {
+            context
+                .inner
+                .StencilFuncSeparate(consts::BACK, consts::EQUAL, 3, 0x0F);
+        }
+        context.active_texture(consts::TEXTURE3);
+        context.bind_texture(consts::TEXTURE_CUBE_MAP, &texture);
+        context.active_texture(consts::TEXTURE1);
+        context.bind_buffer_base(consts::UNIFORM_BUFFER, 2, &buffer);
+        context.enable(consts::CLIP_DISTANCE1);
+        context.enable(consts::PRIMITIVE_RESTART);
+        context.primitive_restart_index(7);
+        context.disable(consts::PROGRAM_POINT_SIZE);
+
+        context.push_state();
+        assert!(context.is_enabled(consts::PROGRAM_POINT_SIZE));
+        assert_eq!(context.get_integer(consts::UNIFORM_BUFFER_BINDING), 0);
+
+        // The state changed when rendering with this crate
+        context.stencil_func(consts::ALWAYS, 0, 0xFF);
+        context.active_texture(consts::TEXTURE3);
+        unsafe {
+            context.inner.BindTexture(consts::TEXTURE_CUBE_MAP, 0);
+        }
+        context.unbind_buffer_base(consts::UNIFORM_BUFFER, 2);
+        context.disable(consts::CLIP_DISTANCE1);
+        context.disable(consts::PRIMITIVE_RESTART);
+        context.primitive_restart_index(u32::MAX);
+
+        context.pop_state();
+        assert_eq!(
+            context.get_integer(consts::STENCIL_FUNC) as u32,
+            consts::ALWAYS
+        );
+        assert_eq!(
+            context.get_integer(consts::STENCIL_BACK_FUNC) as u32,
+            consts::EQUAL
+        );
+        assert_eq!(context.get_integer(consts::STENCIL_BACK_REF), 3);
+        assert_eq!(context.get_integer(consts::STENCIL_BACK_VALUE_MASK), 0x0F);
+        assert_eq!(
+            context.get_integer(consts::ACTIVE_TEXTURE) as u32,
+            consts::TEXTURE1
+        );
+        context.active_texture(consts::TEXTURE3);
+        assert_eq!(
+            context.get_integer(consts::TEXTURE_BINDING_CUBE_MAP) as u32,
+            texture
+        );
+        assert_eq!(uniform_buffer_binding(2), buffer);
+        assert!(context.is_enabled(consts::CLIP_DISTANCE1));
+        assert!(context.is_enabled(consts::PRIMITIVE_RESTART));
+        assert_eq!(context.get_integer(consts::PRIMITIVE_RESTART_INDEX), 7);
+        assert!(!context.is_enabled(consts::PROGRAM_POINT_SIZE));
+    }
+}
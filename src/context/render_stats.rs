@@ -0,0 +1,49 @@
+use crate::context::consts;
+
+///
+/// Counters of the GL calls made through a [context](crate::context::Context) since the counters were last reset
+/// (see [render_stats](crate::context::Glstruct::render_stats) and [reset_render_stats](crate::context::Glstruct::reset_render_stats)),
+/// which is useful for finding out why a frame is slow.
+/// Reset the counters at the beginning of each frame to get the counts per frame.
+///
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct RenderStats {
+    ///
+    /// The number of draw calls, including instanced draw calls.
+    ///
+    pub draw_calls: u32,
+
+    ///
+    /// The number of triangles submitted in the draw calls, including all instances.
+    ///
+    pub triangles: u64,
+
+    ///
+    /// The number of times a shader program is bound.
+    ///
+    pub program_binds: u32,
+
+    ///
+    /// The number of times a framebuffer, including the screen, is bound.
+    ///
+    pub framebuffer_binds: u32,
+
+    ///
+    /// The number of GL calls which change the render state, for example enabling the depth test or setting the blend function,
+    /// the write mask or the viewport. The render state is only changed when it is different from the current state,
+    /// so this is usually much lower than the number of draw calls times the number of [render states](crate::RenderStates).
+    ///
+    pub state_changes: u32,
+}
+
+impl RenderStats {
+    pub(crate) fn add_draw_call(&mut self, mode: u32, count: u32, instance_count: u32) {
+        let triangles = match mode {
+            consts::TRIANGLES => count / 3,
+            consts::TRIANGLE_STRIP | consts::TRIANGLE_FAN => count.saturating_sub(2),
+            _ => 0,
+        };
+        self.draw_calls += 1;
+        self.triangles += triangles as u64 * instance_count as u64;
+    }
+}
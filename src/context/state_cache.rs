@@ -0,0 +1,117 @@
+///
+/// A shadow of the GL state which is changed in most render calls (see [RenderStates](crate::RenderStates)),
+/// used to skip GL calls which would not change the state.
+/// Each value is `None` if the state is unknown, for example because it has not been set yet.
+///
+#[derive(Clone, Default)]
+pub(crate) struct StateCache {
+    pub viewport: Option<(i32, i32, usize, usize)>,
+    pub cull_face_enabled: Option<bool>,
+    pub cull_face: Option<u32>,
+    pub blend_enabled: Option<bool>,
+    pub blend_func: Option<(u32, u32, u32, u32)>,
+    pub blend_equation: Option<(u32, u32)>,
+    pub color_mask: Option<(bool, bool, bool, bool)>,
+    pub depth_test_enabled: Option<bool>,
+    pub depth_mask: Option<bool>,
+    pub depth_func: Option<u32>,
+    pub stencil_test_enabled: Option<bool>,
+    pub stencil_func: Option<(u32, i32, u32)>,
+    pub stencil_op: Option<(u32, u32, u32)>,
+    pub stencil_mask: Option<u32>,
+    pub sample_shading_enabled: Option<bool>,
+    pub min_sample_shading: Option<f32>,
+    pub alpha_to_coverage_enabled: Option<bool>,
+    pub primitive_restart_enabled: Option<bool>,
+    pub primitive_restart_index: Option<u32>,
+    pub clip_distance_enabled: [Option<bool>; crate::MAX_CLIP_PLANES],
+    pub active_texture_unit: Option<u32>,
+    pub bound_textures: std::collections::HashMap<u32, crate::context::Texture>,
+    pub screen_size: Option<(usize, usize)>,
+    pub draw_framebuffer_size: Option<(usize, usize)>,
+    // The framebuffer used by RenderTargetArray::copy_depth_layer_to, which is not GL state
+    pub copy_framebuffer: Option<crate::context::Framebuffer>,
+}
+
+impl StateCache {
+    ///
+    /// Forgets all of the GL state, for example because the state has been changed by GL calls outside of this crate,
+    /// except the screen and framebuffer sizes and the copy framebuffer which are not GL state.
+    ///
+    pub fn invalidate(&mut self) {
+        *self = Self {
+            screen_size: self.screen_size,
+            draw_framebuffer_size: self.draw_framebuffer_size,
+            copy_framebuffer: self.copy_framebuffer.take(),
+            ..Default::default()
+        };
+    }
+}
+
+///
+/// The texture targets and the corresponding binding queries for which the bound textures are saved on each texture unit by
+/// [push_state](crate::context::Glstruct::push_state), ie. all of the targets used by this crate.
+///
+pub(crate) const SAVED_TEXTURE_TARGETS: [(u32, u32); 4] = [
+    (
+        crate::context::consts::TEXTURE_2D,
+        crate::context::consts::TEXTURE_BINDING_2D,
+    ),
+    (
+        crate::context::consts::TEXTURE_2D_ARRAY,
+        crate::context::consts::TEXTURE_BINDING_2D_ARRAY,
+    ),
+    (
+        crate::context::consts::TEXTURE_3D,
+        crate::context::consts::TEXTURE_BINDING_3D,
+    ),
+    (
+        crate::context::consts::TEXTURE_CUBE_MAP,
+        crate::context::consts::TEXTURE_BINDING_CUBE_MAP,
+    ),
+];
+
+///
+/// The GL state saved by [push_state](crate::context::Glstruct::push_state) and restored by
+/// [pop_state](crate::context::Glstruct::pop_state).
+///
+#[derive(Clone)]
+pub(crate) struct SavedState {
+    pub draw_framebuffer: Option<crate::context::Framebuffer>,
+    pub read_framebuffer: Option<crate::context::Framebuffer>,
+    pub program: Option<crate::context::Program>,
+    pub vertex_array: Option<crate::context::VertexArrayObject>,
+    pub array_buffer: Option<crate::context::Buffer>,
+    pub active_texture: u32,
+    // The textures bound to each texture unit in the order of SAVED_TEXTURE_TARGETS
+    pub textures: Vec<[Option<crate::context::Texture>; 4]>,
+    pub uniform_buffer: Option<crate::context::Buffer>,
+    pub uniform_buffers: Vec<Option<crate::context::Buffer>>,
+    pub viewport: [i32; 4],
+    pub cull_face_enabled: bool,
+    pub cull_face: u32,
+    pub blend_enabled: bool,
+    pub blend_func: (u32, u32, u32, u32),
+    pub blend_equation: (u32, u32),
+    pub color_mask: [bool; 4],
+    pub depth_test_enabled: bool,
+    pub depth_mask: bool,
+    pub depth_func: u32,
+    pub stencil_test_enabled: bool,
+    // The stencil state of the front and back faces
+    pub stencil_func: [(u32, i32, u32); 2],
+    pub stencil_op: [(u32, u32, u32); 2],
+    pub stencil_mask: [u32; 2],
+    pub alpha_to_coverage_enabled: bool,
+    #[cfg(not(target_arch = "wasm32"))]
+    pub clip_distance_enabled: [bool; crate::MAX_CLIP_PLANES],
+    #[cfg(not(target_arch = "wasm32"))]
+    pub primitive_restart_enabled: bool,
+    #[cfg(not(target_arch = "wasm32"))]
+    pub primitive_restart_index: u32,
+    // Whether sample shading is enabled and the minimum fraction of samples, or None if sample shading is not supported
+    #[cfg(not(target_arch = "wasm32"))]
+    pub sample_shading: Option<(bool, f32)>,
+    #[cfg(not(target_arch = "wasm32"))]
+    pub program_point_size_enabled: bool,
+}
@@ -1,3 +1,4 @@
+use crate::context::{Capabilities, RenderStats, SavedState, StateCache, SAVED_TEXTURE_TARGETS};
 use web_sys::WebGl2RenderingContext as InnerGl;
 
 #[allow(non_camel_case_types)]
@@ -11,23 +12,347 @@ pub use web_sys::WebGlProgram as Program;
 pub use web_sys::WebGlShader as Shader;
 pub use web_sys::WebGlSync as Sync;
 pub use web_sys::WebGlTexture as Texture;
+pub use web_sys::WebGlTransformFeedback as TransformFeedback;
 pub use web_sys::WebGlUniformLocation as UniformLocation;
 pub use web_sys::WebGlVertexArrayObject as VertexArrayObject;
 
 #[derive(Clone)]
 pub struct Glstruct {
     inner: InnerGl,
+    capabilities: Capabilities,
+    state_cache: std::cell::RefCell<StateCache>,
+    render_stats: std::cell::Cell<RenderStats>,
+    saved_states: std::cell::RefCell<Vec<SavedState>>,
 }
 
 pub type Context = std::rc::Rc<Glstruct>;
 
 impl Glstruct {
     pub fn new(webgl_context: InnerGl) -> Context {
+        let get_integer = |pname| {
+            webgl_context
+                .get_parameter(pname)
+                .ok()
+                .and_then(|v| v.as_f64())
+                .unwrap_or(0.0) as u32
+        };
+        let max_anisotropy = match webgl_context.get_extension("EXT_texture_filter_anisotropic") {
+            Ok(Some(_)) => webgl_context
+                .get_parameter(MAX_TEXTURE_MAX_ANISOTROPY)
+                .ok()
+                .and_then(|v| v.as_f64())
+                .map(|v| v as f32),
+            _ => None,
+        };
+        let capabilities = Capabilities {
+            max_texture_size: get_integer(consts::MAX_TEXTURE_SIZE),
+            max_cube_map_texture_size: get_integer(consts::MAX_CUBE_MAP_TEXTURE_SIZE),
+            max_samples: get_integer(consts::MAX_SAMPLES),
+            max_color_attachments: get_integer(consts::MAX_COLOR_ATTACHMENTS),
+            max_array_texture_layers: get_integer(consts::MAX_ARRAY_TEXTURE_LAYERS),
+            max_texture_image_units: get_integer(consts::MAX_TEXTURE_IMAGE_UNITS),
+            max_anisotropy,
+            parallel_shader_compile: matches!(
+                webgl_context.get_extension("KHR_parallel_shader_compile"),
+                Ok(Some(_))
+            ),
+            color_buffer_float: matches!(
+                webgl_context.get_extension("EXT_color_buffer_float"),
+                Ok(Some(_))
+            ),
+            sample_shading: false,
+        };
         std::rc::Rc::new(Glstruct {
             inner: webgl_context,
+            capabilities,
+            state_cache: std::cell::RefCell::new(StateCache::default()),
+            render_stats: std::cell::Cell::new(RenderStats::default()),
+            saved_states: std::cell::RefCell::new(Vec::new()),
         })
     }
 
+    ///
+    /// Returns the hardware and driver limits of this context.
+    ///
+    pub fn capabilities(&self) -> Capabilities {
+        self.capabilities
+    }
+
+    ///
+    /// Returns true if the context is lost, for example because of a GPU reset,
+    /// in which case all GPU resources are invalid until the context is restored.
+    ///
+    pub fn is_lost(&self) -> bool {
+        let lost = self.inner.is_context_lost();
+        if lost {
+            // The state is reset when the context is restored
+            *self.state_cache() = StateCache::default();
+        }
+        lost
+    }
+
+    ///
+    /// Saves the GL state which is changed by this crate on a stack, so that it can be restored by [pop_state](Self::pop_state).
+    /// Use this to combine rendering with this crate and rendering with other WebGL code, for example a user interface or the renderer of a host application,
+    /// by calling this function before and [pop_state](Self::pop_state) after rendering with this crate.
+    /// The saved state is the bound framebuffers, program, vertex array and array buffer, the active texture unit,
+    /// the 2D, 2D array, 3D and cube map textures bound to each texture unit, the generic and indexed uniform buffer bindings,
+    /// the viewport and the cull, blend, write mask, depth, front and back face stencil and alpha to coverage states.
+    /// The indexed uniform buffers are restored with `bindBufferBase`,
+    /// so a buffer bound to only a range of an index is bound to the whole buffer after [pop_state](Self::pop_state).
+    /// Any other state, for example the texture parameters, the scissor test or the buffer contents, is not saved.
+    ///
+    /// Since the other WebGL code might have changed the state, this crate forgets the state it has previously set (see [RenderStates](crate::RenderStates)),
+    /// binds the default vertex array which is used by this crate and unbinds the generic uniform buffer binding.
+    ///
+    pub fn push_state(&self) {
+        use wasm_bindgen::JsCast;
+        fn get_object<T: JsCast>(gl: &InnerGl, pname: u32) -> Option<T> {
+            gl.get_parameter(pname).ok().and_then(|v| v.dyn_into().ok())
+        }
+        let get_integer = |pname| {
+            self.inner
+                .get_parameter(pname)
+                .ok()
+                .and_then(|v| v.as_f64())
+                .unwrap_or(0.0)
+        };
+        let get_bool = |pname| {
+            self.inner
+                .get_parameter(pname)
+                .ok()
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false)
+        };
+        let state = SavedState {
+            draw_framebuffer: get_object(&self.inner, consts::DRAW_FRAMEBUFFER_BINDING),
+            read_framebuffer: get_object(&self.inner, consts::READ_FRAMEBUFFER_BINDING),
+            program: get_object(&self.inner, consts::CURRENT_PROGRAM),
+            vertex_array: get_object(&self.inner, consts::VERTEX_ARRAY_BINDING),
+            array_buffer: get_object(&self.inner, consts::ARRAY_BUFFER_BINDING),
+            active_texture: get_integer(consts::ACTIVE_TEXTURE) as u32,
+            textures: (0..self.capabilities.max_texture_image_units)
+                .map(|unit| {
+                    self.inner.active_texture(consts::TEXTURE0 + unit);
+                    let mut textures = [None, None, None, None];
+                    for (texture, (_, binding)) in textures.iter_mut().zip(&SAVED_TEXTURE_TARGETS) {
+                        *texture = get_object(&self.inner, *binding);
+                    }
+                    textures
+                })
+                .collect(),
+            uniform_buffer: get_object(&self.inner, consts::UNIFORM_BUFFER_BINDING),
+            uniform_buffers: (0..get_integer(consts::MAX_UNIFORM_BUFFER_BINDINGS) as u32)
+                .map(|index| {
+                    self.inner
+                        .get_indexed_parameter(consts::UNIFORM_BUFFER_BINDING, index)
+                        .ok()
+                        .and_then(|v| v.dyn_into().ok())
+                })
+                .collect(),
+            viewport: {
+                let mut viewport = [0; 4];
+                if let Some(array) = get_object::<js_sys::Int32Array>(&self.inner, consts::VIEWPORT)
+                {
+                    array.copy_to(&mut viewport);
+                }
+                viewport
+            },
+            cull_face_enabled: self.inner.is_enabled(consts::CULL_FACE),
+            cull_face: get_integer(consts::CULL_FACE_MODE) as u32,
+            blend_enabled: self.inner.is_enabled(consts::BLEND),
+            blend_func: (
+                get_integer(consts::BLEND_SRC_RGB) as u32,
+                get_integer(consts::BLEND_DST_RGB) as u32,
+                get_integer(consts::BLEND_SRC_ALPHA) as u32,
+                get_integer(consts::BLEND_DST_ALPHA) as u32,
+            ),
+            blend_equation: (
+                get_integer(consts::BLEND_EQUATION_RGB) as u32,
+                get_integer(consts::BLEND_EQUATION_ALPHA) as u32,
+            ),
+            color_mask: {
+                let mut color_mask = [true; 4];
+                if let Some(array) =
+                    get_object::<js_sys::Array>(&self.inner, consts::COLOR_WRITEMASK)
+                {
+                    for (i, mask) in color_mask.iter_mut().enumerate() {
+                        *mask = array.get(i as u32).as_bool().unwrap_or(true);
+                    }
+                }
+                color_mask
+            },
+            depth_test_enabled: self.inner.is_enabled(consts::DEPTH_TEST),
+            depth_mask: get_bool(consts::DEPTH_WRITEMASK),
+            depth_func: get_integer(consts::DEPTH_FUNC) as u32,
+            stencil_test_enabled: self.inner.is_enabled(consts::STENCIL_TEST),
+            stencil_func: [
+                (
+                    get_integer(consts::STENCIL_FUNC) as u32,
+                    get_integer(consts::STENCIL_REF) as i32,
+                    get_integer(consts::STENCIL_VALUE_MASK) as u32,
+                ),
+                (
+                    get_integer(consts::STENCIL_BACK_FUNC) as u32,
+                    get_integer(consts::STENCIL_BACK_REF) as i32,
+                    get_integer(consts::STENCIL_BACK_VALUE_MASK) as u32,
+                ),
+            ],
+            stencil_op: [
+                (
+                    get_integer(consts::STENCIL_FAIL) as u32,
+                    get_integer(consts::STENCIL_PASS_DEPTH_FAIL) as u32,
+                    get_integer(consts::STENCIL_PASS_DEPTH_PASS) as u32,
+                ),
+                (
+                    get_integer(consts::STENCIL_BACK_FAIL) as u32,
+                    get_integer(consts::STENCIL_BACK_PASS_DEPTH_FAIL) as u32,
+                    get_integer(consts::STENCIL_BACK_PASS_DEPTH_PASS) as u32,
+                ),
+            ],
+            stencil_mask: [
+                get_integer(consts::STENCIL_WRITEMASK) as u32,
+                get_integer(consts::STENCIL_BACK_WRITEMASK) as u32,
+            ],
+            alpha_to_coverage_enabled: self.inner.is_enabled(consts::SAMPLE_ALPHA_TO_COVERAGE),
+        };
+        self.inner.active_texture(state.active_texture);
+        self.saved_states.borrow_mut().push(state);
+        self.state_cache().invalidate();
+        self.inner.bind_vertex_array(None);
+        self.inner.bind_buffer(consts::UNIFORM_BUFFER, None);
+    }
+
+    ///
+    /// Restores the GL state saved by the last call to [push_state](Self::push_state) and removes it from the stack.
+    /// Does nothing if there is no saved state.
+    ///
+    pub fn pop_state(&self) {
+        let state = match self.saved_states.borrow_mut().pop() {
+            Some(state) => state,
+            None => return,
+        };
+        let set_enabled = |cap, enabled| {
+            if enabled {
+                self.inner.enable(cap);
+            } else {
+                self.inner.disable(cap);
+            }
+        };
+        self.inner
+            .bind_framebuffer(consts::DRAW_FRAMEBUFFER, state.draw_framebuffer.as_ref());
+        self.inner
+            .bind_framebuffer(consts::READ_FRAMEBUFFER, state.read_framebuffer.as_ref());
+        self.inner.use_program(state.program.as_ref());
+        self.inner.bind_vertex_array(state.vertex_array.as_ref());
+        self.inner
+            .bind_buffer(consts::ARRAY_BUFFER, state.array_buffer.as_ref());
+        for (unit, textures) in state.textures.iter().enumerate() {
+            self.inner.active_texture(consts::TEXTURE0 + unit as u32);
+            for (texture, (target, _)) in textures.iter().zip(&SAVED_TEXTURE_TARGETS) {
+                self.inner.bind_texture(*target, texture.as_ref());
+            }
+        }
+        self.inner.active_texture(state.active_texture);
+        for (index, buffer) in state.uniform_buffers.iter().enumerate() {
+            self.inner
+                .bind_buffer_base(consts::UNIFORM_BUFFER, index as u32, buffer.as_ref());
+        }
+        self.inner
+            .bind_buffer(consts::UNIFORM_BUFFER, state.uniform_buffer.as_ref());
+        let [x, y, width, height] = state.viewport;
+        self.inner.viewport(x, y, width, height);
+        set_enabled(consts::CULL_FACE, state.cull_face_enabled);
+        self.inner.cull_face(state.cull_face);
+        set_enabled(consts::BLEND, state.blend_enabled);
+        let (src_rgb, dst_rgb, src_alpha, dst_alpha) = state.blend_func;
+        self.inner
+            .blend_func_separate(src_rgb, dst_rgb, src_alpha, dst_alpha);
+        self.inner
+            .blend_equation_separate(state.blend_equation.0, state.blend_equation.1);
+        let [red, green, blue, alpha] = state.color_mask;
+        self.inner.color_mask(red, green, blue, alpha);
+        set_enabled(consts::DEPTH_TEST, state.depth_test_enabled);
+        self.inner.depth_mask(state.depth_mask);
+        self.inner.depth_func(state.depth_func);
+        set_enabled(consts::STENCIL_TEST, state.stencil_test_enabled);
+        for (i, face) in [consts::FRONT, consts::BACK].iter().enumerate() {
+            let (func, reference, mask) = state.stencil_func[i];
+            self.inner
+                .stencil_func_separate(*face, func, reference, mask);
+            let (fail, depth_fail, pass) = state.stencil_op[i];
+            self.inner
+                .stencil_op_separate(*face, fail, depth_fail, pass);
+            self.inner
+                .stencil_mask_separate(*face, state.stencil_mask[i]);
+        }
+        set_enabled(
+            consts::SAMPLE_ALPHA_TO_COVERAGE,
+            state.alpha_to_coverage_enabled,
+        );
+        self.state_cache().invalidate();
+    }
+
+    pub(crate) fn state_cache(&self) -> std::cell::RefMut<'_, StateCache> {
+        self.state_cache.borrow_mut()
+    }
+
+    ///
+    /// Returns the number of draw calls, triangles, program binds, framebuffer binds and state changes since the last call to
+    /// [reset_render_stats](Self::reset_render_stats) or since the context was created.
+    ///
+    pub fn render_stats(&self) -> RenderStats {
+        self.render_stats.get()
+    }
+
+    ///
+    /// Resets all of the [render stats](Self::render_stats) to zero, typically at the beginning of each frame.
+    ///
+    pub fn reset_render_stats(&self) {
+        self.render_stats.set(RenderStats::default());
+    }
+
+    ///
+    /// Sets the size of the [screen](crate::Screen) in physical pixels, which is used for example by
+    /// [ImageEffect::apply_fullscreen](crate::ImageEffect::apply_fullscreen).
+    /// This is done by the window before each frame, so it is only needed when the context is not created by a window.
+    ///
+    pub fn set_screen_size(&self, width: usize, height: usize) {
+        self.state_cache().screen_size = Some((width, height));
+    }
+
+    ///
+    /// Enables or disables filtering across the faces of cube maps.
+    /// Does nothing, since seamless filtering is always enabled in WebGL2.
+    ///
+    pub fn enable_seamless_cubemaps(&self, _enabled: bool) {}
+
+    ///
+    /// Enables or disables conversion of the colors written to the [screen](crate::Screen) from linear to sRGB color space,
+    /// which gives correct gamma when rendering in linear color space, for example with the forward renderer, without a gamma correction effect.
+    /// The conversion also applies to render targets with a [SRGBA8](crate::Format::SRGBA8) color texture, in which case blending is done in linear color space.
+    /// On the other hand, the colors written to render targets with any other format are not converted,
+    /// so do not enable this when the colors are already converted to sRGB, for example by a custom gamma correction shader,
+    /// since that will apply the conversion twice. Note that sampling a [SRGBA8](crate::Format::SRGBA8) texture always converts from sRGB to linear.
+    /// The conversion is disabled when the context is created and requires an sRGB capable default framebuffer (see [WindowSettings::srgb](crate::WindowSettings::srgb)).
+    /// On web, the default framebuffer never supports sRGB conversion, so this function does nothing.
+    ///
+    pub fn set_framebuffer_srgb(&self, _enabled: bool) {}
+
+    ///
+    /// Returns whether the conversion from linear to sRGB color space is enabled, see [set_framebuffer_srgb](Self::set_framebuffer_srgb).
+    /// Always returns false on web.
+    ///
+    pub fn is_framebuffer_srgb(&self) -> bool {
+        false
+    }
+
+    fn update_render_stats(&self, update: impl FnOnce(&mut RenderStats)) {
+        let mut stats = self.render_stats.get();
+        update(&mut stats);
+        self.render_stats.set(stats);
+    }
+
     pub fn finish(&self) {
         self.inner.finish();
     }
@@ -36,6 +361,10 @@ impl Glstruct {
         self.inner.bind_buffer_base(target, index, Some(buffer));
     }
 
+    pub fn unbind_buffer_base(&self, target: u32, index: u32) {
+        self.inner.bind_buffer_base(target, index, None);
+    }
+
     pub fn bind_buffer(&self, target: u32, buffer: &Buffer) {
         self.inner.bind_buffer(target, Some(buffer));
     }
@@ -86,7 +415,7 @@ impl Glstruct {
     }
 
     pub fn compile_shader(&self, source: &str, shader: &Shader) {
-        let header = "#version 300 es\nprecision highp float;\nprecision highp int;\nprecision highp sampler2DArray;\n";
+        let header = "#version 300 es\nprecision highp float;\nprecision highp int;\nprecision highp sampler2DArray;\nprecision highp usampler2D;\n";
         let s: &str = &[header, source].concat();
 
         self.inner.shader_source(shader, s);
@@ -98,13 +427,49 @@ impl Glstruct {
     }
 
     pub fn link_program(&self, program: &Program) -> bool {
+        self.start_link_program(program);
+        self.get_program_link_status(program)
+    }
+
+    pub fn start_link_program(&self, program: &Program) {
         self.inner.link_program(program);
+    }
+
+    pub fn is_program_link_completed(&self, program: &Program) -> bool {
+        !self.capabilities.parallel_shader_compile
+            || self
+                .inner
+                .get_program_parameter(program, COMPLETION_STATUS)
+                .as_bool()
+                .unwrap_or(true)
+    }
+
+    pub fn get_program_link_status(&self, program: &Program) -> bool {
         self.inner
             .get_program_parameter(program, consts::LINK_STATUS)
             .as_bool()
             .unwrap_or(false)
     }
 
+    pub fn transform_feedback_varyings(
+        &self,
+        program: &Program,
+        varyings: &[&str],
+        buffer_mode: u32,
+    ) {
+        let array = js_sys::Array::new();
+        for name in varyings {
+            array.push(&wasm_bindgen::JsValue::from_str(name));
+        }
+        self.inner
+            .transform_feedback_varyings(program, &array, buffer_mode);
+    }
+
+    pub fn delete_transform_feedback(&self, transform_feedback: &TransformFeedback) {
+        self.inner
+            .delete_transform_feedback(Some(transform_feedback));
+    }
+
     pub fn bind_vertex_array(&self, array: &VertexArrayObject) {
         self.inner.bind_vertex_array(Some(array));
     }
@@ -348,16 +713,11 @@ impl Glstruct {
         target: u32,
         attachment: u32,
         textarget: u32,
-        texture: &Texture,
+        texture: Option<&Texture>,
         level: u32,
     ) {
-        self.inner.framebuffer_texture_2d(
-            target,
-            attachment,
-            textarget,
-            Some(texture),
-            level as i32,
-        );
+        self.inner
+            .framebuffer_texture_2d(target, attachment, textarget, texture, level as i32);
     }
 
     pub fn framebuffer_texture_layer(
@@ -378,14 +738,87 @@ impl Glstruct {
     }
 
     pub fn viewport(&self, x: i32, y: i32, width: usize, height: usize) {
+        self.update_render_stats(|stats| stats.state_changes += 1);
         self.inner.viewport(x, y, width as i32, height as i32);
     }
 
+    pub fn enable(&self, cap: u32) {
+        self.update_render_stats(|stats| stats.state_changes += 1);
+        self.inner.enable(cap);
+    }
+
+    pub fn disable(&self, cap: u32) {
+        self.update_render_stats(|stats| stats.state_changes += 1);
+        self.inner.disable(cap);
+    }
+
+    pub fn blend_func(&self, sfactor: u32, dfactor: u32) {
+        self.update_render_stats(|stats| stats.state_changes += 1);
+        self.inner.blend_func(sfactor, dfactor);
+    }
+
+    pub fn blend_func_separate(&self, src_rgb: u32, dst_rgb: u32, src_alpha: u32, dst_alpha: u32) {
+        self.update_render_stats(|stats| stats.state_changes += 1);
+        self.inner
+            .blend_func_separate(src_rgb, dst_rgb, src_alpha, dst_alpha);
+    }
+
+    pub fn blend_equation(&self, mode: u32) {
+        self.update_render_stats(|stats| stats.state_changes += 1);
+        self.inner.blend_equation(mode);
+    }
+
+    pub fn blend_equation_separate(&self, mode_rgb: u32, mode_alpha: u32) {
+        self.update_render_stats(|stats| stats.state_changes += 1);
+        self.inner.blend_equation_separate(mode_rgb, mode_alpha);
+    }
+
+    pub fn cull_face(&self, mode: u32) {
+        self.update_render_stats(|stats| stats.state_changes += 1);
+        self.inner.cull_face(mode);
+    }
+
+    pub fn depth_func(&self, func: u32) {
+        self.update_render_stats(|stats| stats.state_changes += 1);
+        self.inner.depth_func(func);
+    }
+
+    pub fn color_mask(&self, red: bool, green: bool, blue: bool, alpha: bool) {
+        self.update_render_stats(|stats| stats.state_changes += 1);
+        self.inner.color_mask(red, green, blue, alpha);
+    }
+
+    pub fn depth_mask(&self, flag: bool) {
+        self.update_render_stats(|stats| stats.state_changes += 1);
+        self.inner.depth_mask(flag);
+    }
+
+    pub fn stencil_func(&self, func: u32, reference: i32, mask: u32) {
+        self.update_render_stats(|stats| stats.state_changes += 1);
+        self.inner.stencil_func(func, reference, mask);
+    }
+
+    pub fn stencil_op(&self, fail: u32, depth_fail: u32, pass: u32) {
+        self.update_render_stats(|stats| stats.state_changes += 1);
+        self.inner.stencil_op(fail, depth_fail, pass);
+    }
+
+    pub fn stencil_mask(&self, mask: u32) {
+        self.update_render_stats(|stats| stats.state_changes += 1);
+        self.inner.stencil_mask(mask);
+    }
+
     pub fn get_attrib_location(&self, program: &Program, name: &str) -> Option<AttributeLocation> {
         Some(self.inner.get_attrib_location(program, name) as u32)
     }
 
+    pub fn bind_framebuffer(&self, target: u32, framebuffer: Option<&Framebuffer>) {
+        self.update_render_stats(|stats| stats.framebuffer_binds += 1);
+        self.inner.bind_framebuffer(target, framebuffer);
+    }
+
     pub fn use_program(&self, program: &Program) {
+        self.update_render_stats(|stats| stats.program_binds += 1);
         self.inner.use_program(Some(program));
     }
 
@@ -398,6 +831,7 @@ impl Glstruct {
     }
 
     pub fn draw_arrays(&self, mode: u32, first: u32, count: u32) {
+        self.update_render_stats(|stats| stats.add_draw_call(mode, count, 1));
         self.inner.draw_arrays(
             mode,
             first as i32, // starting index in the enabled arrays
@@ -406,6 +840,7 @@ impl Glstruct {
     }
 
     pub fn draw_arrays_instanced(&self, mode: u32, first: u32, count: u32, instance_count: u32) {
+        self.update_render_stats(|stats| stats.add_draw_call(mode, count, instance_count));
         self.inner.draw_arrays_instanced(
             mode,
             first as i32, // starting index in the enabled arrays
@@ -415,6 +850,7 @@ impl Glstruct {
     }
 
     pub fn draw_elements(&self, mode: u32, count: u32, data_type: u32, offset: u32) {
+        self.update_render_stats(|stats| stats.add_draw_call(mode, count, 1));
         self.inner
             .draw_elements_with_i32(mode, count as i32, data_type, offset as i32);
     }
@@ -427,6 +863,7 @@ impl Glstruct {
         offset: u32,
         instance_count: u32,
     ) {
+        self.update_render_stats(|stats| stats.add_draw_call(mode, count, instance_count));
         self.inner.draw_elements_instanced_with_i32(
             mode,
             count as i32,
@@ -495,6 +932,16 @@ impl Glstruct {
         }
     }
 
+    pub fn clear_bufferfv(&self, buffer: u32, draw_buffer: i32, values: &[f32]) {
+        self.inner
+            .clear_bufferfv_with_f32_array(buffer, draw_buffer, values);
+    }
+
+    pub fn clear_bufferuiv(&self, buffer: u32, draw_buffer: i32, values: &[u32]) {
+        self.inner
+            .clear_bufferuiv_with_u32_array(buffer, draw_buffer, values);
+    }
+
     pub fn uniform1f(&self, location: &UniformLocation, data: f32) {
         self.inner.uniform1f(Some(location), data);
     }
@@ -585,6 +1032,12 @@ impl std::ops::Deref for Glstruct {
     }
 }
 
+// From the EXT_texture_filter_anisotropic extension.
+const MAX_TEXTURE_MAX_ANISOTROPY: u32 = 0x84FF;
+
+// From the KHR_parallel_shader_compile extension.
+const COMPLETION_STATUS: u32 = 0x91B1;
+
 pub fn byte_size_for_type(data_type: u32, count: u32) -> u32 {
     match data_type {
         consts::FLOAT => count * std::mem::size_of::<f32>() as u32,
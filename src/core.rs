@@ -32,6 +32,11 @@ pub mod uniform_buffer;
 #[doc(inline)]
 pub use uniform_buffer::*;
 
+#[doc(hidden)]
+pub mod transform_feedback;
+#[doc(inline)]
+pub use transform_feedback::*;
+
 #[doc(hidden)]
 pub mod render_target;
 #[doc(inline)]
@@ -42,8 +47,23 @@ pub mod program;
 #[doc(inline)]
 pub use program::*;
 
+#[doc(hidden)]
+#[cfg(all(feature = "compute-shader", not(target_arch = "wasm32")))]
+pub mod compute_program;
+#[doc(inline)]
+#[cfg(all(feature = "compute-shader", not(target_arch = "wasm32")))]
+pub use compute_program::*;
+
+#[doc(hidden)]
+#[cfg(all(feature = "compute-shader", not(target_arch = "wasm32")))]
+pub mod shader_storage_buffer;
+#[doc(inline)]
+#[cfg(all(feature = "compute-shader", not(target_arch = "wasm32")))]
+pub use shader_storage_buffer::*;
+
 ///
 /// Error message from the [core](crate::core) module.
+/// The variant names are stable, so it is safe to match on them, and the message describes the details of the error.
 ///
 #[derive(Debug)]
 pub enum Error {
@@ -75,10 +95,59 @@ pub enum Error {
     FailedToCreateTexture {
         message: String,
     },
+    FailedToBindTexture {
+        message: String,
+    },
     FailedToUpdateBuffer {
         message: String,
     },
     FailedToCreateMesh {
         message: String,
     },
+    FailedToCreateTransformFeedback {
+        message: String,
+    },
+    ContextLost {
+        message: String,
+    },
+    ComputeShaderNotSupported {
+        message: String,
+    },
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::FailedToCreateShader {
+                shader_type,
+                message,
+            } => write!(f, "{}: {}", shader_type, message),
+            Error::UnknownShaderType { message }
+            | Error::FailedToLinkProgram { message }
+            | Error::FailedToFindAttribute { message }
+            | Error::FailedToFindUniform { message }
+            | Error::FailedToCreateFramebuffer { message }
+            | Error::FailedToCopyFromRenderTarget { message }
+            | Error::FailedToWriteToRenderTarget { message }
+            | Error::FailedToCreateTexture { message }
+            | Error::FailedToBindTexture { message }
+            | Error::FailedToUpdateBuffer { message }
+            | Error::FailedToCreateMesh { message }
+            | Error::FailedToCreateTransformFeedback { message }
+            | Error::ContextLost { message }
+            | Error::ComputeShaderNotSupported { message } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+pub(crate) fn check_context(context: &Context) -> Result<(), Error> {
+    if context.is_lost() {
+        Err(Error::ContextLost {
+            message: "The graphics context is lost".to_string(),
+        })
+    } else {
+        Ok(())
+    }
 }
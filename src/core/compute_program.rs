@@ -0,0 +1,180 @@
+use crate::context::{consts, Context};
+use crate::core::*;
+use crate::math::*;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+///
+/// A shader program consisting of a single programmable compute shader.
+/// Data is transferred to and from the compute shader using [shader storage buffers](crate::ShaderStorageBuffer)
+/// and uniform data (see the use_uniform functionality) and the work is executed using [dispatch](Self::dispatch).
+///
+/// Only available on contexts supporting OpenGL 4.3 or newer, otherwise
+/// [Error::ComputeShaderNotSupported](crate::Error::ComputeShaderNotSupported) is returned when constructing the program.
+///
+pub struct ComputeProgram {
+    context: Context,
+    id: crate::context::Program,
+    uniforms: HashMap<String, crate::context::UniformLocation>,
+    storage_blocks: RefCell<HashMap<String, (u32, u32)>>,
+}
+
+impl ComputeProgram {
+    pub fn from_source(
+        context: &Context,
+        compute_shader_source: &str,
+    ) -> Result<ComputeProgram, Error> {
+        if !context.supports_compute_shader() {
+            return Err(Error::ComputeShaderNotSupported {
+                message: "Compute shaders require OpenGL 4.3 or newer.".to_string(),
+            });
+        }
+        let shader =
+            context
+                .create_shader(consts::COMPUTE_SHADER)
+                .ok_or(Error::FailedToCreateShader {
+                    shader_type: "Compute shader".to_string(),
+                    message: "Unable to create shader object".to_string(),
+                })?;
+        context.compile_shader(compute_shader_source, &shader);
+
+        let id = context.create_program();
+        context.attach_shader(&id, &shader);
+        let success = context.link_program(&id);
+
+        if !success {
+            let mut message = "Failed to compile shader program:\n".to_string();
+            if let Some(log) = context.get_program_info_log(&id) {
+                message = format!("{}\nLink error: {}", message, log);
+            }
+            if let Some(log) = context.get_shader_info_log(&shader) {
+                message = format!("{}\nCompute shader error: {}", message, log);
+            }
+            return Err(Error::FailedToLinkProgram { message });
+        }
+
+        context.detach_shader(&id, &shader);
+        context.delete_shader(Some(&shader));
+
+        // Init uniforms
+        let num_uniforms = context.get_program_parameter(&id, consts::ACTIVE_UNIFORMS);
+        let mut uniforms = HashMap::new();
+        for i in 0..num_uniforms {
+            let info = context.get_active_uniform(&id, i);
+            let location = context.get_uniform_location(&id, &info.name());
+            if let Some(loc) = location {
+                uniforms.insert(info.name(), loc);
+            }
+        }
+
+        Ok(ComputeProgram {
+            context: context.clone(),
+            id,
+            uniforms,
+            storage_blocks: RefCell::new(HashMap::new()),
+        })
+    }
+
+    pub fn use_uniform_int(&self, name: &str, data: &i32) -> Result<(), Error> {
+        let location = self.get_uniform_location(name)?;
+        self.context.uniform1i(location, *data);
+        self.context.unuse_program();
+        Ok(())
+    }
+
+    pub fn use_uniform_float(&self, name: &str, data: &f32) -> Result<(), Error> {
+        let location = self.get_uniform_location(name)?;
+        self.context.uniform1f(location, *data);
+        self.context.unuse_program();
+        Ok(())
+    }
+
+    pub fn use_uniform_vec2(&self, name: &str, data: &Vec2) -> Result<(), Error> {
+        let location = self.get_uniform_location(name)?;
+        self.context.uniform2fv(location, &[data.x, data.y]);
+        self.context.unuse_program();
+        Ok(())
+    }
+
+    pub fn use_uniform_vec3(&self, name: &str, data: &Vec3) -> Result<(), Error> {
+        let location = self.get_uniform_location(name)?;
+        self.context.uniform3fv(location, &[data.x, data.y, data.z]);
+        self.context.unuse_program();
+        Ok(())
+    }
+
+    pub fn use_uniform_vec4(&self, name: &str, data: &Vec4) -> Result<(), Error> {
+        let location = self.get_uniform_location(name)?;
+        self.context
+            .uniform4fv(location, &[data.x, data.y, data.z, data.w]);
+        self.context.unuse_program();
+        Ok(())
+    }
+
+    fn get_uniform_location(&self, name: &str) -> Result<&crate::context::UniformLocation, Error> {
+        self.context.use_program(&self.id);
+        let loc = self
+            .uniforms
+            .get(name)
+            .ok_or_else(|| Error::FailedToFindUniform {
+                message: format!("Failed to find uniform {}", name),
+            })?;
+        Ok(loc)
+    }
+
+    ///
+    /// Binds the given buffer to the shader storage block with the given name, for example
+    /// `layout (std430) buffer Particles { vec4 positions[]; };` has the block name `Particles`.
+    /// Returns an error if the program does not contain an active storage block with the given name.
+    ///
+    pub fn use_storage_buffer(
+        &self,
+        buffer: &ShaderStorageBuffer,
+        block_name: &str,
+    ) -> Result<(), Error> {
+        if !self.storage_blocks.borrow().contains_key(block_name) {
+            let mut map = self.storage_blocks.borrow_mut();
+            let location = self.context.get_program_resource_index(
+                &self.id,
+                consts::SHADER_STORAGE_BLOCK,
+                block_name,
+            );
+            if location == consts::INVALID_INDEX {
+                Err(Error::FailedToFindUniform {
+                    message: format!("Failed to find storage block {}", block_name),
+                })?;
+            }
+            let index = map.len() as u32;
+            map.insert(block_name.to_owned(), (location, index));
+        };
+        let (location, index) = self
+            .storage_blocks
+            .borrow()
+            .get(block_name)
+            .unwrap()
+            .clone();
+        self.context
+            .shader_storage_block_binding(&self.id, location, index);
+        buffer.bind(index);
+        self.context.unbind_buffer(consts::SHADER_STORAGE_BUFFER);
+        Ok(())
+    }
+
+    ///
+    /// Executes the compute shader with the given number of work groups in each dimension.
+    /// A memory barrier is issued afterwards so the result is visible to subsequent draw calls and buffer reads.
+    ///
+    pub fn dispatch(&self, num_groups_x: u32, num_groups_y: u32, num_groups_z: u32) {
+        self.context.use_program(&self.id);
+        self.context
+            .dispatch_compute(num_groups_x, num_groups_y, num_groups_z);
+        self.context.memory_barrier(consts::ALL_BARRIER_BITS);
+        self.context.unuse_program();
+    }
+}
+
+impl Drop for ComputeProgram {
+    fn drop(&mut self) {
+        self.context.delete_program(&self.id);
+    }
+}
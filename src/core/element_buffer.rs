@@ -1,5 +1,5 @@
 use crate::context::{consts, Context};
-use crate::core::Error;
+use crate::core::{check_context, BufferUsage, Error};
 
 ///
 /// A buffer containing indices for rendering, see for example [draw_elements](crate::Program::draw_elements).
@@ -13,6 +13,22 @@ pub struct ElementBuffer {
 
 impl ElementBuffer {
     pub fn new_with_u32(context: &Context, data: &[u32]) -> Result<ElementBuffer, Error> {
+        Self::new_with_usage_u32(context, data, BufferUsage::Static)
+    }
+
+    pub fn fill_with_u32(&mut self, data: &[u32]) {
+        self.fill_with_usage_u32(data, BufferUsage::Static);
+    }
+
+    ///
+    /// Creates a new element buffer with the given indices and [usage](crate::BufferUsage) hint.
+    ///
+    pub fn new_with_usage_u32(
+        context: &Context,
+        data: &[u32],
+        usage: BufferUsage,
+    ) -> Result<ElementBuffer, Error> {
+        check_context(context)?;
         let id = context.create_buffer().unwrap();
         let mut buffer = ElementBuffer {
             context: context.clone(),
@@ -20,15 +36,18 @@ impl ElementBuffer {
             count: 0,
         };
         if data.len() > 0 {
-            buffer.fill_with_u32(data);
+            buffer.fill_with_usage_u32(data, usage);
         }
         Ok(buffer)
     }
 
-    pub fn fill_with_u32(&mut self, data: &[u32]) {
+    ///
+    /// Replaces the indices in the buffer with the given indices and [usage](crate::BufferUsage) hint.
+    ///
+    pub fn fill_with_usage_u32(&mut self, data: &[u32], usage: BufferUsage) {
         self.bind();
         self.context
-            .buffer_data_u32(consts::ELEMENT_ARRAY_BUFFER, data, consts::STATIC_DRAW);
+            .buffer_data_u32(consts::ELEMENT_ARRAY_BUFFER, data, usage.to_const());
         self.context.unbind_buffer(consts::ELEMENT_ARRAY_BUFFER);
         self.count = data.len();
     }
@@ -13,10 +13,19 @@ use std::collections::HashMap;
 pub struct Program {
     context: Context,
     id: crate::context::Program,
-    vertex_attributes: HashMap<String, u32>,
+    vertex_attributes: RefCell<HashMap<String, u32>>,
     textures: RefCell<HashMap<String, u32>>,
-    uniforms: HashMap<String, crate::context::UniformLocation>,
+    uniforms: RefCell<HashMap<String, crate::context::UniformLocation>>,
     uniform_blocks: RefCell<HashMap<String, (u32, u32)>>,
+    uses_clip_distances: bool,
+    pending_shaders: RefCell<
+        Option<(
+            crate::context::Shader,
+            crate::context::Shader,
+            Option<crate::context::Shader>,
+        )>,
+    >,
+    link_error: RefCell<Option<String>>,
 }
 
 impl Program {
@@ -25,6 +34,92 @@ impl Program {
         vertex_shader_source: &str,
         fragment_shader_source: &str,
     ) -> Result<Program, Error> {
+        Self::from_source_internal(
+            context,
+            vertex_shader_source,
+            fragment_shader_source,
+            None,
+            &[],
+            false,
+        )
+    }
+
+    ///
+    /// Same as [from_source](Self::from_source), except that this function does not wait for the program to be compiled and linked
+    /// if the context supports parallel shader compilation (see [Capabilities::parallel_shader_compile](crate::Capabilities::parallel_shader_compile)).
+    /// Use [is_ready](Self::is_ready) to check whether the program is ready, for example use another program until it is,
+    /// to avoid that the render loop stalls when a new program is needed.
+    /// Using the program before it is ready waits for the compilation to finish.
+    ///
+    pub fn from_source_parallel(
+        context: &Context,
+        vertex_shader_source: &str,
+        fragment_shader_source: &str,
+    ) -> Result<Program, Error> {
+        Self::from_source_internal(
+            context,
+            vertex_shader_source,
+            fragment_shader_source,
+            None,
+            &[],
+            true,
+        )
+    }
+
+    ///
+    /// Creates a new shader program where the given vertex shader outputs (varyings) are captured into separate vertex buffers
+    /// when drawing with [draw_arrays_with_transform_feedback](Self::draw_arrays_with_transform_feedback).
+    /// The i'th varying is written to the i'th output buffer.
+    ///
+    pub fn from_source_with_transform_feedback(
+        context: &Context,
+        vertex_shader_source: &str,
+        fragment_shader_source: &str,
+        varyings: &[&str],
+    ) -> Result<Program, Error> {
+        Self::from_source_internal(
+            context,
+            vertex_shader_source,
+            fragment_shader_source,
+            None,
+            varyings,
+            false,
+        )
+    }
+
+    ///
+    /// Creates a new shader program with a geometry shader between the vertex and fragment shader,
+    /// which for example can be used for [layered rendering](crate::RenderTargetArray::write_layers).
+    /// The geometry shader receives each primitive output from the vertex shader and can emit any number of primitives,
+    /// each to the texture layer defined by `gl_Layer`.
+    /// Only available on desktop.
+    ///
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_source_with_geometry_shader(
+        context: &Context,
+        vertex_shader_source: &str,
+        geometry_shader_source: &str,
+        fragment_shader_source: &str,
+    ) -> Result<Program, Error> {
+        Self::from_source_internal(
+            context,
+            vertex_shader_source,
+            fragment_shader_source,
+            Some(geometry_shader_source),
+            &[],
+            false,
+        )
+    }
+
+    fn from_source_internal(
+        context: &Context,
+        vertex_shader_source: &str,
+        fragment_shader_source: &str,
+        geometry_shader_source: Option<&str>,
+        varyings: &[&str],
+        parallel: bool,
+    ) -> Result<Program, Error> {
+        check_context(context)?;
         let vert_shader =
             context
                 .create_shader(consts::VERTEX_SHADER)
@@ -41,86 +136,152 @@ impl Program {
                 })?;
         context.compile_shader(vertex_shader_source, &vert_shader);
         context.compile_shader(fragment_shader_source, &frag_shader);
+        let geom_shader = match geometry_shader_source {
+            Some(source) => Some(create_geometry_shader(context, source)?),
+            None => None,
+        };
 
         let id = context.create_program();
         context.attach_shader(&id, &vert_shader);
         context.attach_shader(&id, &frag_shader);
-        let success = context.link_program(&id);
-
-        if !success {
-            let mut message = "Failed to compile shader program:\n".to_string();
-            if let Some(log) = context.get_program_info_log(&id) {
-                message = format!("{}\nLink error: {}", message, log);
-            }
-            if let Some(log) = context.get_shader_info_log(&vert_shader) {
-                message = format!("{}\nVertex shader error: {}", message, log);
-            }
-            if let Some(log) = context.get_shader_info_log(&frag_shader) {
-                message = format!("{}\nFragment shader error: {}", message, log);
-            }
-            return Err(Error::FailedToLinkProgram { message });
-        }
-
-        context.detach_shader(&id, &vert_shader);
-        context.detach_shader(&id, &frag_shader);
-        context.delete_shader(Some(&vert_shader));
-        context.delete_shader(Some(&frag_shader));
-
-        // Init vertex attributes
-        let num_attribs = context.get_program_parameter(&id, consts::ACTIVE_ATTRIBUTES);
-        let mut vertex_attributes = HashMap::new();
-        for i in 0..num_attribs {
-            let info = context.get_active_attrib(&id, i);
-            let location = context.get_attrib_location(&id, &info.name()).unwrap();
-            //println!("Attribute location: {}, name: {}, type: {}, size: {}", location, info.name(), info.type_(), info.size());
-            vertex_attributes.insert(info.name(), location);
-        }
-
-        // Init uniforms
-        let num_uniforms = context.get_program_parameter(&id, consts::ACTIVE_UNIFORMS);
-        let mut uniforms = HashMap::new();
-        for i in 0..num_uniforms {
-            let info = context.get_active_uniform(&id, i);
-            let location = context.get_uniform_location(&id, &info.name());
-            //println!("Uniform location: {:?}, name: {}, type: {}, size: {}", location, info.name(), info.type_(), info.size());
-            if let Some(loc) = location {
-                uniforms.insert(info.name(), loc);
-            }
+        if let Some(ref geom_shader) = geom_shader {
+            context.attach_shader(&id, geom_shader);
         }
+        if !varyings.is_empty() {
+            context.transform_feedback_varyings(&id, varyings, consts::SEPARATE_ATTRIBS);
+        }
+        context.start_link_program(&id);
 
-        Ok(Program {
+        let program = Program {
             context: context.clone(),
             id,
-            vertex_attributes,
-            uniforms,
+            vertex_attributes: RefCell::new(HashMap::new()),
+            uniforms: RefCell::new(HashMap::new()),
             uniform_blocks: RefCell::new(HashMap::new()),
             textures: RefCell::new(HashMap::new()),
-        })
+            uses_clip_distances: vertex_shader_source.contains("gl_ClipDistance"),
+            pending_shaders: RefCell::new(Some((vert_shader, frag_shader, geom_shader))),
+            link_error: RefCell::new(None),
+        };
+        if !parallel {
+            program.finish_link()?;
+        }
+        Ok(program)
+    }
+
+    ///
+    /// Returns whether or not the program is compiled and linked and therefore can be used without waiting.
+    /// Always true unless the program is created with [from_source_parallel](Self::from_source_parallel).
+    ///
+    /// # Errors
+    /// Will return an error if the program failed to compile or link, also when called again after that.
+    ///
+    pub fn is_ready(&self) -> Result<bool, Error> {
+        if self.pending_shaders.borrow().is_some()
+            && !self.context.is_program_link_completed(&self.id)
+        {
+            return Ok(false);
+        }
+        self.finish_link()?;
+        Ok(true)
+    }
+
+    ///
+    /// Finishes linking the program if it is not already done.
+    /// If the program failed to compile or link, the same error is returned every time this is called.
+    ///
+    pub(crate) fn finish_link(&self) -> Result<(), Error> {
+        if let Some(ref message) = *self.link_error.borrow() {
+            return Err(Error::FailedToLinkProgram {
+                message: message.clone(),
+            });
+        }
+        let pending_shaders = self.pending_shaders.borrow_mut().take();
+        if let Some((vert_shader, frag_shader, geom_shader)) = pending_shaders {
+            let context = &self.context;
+            let id = &self.id;
+            let success = context.get_program_link_status(id);
+
+            if !success {
+                let mut message = "Failed to compile shader program:\n".to_string();
+                if let Some(log) = context.get_program_info_log(id) {
+                    message = format!("{}\nLink error: {}", message, log);
+                }
+                if let Some(log) = context.get_shader_info_log(&vert_shader) {
+                    message = format!("{}\nVertex shader error: {}", message, log);
+                }
+                if let Some(log) = context.get_shader_info_log(&frag_shader) {
+                    message = format!("{}\nFragment shader error: {}", message, log);
+                }
+                if let Some(ref geom_shader) = geom_shader {
+                    if let Some(log) = context.get_shader_info_log(geom_shader) {
+                        message = format!("{}\nGeometry shader error: {}", message, log);
+                    }
+                    context.delete_shader(Some(geom_shader));
+                }
+                context.delete_shader(Some(&vert_shader));
+                context.delete_shader(Some(&frag_shader));
+                *self.link_error.borrow_mut() = Some(message.clone());
+                return Err(Error::FailedToLinkProgram { message });
+            }
+
+            context.detach_shader(id, &vert_shader);
+            context.detach_shader(id, &frag_shader);
+            context.delete_shader(Some(&vert_shader));
+            context.delete_shader(Some(&frag_shader));
+            if let Some(ref geom_shader) = geom_shader {
+                context.detach_shader(id, geom_shader);
+                context.delete_shader(Some(geom_shader));
+            }
+
+            // Init vertex attributes
+            let num_attribs = context.get_program_parameter(id, consts::ACTIVE_ATTRIBUTES);
+            let mut vertex_attributes = self.vertex_attributes.borrow_mut();
+            for i in 0..num_attribs {
+                let info = context.get_active_attrib(id, i);
+                let location = context.get_attrib_location(id, &info.name()).unwrap();
+                //println!("Attribute location: {}, name: {}, type: {}, size: {}", location, info.name(), info.type_(), info.size());
+                vertex_attributes.insert(info.name(), location);
+            }
+
+            // Init uniforms
+            let num_uniforms = context.get_program_parameter(id, consts::ACTIVE_UNIFORMS);
+            let mut uniforms = self.uniforms.borrow_mut();
+            for i in 0..num_uniforms {
+                let info = context.get_active_uniform(id, i);
+                let location = context.get_uniform_location(id, &info.name());
+                //println!("Uniform location: {:?}, name: {}, type: {}, size: {}", location, info.name(), info.type_(), info.size());
+                if let Some(loc) = location {
+                    uniforms.insert(info.name(), loc);
+                }
+            }
+        }
+        Ok(())
     }
 
     pub fn use_uniform_int(&self, name: &str, data: &i32) -> Result<(), Error> {
-        let location = self.get_uniform_location(name)?;
+        let location = &self.get_uniform_location(name)?;
         self.context.uniform1i(location, *data);
         self.context.unuse_program();
         Ok(())
     }
 
     pub fn use_uniform_float(&self, name: &str, data: &f32) -> Result<(), Error> {
-        let location = self.get_uniform_location(name)?;
+        let location = &self.get_uniform_location(name)?;
         self.context.uniform1f(location, *data);
         self.context.unuse_program();
         Ok(())
     }
 
     pub fn use_uniform_vec2(&self, name: &str, data: &Vec2) -> Result<(), Error> {
-        let location = self.get_uniform_location(name)?;
+        let location = &self.get_uniform_location(name)?;
         self.context.uniform2fv(location, &mut [data.x, data.y]);
         self.context.unuse_program();
         Ok(())
     }
 
     pub fn use_uniform_vec3(&self, name: &str, data: &Vec3) -> Result<(), Error> {
-        let location = self.get_uniform_location(name)?;
+        let location = &self.get_uniform_location(name)?;
         self.context
             .uniform3fv(location, &mut [data.x, data.y, data.z]);
         self.context.unuse_program();
@@ -128,7 +289,7 @@ impl Program {
     }
 
     pub fn use_uniform_vec4(&self, name: &str, data: &Vec4) -> Result<(), Error> {
-        let location = self.get_uniform_location(name)?;
+        let location = &self.get_uniform_location(name)?;
         self.context
             .uniform4fv(location, &mut [data.x, data.y, data.z, data.w]);
         self.context.unuse_program();
@@ -136,7 +297,7 @@ impl Program {
     }
 
     pub fn use_uniform_mat2(&self, name: &str, data: &Mat2) -> Result<(), Error> {
-        let location = self.get_uniform_location(name)?;
+        let location = &self.get_uniform_location(name)?;
         self.context
             .uniform_matrix2fv(location, &mut data.to_slice());
         self.context.unuse_program();
@@ -144,7 +305,7 @@ impl Program {
     }
 
     pub fn use_uniform_mat3(&self, name: &str, data: &Mat3) -> Result<(), Error> {
-        let location = self.get_uniform_location(name)?;
+        let location = &self.get_uniform_location(name)?;
         self.context
             .uniform_matrix3fv(location, &mut data.to_slice());
         self.context.unuse_program();
@@ -152,28 +313,44 @@ impl Program {
     }
 
     pub fn use_uniform_mat4(&self, name: &str, data: &Mat4) -> Result<(), Error> {
-        let location = self.get_uniform_location(name)?;
+        let location = &self.get_uniform_location(name)?;
         self.context
             .uniform_matrix4fv(location, &mut data.to_slice());
         self.context.unuse_program();
         Ok(())
     }
 
-    fn get_uniform_location(&self, name: &str) -> Result<&crate::context::UniformLocation, Error> {
+    fn get_uniform_location(&self, name: &str) -> Result<crate::context::UniformLocation, Error> {
+        self.finish_link()?;
         self.set_used();
-        let loc = self
-            .uniforms
-            .get(name)
-            .ok_or_else(|| Error::FailedToFindUniform {
+        let loc = self.uniforms.borrow().get(name).cloned().ok_or_else(|| {
+            Error::FailedToFindUniform {
                 message: format!("Failed to find uniform {}", name),
-            })?;
+            }
+        })?;
         Ok(loc)
     }
 
+    ///
+    /// Use the given texture in this shader program and associate it with the given named variable.
+    /// The texture is bound to the first texture unit which is not already used by another texture variable in this program,
+    /// which also skips the units assigned with [use_texture_at](Self::use_texture_at).
+    ///
     pub fn use_texture(&self, texture: &dyn Texture, texture_name: &str) -> Result<(), Error> {
         if !self.textures.borrow().contains_key(texture_name) {
             let mut map = self.textures.borrow_mut();
-            let index = map.len() as u32;
+            let index = (0..)
+                .find(|index| !map.values().any(|unit| unit == index))
+                .unwrap();
+            let max_texture_image_units = self.context.capabilities().max_texture_image_units;
+            if index >= max_texture_image_units {
+                Err(Error::FailedToBindTexture {
+                    message: format!(
+                        "Cannot use texture {} since the maximum number of textures in one program is {}",
+                        texture_name, max_texture_image_units
+                    ),
+                })?;
+            }
             map.insert(texture_name.to_owned(), index);
         };
         let index = self.textures.borrow().get(texture_name).unwrap().clone();
@@ -182,6 +359,55 @@ impl Program {
         Ok(())
     }
 
+    ///
+    /// Same as [use_texture](Self::use_texture), except that the texture is bound to the given texture unit.
+    /// A texture is only bound if it is not already bound to the texture unit,
+    /// so using the same unit for a texture that is shared between many draw calls and programs,
+    /// for example an environment map or a shadow map, avoids binding it again for each draw call.
+    /// The units assigned by [use_texture](Self::use_texture) starts from 0, so consider using the last units
+    /// (see [Capabilities::max_texture_image_units](crate::Capabilities::max_texture_image_units)) to avoid that
+    /// other textures are bound to the same unit in other programs.
+    ///
+    /// # Errors
+    /// Will return an error if the unit is not less than the maximum number of texture units
+    /// or if the unit is already used by another texture variable in this program.
+    ///
+    pub fn use_texture_at(
+        &self,
+        texture: &dyn Texture,
+        texture_name: &str,
+        unit: u32,
+    ) -> Result<(), Error> {
+        let max_texture_image_units = self.context.capabilities().max_texture_image_units;
+        if unit >= max_texture_image_units {
+            Err(Error::FailedToBindTexture {
+                message: format!(
+                    "Cannot use texture {} at unit {} since the maximum number of texture units is {}",
+                    texture_name, unit, max_texture_image_units
+                ),
+            })?;
+        }
+        if let Some((other_name, _)) = self
+            .textures
+            .borrow()
+            .iter()
+            .find(|(name, other_unit)| **other_unit == unit && name.as_str() != texture_name)
+        {
+            Err(Error::FailedToBindTexture {
+                message: format!(
+                    "Cannot use texture {} at unit {} since the unit is already used by texture {} in this program",
+                    texture_name, unit, other_name
+                ),
+            })?;
+        }
+        self.textures
+            .borrow_mut()
+            .insert(texture_name.to_owned(), unit);
+        texture.bind(unit);
+        self.use_uniform_int(texture_name, &(unit as i32))?;
+        Ok(())
+    }
+
     pub fn use_uniform_block(&self, buffer: &UniformBuffer, block_name: &str) {
         if !self.uniform_blocks.borrow().contains_key(block_name) {
             let mut map = self.uniform_blocks.borrow_mut();
@@ -201,11 +427,21 @@ impl Program {
         self.context.unbind_buffer(consts::UNIFORM_BUFFER);
     }
 
+    ///
+    /// Uses the given [VertexBuffer] data in this shader program (vertex shader) as the `in float` attribute with the given name.
+    /// The buffer contains one value per vertex, ie. the divisor is 0.
+    ///
     pub fn use_attribute(&self, buffer: &VertexBuffer, attribute_name: &str) -> Result<(), Error> {
         self.use_attribute_divisor(buffer, attribute_name, 0)?;
         Ok(())
     }
 
+    ///
+    /// Same as [use_attribute](Self::use_attribute), except that the attribute advances once per `divisor` instances
+    /// instead of once per vertex when the divisor is larger than 0.
+    /// Use a divisor of 1 for custom per-instance data, for example a uv offset or a scalar parameter per instance,
+    /// in combination with [draw_arrays_instanced](Self::draw_arrays_instanced) or [draw_elements_instanced](Self::draw_elements_instanced).
+    ///
     pub fn use_attribute_divisor(
         &self,
         buffer: &VertexBuffer,
@@ -225,6 +461,10 @@ impl Program {
         Ok(())
     }
 
+    ///
+    /// Uses the given [VertexBuffer] data in this shader program (vertex shader) as the `in vec2` attribute with the given name.
+    /// The buffer contains one value per vertex, ie. the divisor is 0.
+    ///
     pub fn use_attribute_vec2(
         &self,
         buffer: &VertexBuffer,
@@ -234,6 +474,12 @@ impl Program {
         Ok(())
     }
 
+    ///
+    /// Same as [use_attribute_vec2](Self::use_attribute_vec2), except that the attribute advances once per `divisor` instances
+    /// instead of once per vertex when the divisor is larger than 0.
+    /// Use a divisor of 1 for custom per-instance data, for example a uv offset or a scalar parameter per instance,
+    /// in combination with [draw_arrays_instanced](Self::draw_arrays_instanced) or [draw_elements_instanced](Self::draw_elements_instanced).
+    ///
     pub fn use_attribute_vec2_divisor(
         &self,
         buffer: &VertexBuffer,
@@ -253,6 +499,10 @@ impl Program {
         Ok(())
     }
 
+    ///
+    /// Uses the given [VertexBuffer] data in this shader program (vertex shader) as the `in vec3` attribute with the given name.
+    /// The buffer contains one value per vertex, ie. the divisor is 0.
+    ///
     pub fn use_attribute_vec3(
         &self,
         buffer: &VertexBuffer,
@@ -262,6 +512,12 @@ impl Program {
         Ok(())
     }
 
+    ///
+    /// Same as [use_attribute_vec3](Self::use_attribute_vec3), except that the attribute advances once per `divisor` instances
+    /// instead of once per vertex when the divisor is larger than 0.
+    /// Use a divisor of 1 for custom per-instance data, for example a uv offset or a scalar parameter per instance,
+    /// in combination with [draw_arrays_instanced](Self::draw_arrays_instanced) or [draw_elements_instanced](Self::draw_elements_instanced).
+    ///
     pub fn use_attribute_vec3_divisor(
         &self,
         buffer: &VertexBuffer,
@@ -281,6 +537,10 @@ impl Program {
         Ok(())
     }
 
+    ///
+    /// Uses the given [VertexBuffer] data in this shader program (vertex shader) as the `in vec4` attribute with the given name.
+    /// The buffer contains one value per vertex, ie. the divisor is 0.
+    ///
     pub fn use_attribute_vec4(
         &self,
         buffer: &VertexBuffer,
@@ -290,6 +550,12 @@ impl Program {
         Ok(())
     }
 
+    ///
+    /// Same as [use_attribute_vec4](Self::use_attribute_vec4), except that the attribute advances once per `divisor` instances
+    /// instead of once per vertex when the divisor is larger than 0.
+    /// Use a divisor of 1 for custom per-instance data, for example a uv offset or a scalar parameter per instance,
+    /// in combination with [draw_arrays_instanced](Self::draw_arrays_instanced) or [draw_elements_instanced](Self::draw_elements_instanced).
+    ///
     pub fn use_attribute_vec4_divisor(
         &self,
         buffer: &VertexBuffer,
@@ -309,12 +575,95 @@ impl Program {
         Ok(())
     }
 
+    ///
+    /// Uses the given interleaved [VertexBuffer] data in this shader program (vertex shader) as the attribute with the given name,
+    /// which has the given number of components between 1 and 4, ie. `in float`, `in vec2`, `in vec3` or `in vec4`.
+    /// The data for each vertex consists of `stride` values, of which the attribute is the `components` values starting at `offset`,
+    /// so several attributes can be stored in the same buffer.
+    /// The buffer contains one value per vertex, ie. the divisor is 0.
+    ///
+    pub fn use_attribute_interleaved(
+        &self,
+        buffer: &VertexBuffer,
+        attribute_name: &str,
+        components: u32,
+        stride: u32,
+        offset: u32,
+    ) -> Result<(), Error> {
+        if buffer.count() > 0 {
+            buffer.bind();
+            let loc = self.location(attribute_name)?;
+            self.context.enable_vertex_attrib_array(loc);
+            self.context.vertex_attrib_pointer(
+                loc,
+                components,
+                buffer.data_type(),
+                false,
+                stride,
+                offset,
+            );
+            self.context.vertex_attrib_divisor(loc, 0);
+            self.context.unbind_buffer(consts::ARRAY_BUFFER);
+            self.context.unuse_program();
+        }
+        Ok(())
+    }
+
     pub fn draw_arrays(&self, render_states: RenderStates, viewport: Viewport, count: u32) {
         Self::set_viewport(&self.context, viewport);
-        Self::set_states(&self.context, render_states);
+        self.set_states(render_states);
         self.set_used();
         self.context.draw_arrays(consts::TRIANGLES, 0, count);
-        for location in self.vertex_attributes.values() {
+        for location in self.vertex_attributes.borrow().values() {
+            self.context.disable_vertex_attrib_array(*location);
+        }
+        self.context.unuse_program();
+    }
+
+    ///
+    /// Draws the first `count` vertices as points instead of triangles.
+    /// The size of each point in pixels is given by writing to `gl_PointSize` in the vertex shader
+    /// and the fragment shader can use `gl_PointCoord` to find the position of the fragment inside the point.
+    ///
+    pub fn draw_points(&self, render_states: RenderStates, viewport: Viewport, count: u32) {
+        Self::set_viewport(&self.context, viewport);
+        self.set_states(render_states);
+        self.set_used();
+        self.context.draw_arrays(consts::POINTS, 0, count);
+        for location in self.vertex_attributes.borrow().values() {
+            self.context.disable_vertex_attrib_array(*location);
+        }
+        self.context.unuse_program();
+    }
+
+    ///
+    /// Runs the vertex shader once for each of the first `count` vertices and writes the captured varyings
+    /// (see [from_source_with_transform_feedback](Self::from_source_with_transform_feedback)) to the output buffers.
+    /// Rasterization is disabled, so nothing is written to the current render target.
+    /// The output buffers must be large enough to contain the output and must not be used as input in the same call.
+    ///
+    pub fn draw_arrays_with_transform_feedback(
+        &self,
+        count: u32,
+        transform_feedback: &TransformFeedback,
+        output_buffers: &[&VertexBuffer],
+    ) {
+        self.set_used();
+        self.context.enable(consts::RASTERIZER_DISCARD);
+        transform_feedback.bind();
+        for (index, buffer) in output_buffers.iter().enumerate() {
+            buffer.bind_as_transform_feedback_target(index as u32);
+        }
+        self.context.begin_transform_feedback(consts::POINTS);
+        self.context.draw_arrays(consts::POINTS, 0, count);
+        self.context.end_transform_feedback();
+        for index in 0..output_buffers.len() {
+            self.context
+                .unbind_buffer_base(consts::TRANSFORM_FEEDBACK_BUFFER, index as u32);
+        }
+        transform_feedback.unbind();
+        self.context.disable(consts::RASTERIZER_DISCARD);
+        for location in self.vertex_attributes.borrow().values() {
             self.context.disable_vertex_attrib_array(*location);
         }
         self.context.unuse_program();
@@ -328,12 +677,12 @@ impl Program {
         instance_count: u32,
     ) {
         Self::set_viewport(&self.context, viewport);
-        Self::set_states(&self.context, render_states);
+        self.set_states(render_states);
         self.set_used();
         self.context
             .draw_arrays_instanced(consts::TRIANGLES, 0, count, instance_count);
         self.context.unbind_buffer(consts::ELEMENT_ARRAY_BUFFER);
-        for location in self.vertex_attributes.values() {
+        for location in self.vertex_attributes.borrow().values() {
             self.context.disable_vertex_attrib_array(*location);
         }
         self.context.unuse_program();
@@ -363,14 +712,14 @@ impl Program {
         count: u32,
     ) {
         Self::set_viewport(&self.context, viewport);
-        Self::set_states(&self.context, render_states);
+        self.set_states(render_states);
         self.set_used();
         element_buffer.bind();
         self.context
             .draw_elements(consts::TRIANGLES, count, consts::UNSIGNED_INT, first);
         self.context.unbind_buffer(consts::ELEMENT_ARRAY_BUFFER);
 
-        for location in self.vertex_attributes.values() {
+        for location in self.vertex_attributes.borrow().values() {
             self.context.disable_vertex_attrib_array(*location);
         }
         self.context.unuse_program();
@@ -384,7 +733,7 @@ impl Program {
         count: u32,
     ) {
         Self::set_viewport(&self.context, viewport);
-        Self::set_states(&self.context, render_states);
+        self.set_states(render_states);
         self.set_used();
         element_buffer.bind();
         self.context.draw_elements_instanced(
@@ -395,31 +744,44 @@ impl Program {
             count,
         );
         self.context.unbind_buffer(consts::ELEMENT_ARRAY_BUFFER);
-        for location in self.vertex_attributes.values() {
+        for location in self.vertex_attributes.borrow().values() {
             self.context.disable_vertex_attrib_array(*location);
         }
         self.context.unuse_program();
     }
 
+    ///
+    /// Returns whether the vertex shader of the linked program has an active attribute with the given name,
+    /// ie. an attribute which is used and therefore must be set with one of the use_attribute functions.
+    ///
+    pub(crate) fn has_attribute(&self, name: &str) -> Result<bool, Error> {
+        self.finish_link()?;
+        Ok(self.vertex_attributes.borrow().contains_key(name))
+    }
+
     fn location(&self, name: &str) -> Result<u32, Error> {
+        self.finish_link()?;
         self.set_used();
-        let location =
-            self.vertex_attributes
-                .get(name)
-                .ok_or_else(|| Error::FailedToFindAttribute {
-                    message: format!(
-                        "The attribute {} is sent to the shader but never used.",
-                        name
-                    ),
-                })?;
-        Ok(*location)
+        let location = self
+            .vertex_attributes
+            .borrow()
+            .get(name)
+            .cloned()
+            .ok_or_else(|| Error::FailedToFindAttribute {
+                message: format!(
+                    "The attribute {} is sent to the shader but never used.",
+                    name
+                ),
+            })?;
+        Ok(location)
     }
 
     fn set_used(&self) {
         self.context.use_program(&self.id);
     }
 
-    fn set_states(context: &Context, render_states: RenderStates) {
+    fn set_states(&self, render_states: RenderStates) {
+        let context = &self.context;
         Self::set_cull(context, render_states.cull);
         Self::set_write_mask(context, render_states.write_mask);
         Self::set_depth(
@@ -428,73 +790,225 @@ impl Program {
             render_states.write_mask.depth,
         );
         Self::set_blend(context, render_states.blend);
+        Self::set_stencil(context, render_states.stencil);
+        Self::set_sample_shading(context, render_states.sample_shading);
+        Self::set_alpha_to_coverage(context, render_states.alpha_to_coverage);
+        Self::set_primitive_restart(context, render_states.primitive_restart);
+        if self.uses_clip_distances {
+            Self::set_clip_distances(context, &render_states.clip_planes);
+        } else {
+            Self::set_clip_distances(context, &[None; MAX_CLIP_PLANES]);
+        }
     }
 
     fn set_viewport(context: &Context, viewport: Viewport) {
-        unsafe {
-            static mut CURRENT_VIEWPORT: Viewport = Viewport {
-                x: 0,
-                y: 0,
-                width: 0,
-                height: 0,
-            };
-            if viewport != CURRENT_VIEWPORT {
-                context.viewport(viewport.x, viewport.y, viewport.width, viewport.height);
-                CURRENT_VIEWPORT = viewport;
+        let mut cache = context.state_cache();
+        let value = (viewport.x, viewport.y, viewport.width, viewport.height);
+        if cache.viewport != Some(value) {
+            context.viewport(viewport.x, viewport.y, viewport.width, viewport.height);
+            cache.viewport = Some(value);
+        }
+    }
+
+    fn set_capability(context: &Context, current: &mut Option<bool>, cap: u32, enabled: bool) {
+        if *current != Some(enabled) {
+            if enabled {
+                context.enable(cap);
+            } else {
+                context.disable(cap);
+            }
+            *current = Some(enabled);
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn set_clip_distances(context: &Context, clip_planes: &[Option<Vec4>; MAX_CLIP_PLANES]) {
+        let mut cache = context.state_cache();
+        for (i, plane) in clip_planes.iter().enumerate() {
+            Self::set_capability(
+                context,
+                &mut cache.clip_distance_enabled[i],
+                consts::CLIP_DISTANCE0 + i as u32,
+                plane.is_some(),
+            );
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn set_clip_distances(_context: &Context, _clip_planes: &[Option<Vec4>; MAX_CLIP_PLANES]) {}
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn set_sample_shading(context: &Context, sample_shading: Option<f32>) {
+        if !context.capabilities().sample_shading {
+            return;
+        }
+        let mut cache = context.state_cache();
+        // Sample shading is disabled by default, so it is only disabled if it has been enabled
+        if sample_shading.is_some() || cache.sample_shading_enabled == Some(true) {
+            Self::set_capability(
+                context,
+                &mut cache.sample_shading_enabled,
+                consts::SAMPLE_SHADING,
+                sample_shading.is_some(),
+            );
+        }
+        if let Some(value) = sample_shading {
+            let value = value.clamp(0.0, 1.0);
+            if cache.min_sample_shading != Some(value) {
+                context.min_sample_shading(value);
+                cache.min_sample_shading = Some(value);
             }
         }
     }
 
+    #[cfg(target_arch = "wasm32")]
+    fn set_sample_shading(_context: &Context, _sample_shading: Option<f32>) {}
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn set_primitive_restart(context: &Context, primitive_restart: Option<u32>) {
+        // The indices are always 32 bit, so the fixed restart index on web, which needs OpenGL 4.3 on desktop,
+        // is the same as a restart index of u32::MAX which only needs OpenGL 3.1
+        let mut cache = context.state_cache();
+        Self::set_capability(
+            context,
+            &mut cache.primitive_restart_enabled,
+            consts::PRIMITIVE_RESTART,
+            primitive_restart.is_some(),
+        );
+        if let Some(index) = primitive_restart {
+            if cache.primitive_restart_index != Some(index) {
+                context.primitive_restart_index(index);
+                cache.primitive_restart_index = Some(index);
+            }
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn set_primitive_restart(_context: &Context, _primitive_restart: Option<u32>) {}
+
+    fn set_alpha_to_coverage(context: &Context, enabled: bool) {
+        let mut cache = context.state_cache();
+        Self::set_capability(
+            context,
+            &mut cache.alpha_to_coverage_enabled,
+            consts::SAMPLE_ALPHA_TO_COVERAGE,
+            enabled,
+        );
+    }
+
     fn set_cull(context: &Context, cull: CullType) {
-        unsafe {
-            static mut CURRENT_CULL: CullType = CullType::None;
-            if cull != CURRENT_CULL {
-                match cull {
-                    CullType::None => {
-                        context.disable(consts::CULL_FACE);
-                    }
-                    CullType::Back => {
-                        context.enable(consts::CULL_FACE);
-                        context.cull_face(consts::BACK);
-                    }
-                    CullType::Front => {
-                        context.enable(consts::CULL_FACE);
-                        context.cull_face(consts::FRONT);
-                    }
-                    CullType::FrontAndBack => {
-                        context.enable(consts::CULL_FACE);
-                        context.cull_face(consts::FRONT_AND_BACK);
-                    }
-                }
-                CURRENT_CULL = cull;
+        let mut cache = context.state_cache();
+        let mode = match cull {
+            CullType::None => None,
+            CullType::Back => Some(consts::BACK),
+            CullType::Front => Some(consts::FRONT),
+            CullType::FrontAndBack => Some(consts::FRONT_AND_BACK),
+        };
+        Self::set_capability(
+            context,
+            &mut cache.cull_face_enabled,
+            consts::CULL_FACE,
+            mode.is_some(),
+        );
+        if let Some(mode) = mode {
+            if cache.cull_face != Some(mode) {
+                context.cull_face(mode);
+                cache.cull_face = Some(mode);
+            }
+        }
+    }
+
+    fn set_stencil(context: &Context, stencil: Option<StencilParameters>) {
+        let mut cache = context.state_cache();
+        Self::set_capability(
+            context,
+            &mut cache.stencil_test_enabled,
+            consts::STENCIL_TEST,
+            stencil.is_some(),
+        );
+        if let Some(stencil) = stencil {
+            let func = (
+                match stencil.test {
+                    StencilTestType::Never => consts::NEVER,
+                    StencilTestType::Less => consts::LESS,
+                    StencilTestType::Equal => consts::EQUAL,
+                    StencilTestType::LessOrEqual => consts::LEQUAL,
+                    StencilTestType::Greater => consts::GREATER,
+                    StencilTestType::NotEqual => consts::NOTEQUAL,
+                    StencilTestType::GreaterOrEqual => consts::GEQUAL,
+                    StencilTestType::Always => consts::ALWAYS,
+                },
+                stencil.reference as i32,
+                stencil.read_mask as u32,
+            );
+            if cache.stencil_func != Some(func) {
+                context.stencil_func(func.0, func.1, func.2);
+                cache.stencil_func = Some(func);
             }
+            let op = (
+                Self::stencil_const_from_operation(stencil.fail),
+                Self::stencil_const_from_operation(stencil.depth_fail),
+                Self::stencil_const_from_operation(stencil.pass),
+            );
+            if cache.stencil_op != Some(op) {
+                context.stencil_op(op.0, op.1, op.2);
+                cache.stencil_op = Some(op);
+            }
+        }
+        drop(cache);
+        Self::set_stencil_mask(
+            context,
+            stencil.map(|s| s.write_mask as u32).unwrap_or(0xFF),
+        );
+    }
+
+    pub(crate) fn set_stencil_mask(context: &Context, mask: u32) {
+        let mut cache = context.state_cache();
+        if cache.stencil_mask != Some(mask) {
+            context.stencil_mask(mask);
+            cache.stencil_mask = Some(mask);
+        }
+    }
+
+    fn stencil_const_from_operation(operation: StencilOperation) -> u32 {
+        match operation {
+            StencilOperation::Keep => consts::KEEP,
+            StencilOperation::Zero => consts::ZERO,
+            StencilOperation::Replace => consts::REPLACE,
+            StencilOperation::Increment => consts::INCR,
+            StencilOperation::IncrementWrap => consts::INCR_WRAP,
+            StencilOperation::Decrement => consts::DECR,
+            StencilOperation::DecrementWrap => consts::DECR_WRAP,
+            StencilOperation::Invert => consts::INVERT,
         }
     }
 
     fn set_blend(context: &Context, blend: Option<BlendParameters>) {
-        unsafe {
-            static mut CURRENT: Option<BlendParameters> = None;
-            if blend != CURRENT {
-                if let Some(blend_parameters) = blend {
-                    context.enable(consts::BLEND);
-                    context.blend_func_separate(
-                        Self::blend_const_from_multiplier(blend_parameters.source_rgb_multiplier),
-                        Self::blend_const_from_multiplier(
-                            blend_parameters.destination_rgb_multiplier,
-                        ),
-                        Self::blend_const_from_multiplier(blend_parameters.source_alpha_multiplier),
-                        Self::blend_const_from_multiplier(
-                            blend_parameters.destination_alpha_multiplier,
-                        ),
-                    );
-                    context.blend_equation_separate(
-                        Self::blend_const_from_equation(blend_parameters.rgb_equation),
-                        Self::blend_const_from_equation(blend_parameters.alpha_equation),
-                    );
-                } else {
-                    context.disable(consts::BLEND);
-                }
-                CURRENT = blend;
+        let mut cache = context.state_cache();
+        Self::set_capability(
+            context,
+            &mut cache.blend_enabled,
+            consts::BLEND,
+            blend.is_some(),
+        );
+        if let Some(blend_parameters) = blend {
+            let func = (
+                Self::blend_const_from_multiplier(blend_parameters.source_rgb_multiplier),
+                Self::blend_const_from_multiplier(blend_parameters.destination_rgb_multiplier),
+                Self::blend_const_from_multiplier(blend_parameters.source_alpha_multiplier),
+                Self::blend_const_from_multiplier(blend_parameters.destination_alpha_multiplier),
+            );
+            if cache.blend_func != Some(func) {
+                context.blend_func_separate(func.0, func.1, func.2, func.3);
+                cache.blend_func = Some(func);
+            }
+            let equation = (
+                Self::blend_const_from_equation(blend_parameters.rgb_equation),
+                Self::blend_const_from_equation(blend_parameters.alpha_equation),
+            );
+            if cache.blend_equation != Some(equation) {
+                context.blend_equation_separate(equation.0, equation.1);
+                cache.blend_equation = Some(equation);
             }
         }
     }
@@ -526,80 +1040,191 @@ impl Program {
     }
 
     pub(crate) fn set_write_mask(context: &Context, write_mask: WriteMask) {
-        unsafe {
-            static mut CURRENT_COLOR_MASK: WriteMask = WriteMask::COLOR_AND_DEPTH;
-            if write_mask != CURRENT_COLOR_MASK {
+        {
+            let mut cache = context.state_cache();
+            let color_mask = (
+                write_mask.red,
+                write_mask.green,
+                write_mask.blue,
+                write_mask.alpha,
+            );
+            if cache.color_mask != Some(color_mask) {
                 context.color_mask(
                     write_mask.red,
                     write_mask.green,
                     write_mask.blue,
                     write_mask.alpha,
                 );
-                Self::set_depth(context, None, write_mask.depth);
-                CURRENT_COLOR_MASK = write_mask;
+                cache.color_mask = Some(color_mask);
             }
         }
+        Self::set_depth(context, None, write_mask.depth);
     }
 
     fn set_depth(context: &Context, depth_test: Option<DepthTestType>, depth_mask: bool) {
-        unsafe {
-            static mut CURRENT_DEPTH_ENABLE: bool = false;
-            static mut CURRENT_DEPTH_MASK: bool = true;
-            static mut CURRENT_DEPTH_TEST: DepthTestType = DepthTestType::Less;
-
-            if depth_mask == false && depth_test == Some(DepthTestType::Always) {
-                if CURRENT_DEPTH_ENABLE {
-                    context.disable(consts::DEPTH_TEST);
-                    CURRENT_DEPTH_ENABLE = false;
-                    return;
-                }
-            } else {
-                if !CURRENT_DEPTH_ENABLE {
-                    context.enable(consts::DEPTH_TEST);
-                    CURRENT_DEPTH_ENABLE = true;
-                }
-            }
-
-            if depth_mask != CURRENT_DEPTH_MASK {
-                context.depth_mask(depth_mask);
-                CURRENT_DEPTH_MASK = depth_mask;
+        let mut cache = context.state_cache();
+        if let Some(depth_test) = depth_test {
+            // The depth test is disabled when it has no effect, which also disables writing to the depth buffer.
+            Self::set_capability(
+                context,
+                &mut cache.depth_test_enabled,
+                consts::DEPTH_TEST,
+                depth_mask || depth_test != DepthTestType::Always,
+            );
+            let func = match depth_test {
+                DepthTestType::Never => consts::NEVER,
+                DepthTestType::Less => consts::LESS,
+                DepthTestType::Equal => consts::EQUAL,
+                DepthTestType::LessOrEqual => consts::LEQUAL,
+                DepthTestType::Greater => consts::GREATER,
+                DepthTestType::NotEqual => consts::NOTEQUAL,
+                DepthTestType::GreaterOrEqual => consts::GEQUAL,
+                DepthTestType::Always => consts::ALWAYS,
+            };
+            if cache.depth_func != Some(func) {
+                context.depth_func(func);
+                cache.depth_func = Some(func);
             }
+        }
 
-            if depth_test.is_some() && depth_test.unwrap() != CURRENT_DEPTH_TEST {
-                match depth_test.unwrap() {
-                    DepthTestType::Never => {
-                        context.depth_func(consts::NEVER);
-                    }
-                    DepthTestType::Less => {
-                        context.depth_func(consts::LESS);
-                    }
-                    DepthTestType::Equal => {
-                        context.depth_func(consts::EQUAL);
-                    }
-                    DepthTestType::LessOrEqual => {
-                        context.depth_func(consts::LEQUAL);
-                    }
-                    DepthTestType::Greater => {
-                        context.depth_func(consts::GREATER);
-                    }
-                    DepthTestType::NotEqual => {
-                        context.depth_func(consts::NOTEQUAL);
-                    }
-                    DepthTestType::GreaterOrEqual => {
-                        context.depth_func(consts::GEQUAL);
-                    }
-                    DepthTestType::Always => {
-                        context.depth_func(consts::ALWAYS);
-                    }
-                }
-                CURRENT_DEPTH_TEST = depth_test.unwrap();
-            }
+        if cache.depth_mask != Some(depth_mask) {
+            context.depth_mask(depth_mask);
+            cache.depth_mask = Some(depth_mask);
         }
     }
 }
 
 impl Drop for Program {
     fn drop(&mut self) {
+        if let Some((vert_shader, frag_shader, geom_shader)) =
+            self.pending_shaders.borrow_mut().take()
+        {
+            self.context.delete_shader(Some(&vert_shader));
+            self.context.delete_shader(Some(&frag_shader));
+            if let Some(geom_shader) = geom_shader {
+                self.context.delete_shader(Some(&geom_shader));
+            }
+        }
         self.context.delete_program(&self.id);
     }
 }
+
+#[cfg(not(target_arch = "wasm32"))]
+fn create_geometry_shader(
+    context: &Context,
+    geometry_shader_source: &str,
+) -> Result<crate::context::Shader, Error> {
+    let geom_shader =
+        context
+            .create_shader(consts::GEOMETRY_SHADER)
+            .ok_or(Error::FailedToCreateShader {
+                shader_type: "Geometry shader".to_string(),
+                message: "Unable to create shader object".to_string(),
+            })?;
+    context.compile_shader(geometry_shader_source, &geom_shader);
+    Ok(geom_shader)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn create_geometry_shader(
+    _context: &Context,
+    _geometry_shader_source: &str,
+) -> Result<crate::context::Shader, Error> {
+    Err(Error::FailedToCreateShader {
+        shader_type: "Geometry shader".to_string(),
+        message: "Geometry shaders are not supported on web".to_string(),
+    })
+}
+
+#[cfg(all(test, feature = "headless", not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::test_util::*;
+
+    #[test]
+    fn skip_redundant_state_changes() {
+        let test_context = TestContext::new();
+        let context = test_context.gl();
+        let camera = orthographic_camera(&context);
+        let viewport = Viewport::new_at_origo(8, 8);
+        let mesh = crate::Mesh::new(&context, &triangle_soup(-1.0, 1.0, 0.0)).unwrap();
+        let render = |render_states: RenderStates| {
+            mesh.render_with_color(
+                &vec4(0.0, 1.0, 0.0, 1.0),
+                render_states,
+                viewport,
+                &Mat4::identity(),
+                &camera,
+            )
+        };
+        render_to_cpu(&context, 8, 8, &ClearState::default(), || {
+            context.reset_render_stats();
+            render(RenderStates::default())?;
+            assert!(context.render_stats().state_changes > 0);
+
+            // Nothing is changed when rendering with the same render states and viewport again
+            context.reset_render_stats();
+            render(RenderStates::default())?;
+            assert_eq!(context.render_stats().draw_calls, 1);
+            assert_eq!(context.render_stats().state_changes, 0);
+
+            // Only the cull state is changed, ie. culling is enabled and the culled face is set
+            context.reset_render_stats();
+            render(RenderStates {
+                cull: CullType::Back,
+                ..Default::default()
+            })?;
+            assert_eq!(context.render_stats().state_changes, 2);
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn use_texture_at_reserved_unit() {
+        let test_context = TestContext::new();
+        let context = test_context.gl();
+        let program = Program::from_source(
+            &context,
+            "in vec3 position;
+            void main() { gl_Position = vec4(position, 1.0); }",
+            "uniform sampler2D a;
+            uniform sampler2D b;
+            uniform sampler2D c;
+            layout (location = 0) out vec4 color;
+            void main() { color = texture(a, vec2(0.5)) + texture(b, vec2(0.5)) + texture(c, vec2(0.5)); }",
+        )
+        .unwrap();
+        let texture = Texture2D::new_with_u8(&context, &crate::CPUTexture::default()).unwrap();
+
+        program.use_texture(&texture, "a").unwrap();
+        assert!(program.use_texture_at(&texture, "b", 0).is_err());
+        program.use_texture_at(&texture, "b", 1).unwrap();
+        program.use_texture_at(&texture, "b", 1).unwrap();
+
+        // The units used by a and b are skipped
+        program.use_texture(&texture, "c").unwrap();
+        assert_eq!(program.textures.borrow().get("a"), Some(&0));
+        assert_eq!(program.textures.borrow().get("b"), Some(&1));
+        assert_eq!(program.textures.borrow().get("c"), Some(&2));
+    }
+
+    #[test]
+    fn failed_link_is_reported_every_time() {
+        let test_context = TestContext::new();
+        let context = test_context.gl();
+        let program = Program::from_source_parallel(
+            &context,
+            "in vec3 position;
+            void main() { gl_Position = vec4(position, 1.0); }",
+            "layout (location = 0) out vec4 color;
+            void main() { color = undefined_variable; }",
+        )
+        .unwrap();
+
+        // Using the program waits for the link to finish
+        assert!(program.use_uniform_float("value", &1.0).is_err());
+        assert!(program.is_ready().is_err());
+        assert!(program.is_ready().is_err());
+    }
+}
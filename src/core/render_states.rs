@@ -1,3 +1,10 @@
+use crate::math::*;
+
+///
+/// The maximum number of [clip planes](RenderStates::clip_planes) in a render call.
+///
+pub const MAX_CLIP_PLANES: usize = 4;
+
 ///
 /// A set of render specific states that has to be specified at each render call.
 ///
@@ -27,6 +34,48 @@ pub struct RenderStates {
     /// This is usually used to simulate transparency.
     ///
     pub blend: Option<BlendParameters>,
+
+    ///
+    /// Defines the stencil test and how the stencil buffer is updated in a render call, `None` disables the stencil test.
+    /// Requires that the render target has a stencil buffer, for example a [DepthTargetTexture2D](crate::DepthTargetTexture2D)
+    /// with the [Depth24Stencil8](crate::DepthFormat::Depth24Stencil8) format. See also [StencilMask].
+    ///
+    pub stencil: Option<StencilParameters>,
+
+    ///
+    /// Defines up to [MAX_CLIP_PLANES](MAX_CLIP_PLANES) planes in world space which clips the geometry in a render call.
+    /// A plane is given as `(a, b, c, d)` and a fragment at the world position `(x, y, z)` is discarded if `a*x + b*y + c*z + d < 0`.
+    /// Only applies to shader programs where the vertex shader writes to `gl_ClipDistance`,
+    /// for example [Mesh](crate::Mesh) and [InstancedMesh](crate::InstancedMesh) rendering.
+    /// Clip planes are not supported on web.
+    ///
+    pub clip_planes: [Option<Vec4>; MAX_CLIP_PLANES],
+
+    ///
+    /// Enables sample shading when rendering to a multisample render target, `None` disables it.
+    /// The value is the minimum fraction of samples, between 0 and 1, which are shaded individually,
+    /// so that alpha-tested geometry, for example [cutout materials](crate::PhongMaterial::alpha_cutoff), also gets antialiased edges.
+    /// Sample shading requires OpenGL 4.0 or the `GL_ARB_sample_shading` extension (see [Capabilities::sample_shading](crate::Capabilities::sample_shading))
+    /// and is ignored otherwise, for example on web.
+    ///
+    pub sample_shading: Option<f32>,
+
+    ///
+    /// Whether or not to use the alpha value of the output color to decide how many of the samples are covered
+    /// when rendering to a multisample render target. Gives smooth edges on cutout geometry, for example foliage,
+    /// without sorting and [blending](RenderStates::blend).
+    /// Requires a multisample render target, for example the screen of a window created with
+    /// [multisamples](crate::WindowSettings::multisamples) larger than 0, otherwise it has no visible effect.
+    ///
+    pub alpha_to_coverage: bool,
+
+    ///
+    /// The index which restarts the primitive, for example a triangle strip, when drawing with an [element buffer](crate::ElementBuffer),
+    /// `None` disables primitive restart. The indices in an element buffer are 32 bit, so the fixed restart index is `u32::MAX`
+    /// (it would be `u16::MAX` for 16 bit indices) which is the only supported restart index on web.
+    /// Note that primitive restart is always enabled on web.
+    ///
+    pub primitive_restart: Option<u32>,
 }
 
 impl Default for RenderStates {
@@ -36,6 +85,11 @@ impl Default for RenderStates {
             depth_test: DepthTestType::Less,
             cull: CullType::None,
             blend: None,
+            stencil: None,
+            clip_planes: [None; MAX_CLIP_PLANES],
+            sample_shading: None,
+            alpha_to_coverage: false,
+            primitive_restart: None,
         }
     }
 }
@@ -68,8 +122,137 @@ pub enum DepthTestType {
     Always,
 }
 
+///
+/// Defines the stencil test and how the stencil buffer is updated in a render call.
+/// The stencil test compares the reference value with the value in the stencil buffer,
+/// both masked with the read mask, and discards the fragment if the test fails.
+///
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct StencilParameters {
+    pub test: StencilTestType,
+    pub reference: u8,
+    pub read_mask: u8,
+    pub write_mask: u8,
+    ///
+    /// The operation applied to the stencil buffer when the stencil test fails.
+    ///
+    pub fail: StencilOperation,
+    ///
+    /// The operation applied to the stencil buffer when the stencil test passes, but the depth test fails.
+    ///
+    pub depth_fail: StencilOperation,
+    ///
+    /// The operation applied to the stencil buffer when both the stencil and depth test passes.
+    ///
+    pub pass: StencilOperation,
+}
+
+impl Default for StencilParameters {
+    fn default() -> Self {
+        Self {
+            test: StencilTestType::Always,
+            reference: 0,
+            read_mask: 0xFF,
+            write_mask: 0xFF,
+            fail: StencilOperation::Keep,
+            depth_fail: StencilOperation::Keep,
+            pass: StencilOperation::Keep,
+        }
+    }
+}
+
+///
+/// Defines how the reference value is compared to the value in the stencil buffer in the [stencil test](crate::StencilParameters),
+/// for example [Less](StencilTestType::Less) passes if the reference value is less than the value in the stencil buffer.
+///
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum StencilTestType {
+    Never,
+    Less,
+    Equal,
+    LessOrEqual,
+    Greater,
+    NotEqual,
+    GreaterOrEqual,
+    Always,
+}
+
+///
+/// Defines how the value in the stencil buffer is updated in the [stencil test](crate::StencilParameters).
+///
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum StencilOperation {
+    Keep,
+    Zero,
+    Replace,
+    Increment,
+    IncrementWrap,
+    Decrement,
+    DecrementWrap,
+    Invert,
+}
+
+///
+/// Helper for masking out a part of the render target using the stencil buffer,
+/// for example for planar reflections (see [Camera::reflected_across](crate::Camera::reflected_across)) or portals.
+/// First render the shape of the mask, for example the mirror quad, with the render states returned by [begin](Self::begin),
+/// then render the content with the render states returned by [end](Self::end), which only affects the pixels inside the mask.
+/// Remember to clear the stencil buffer (see [ClearState::stencil](crate::ClearState::stencil)) before rendering the mask.
+///
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct StencilMask {
+    ///
+    /// The value written to the stencil buffer inside the mask. Use different values to use more than one mask at a time.
+    ///
+    pub reference: u8,
+}
+
+impl StencilMask {
+    pub fn new(reference: u8) -> Self {
+        Self { reference }
+    }
+
+    ///
+    /// Returns the render states for rendering the shape of the mask.
+    /// The shape is only written to the stencil buffer, not to the color and depth buffers,
+    /// and it is depth tested so that the parts of the shape which are hidden are not part of the mask.
+    ///
+    pub fn begin(&self) -> RenderStates {
+        RenderStates {
+            write_mask: WriteMask::NONE,
+            depth_test: DepthTestType::LessOrEqual,
+            stencil: Some(StencilParameters {
+                reference: self.reference,
+                pass: StencilOperation::Replace,
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    ///
+    /// Returns the given render states extended with a stencil test which only passes inside the mask.
+    /// The stencil buffer is not changed when rendering with the returned render states.
+    ///
+    pub fn end(&self, render_states: RenderStates) -> RenderStates {
+        RenderStates {
+            stencil: Some(StencilParameters {
+                test: StencilTestType::Equal,
+                reference: self.reference,
+                write_mask: 0,
+                ..Default::default()
+            }),
+            ..render_states
+        }
+    }
+}
+
 ///
 /// Defines which channels (red, green, blue, alpha and depth) to write to in a render call.
+/// Besides the predefined masks, any combination of channels can be specified through the [write_mask](RenderStates::write_mask)
+/// of the render states of any render call, for example
+/// `WriteMask { red: true, alpha: true, ..WriteMask::NONE }` to only write to the red and alpha channels,
+/// which can be used to pack different data into the channels of the same render target in several passes.
 ///
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub struct WriteMask {
@@ -161,6 +344,20 @@ impl BlendParameters {
         alpha_equation: BlendEquationType::Add,
     };
 
+    ///
+    /// Transparency blending for colors with premultiplied alpha, ie. where the color channels are already multiplied by the alpha value,
+    /// for example textures uploaded with [premultiply_alpha](crate::CPUTexture::premultiply_alpha) or authored with premultiplied alpha.
+    /// Avoids the dark fringes which straight alpha [transparency](Self::TRANSPARENCY) blending produces at the edges of such textures.
+    ///
+    pub const PREMULTIPLIED_ALPHA: Self = Self {
+        source_rgb_multiplier: BlendMultiplierType::One,
+        source_alpha_multiplier: BlendMultiplierType::One,
+        destination_rgb_multiplier: BlendMultiplierType::OneMinusSrcAlpha,
+        destination_alpha_multiplier: BlendMultiplierType::OneMinusSrcAlpha,
+        rgb_equation: BlendEquationType::Add,
+        alpha_equation: BlendEquationType::Add,
+    };
+
     ///
     /// Adds the color of the render target with the output color of the render call.
     ///
@@ -172,6 +369,30 @@ impl BlendParameters {
         rgb_equation: BlendEquationType::Add,
         alpha_equation: BlendEquationType::Add,
     };
+
+    ///
+    /// Keeps the maximum of the color of the render target and the output color of the render call, for example to build a max-depth buffer.
+    ///
+    pub const MAX: Self = Self {
+        source_rgb_multiplier: BlendMultiplierType::One,
+        source_alpha_multiplier: BlendMultiplierType::One,
+        destination_rgb_multiplier: BlendMultiplierType::One,
+        destination_alpha_multiplier: BlendMultiplierType::One,
+        rgb_equation: BlendEquationType::Max,
+        alpha_equation: BlendEquationType::Max,
+    };
+
+    ///
+    /// Keeps the minimum of the color of the render target and the output color of the render call.
+    ///
+    pub const MIN: Self = Self {
+        source_rgb_multiplier: BlendMultiplierType::One,
+        source_alpha_multiplier: BlendMultiplierType::One,
+        destination_rgb_multiplier: BlendMultiplierType::One,
+        destination_alpha_multiplier: BlendMultiplierType::One,
+        rgb_equation: BlendEquationType::Min,
+        alpha_equation: BlendEquationType::Min,
+    };
 }
 
 impl Default for BlendParameters {
@@ -200,6 +421,7 @@ pub enum BlendMultiplierType {
 
 ///
 /// How the source and target color or alpha value are combined in [blend parameters](crate::BlendParameters).
+/// Note that the multipliers are ignored when using [Max](BlendEquationType::Max) or [Min](BlendEquationType::Min).
 ///
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum BlendEquationType {
@@ -1,12 +1,15 @@
 use crate::context::{consts, Context};
 use crate::core::*;
+use crate::cpu_texture::*;
 use crate::math::*;
 use crate::ImageEffect;
 
 ///
-/// Defines which channels (red, green, blue, alpha and depth) to clear when starting to write to a
+/// Defines which channels (red, green, blue, alpha, depth and stencil) to clear when starting to write to a
 /// [render target](crate::RenderTarget) or the [screen](crate::Screen) and which values they are set to
-/// (the values must be between 0 and 1).
+/// (the color and depth values must be between 0 and 1).
+/// When clearing a color texture with an integer format, for example [R32UI](crate::Format::R32UI),
+/// the color values are instead converted to unsigned integers, ie. use `u32::MAX as f32` to clear to the maximum value.
 ///
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub struct ClearState {
@@ -15,11 +18,13 @@ pub struct ClearState {
     pub blue: Option<f32>,
     pub alpha: Option<f32>,
     pub depth: Option<f32>,
+    pub stencil: Option<u8>,
 }
 
 impl ClearState {
     ///
-    /// Nothing will be cleared.
+    /// Nothing will be cleared, so the previous contents of the target are kept intact
+    /// and the write mask is left untouched. Use this for passes that accumulate on top of earlier results.
     ///
     pub const fn none() -> Self {
         Self {
@@ -28,6 +33,7 @@ impl ClearState {
             blue: None,
             alpha: None,
             depth: None,
+            stencil: None,
         }
     }
 
@@ -41,6 +47,7 @@ impl ClearState {
             blue: None,
             alpha: None,
             depth: Some(depth),
+            stencil: None,
         }
     }
 
@@ -54,6 +61,7 @@ impl ClearState {
             blue: Some(blue),
             alpha: Some(alpha),
             depth: None,
+            stencil: None,
         }
     }
 
@@ -67,6 +75,7 @@ impl ClearState {
             blue: Some(blue),
             alpha: Some(alpha),
             depth: Some(depth),
+            stencil: None,
         }
     }
 }
@@ -86,24 +95,46 @@ impl Screen {
     ///
     /// Call this function and make a render call (for example on some [object](crate::object))
     /// in the **render** closure to render something to the screen.
+    /// The colors are written as they are, unless the conversion to sRGB color space is enabled
+    /// (see [set_framebuffer_srgb](crate::context::Glstruct::set_framebuffer_srgb)).
     ///
     pub fn write<F: FnOnce() -> Result<(), Error>>(
         context: &Context,
         clear_state: &ClearState,
         render: F,
     ) -> Result<(), Error> {
+        check_context(context)?;
         context.bind_framebuffer(consts::DRAW_FRAMEBUFFER, None);
+        let mut state_cache = context.state_cache();
+        state_cache.draw_framebuffer_size = state_cache.screen_size;
+        drop(state_cache);
         clear(context, clear_state);
         render()?;
         Ok(())
     }
 
     ///
-    /// Returns the RGB color values from the screen as a list of bytes (one byte for each color channel).
+    /// Same as [write](Screen::write) with [ClearState::none], ie. renders on top of the current contents of the screen.
+    ///
+    pub fn write_no_clear<F: FnOnce() -> Result<(), Error>>(
+        context: &Context,
+        render: F,
+    ) -> Result<(), Error> {
+        Self::write(context, &ClearState::none(), render)
+    }
+
+    ///
+    /// Returns the RGB color values inside the given viewport of the screen as a list of bytes (one byte for each color channel).
+    /// The first row is the bottom row of the viewport, unless **flip_y** is true in which case the first row is the top row,
+    /// which is the usual order of rows in an image.
     /// Only available on desktop.
     ///
     #[cfg(not(target_arch = "wasm32"))]
-    pub fn read_color(context: &Context, viewport: Viewport) -> Result<Vec<u8>, Error> {
+    pub fn read_color(
+        context: &Context,
+        viewport: Viewport,
+        flip_y: bool,
+    ) -> Result<Vec<u8>, Error> {
         // TODO: Possible to change format
         let mut pixels = vec![0u8; viewport.width * viewport.height * 3];
         context.bind_framebuffer(consts::READ_FRAMEBUFFER, None);
@@ -116,11 +147,14 @@ impl Screen {
             consts::UNSIGNED_BYTE,
             &mut pixels,
         );
+        if flip_y {
+            pixels = crate::cpu_texture::flip_rows(&pixels, viewport.height, 1);
+        }
         Ok(pixels)
     }
 
     ///
-    /// Returns the depth values from the screen as a list of 32-bit floats.
+    /// Returns the depth values inside the given viewport of the screen as a list of 32-bit floats.
     /// Only available on desktop.
     ///
     #[cfg(not(target_arch = "wasm32"))]
@@ -139,6 +173,85 @@ impl Screen {
         );
         Ok(pixels)
     }
+
+    ///
+    /// Returns the RGBA color value of the pixel at the given position on the screen, where (0, 0) is the bottom left corner.
+    /// Only the single pixel is read, so this is a lot faster than [read_color](Self::read_color) for example when picking.
+    /// Only available on desktop.
+    ///
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn read_color_at(context: &Context, x: usize, y: usize) -> Result<[u8; 4], Error> {
+        let mut pixel = [0u8; 4];
+        context.bind_framebuffer(consts::READ_FRAMEBUFFER, None);
+        context.read_pixels_with_u8_data(
+            x as u32,
+            y as u32,
+            1,
+            1,
+            consts::RGBA,
+            consts::UNSIGNED_BYTE,
+            &mut pixel,
+        );
+        Ok(pixel)
+    }
+
+    ///
+    /// Returns the depth value of the pixel at the given position on the screen, where (0, 0) is the bottom left corner.
+    /// Only the single pixel is read, so this is a lot faster than [read_depth](Self::read_depth) for example when picking.
+    /// Only available on desktop.
+    ///
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn read_depth_at(context: &Context, x: usize, y: usize) -> Result<f32, Error> {
+        let mut pixel = [0f32; 1];
+        context.bind_framebuffer(consts::READ_FRAMEBUFFER, None);
+        context.read_pixels_with_f32_data(
+            x as u32,
+            y as u32,
+            1,
+            1,
+            consts::DEPTH_COMPONENT,
+            consts::FLOAT,
+            &mut pixel,
+        );
+        Ok(pixel[0])
+    }
+}
+
+///
+/// Renders whatever rendered in the **render** closure into a new [color texture](crate::ColorTargetTexture2D)
+/// with the given size and returns it, for example for thumbnails, reflection probes or procedural textures.
+/// The color texture has the [RGBA8](crate::Format::RGBA8) format and is rendered together with a temporary
+/// [depth texture](crate::DepthTargetTexture2D), both are cleared based on the given clear state before rendering.
+/// Use a viewport of the same size as the texture, ie. `Viewport::new_at_origo(width, height)`, in the render calls.
+///
+pub fn render_to_texture<F: FnOnce() -> Result<(), Error>>(
+    context: &Context,
+    width: usize,
+    height: usize,
+    clear_state: &ClearState,
+    render: F,
+) -> Result<ColorTargetTexture2D, Error> {
+    let color_texture = ColorTargetTexture2D::new(
+        context,
+        width,
+        height,
+        Interpolation::Linear,
+        Interpolation::Linear,
+        None,
+        Wrapping::ClampToEdge,
+        Wrapping::ClampToEdge,
+        Format::RGBA8,
+    )?;
+    let depth_texture = DepthTargetTexture2D::new(
+        context,
+        width,
+        height,
+        Wrapping::ClampToEdge,
+        Wrapping::ClampToEdge,
+        DepthFormat::Depth32F,
+    )?;
+    RenderTarget::new(context, &color_texture, &depth_texture)?.write(clear_state, render)?;
+    Ok(color_texture)
 }
 
 ///
@@ -211,14 +324,24 @@ impl<'a, 'b> RenderTarget<'a, 'b> {
         render: F,
     ) -> Result<(), Error> {
         self.bind()?;
+        let integer_color_texture = self
+            .color_texture
+            .filter(|texture| is_integer_format(texture.format()));
+        let clear_color_texture = if integer_color_texture.is_some() {
+            clear_integer_color_buffer(&self.context, 0, clear_state);
+            None
+        } else {
+            self.color_texture
+        };
         clear(
             &self.context,
             &ClearState {
-                red: self.color_texture.and(clear_state.red),
-                green: self.color_texture.and(clear_state.green),
-                blue: self.color_texture.and(clear_state.blue),
-                alpha: self.color_texture.and(clear_state.alpha),
+                red: clear_color_texture.and(clear_state.red),
+                green: clear_color_texture.and(clear_state.green),
+                blue: clear_color_texture.and(clear_state.blue),
+                alpha: clear_color_texture.and(clear_state.alpha),
                 depth: self.depth_texture.and(clear_state.depth),
+                stencil: self.depth_texture.and(clear_state.stencil),
             },
         );
         render()?;
@@ -228,6 +351,13 @@ impl<'a, 'b> RenderTarget<'a, 'b> {
         Ok(())
     }
 
+    ///
+    /// Same as [write](RenderTarget::write) with [ClearState::none], ie. renders on top of the current contents of the textures.
+    ///
+    pub fn write_no_clear<F: FnOnce() -> Result<(), Error>>(&self, render: F) -> Result<(), Error> {
+        self.write(&ClearState::none(), render)
+    }
+
     ///
     /// Copies the content of the color and depth textures in this render target to the screen.
     ///
@@ -241,17 +371,19 @@ impl<'a, 'b> RenderTarget<'a, 'b> {
         }
         Screen::write(&self.context, &ClearState::none(), || {
             let effect = get_copy_effect(&self.context)?;
-            effect.use_texture(self.color_texture.unwrap(), "colorMap")?;
-            effect.use_texture(self.depth_texture.unwrap(), "depthMap")?;
-            effect.apply(
-                RenderStates {
-                    cull: CullType::Back,
-                    depth_test: DepthTestType::Always,
-                    ..Default::default()
-                },
-                viewport,
-            )?;
-            Ok(())
+            effect
+                .inputs(&[
+                    ("colorMap", self.color_texture.unwrap()),
+                    ("depthMap", self.depth_texture.unwrap()),
+                ])
+                .apply(
+                    RenderStates {
+                        cull: CullType::Back,
+                        depth_test: DepthTestType::Always,
+                        ..Default::default()
+                    },
+                    viewport,
+                )
         })?;
         Ok(())
     }
@@ -288,11 +420,17 @@ impl<'a, 'b> RenderTarget<'a, 'b> {
 
     ///
     /// Copies the content of the depth texture in this render target to the screen.
+    /// The depth test decides whether or not the copied depth replaces the depth already on the screen,
+    /// for example [Less](DepthTestType::Less) keeps the nearest depth and [Always](DepthTestType::Always) always overwrites it.
     ///
     /// # Errors
     /// Will return an error if this render target is not constructed with a depth texture.
     ///
-    pub fn copy_depth_to_screen(&self, viewport: Viewport) -> Result<(), Error> {
+    pub fn copy_depth_to_screen(
+        &self,
+        viewport: Viewport,
+        depth_test: DepthTestType,
+    ) -> Result<(), Error> {
         if self.depth_texture.is_none() {
             Err(Error::FailedToCopyFromRenderTarget {
                 message: "Cannot copy depth when the render target does not have a depth texture."
@@ -305,7 +443,7 @@ impl<'a, 'b> RenderTarget<'a, 'b> {
             effect.apply(
                 RenderStates {
                     cull: CullType::Back,
-                    depth_test: DepthTestType::Always,
+                    depth_test,
                     write_mask: WriteMask::DEPTH,
                     ..Default::default()
                 },
@@ -333,17 +471,19 @@ impl<'a, 'b> RenderTarget<'a, 'b> {
         }
         other.write(&ClearState::none(), || {
             let effect = get_copy_effect(&self.context)?;
-            effect.use_texture(self.color_texture.unwrap(), "colorMap")?;
-            effect.use_texture(self.depth_texture.unwrap(), "depthMap")?;
-            effect.apply(
-                RenderStates {
-                    cull: CullType::Back,
-                    depth_test: DepthTestType::Always,
-                    ..Default::default()
-                },
-                viewport,
-            )?;
-            Ok(())
+            effect
+                .inputs(&[
+                    ("colorMap", self.color_texture.unwrap()),
+                    ("depthMap", self.depth_texture.unwrap()),
+                ])
+                .apply(
+                    RenderStates {
+                        cull: CullType::Back,
+                        depth_test: DepthTestType::Always,
+                        ..Default::default()
+                    },
+                    viewport,
+                )
         })?;
         Ok(())
     }
@@ -379,13 +519,35 @@ impl<'a, 'b> RenderTarget<'a, 'b> {
         Ok(())
     }
 
+    ///
+    /// Copies the content of the color texture in this render target to the given color texture,
+    /// for example to capture the rendered scene so that it can be sampled when rendering [refraction](crate::Mesh::render_refraction).
+    ///
+    /// # Errors
+    /// Will return an error if this render target is not constructed with a color texture.
+    ///
+    pub fn copy_color_to_texture(
+        &self,
+        texture: &ColorTargetTexture2D,
+        viewport: Viewport,
+    ) -> Result<(), Error> {
+        self.copy_color(&RenderTarget::new_color(&self.context, texture)?, viewport)
+    }
+
     ///
     /// Copies the content of the depth texture in this render target to another render target.
+    /// The depth test decides whether or not the copied depth replaces the depth already in the other render target,
+    /// for example [Less](DepthTestType::Less) keeps the nearest depth when combining two depth buffers.
     ///
     /// # Errors
     /// Will return an error if this render target is not constructed with a depth texture.
     ///
-    pub fn copy_depth(&self, other: &Self, viewport: Viewport) -> Result<(), Error> {
+    pub fn copy_depth(
+        &self,
+        other: &Self,
+        viewport: Viewport,
+        depth_test: DepthTestType,
+    ) -> Result<(), Error> {
         if self.depth_texture.is_none() || other.depth_texture.is_none() {
             Err(Error::FailedToCopyFromRenderTarget {
                 message: "Cannot copy depth when the render target does not have a depth texture."
@@ -398,7 +560,7 @@ impl<'a, 'b> RenderTarget<'a, 'b> {
             effect.apply(
                 RenderStates {
                     cull: CullType::Back,
-                    depth_test: DepthTestType::Always,
+                    depth_test,
                     write_mask: WriteMask::DEPTH,
                     ..Default::default()
                 },
@@ -409,9 +571,156 @@ impl<'a, 'b> RenderTarget<'a, 'b> {
         Ok(())
     }
 
+    ///
+    /// Reads the content of the color texture in this render target inside the given viewport into a [CPUTexture](crate::CPUTexture)
+    /// with the [RGBA8](crate::Format::RGBA8) format and the same width and height as the viewport,
+    /// for example to save it to disk or to construct a [Texture2D](crate::Texture2D) from it.
+    /// The rows are ordered from bottom to top, which is the same order as expected when constructing a texture.
+    /// If the color texture has a floating point format, for example [RGBA16F](crate::Format::RGBA16F) for a baked HDR lightmap,
+    /// the values are clamped to the range between 0 and 1 and converted to 8 bits,
+    /// use [copy_color_to_cpu_f32](Self::copy_color_to_cpu_f32) to keep the full range and precision.
+    /// Only available on desktop.
+    ///
+    /// # Errors
+    /// Will return an error if this render target is not constructed with a color texture
+    /// or if the color texture has an integer format, in which case [read_color_u32](Self::read_color_u32) must be used instead.
+    ///
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn copy_color_to_cpu(&self, viewport: Viewport) -> Result<crate::CPUTexture<u8>, Error> {
+        self.check_non_integer_color()?;
+        self.bind()?;
+        self.context
+            .bind_framebuffer(consts::READ_FRAMEBUFFER, Some(&self.id));
+        let mut pixels = vec![0u8; viewport.width * viewport.height * 4];
+        self.context.read_pixels_with_u8_data(
+            viewport.x as u32,
+            viewport.y as u32,
+            viewport.width as u32,
+            viewport.height as u32,
+            consts::RGBA,
+            consts::UNSIGNED_BYTE,
+            &mut pixels,
+        );
+        self.context
+            .bind_framebuffer(consts::READ_FRAMEBUFFER, None);
+        Ok(crate::CPUTexture {
+            data: pixels,
+            width: viewport.width,
+            height: viewport.height,
+            format: crate::Format::RGBA8,
+            ..Default::default()
+        })
+    }
+
+    ///
+    /// Same as [copy_color_to_cpu](Self::copy_color_to_cpu), except that the content is read into a [CPUTexture](crate::CPUTexture)
+    /// with the [RGBA32F](crate::Format::RGBA32F) format, so the values of a color texture with a floating point format are neither clamped
+    /// nor converted to 8 bits. The result can be used to construct a texture using [Texture2D::new_with_f32](crate::Texture2D::new_with_f32).
+    /// Only available on desktop.
+    ///
+    /// # Errors
+    /// Will return an error if this render target is not constructed with a color texture
+    /// or if the color texture has an integer format, in which case [read_color_u32](Self::read_color_u32) must be used instead.
+    ///
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn copy_color_to_cpu_f32(
+        &self,
+        viewport: Viewport,
+    ) -> Result<crate::CPUTexture<f32>, Error> {
+        self.check_non_integer_color()?;
+        self.bind()?;
+        self.context
+            .bind_framebuffer(consts::READ_FRAMEBUFFER, Some(&self.id));
+        let mut pixels = vec![0f32; viewport.width * viewport.height * 4];
+        self.context.read_pixels_with_f32_data(
+            viewport.x as u32,
+            viewport.y as u32,
+            viewport.width as u32,
+            viewport.height as u32,
+            consts::RGBA,
+            consts::FLOAT,
+            &mut pixels,
+        );
+        self.context
+            .bind_framebuffer(consts::READ_FRAMEBUFFER, None);
+        Ok(crate::CPUTexture {
+            data: pixels,
+            width: viewport.width,
+            height: viewport.height,
+            format: crate::Format::RGBA32F,
+            ..Default::default()
+        })
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn check_non_integer_color(&self) -> Result<(), Error> {
+        match self.color_texture {
+            None => Err(Error::FailedToCopyFromRenderTarget {
+                message: "Cannot copy color when the render target does not have a color texture."
+                    .to_owned(),
+            }),
+            Some(texture) if is_integer_format(texture.format()) => {
+                Err(Error::FailedToCopyFromRenderTarget {
+                    message: format!(
+                        "Cannot copy color from a color texture with the integer format {:?}, use read_color_u32 instead.",
+                        texture.format()
+                    ),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    ///
+    /// Reads the content of the color texture in this render target inside the given viewport,
+    /// when the color texture has an integer format, for example [R32UI](crate::Format::R32UI) for object ids.
+    /// Returns one value for each channel of each pixel, ie. one value per pixel for [R32UI](crate::Format::R32UI)
+    /// and two values per pixel for [RG32UI](crate::Format::RG32UI), and the rows are ordered from bottom to top.
+    /// Only available on desktop.
+    ///
+    /// # Errors
+    /// Will return an error if this render target is not constructed with a color texture with an integer format.
+    ///
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn read_color_u32(&self, viewport: Viewport) -> Result<Vec<u32>, Error> {
+        let format = match self.color_texture {
+            Some(texture) if is_integer_format(texture.format()) => texture.format(),
+            _ => Err(Error::FailedToCopyFromRenderTarget {
+                message: "Cannot read integer color values when the render target does not have a color texture with an integer format."
+                    .to_owned(),
+            })?,
+        };
+        let (pixel_format, channels) = if format == crate::Format::RG32UI {
+            (consts::RG_INTEGER, 2)
+        } else {
+            (consts::RED_INTEGER, 1)
+        };
+        self.bind()?;
+        self.context
+            .bind_framebuffer(consts::READ_FRAMEBUFFER, Some(&self.id));
+        let mut pixels = vec![0u32; viewport.width * viewport.height * channels];
+        self.context.read_pixels_with_u32_data(
+            viewport.x as u32,
+            viewport.y as u32,
+            viewport.width as u32,
+            viewport.height as u32,
+            pixel_format,
+            consts::UNSIGNED_INT,
+            &mut pixels,
+        );
+        self.context
+            .bind_framebuffer(consts::READ_FRAMEBUFFER, None);
+        Ok(pixels)
+    }
+
     fn bind(&self) -> Result<(), Error> {
+        check_context(&self.context)?;
         self.context
             .bind_framebuffer(consts::DRAW_FRAMEBUFFER, Some(&self.id));
+        self.context.state_cache().draw_framebuffer_size = self
+            .color_texture
+            .map(|tex| (tex.width(), tex.height()))
+            .or_else(|| self.depth_texture.map(|tex| (tex.width(), tex.height())));
         if let Some(tex) = self.color_texture {
             self.context.draw_buffers(&[consts::COLOR_ATTACHMENT0]);
             tex.bind_as_color_target(0);
@@ -507,6 +816,124 @@ impl<'a, 'b> RenderTargetArray<'a, 'b> {
                 blue: self.color_texture.and(clear_state.blue),
                 alpha: self.color_texture.and(clear_state.alpha),
                 depth: self.depth_texture.and(clear_state.depth),
+                stencil: self.depth_texture.and(clear_state.stencil),
+            },
+        );
+        render()?;
+        if let Some(color_texture) = self.color_texture {
+            color_texture.generate_mip_maps();
+        }
+        Ok(())
+    }
+
+    ///
+    /// Same as [write](Self::write), except that each of the color texture layers is cleared with its own clear state,
+    /// for example to clear the layers of a gbuffer to different values.
+    /// The color texture layer at the *ith* index in **color_layers** is cleared based on the color channels of the *ith* clear state
+    /// in **color_clear_states**, while the depth and stencil are cleared based on **depth_clear_state**
+    /// (the color channels of the depth clear state are ignored).
+    ///
+    /// # Errors
+    /// Will return an error if the number of color clear states is not the same as the number of color layers.
+    ///
+    pub fn write_with_clear_states<F: FnOnce() -> Result<(), Error>>(
+        &self,
+        color_clear_states: &[ClearState],
+        depth_clear_state: &ClearState,
+        color_layers: &[usize],
+        depth_layer: usize,
+        render: F,
+    ) -> Result<(), Error> {
+        if color_clear_states.len() != color_layers.len() {
+            Err(Error::FailedToWriteToRenderTarget {
+                message: format!(
+                    "Cannot clear {} color layers with {} clear states.",
+                    color_layers.len(),
+                    color_clear_states.len()
+                ),
+            })?;
+        }
+        self.bind(Some(color_layers), Some(depth_layer))?;
+        clear(
+            &self.context,
+            &ClearState {
+                depth: self.depth_texture.and(depth_clear_state.depth),
+                stencil: self.depth_texture.and(depth_clear_state.stencil),
+                ..ClearState::none()
+            },
+        );
+        if self.color_texture.is_some() {
+            clear_color_buffers(&self.context, color_clear_states);
+        }
+        render()?;
+        if let Some(color_texture) = self.color_texture {
+            color_texture.generate_mip_maps();
+        }
+        Ok(())
+    }
+
+    ///
+    /// Renders whatever rendered in the **render** closure into all layers of the textures defined at construction in a single pass,
+    /// for example all cascades of a cascaded shadow map or all faces of a point light shadow map.
+    /// Before writing, all layers of the textures are cleared based on the given clear state.
+    ///
+    /// The render closure must render with a program constructed with a geometry shader
+    /// (see [Program::from_source_with_geometry_shader]) which selects the layer of each output primitive by writing to `gl_Layer`,
+    /// usually by emitting each input primitive once per layer transformed by a per layer view-projection matrix, for example
+    /// ```glsl
+    /// layout (triangles) in;
+    /// layout (triangle_strip, max_vertices = 12) out;
+    /// uniform mat4 viewProjections[4];
+    ///
+    /// void main()
+    /// {
+    ///     for (int layer = 0; layer < 4; layer++)
+    ///     {
+    ///         for (int i = 0; i < 3; i++)
+    ///         {
+    ///             gl_Layer = layer;
+    ///             gl_Position = viewProjections[layer] * gl_in[i].gl_Position;
+    ///             EmitVertex();
+    ///         }
+    ///         EndPrimitive();
+    ///     }
+    /// }
+    /// ```
+    /// where the vertex shader outputs the world position in `gl_Position`.
+    /// The fragment shader output at location 0 is written to the color texture layer given by `gl_Layer`.
+    /// Only available on desktop.
+    ///
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn write_layers<F: FnOnce() -> Result<(), Error>>(
+        &self,
+        clear_state: &ClearState,
+        render: F,
+    ) -> Result<(), Error> {
+        check_context(&self.context)?;
+        self.context
+            .bind_framebuffer(consts::DRAW_FRAMEBUFFER, Some(&self.id));
+        self.context.state_cache().draw_framebuffer_size = self
+            .color_texture
+            .map(|tex| (tex.width(), tex.height()))
+            .or_else(|| self.depth_texture.map(|tex| (tex.width(), tex.height())));
+        if let Some(color_texture) = self.color_texture {
+            self.context.draw_buffers(&[consts::COLOR_ATTACHMENT0]);
+            color_texture.bind_as_layered_color_target();
+        }
+        if let Some(depth_texture) = self.depth_texture {
+            depth_texture.bind_as_layered_depth_target();
+        }
+        #[cfg(feature = "debug")]
+        check(&self.context)?;
+        clear(
+            &self.context,
+            &ClearState {
+                red: self.color_texture.and(clear_state.red),
+                green: self.color_texture.and(clear_state.green),
+                blue: self.color_texture.and(clear_state.blue),
+                alpha: self.color_texture.and(clear_state.alpha),
+                depth: self.depth_texture.and(clear_state.depth),
+                stencil: self.depth_texture.and(clear_state.stencil),
             },
         );
         render()?;
@@ -527,19 +954,21 @@ impl<'a, 'b> RenderTargetArray<'a, 'b> {
         }
         Screen::write(&self.context, &ClearState::none(), || {
             let effect = get_copy_array_effect(&self.context)?;
-            effect.use_texture(self.color_texture.unwrap(), "colorMap")?;
-            effect.use_texture(self.depth_texture.unwrap(), "depthMap")?;
             effect.use_uniform_int("colorLayer", &(color_layer as i32))?;
             effect.use_uniform_int("depthLayer", &(depth_layer as i32))?;
-            effect.apply(
-                RenderStates {
-                    cull: CullType::Back,
-                    depth_test: DepthTestType::Always,
-                    ..Default::default()
-                },
-                viewport,
-            )?;
-            Ok(())
+            effect
+                .inputs(&[
+                    ("colorMap", self.color_texture.unwrap()),
+                    ("depthMap", self.depth_texture.unwrap()),
+                ])
+                .apply(
+                    RenderStates {
+                        cull: CullType::Back,
+                        depth_test: DepthTestType::Always,
+                        ..Default::default()
+                    },
+                    viewport,
+                )
         })?;
         Ok(())
     }
@@ -577,6 +1006,7 @@ impl<'a, 'b> RenderTargetArray<'a, 'b> {
         &self,
         depth_layer: usize,
         viewport: Viewport,
+        depth_test: DepthTestType,
     ) -> Result<(), Error> {
         if self.depth_texture.is_none() {
             Err(Error::FailedToCopyFromRenderTarget {
@@ -591,7 +1021,7 @@ impl<'a, 'b> RenderTargetArray<'a, 'b> {
             effect.apply(
                 RenderStates {
                     cull: CullType::Back,
-                    depth_test: DepthTestType::Always,
+                    depth_test,
                     write_mask: WriteMask::DEPTH,
                     ..Default::default()
                 },
@@ -618,19 +1048,21 @@ impl<'a, 'b> RenderTargetArray<'a, 'b> {
         }
         other.write(&ClearState::none(), || {
             let effect = get_copy_array_effect(&self.context)?;
-            effect.use_texture(self.color_texture.unwrap(), "colorMap")?;
-            effect.use_texture(self.depth_texture.unwrap(), "depthMap")?;
             effect.use_uniform_int("colorLayer", &(color_layer as i32))?;
             effect.use_uniform_int("depthLayer", &(depth_layer as i32))?;
-            effect.apply(
-                RenderStates {
-                    cull: CullType::Back,
-                    depth_test: DepthTestType::Always,
-                    ..Default::default()
-                },
-                viewport,
-            )?;
-            Ok(())
+            effect
+                .inputs(&[
+                    ("colorMap", self.color_texture.unwrap()),
+                    ("depthMap", self.depth_texture.unwrap()),
+                ])
+                .apply(
+                    RenderStates {
+                        cull: CullType::Back,
+                        depth_test: DepthTestType::Always,
+                        ..Default::default()
+                    },
+                    viewport,
+                )
         })?;
         Ok(())
     }
@@ -670,6 +1102,7 @@ impl<'a, 'b> RenderTargetArray<'a, 'b> {
         depth_layer: usize,
         other: &RenderTarget,
         viewport: Viewport,
+        depth_test: DepthTestType,
     ) -> Result<(), Error> {
         if self.depth_texture.is_none() || other.depth_texture.is_none() {
             Err(Error::FailedToCopyFromRenderTarget {
@@ -684,7 +1117,7 @@ impl<'a, 'b> RenderTargetArray<'a, 'b> {
             effect.apply(
                 RenderStates {
                     cull: CullType::Back,
-                    depth_test: DepthTestType::Always,
+                    depth_test,
                     write_mask: WriteMask::DEPTH,
                     ..Default::default()
                 },
@@ -695,13 +1128,78 @@ impl<'a, 'b> RenderTargetArray<'a, 'b> {
         Ok(())
     }
 
+    ///
+    /// Copies the given layer of the depth texture array in this render target to the given 2D depth texture.
+    /// In contrast to [copy_depth](Self::copy_depth), no render target needs to be constructed for the destination,
+    /// instead a framebuffer, which is shared between all calls with the same context, is used.
+    ///
+    pub fn copy_depth_layer_to(
+        &self,
+        depth_layer: usize,
+        destination: &DepthTargetTexture2D,
+        viewport: Viewport,
+    ) -> Result<(), Error> {
+        if self.depth_texture.is_none() {
+            Err(Error::FailedToCopyFromRenderTarget {
+                message: "Cannot copy depth when the render target does not have a depth texture."
+                    .to_owned(),
+            })?;
+        }
+        let framebuffer = match self.context.state_cache().copy_framebuffer.clone() {
+            Some(framebuffer) => framebuffer,
+            None => new_framebuffer(&self.context)?,
+        };
+        self.context.state_cache().copy_framebuffer = Some(framebuffer.clone());
+        self.context
+            .bind_framebuffer(consts::DRAW_FRAMEBUFFER, Some(&framebuffer));
+        self.context.state_cache().draw_framebuffer_size =
+            Some((destination.width(), destination.height()));
+        destination.bind_as_depth_target();
+        let result = (|| {
+            #[cfg(feature = "debug")]
+            check(&self.context)?;
+            let effect = get_copy_array_effect(&self.context)?;
+            effect.use_texture(self.depth_texture.unwrap(), "depthMap")?;
+            effect.use_uniform_int("depthLayer", &(depth_layer as i32))?;
+            effect.apply(
+                RenderStates {
+                    cull: CullType::Back,
+                    depth_test: DepthTestType::Always,
+                    write_mask: WriteMask::DEPTH,
+                    ..Default::default()
+                },
+                viewport,
+            )
+        })();
+        // The destination is detached so that the framebuffer does not keep it alive
+        destination.unbind_as_depth_target();
+        result
+    }
+
     fn bind(
         &self,
         color_layers: Option<&[usize]>,
         depth_layer: Option<usize>,
     ) -> Result<(), Error> {
+        check_context(&self.context)?;
+        if let Some(color_layers) = color_layers {
+            let max_color_attachments = self.context.capabilities().max_color_attachments;
+            if color_layers.len() > max_color_attachments as usize {
+                Err(Error::FailedToWriteToRenderTarget {
+                    message: format!(
+                        "Cannot write to {} color layers at once, the maximum number of color attachments is {}",
+                        color_layers.len(),
+                        max_color_attachments
+                    ),
+                })?;
+            }
+        }
         self.context
             .bind_framebuffer(consts::DRAW_FRAMEBUFFER, Some(&self.id));
+        self.context.state_cache().draw_framebuffer_size = self
+            .color_texture
+            .map(|tex| (tex.width(), tex.height()))
+            .or_else(|| self.depth_texture.map(|tex| (tex.width(), tex.height())));
         if let Some(color_texture) = self.color_texture {
             if let Some(color_layers) = color_layers {
                 self.context.draw_buffers(
@@ -732,6 +1230,7 @@ impl Drop for RenderTargetArray<'_, '_> {
 }
 
 fn new_framebuffer(context: &Context) -> Result<crate::context::Framebuffer, Error> {
+    check_context(context)?;
     Ok(context
         .create_framebuffer()
         .ok_or_else(|| Error::FailedToCreateFramebuffer {
@@ -747,6 +1246,9 @@ fn check(context: &Context) -> Result<(), Error> {
 }
 
 fn clear(context: &Context, clear_state: &ClearState) {
+    if *clear_state == ClearState::none() {
+        return;
+    }
     Program::set_write_mask(
         context,
         WriteMask {
@@ -772,15 +1274,71 @@ fn clear(context: &Context, clear_state: &ClearState) {
     if let Some(depth) = clear_state.depth {
         context.clear_depth(depth);
     }
-    context.clear(if clear_color && clear_state.depth.is_some() {
-        consts::COLOR_BUFFER_BIT | consts::DEPTH_BUFFER_BIT
-    } else {
-        if clear_color {
-            consts::COLOR_BUFFER_BIT
-        } else {
-            consts::DEPTH_BUFFER_BIT
+    if let Some(stencil) = clear_state.stencil {
+        Program::set_stencil_mask(context, 0xFF);
+        context.clear_stencil(stencil as i32);
+    }
+    let mut mask = 0;
+    if clear_color {
+        mask |= consts::COLOR_BUFFER_BIT;
+    }
+    if clear_state.depth.is_some() {
+        mask |= consts::DEPTH_BUFFER_BIT;
+    }
+    if clear_state.stencil.is_some() {
+        mask |= consts::STENCIL_BUFFER_BIT;
+    }
+    if mask != 0 {
+        context.clear(mask);
+    }
+}
+
+fn clear_color_buffers(context: &Context, clear_states: &[ClearState]) {
+    for (draw_buffer, clear_state) in clear_states.iter().enumerate() {
+        let write_mask = WriteMask {
+            red: clear_state.red.is_some(),
+            green: clear_state.green.is_some(),
+            blue: clear_state.blue.is_some(),
+            alpha: clear_state.alpha.is_some(),
+            depth: false,
+        };
+        if write_mask != WriteMask::NONE {
+            Program::set_write_mask(context, write_mask);
+            context.clear_bufferfv(
+                consts::COLOR,
+                draw_buffer as i32,
+                &[
+                    clear_state.red.unwrap_or(0.0),
+                    clear_state.green.unwrap_or(0.0),
+                    clear_state.blue.unwrap_or(0.0),
+                    clear_state.alpha.unwrap_or(1.0),
+                ],
+            );
         }
-    });
+    }
+}
+
+fn clear_integer_color_buffer(context: &Context, draw_buffer: usize, clear_state: &ClearState) {
+    let write_mask = WriteMask {
+        red: clear_state.red.is_some(),
+        green: clear_state.green.is_some(),
+        blue: clear_state.blue.is_some(),
+        alpha: clear_state.alpha.is_some(),
+        depth: false,
+    };
+    if write_mask != WriteMask::NONE {
+        Program::set_write_mask(context, write_mask);
+        context.clear_bufferuiv(
+            consts::COLOR,
+            draw_buffer as i32,
+            &[
+                clear_state.red.unwrap_or(0.0) as u32,
+                clear_state.green.unwrap_or(0.0) as u32,
+                clear_state.blue.unwrap_or(0.0) as u32,
+                clear_state.alpha.unwrap_or(0.0) as u32,
+            ],
+        );
+    }
 }
 
 fn get_copy_effect(context: &Context) -> Result<&ImageEffect, Error> {
@@ -828,3 +1386,99 @@ fn get_copy_array_effect(context: &Context) -> Result<&ImageEffect, Error> {
         Ok(COPY_EFFECT.as_ref().unwrap())
     }
 }
+
+#[cfg(all(test, feature = "headless", not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::test_util::*;
+
+    #[test]
+    fn write_without_clearing() {
+        let test_context = TestContext::new();
+        let context = test_context.gl();
+        let camera = orthographic_camera(&context);
+        let viewport = Viewport::new_at_origo(8, 8);
+        let left = Viewport::new_at_origo(4, 8);
+        let right = Viewport { x: 4, ..left };
+        let color_texture = ColorTargetTexture2D::new(
+            &context,
+            8,
+            8,
+            Interpolation::Nearest,
+            Interpolation::Nearest,
+            None,
+            Wrapping::ClampToEdge,
+            Wrapping::ClampToEdge,
+            Format::RGBA8,
+        )
+        .unwrap();
+        let depth_texture = DepthTargetTexture2D::new(
+            &context,
+            8,
+            8,
+            Wrapping::ClampToEdge,
+            Wrapping::ClampToEdge,
+            DepthFormat::Depth32F,
+        )
+        .unwrap();
+        let render_target = RenderTarget::new(&context, &color_texture, &depth_texture).unwrap();
+        let front = crate::Mesh::new(&context, &triangle_soup(-1.0, 0.0, 0.0)).unwrap();
+        let back = crate::Mesh::new(&context, &triangle_soup(-1.0, 1.0, -1.0)).unwrap();
+        let green = uniform_image(4, 8, [0, 255, 0, 255]);
+        let red = uniform_image(4, 8, [255, 0, 0, 255]);
+
+        render_target
+            .write(
+                &ClearState::color_and_depth(1.0, 0.0, 0.0, 1.0, 1.0),
+                || {
+                    front.render_with_color(
+                        &vec4(0.0, 1.0, 0.0, 1.0),
+                        RenderStates::default(),
+                        viewport,
+                        &Mat4::identity(),
+                        &camera,
+                    )
+                },
+            )
+            .unwrap();
+
+        // The color is kept
+        render_target.write_no_clear(|| Ok(())).unwrap();
+        let image = render_target.copy_color_to_cpu(viewport).unwrap();
+        assert_images_match(&sub_image(&image, left), &green, 1, 0.0);
+        assert_images_match(&sub_image(&image, right), &red, 0, 0.0);
+
+        // The depth is kept, so the mesh behind the first mesh is only visible in the right half
+        render_target
+            .write(&ClearState::none(), || {
+                back.render_with_color(
+                    &vec4(0.0, 0.0, 1.0, 1.0),
+                    RenderStates::default(),
+                    viewport,
+                    &Mat4::identity(),
+                    &camera,
+                )
+            })
+            .unwrap();
+        let image = render_target.copy_color_to_cpu(viewport).unwrap();
+        assert_images_match(&sub_image(&image, left), &green, 1, 0.0);
+        assert_images_match(
+            &sub_image(&image, right),
+            &uniform_image(4, 8, [0, 0, 255, 255]),
+            1,
+            0.0,
+        );
+
+        // The write mask is kept, so clearing inside the render closure does not change anything
+        Program::set_write_mask(&context, WriteMask::NONE);
+        render_target
+            .write(&ClearState::none(), || {
+                context.clear_color(1.0, 1.0, 1.0, 1.0);
+                context.clear(consts::COLOR_BUFFER_BIT);
+                Ok(())
+            })
+            .unwrap();
+        let second_image = render_target.copy_color_to_cpu(viewport).unwrap();
+        assert_images_match(&second_image, &image, 0, 0.0);
+    }
+}
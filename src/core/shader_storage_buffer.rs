@@ -0,0 +1,69 @@
+use crate::context::{consts, Context};
+use crate::core::Error;
+
+///
+/// A buffer which can be both read from and written to in a compute shader
+/// (see also [use_storage_buffer](crate::ComputeProgram::use_storage_buffer)).
+///
+pub struct ShaderStorageBuffer {
+    context: Context,
+    id: crate::context::Buffer,
+    count: usize,
+}
+
+impl ShaderStorageBuffer {
+    pub fn new_with_f32(context: &Context, data: &[f32]) -> Result<ShaderStorageBuffer, Error> {
+        if !context.supports_compute_shader() {
+            return Err(Error::ComputeShaderNotSupported {
+                message: "Shader storage buffers require OpenGL 4.3 or newer.".to_string(),
+            });
+        }
+        let id = context.create_buffer().unwrap();
+        let mut buffer = ShaderStorageBuffer {
+            context: context.clone(),
+            id,
+            count: 0,
+        };
+        if !data.is_empty() {
+            buffer.fill_with_f32(data);
+        }
+        Ok(buffer)
+    }
+
+    pub fn fill_with_f32(&mut self, data: &[f32]) {
+        self.context
+            .bind_buffer(consts::SHADER_STORAGE_BUFFER, &self.id);
+        self.context
+            .buffer_data_f32(consts::SHADER_STORAGE_BUFFER, data, consts::DYNAMIC_COPY);
+        self.context.unbind_buffer(consts::SHADER_STORAGE_BUFFER);
+        self.count = data.len();
+    }
+
+    ///
+    /// Reads the content of the buffer back to the CPU, for example after a [dispatch](crate::ComputeProgram::dispatch).
+    ///
+    pub fn read_f32(&self) -> Vec<f32> {
+        let mut data = vec![0.0; self.count];
+        self.context
+            .bind_buffer(consts::SHADER_STORAGE_BUFFER, &self.id);
+        self.context
+            .get_buffer_sub_data_f32(consts::SHADER_STORAGE_BUFFER, 0, &mut data);
+        self.context.unbind_buffer(consts::SHADER_STORAGE_BUFFER);
+        data
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub(crate) fn bind(&self, index: u32) {
+        self.context
+            .bind_buffer_base(consts::SHADER_STORAGE_BUFFER, index, &self.id);
+    }
+}
+
+impl Drop for ShaderStorageBuffer {
+    fn drop(&mut self) {
+        self.context.delete_buffer(&self.id);
+    }
+}
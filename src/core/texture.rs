@@ -1,6 +1,7 @@
 use crate::context::{consts, Context};
-use crate::core::Error;
+use crate::core::{check_context, Error};
 use crate::cpu_texture::*;
+use std::cell::Cell;
 
 ///
 /// A texture that can be sampled in a fragment shader (see [use_texture](crate::Program::use_texture)).
@@ -12,6 +13,65 @@ pub trait Texture {
     fn depth(&self) -> usize;
 }
 
+///
+/// Texture parameters which can be changed on all of the textures without reallocating them.
+/// Only available on desktop.
+///
+#[cfg(not(target_arch = "wasm32"))]
+pub trait TextureParameters: internal::TextureObject {
+    ///
+    /// Changes the color returned when sampling outside the texture with the [ClampToBorder](Wrapping::ClampToBorder) wrapping mode,
+    /// for example white for a shadow map so that nothing outside the shadow map is in shadow.
+    ///
+    fn set_border_color(&self, color: [f32; 4]) {
+        bind_texture(self.context(), self.target(), self.id());
+        self.context()
+            .tex_parameterfv(self.target(), consts::TEXTURE_BORDER_COLOR, &color);
+    }
+
+    ///
+    /// Defines which channel of the texture, or the constant 0 or 1, is returned in each of the red, green, blue and alpha channels
+    /// when sampling the texture, for example use `set_swizzle(Swizzle::Red, Swizzle::Red, Swizzle::Red, Swizzle::One)`
+    /// to show a single channel texture as grayscale.
+    ///
+    fn set_swizzle(&self, red: Swizzle, green: Swizzle, blue: Swizzle, alpha: Swizzle) {
+        bind_texture(self.context(), self.target(), self.id());
+        let channels = [
+            consts::TEXTURE_SWIZZLE_R,
+            consts::TEXTURE_SWIZZLE_G,
+            consts::TEXTURE_SWIZZLE_B,
+            consts::TEXTURE_SWIZZLE_A,
+        ];
+        for (channel, swizzle) in channels.iter().zip([red, green, blue, alpha].iter()) {
+            let value = match swizzle {
+                Swizzle::Red => consts::RED,
+                Swizzle::Green => consts::GREEN,
+                Swizzle::Blue => consts::BLUE,
+                Swizzle::Alpha => consts::ALPHA,
+                Swizzle::Zero => consts::ZERO,
+                Swizzle::One => consts::ONE,
+            };
+            self.context()
+                .tex_parameteri(self.target(), *channel, value as i32);
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<T: internal::TextureObject> TextureParameters for T {}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod internal {
+    use crate::context::Context;
+
+    // Gives access to the texture object behind a texture without making it public.
+    pub trait TextureObject {
+        fn context(&self) -> &Context;
+        fn id(&self) -> &crate::context::Texture;
+        fn target(&self) -> u32;
+    }
+}
+
 ///
 /// A 2D texture, basically an image that is transferred to the GPU.
 /// For a texture that can be rendered into, see [ColorTargetTexture2D](crate::ColorTargetTexture2D).
@@ -31,7 +91,7 @@ impl Texture2D {
         cpu_texture: &CPUTexture<u8>,
     ) -> Result<Texture2D, Error> {
         let mut texture = Self::new(context, cpu_texture)?;
-        texture.fill_with_u8(&cpu_texture.data)?;
+        texture.fill_with_u8(&cpu_texture.upload_data(1))?;
         Ok(texture)
     }
 
@@ -40,14 +100,14 @@ impl Texture2D {
         cpu_texture: &CPUTexture<f32>,
     ) -> Result<Texture2D, Error> {
         let mut texture = Self::new(context, cpu_texture)?;
-        texture.fill_with_f32(&cpu_texture.data)?;
+        texture.fill_with_f32(&cpu_texture.upload_data(1))?;
         Ok(texture)
     }
 
     pub fn fill_with_u8(&mut self, data: &[u8]) -> Result<(), Error> {
         check_u8_format(self.format)?;
         check_data_length(self.width, self.height, 1, self.format, data.len())?;
-        self.context.bind_texture(consts::TEXTURE_2D, &self.id);
+        bind_texture(&self.context, consts::TEXTURE_2D, &self.id);
         self.context.tex_sub_image_2d_with_u8_data(
             consts::TEXTURE_2D,
             0,
@@ -66,7 +126,7 @@ impl Texture2D {
     pub fn fill_with_f32(&mut self, data: &[f32]) -> Result<(), Error> {
         check_f32_format(self.format)?;
         check_data_length(self.width, self.height, 1, self.format, data.len())?;
-        self.context.bind_texture(consts::TEXTURE_2D, &self.id);
+        bind_texture(&self.context, consts::TEXTURE_2D, &self.id);
         self.context.tex_sub_image_2d_with_f32_data(
             consts::TEXTURE_2D,
             0,
@@ -83,6 +143,7 @@ impl Texture2D {
     }
 
     fn new<T>(context: &Context, cpu_texture: &CPUTexture<T>) -> Result<Texture2D, Error> {
+        check_size(context, cpu_texture.width, cpu_texture.height, 1)?;
         let id = generate(context)?;
         let number_of_mip_maps = calculate_number_of_mip_maps(
             cpu_texture.mip_map_filter,
@@ -122,14 +183,59 @@ impl Texture2D {
         })
     }
 
+    ///
+    /// Changes the interpolation of the texture without reallocating it, see [Interpolation].
+    /// The mip map filter is ignored if the texture does not have mip maps.
+    ///
+    pub fn set_interpolation(
+        &self,
+        min_filter: Interpolation,
+        mag_filter: Interpolation,
+        mip_map_filter: Option<Interpolation>,
+    ) {
+        bind_texture(&self.context, consts::TEXTURE_2D, &self.id);
+        set_interpolation_parameters(
+            &self.context,
+            consts::TEXTURE_2D,
+            min_filter,
+            mag_filter,
+            if self.number_of_mip_maps == 1 {
+                None
+            } else {
+                mip_map_filter
+            },
+        );
+    }
+
+    ///
+    /// Changes the wrapping of the texture without reallocating it, see [Wrapping].
+    ///
+    pub fn set_wrapping(&self, wrap_s: Wrapping, wrap_t: Wrapping) {
+        bind_texture(&self.context, consts::TEXTURE_2D, &self.id);
+        set_wrapping_parameters(&self.context, consts::TEXTURE_2D, wrap_s, wrap_t, None);
+    }
+
     pub(crate) fn generate_mip_maps(&self) {
         if self.number_of_mip_maps > 1 {
-            self.context.bind_texture(consts::TEXTURE_2D, &self.id);
+            bind_texture(&self.context, consts::TEXTURE_2D, &self.id);
             self.context.generate_mipmap(consts::TEXTURE_2D);
         }
     }
 }
 
+#[cfg(not(target_arch = "wasm32"))]
+impl internal::TextureObject for Texture2D {
+    fn context(&self) -> &Context {
+        &self.context
+    }
+    fn id(&self) -> &crate::context::Texture {
+        &self.id
+    }
+    fn target(&self) -> u32 {
+        consts::TEXTURE_2D
+    }
+}
+
 impl Texture for Texture2D {
     fn bind(&self, location: u32) {
         bind_at(&self.context, &self.id, consts::TEXTURE_2D, location);
@@ -147,7 +253,7 @@ impl Texture for Texture2D {
 
 impl Drop for Texture2D {
     fn drop(&mut self) {
-        self.context.delete_texture(&self.id);
+        delete_texture(&self.context, &self.id);
     }
 }
 
@@ -160,6 +266,8 @@ pub struct ColorTargetTexture2D {
     width: usize,
     height: usize,
     number_of_mip_maps: u32,
+    base_level: Cell<u32>,
+    format: Format,
 }
 
 impl ColorTargetTexture2D {
@@ -174,8 +282,59 @@ impl ColorTargetTexture2D {
         wrap_t: Wrapping,
         format: Format,
     ) -> Result<Self, Error> {
+        Self::new_with_mip_maps(
+            context,
+            width,
+            height,
+            calculate_number_of_mip_maps(mip_map_filter, width, height, 1),
+            min_filter,
+            mag_filter,
+            mip_map_filter,
+            wrap_s,
+            wrap_t,
+            format,
+        )
+    }
+
+    ///
+    /// Same as [new](Self::new), except that exactly the given number of mip levels are allocated instead of the full mip chain.
+    /// The mip maps are generated each time the texture has been written to and the mip map filter is ignored if the number of mip levels is 1.
+    ///
+    /// # Errors
+    /// Will return an error if the number of mip levels is 0 or larger than the number of levels in the full mip chain.
+    /// Will also return an error if the format is a float format, for example [RGBA16F](crate::Format::RGBA16F),
+    /// and rendering into float textures is not supported (see [Capabilities::color_buffer_float](crate::Capabilities::color_buffer_float)).
+    /// Will also return an error if the format is an integer format (for example [R32UI](crate::Format::R32UI))
+    /// and the texture is filtered linearly or has more than one mip level, since integer textures cannot be interpolated.
+    ///
+    pub fn new_with_mip_maps(
+        context: &Context,
+        width: usize,
+        height: usize,
+        number_of_mip_maps: u32,
+        min_filter: Interpolation,
+        mag_filter: Interpolation,
+        mip_map_filter: Option<Interpolation>,
+        wrap_s: Wrapping,
+        wrap_t: Wrapping,
+        format: Format,
+    ) -> Result<Self, Error> {
+        check_size(context, width, height, 1)?;
+        check_number_of_mip_maps(number_of_mip_maps, width, height)?;
+        check_color_target_format(context, format)?;
+        if is_integer_format(format)
+            && (min_filter != Interpolation::Nearest
+                || mag_filter != Interpolation::Nearest
+                || number_of_mip_maps > 1)
+        {
+            Err(Error::FailedToCreateTexture {
+                message: format!(
+                    "A texture with the integer format {:?} must use nearest filtering and no mip maps.",
+                    format
+                ),
+            })?;
+        }
         let id = generate(context)?;
-        let number_of_mip_maps = calculate_number_of_mip_maps(mip_map_filter, width, height, 1);
         set_parameters(
             context,
             &id,
@@ -204,13 +363,82 @@ impl ColorTargetTexture2D {
             width,
             height,
             number_of_mip_maps,
+            base_level: Cell::new(0),
+            format,
         })
     }
 
+    ///
+    /// Returns the number of allocated mip levels.
+    ///
+    pub fn number_of_mip_maps(&self) -> u32 {
+        self.number_of_mip_maps
+    }
+
+    pub(crate) fn format(&self) -> Format {
+        self.format
+    }
+
+    ///
+    /// Sets the lowest mip level which is used when sampling the texture, ie. the level used when sampling at full resolution.
+    ///
+    pub fn set_base_level(&self, level: u32) {
+        self.base_level.set(level);
+        bind_texture(&self.context, consts::TEXTURE_2D, &self.id);
+        self.context
+            .tex_parameteri(consts::TEXTURE_2D, consts::TEXTURE_BASE_LEVEL, level as i32);
+    }
+
+    ///
+    /// Sets the highest mip level which is used when sampling the texture. Mip levels above this level are not generated either.
+    ///
+    pub fn set_max_level(&self, level: u32) {
+        bind_texture(&self.context, consts::TEXTURE_2D, &self.id);
+        self.context
+            .tex_parameteri(consts::TEXTURE_2D, consts::TEXTURE_MAX_LEVEL, level as i32);
+    }
+
+    ///
+    /// Changes the interpolation of the texture without reallocating it, see [Interpolation].
+    /// The mip map filter is ignored if the texture does not have mip maps.
+    /// Textures with an integer format, for example [R32UI](crate::Format::R32UI), must use nearest interpolation.
+    ///
+    pub fn set_interpolation(
+        &self,
+        min_filter: Interpolation,
+        mag_filter: Interpolation,
+        mip_map_filter: Option<Interpolation>,
+    ) {
+        bind_texture(&self.context, consts::TEXTURE_2D, &self.id);
+        set_interpolation_parameters(
+            &self.context,
+            consts::TEXTURE_2D,
+            min_filter,
+            mag_filter,
+            if self.number_of_mip_maps == 1 {
+                None
+            } else {
+                mip_map_filter
+            },
+        );
+    }
+
+    ///
+    /// Changes the wrapping of the texture without reallocating it, see [Wrapping].
+    ///
+    pub fn set_wrapping(&self, wrap_s: Wrapping, wrap_t: Wrapping) {
+        bind_texture(&self.context, consts::TEXTURE_2D, &self.id);
+        set_wrapping_parameters(&self.context, consts::TEXTURE_2D, wrap_s, wrap_t, None);
+    }
+
     pub(crate) fn generate_mip_maps(&self) {
         if self.number_of_mip_maps > 1 {
-            self.context.bind_texture(consts::TEXTURE_2D, &self.id);
-            self.context.generate_mipmap(consts::TEXTURE_2D);
+            generate_mip_maps_from_level_zero(
+                &self.context,
+                &self.id,
+                consts::TEXTURE_2D,
+                self.base_level.get(),
+            );
         }
     }
 
@@ -219,12 +447,25 @@ impl ColorTargetTexture2D {
             consts::FRAMEBUFFER,
             consts::COLOR_ATTACHMENT0 + channel as u32,
             consts::TEXTURE_2D,
-            &self.id,
+            Some(&self.id),
             0,
         );
     }
 }
 
+#[cfg(not(target_arch = "wasm32"))]
+impl internal::TextureObject for ColorTargetTexture2D {
+    fn context(&self) -> &Context {
+        &self.context
+    }
+    fn id(&self) -> &crate::context::Texture {
+        &self.id
+    }
+    fn target(&self) -> u32 {
+        consts::TEXTURE_2D
+    }
+}
+
 impl Texture for ColorTargetTexture2D {
     fn bind(&self, location: u32) {
         bind_at(&self.context, &self.id, consts::TEXTURE_2D, location);
@@ -242,10 +483,25 @@ impl Texture for ColorTargetTexture2D {
 
 impl Drop for ColorTargetTexture2D {
     fn drop(&mut self) {
-        self.context.delete_texture(&self.id);
+        delete_texture(&self.context, &self.id);
     }
 }
 
+///
+/// The source of a channel when sampling a texture, see [TextureParameters::set_swizzle](crate::TextureParameters::set_swizzle).
+/// Only available on desktop.
+///
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum Swizzle {
+    Red,
+    Green,
+    Blue,
+    Alpha,
+    Zero,
+    One,
+}
+
 ///
 /// Type of formats for depth render targets ([DepthTargetTexture2D](crate::DepthTargetTexture2D) and
 /// [DepthTargetTexture2DArray](crate::DepthTargetTexture2DArray)).
@@ -255,6 +511,10 @@ pub enum DepthFormat {
     Depth16,
     Depth24,
     Depth32F,
+    ///
+    /// 24 bit depth combined with an 8 bit stencil buffer, see [RenderStates::stencil](crate::RenderStates::stencil).
+    ///
+    Depth24Stencil8,
 }
 
 ///
@@ -265,6 +525,7 @@ pub struct DepthTargetTexture2D {
     id: crate::context::Texture,
     width: usize,
     height: usize,
+    format: DepthFormat,
 }
 
 impl DepthTargetTexture2D {
@@ -276,18 +537,66 @@ impl DepthTargetTexture2D {
         wrap_t: Wrapping,
         format: DepthFormat,
     ) -> Result<Self, Error> {
+        Self::new_internal(context, width, height, wrap_s, wrap_t, format, false)
+    }
+
+    ///
+    /// Same as [new](Self::new), except that the texture is sampled with depth comparison,
+    /// ie. it must be sampled as a `sampler2DShadow` in the shader which returns the result of comparing the given reference value
+    /// with the depth in the texture (1 if the reference value is less than or equal to the depth and 0 otherwise).
+    /// The comparison results of neighbouring texels are filtered linearly by the hardware,
+    /// which gives smooth shadow edges at no extra cost when the texture is used as a shadow map.
+    ///
+    pub fn new_with_comparison(
+        context: &Context,
+        width: usize,
+        height: usize,
+        wrap_s: Wrapping,
+        wrap_t: Wrapping,
+        format: DepthFormat,
+    ) -> Result<Self, Error> {
+        Self::new_internal(context, width, height, wrap_s, wrap_t, format, true)
+    }
+
+    fn new_internal(
+        context: &Context,
+        width: usize,
+        height: usize,
+        wrap_s: Wrapping,
+        wrap_t: Wrapping,
+        format: DepthFormat,
+        comparison: bool,
+    ) -> Result<Self, Error> {
+        check_size(context, width, height, 1)?;
         let id = generate(context)?;
+        let interpolation = if comparison {
+            Interpolation::Linear
+        } else {
+            Interpolation::Nearest
+        };
         set_parameters(
             context,
             &id,
             consts::TEXTURE_2D,
-            Interpolation::Nearest,
-            Interpolation::Nearest,
+            interpolation,
+            interpolation,
             None,
             wrap_s,
             wrap_t,
             None,
         );
+        if comparison {
+            context.tex_parameteri(
+                consts::TEXTURE_2D,
+                consts::TEXTURE_COMPARE_MODE,
+                consts::COMPARE_REF_TO_TEXTURE as i32,
+            );
+            context.tex_parameteri(
+                consts::TEXTURE_2D,
+                consts::TEXTURE_COMPARE_FUNC,
+                consts::LEQUAL as i32,
+            );
+        }
         context.tex_storage_2d(
             consts::TEXTURE_2D,
             1,
@@ -300,20 +609,52 @@ impl DepthTargetTexture2D {
             id,
             width,
             height,
+            format,
         })
     }
 
+    ///
+    /// Changes the wrapping of the texture without reallocating it, see [Wrapping].
+    ///
+    pub fn set_wrapping(&self, wrap_s: Wrapping, wrap_t: Wrapping) {
+        bind_texture(&self.context, consts::TEXTURE_2D, &self.id);
+        set_wrapping_parameters(&self.context, consts::TEXTURE_2D, wrap_s, wrap_t, None);
+    }
+
     pub(crate) fn bind_as_depth_target(&self) {
         self.context.framebuffer_texture_2d(
             consts::FRAMEBUFFER,
-            consts::DEPTH_ATTACHMENT,
+            attachment_from_depth(self.format),
             consts::TEXTURE_2D,
-            &self.id,
+            Some(&self.id),
+            0,
+        );
+    }
+
+    pub(crate) fn unbind_as_depth_target(&self) {
+        self.context.framebuffer_texture_2d(
+            consts::FRAMEBUFFER,
+            attachment_from_depth(self.format),
+            consts::TEXTURE_2D,
+            None,
             0,
         );
     }
 }
 
+#[cfg(not(target_arch = "wasm32"))]
+impl internal::TextureObject for DepthTargetTexture2D {
+    fn context(&self) -> &Context {
+        &self.context
+    }
+    fn id(&self) -> &crate::context::Texture {
+        &self.id
+    }
+    fn target(&self) -> u32 {
+        consts::TEXTURE_2D
+    }
+}
+
 impl Texture for DepthTargetTexture2D {
     fn bind(&self, location: u32) {
         bind_at(&self.context, &self.id, consts::TEXTURE_2D, location);
@@ -331,7 +672,7 @@ impl Texture for DepthTargetTexture2D {
 
 impl Drop for DepthTargetTexture2D {
     fn drop(&mut self) {
-        self.context.delete_texture(&self.id);
+        delete_texture(&self.context, &self.id);
     }
 }
 
@@ -350,7 +691,7 @@ pub struct TextureCubeMap {
 impl TextureCubeMap {
     pub fn new_with_u8(context: &Context, cpu_texture: &CPUTexture<u8>) -> Result<Self, Error> {
         let mut texture = Self::new(context, cpu_texture)?;
-        texture.fill_with_u8(&cpu_texture.data)?;
+        texture.fill_with_u8(&cpu_texture.upload_data(6))?;
         Ok(texture)
     }
 
@@ -359,8 +700,7 @@ impl TextureCubeMap {
         check_u8_format(self.format)?;
         let offset = data.len() / 6;
         check_data_length(self.width, self.height, 1, self.format, offset)?;
-        self.context
-            .bind_texture(consts::TEXTURE_CUBE_MAP, &self.id);
+        bind_texture(&self.context, consts::TEXTURE_CUBE_MAP, &self.id);
         for i in 0..6 {
             self.context.tex_sub_image_2d_with_u8_data(
                 consts::TEXTURE_CUBE_MAP_POSITIVE_X + i as u32,
@@ -379,6 +719,15 @@ impl TextureCubeMap {
     }
 
     fn new<T>(context: &Context, cpu_texture: &CPUTexture<T>) -> Result<TextureCubeMap, Error> {
+        check_cube_map_size(context, cpu_texture.width, cpu_texture.height)?;
+        if cpu_texture.width != cpu_texture.height {
+            Err(Error::FailedToCreateTexture {
+                message: format!(
+                    "The faces of a cube map must be square, got {}x{} pixels",
+                    cpu_texture.width, cpu_texture.height
+                ),
+            })?;
+        }
         let id = generate(context)?;
         let number_of_mip_maps = calculate_number_of_mip_maps(
             cpu_texture.mip_map_filter,
@@ -401,7 +750,7 @@ impl TextureCubeMap {
             cpu_texture.wrap_t,
             Some(cpu_texture.wrap_r),
         );
-        context.bind_texture(consts::TEXTURE_CUBE_MAP, &id);
+        bind_texture(context, consts::TEXTURE_CUBE_MAP, &id);
         context.tex_storage_2d(
             consts::TEXTURE_CUBE_MAP,
             number_of_mip_maps,
@@ -419,15 +768,65 @@ impl TextureCubeMap {
         })
     }
 
+    ///
+    /// Changes the interpolation of the texture without reallocating it, see [Interpolation].
+    /// The mip map filter is ignored if the texture does not have mip maps.
+    ///
+    pub fn set_interpolation(
+        &self,
+        min_filter: Interpolation,
+        mag_filter: Interpolation,
+        mip_map_filter: Option<Interpolation>,
+    ) {
+        bind_texture(&self.context, consts::TEXTURE_CUBE_MAP, &self.id);
+        set_interpolation_parameters(
+            &self.context,
+            consts::TEXTURE_CUBE_MAP,
+            min_filter,
+            mag_filter,
+            if self.number_of_mip_maps == 1 {
+                None
+            } else {
+                mip_map_filter
+            },
+        );
+    }
+
+    ///
+    /// Changes the wrapping of the texture without reallocating it, see [Wrapping].
+    ///
+    pub fn set_wrapping(&self, wrap_s: Wrapping, wrap_t: Wrapping, wrap_r: Wrapping) {
+        bind_texture(&self.context, consts::TEXTURE_CUBE_MAP, &self.id);
+        set_wrapping_parameters(
+            &self.context,
+            consts::TEXTURE_CUBE_MAP,
+            wrap_s,
+            wrap_t,
+            Some(wrap_r),
+        );
+    }
+
     pub(crate) fn generate_mip_maps(&self) {
         if self.number_of_mip_maps > 1 {
-            self.context
-                .bind_texture(consts::TEXTURE_CUBE_MAP, &self.id);
+            bind_texture(&self.context, consts::TEXTURE_CUBE_MAP, &self.id);
             self.context.generate_mipmap(consts::TEXTURE_CUBE_MAP);
         }
     }
 }
 
+#[cfg(not(target_arch = "wasm32"))]
+impl internal::TextureObject for TextureCubeMap {
+    fn context(&self) -> &Context {
+        &self.context
+    }
+    fn id(&self) -> &crate::context::Texture {
+        &self.id
+    }
+    fn target(&self) -> u32 {
+        consts::TEXTURE_CUBE_MAP
+    }
+}
+
 impl Texture for TextureCubeMap {
     fn bind(&self, location: u32) {
         bind_at(&self.context, &self.id, consts::TEXTURE_CUBE_MAP, location);
@@ -445,7 +844,7 @@ impl Texture for TextureCubeMap {
 
 impl Drop for TextureCubeMap {
     fn drop(&mut self) {
-        self.context.delete_texture(&self.id);
+        delete_texture(&self.context, &self.id);
     }
 }
 
@@ -459,6 +858,7 @@ pub struct ColorTargetTexture2DArray {
     height: usize,
     depth: usize,
     number_of_mip_maps: u32,
+    base_level: Cell<u32>,
 }
 
 impl ColorTargetTexture2DArray {
@@ -474,8 +874,47 @@ impl ColorTargetTexture2DArray {
         wrap_t: Wrapping,
         format: Format,
     ) -> Result<Self, Error> {
+        Self::new_with_mip_maps(
+            context,
+            width,
+            height,
+            depth,
+            calculate_number_of_mip_maps(mip_map_filter, width, height, 1),
+            min_filter,
+            mag_filter,
+            mip_map_filter,
+            wrap_s,
+            wrap_t,
+            format,
+        )
+    }
+
+    ///
+    /// Same as [new](Self::new), except that exactly the given number of mip levels are allocated instead of the full mip chain.
+    /// The mip maps are generated each time the texture has been written to and the mip map filter is ignored if the number of mip levels is 1.
+    ///
+    /// # Errors
+    /// Will return an error if the number of mip levels is 0 or larger than the number of levels in the full mip chain.
+    /// Will also return an error if the format is a float format, for example [RGBA16F](crate::Format::RGBA16F),
+    /// and rendering into float textures is not supported (see [Capabilities::color_buffer_float](crate::Capabilities::color_buffer_float)).
+    ///
+    pub fn new_with_mip_maps(
+        context: &Context,
+        width: usize,
+        height: usize,
+        depth: usize,
+        number_of_mip_maps: u32,
+        min_filter: Interpolation,
+        mag_filter: Interpolation,
+        mip_map_filter: Option<Interpolation>,
+        wrap_s: Wrapping,
+        wrap_t: Wrapping,
+        format: Format,
+    ) -> Result<Self, Error> {
+        check_size(context, width, height, depth)?;
+        check_number_of_mip_maps(number_of_mip_maps, width, height)?;
+        check_color_target_format(context, format)?;
         let id = generate(context)?;
-        let number_of_mip_maps = calculate_number_of_mip_maps(mip_map_filter, width, height, depth);
         set_parameters(
             context,
             &id,
@@ -491,7 +930,7 @@ impl ColorTargetTexture2DArray {
             wrap_t,
             None,
         );
-        context.bind_texture(consts::TEXTURE_2D_ARRAY, &id);
+        bind_texture(context, consts::TEXTURE_2D_ARRAY, &id);
         context.tex_storage_3d(
             consts::TEXTURE_2D_ARRAY,
             number_of_mip_maps,
@@ -507,14 +946,88 @@ impl ColorTargetTexture2DArray {
             height,
             depth,
             number_of_mip_maps,
+            base_level: Cell::new(0),
         })
     }
 
+    ///
+    /// Returns the number of allocated mip levels.
+    ///
+    pub fn number_of_mip_maps(&self) -> u32 {
+        self.number_of_mip_maps
+    }
+
+    ///
+    /// Sets the lowest mip level which is used when sampling the texture, ie. the level used when sampling at full resolution.
+    ///
+    pub fn set_base_level(&self, level: u32) {
+        self.base_level.set(level);
+        bind_texture(&self.context, consts::TEXTURE_2D_ARRAY, &self.id);
+        self.context.tex_parameteri(
+            consts::TEXTURE_2D_ARRAY,
+            consts::TEXTURE_BASE_LEVEL,
+            level as i32,
+        );
+    }
+
+    ///
+    /// Sets the highest mip level which is used when sampling the texture. Mip levels above this level are not generated either.
+    ///
+    pub fn set_max_level(&self, level: u32) {
+        bind_texture(&self.context, consts::TEXTURE_2D_ARRAY, &self.id);
+        self.context.tex_parameteri(
+            consts::TEXTURE_2D_ARRAY,
+            consts::TEXTURE_MAX_LEVEL,
+            level as i32,
+        );
+    }
+
+    ///
+    /// Changes the interpolation of the texture without reallocating it, see [Interpolation].
+    /// The mip map filter is ignored if the texture does not have mip maps.
+    ///
+    pub fn set_interpolation(
+        &self,
+        min_filter: Interpolation,
+        mag_filter: Interpolation,
+        mip_map_filter: Option<Interpolation>,
+    ) {
+        bind_texture(&self.context, consts::TEXTURE_2D_ARRAY, &self.id);
+        set_interpolation_parameters(
+            &self.context,
+            consts::TEXTURE_2D_ARRAY,
+            min_filter,
+            mag_filter,
+            if self.number_of_mip_maps == 1 {
+                None
+            } else {
+                mip_map_filter
+            },
+        );
+    }
+
+    ///
+    /// Changes the wrapping of the texture without reallocating it, see [Wrapping].
+    ///
+    pub fn set_wrapping(&self, wrap_s: Wrapping, wrap_t: Wrapping) {
+        bind_texture(&self.context, consts::TEXTURE_2D_ARRAY, &self.id);
+        set_wrapping_parameters(
+            &self.context,
+            consts::TEXTURE_2D_ARRAY,
+            wrap_s,
+            wrap_t,
+            None,
+        );
+    }
+
     pub(crate) fn generate_mip_maps(&self) {
         if self.number_of_mip_maps > 1 {
-            self.context
-                .bind_texture(consts::TEXTURE_2D_ARRAY, &self.id);
-            self.context.generate_mipmap(consts::TEXTURE_2D_ARRAY);
+            generate_mip_maps_from_level_zero(
+                &self.context,
+                &self.id,
+                consts::TEXTURE_2D_ARRAY,
+                self.base_level.get(),
+            );
         }
     }
 
@@ -527,6 +1040,29 @@ impl ColorTargetTexture2DArray {
             layer as u32,
         );
     }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn bind_as_layered_color_target(&self) {
+        self.context.framebuffer_texture(
+            consts::DRAW_FRAMEBUFFER,
+            consts::COLOR_ATTACHMENT0,
+            &self.id,
+            0,
+        );
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl internal::TextureObject for ColorTargetTexture2DArray {
+    fn context(&self) -> &Context {
+        &self.context
+    }
+    fn id(&self) -> &crate::context::Texture {
+        &self.id
+    }
+    fn target(&self) -> u32 {
+        consts::TEXTURE_2D_ARRAY
+    }
 }
 
 impl Texture for ColorTargetTexture2DArray {
@@ -546,7 +1082,7 @@ impl Texture for ColorTargetTexture2DArray {
 
 impl Drop for ColorTargetTexture2DArray {
     fn drop(&mut self) {
-        self.context.delete_texture(&self.id);
+        delete_texture(&self.context, &self.id);
     }
 }
 
@@ -559,6 +1095,7 @@ pub struct DepthTargetTexture2DArray {
     width: usize,
     height: usize,
     depth: usize,
+    format: DepthFormat,
 }
 
 impl DepthTargetTexture2DArray {
@@ -571,6 +1108,7 @@ impl DepthTargetTexture2DArray {
         wrap_t: Wrapping,
         format: DepthFormat,
     ) -> Result<Self, Error> {
+        check_size(context, width, height, depth)?;
         let id = generate(context)?;
         set_parameters(
             context,
@@ -583,7 +1121,7 @@ impl DepthTargetTexture2DArray {
             wrap_t,
             None,
         );
-        context.bind_texture(consts::TEXTURE_2D_ARRAY, &id);
+        bind_texture(context, consts::TEXTURE_2D_ARRAY, &id);
         context.tex_storage_3d(
             consts::TEXTURE_2D_ARRAY,
             1,
@@ -598,18 +1136,56 @@ impl DepthTargetTexture2DArray {
             width,
             height,
             depth,
+            format,
         })
     }
 
+    ///
+    /// Changes the wrapping of the texture without reallocating it, see [Wrapping].
+    ///
+    pub fn set_wrapping(&self, wrap_s: Wrapping, wrap_t: Wrapping) {
+        bind_texture(&self.context, consts::TEXTURE_2D_ARRAY, &self.id);
+        set_wrapping_parameters(
+            &self.context,
+            consts::TEXTURE_2D_ARRAY,
+            wrap_s,
+            wrap_t,
+            None,
+        );
+    }
+
     pub(crate) fn bind_as_depth_target(&self, layer: usize) {
         self.context.framebuffer_texture_layer(
             consts::DRAW_FRAMEBUFFER,
-            consts::DEPTH_ATTACHMENT,
+            attachment_from_depth(self.format),
             &self.id,
             0,
             layer as u32,
         );
     }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn bind_as_layered_depth_target(&self) {
+        self.context.framebuffer_texture(
+            consts::DRAW_FRAMEBUFFER,
+            attachment_from_depth(self.format),
+            &self.id,
+            0,
+        );
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl internal::TextureObject for DepthTargetTexture2DArray {
+    fn context(&self) -> &Context {
+        &self.context
+    }
+    fn id(&self) -> &crate::context::Texture {
+        &self.id
+    }
+    fn target(&self) -> u32 {
+        consts::TEXTURE_2D_ARRAY
+    }
 }
 
 impl Texture for DepthTargetTexture2DArray {
@@ -629,12 +1205,13 @@ impl Texture for DepthTargetTexture2DArray {
 
 impl Drop for DepthTargetTexture2DArray {
     fn drop(&mut self) {
-        self.context.delete_texture(&self.id);
+        delete_texture(&self.context, &self.id);
     }
 }
 
 // COMMON FUNCTIONS
 fn generate(context: &Context) -> Result<crate::context::Texture, Error> {
+    check_context(context)?;
     context
         .create_texture()
         .ok_or_else(|| Error::FailedToCreateTexture {
@@ -642,9 +1219,73 @@ fn generate(context: &Context) -> Result<crate::context::Texture, Error> {
         })
 }
 
+fn check_size(context: &Context, width: usize, height: usize, depth: usize) -> Result<(), Error> {
+    let capabilities = context.capabilities();
+    if width > capabilities.max_texture_size as usize
+        || height > capabilities.max_texture_size as usize
+    {
+        Err(Error::FailedToCreateTexture {
+            message: format!(
+                "The texture size {}x{} exceeds the maximum texture size {}",
+                width, height, capabilities.max_texture_size
+            ),
+        })?;
+    }
+    if depth > capabilities.max_array_texture_layers as usize {
+        Err(Error::FailedToCreateTexture {
+            message: format!(
+                "The number of texture array layers {} exceeds the maximum number of layers {}",
+                depth, capabilities.max_array_texture_layers
+            ),
+        })?;
+    }
+    Ok(())
+}
+
+fn check_cube_map_size(context: &Context, width: usize, height: usize) -> Result<(), Error> {
+    let max_size = context.capabilities().max_cube_map_texture_size as usize;
+    if width > max_size || height > max_size {
+        Err(Error::FailedToCreateTexture {
+            message: format!(
+                "The cube map size {}x{} exceeds the maximum cube map size {}",
+                width, height, max_size
+            ),
+        })?;
+    }
+    Ok(())
+}
+
 fn bind_at(context: &Context, id: &crate::context::Texture, target: u32, location: u32) {
-    context.active_texture(consts::TEXTURE0 + location);
+    let mut cache = context.state_cache();
+    if cache.bound_textures.get(&location) == Some(id) {
+        return;
+    }
+    if cache.active_texture_unit != Some(location) {
+        context.active_texture(consts::TEXTURE0 + location);
+        cache.active_texture_unit = Some(location);
+    }
+    drop(cache);
+    bind_texture(context, target, id);
+}
+
+fn bind_texture(context: &Context, target: u32, id: &crate::context::Texture) {
     context.bind_texture(target, id);
+    let mut cache = context.state_cache();
+    // The texture could have been bound to any of the texture units, since the active unit is unknown
+    match cache.active_texture_unit {
+        Some(unit) => {
+            cache.bound_textures.insert(unit, id.to_owned());
+        }
+        None => cache.bound_textures.clear(),
+    }
+}
+
+fn delete_texture(context: &Context, id: &crate::context::Texture) {
+    context
+        .state_cache()
+        .bound_textures
+        .retain(|_, bound_id| bound_id != id);
+    context.delete_texture(id);
 }
 
 fn set_parameters(
@@ -658,7 +1299,18 @@ fn set_parameters(
     wrap_t: Wrapping,
     wrap_r: Option<Wrapping>,
 ) {
-    context.bind_texture(target, id);
+    bind_texture(context, target, id);
+    set_interpolation_parameters(context, target, min_filter, mag_filter, mip_map_filter);
+    set_wrapping_parameters(context, target, wrap_s, wrap_t, wrap_r);
+}
+
+fn set_interpolation_parameters(
+    context: &Context,
+    target: u32,
+    min_filter: Interpolation,
+    mag_filter: Interpolation,
+    mip_map_filter: Option<Interpolation>,
+) {
     match mip_map_filter {
         None => context.tex_parameteri(
             target,
@@ -701,6 +1353,15 @@ fn set_parameters(
         consts::TEXTURE_MAG_FILTER,
         interpolation_from(mag_filter),
     );
+}
+
+fn set_wrapping_parameters(
+    context: &Context,
+    target: u32,
+    wrap_s: Wrapping,
+    wrap_t: Wrapping,
+    wrap_r: Option<Wrapping>,
+) {
     context.tex_parameteri(target, consts::TEXTURE_WRAP_S, wrapping_from(wrap_s));
     context.tex_parameteri(target, consts::TEXTURE_WRAP_T, wrapping_from(wrap_t));
     if let Some(r) = wrap_r {
@@ -715,15 +1376,50 @@ fn calculate_number_of_mip_maps(
     depth: usize,
 ) -> u32 {
     if mip_map_filter.is_some() {
-        let w = (width as f64).log2().ceil();
-        let h = (height as f64).log2().ceil();
-        let d = (depth as f64).log2().ceil();
-        w.max(h).max(d).floor() as u32 + 1
+        // floor(log2(size)) + 1, since the size of each level is the size of the previous level divided by two and rounded down
+        let size = width.max(height).max(depth).max(1);
+        (usize::BITS - size.leading_zeros()) as u32
     } else {
         1
     }
 }
 
+fn check_number_of_mip_maps(
+    number_of_mip_maps: u32,
+    width: usize,
+    height: usize,
+) -> Result<(), Error> {
+    let max = calculate_number_of_mip_maps(Some(Interpolation::Nearest), width, height, 1);
+    if number_of_mip_maps == 0 || number_of_mip_maps > max {
+        Err(Error::FailedToCreateTexture {
+            message: format!(
+                "The number of mip levels must be between 1 and {} for a {}x{} texture, but {} was given.",
+                max, width, height, number_of_mip_maps
+            ),
+        })
+    } else {
+        Ok(())
+    }
+}
+
+// The mip maps are generated from the base level, but the texture is always written to level 0,
+// so the base level is temporarily reset to 0.
+fn generate_mip_maps_from_level_zero(
+    context: &Context,
+    id: &crate::context::Texture,
+    target: u32,
+    base_level: u32,
+) {
+    bind_texture(context, target, id);
+    if base_level != 0 {
+        context.tex_parameteri(target, consts::TEXTURE_BASE_LEVEL, 0);
+    }
+    context.generate_mipmap(target);
+    if base_level != 0 {
+        context.tex_parameteri(target, consts::TEXTURE_BASE_LEVEL, base_level as i32);
+    }
+}
+
 fn check_u8_format(format: Format) -> Result<(), Error> {
     if format == Format::R8
         || format == Format::RGB8
@@ -740,7 +1436,7 @@ fn check_u8_format(format: Format) -> Result<(), Error> {
 }
 
 fn check_f32_format(format: Format) -> Result<(), Error> {
-    if format == Format::R32F || format == Format::RGB32F || format == Format::RGBA32F {
+    if is_float_format(format) {
         Ok(())
     } else {
         Err(Error::FailedToCreateTexture {
@@ -760,6 +1456,9 @@ fn check_data_length(
     let actual_pixels = length
         / match format_from(format) {
             consts::RED => 1,
+            consts::RED_INTEGER => 1,
+            consts::RG => 2,
+            consts::RG_INTEGER => 2,
             consts::RGB => 3,
             consts::RGBA => 4,
             _ => unreachable!(),
@@ -786,14 +1485,52 @@ fn internal_format_from(format: Format) -> u32 {
         Format::R32F => consts::R32F,
         Format::RGB32F => consts::RGB32F,
         Format::RGBA32F => consts::RGBA32F,
+        Format::R16F => consts::R16F,
+        Format::RG16F => consts::RG16F,
+        Format::RGBA16F => consts::RGBA16F,
+        Format::R32UI => consts::R32UI,
+        Format::RG32UI => consts::RG32UI,
     }
 }
 
+fn is_float_format(format: Format) -> bool {
+    format == Format::R32F
+        || format == Format::RGB32F
+        || format == Format::RGBA32F
+        || format == Format::R16F
+        || format == Format::RG16F
+        || format == Format::RGBA16F
+}
+
+fn check_color_target_format(context: &Context, format: Format) -> Result<(), Error> {
+    if is_float_format(format) && !context.capabilities().color_buffer_float {
+        Err(Error::FailedToCreateTexture {
+            message: format!(
+                "Cannot render to a texture with the float format {:?} since it is not supported by the context.",
+                format
+            ),
+        })?;
+    }
+    Ok(())
+}
+
+pub(crate) fn is_integer_format(format: Format) -> bool {
+    format == Format::R32UI || format == Format::RG32UI
+}
+
 fn internal_format_from_depth(format: DepthFormat) -> u32 {
     match format {
         DepthFormat::Depth16 => consts::DEPTH_COMPONENT16,
         DepthFormat::Depth24 => consts::DEPTH_COMPONENT24,
         DepthFormat::Depth32F => consts::DEPTH_COMPONENT32F,
+        DepthFormat::Depth24Stencil8 => consts::DEPTH24_STENCIL8,
+    }
+}
+
+fn attachment_from_depth(format: DepthFormat) -> u32 {
+    match format {
+        DepthFormat::Depth24Stencil8 => consts::DEPTH_STENCIL_ATTACHMENT,
+        _ => consts::DEPTH_ATTACHMENT,
     }
 }
 
@@ -807,6 +1544,11 @@ fn format_from(format: Format) -> u32 {
         Format::RGBA8 => consts::RGBA,
         Format::RGBA32F => consts::RGBA,
         Format::SRGBA8 => consts::RGBA,
+        Format::R16F => consts::RED,
+        Format::RG16F => consts::RG,
+        Format::RGBA16F => consts::RGBA,
+        Format::R32UI => consts::RED_INTEGER,
+        Format::RG32UI => consts::RG_INTEGER,
     }
 }
 
@@ -815,6 +1557,8 @@ fn wrapping_from(wrapping: Wrapping) -> i32 {
         Wrapping::Repeat => consts::REPEAT,
         Wrapping::MirroredRepeat => consts::MIRRORED_REPEAT,
         Wrapping::ClampToEdge => consts::CLAMP_TO_EDGE,
+        #[cfg(not(target_arch = "wasm32"))]
+        Wrapping::ClampToBorder => consts::CLAMP_TO_BORDER,
     }) as i32
 }
 
@@ -824,3 +1568,22 @@ fn interpolation_from(interpolation: Interpolation) -> i32 {
         Interpolation::Linear => consts::LINEAR,
     }) as i32
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn number_of_mip_maps() {
+        let filter = Some(Interpolation::Linear);
+        assert_eq!(calculate_number_of_mip_maps(None, 8, 8, 1), 1);
+        assert_eq!(calculate_number_of_mip_maps(filter, 1, 1, 1), 1);
+        assert_eq!(calculate_number_of_mip_maps(filter, 8, 8, 1), 4);
+        assert_eq!(calculate_number_of_mip_maps(filter, 8, 2, 1), 4);
+        // Non power of two sizes are rounded down at each level, ie. 5x5, 2x2 and 1x1
+        assert_eq!(calculate_number_of_mip_maps(filter, 5, 5, 1), 3);
+        assert_eq!(calculate_number_of_mip_maps(filter, 7, 3, 1), 3);
+        assert!(check_number_of_mip_maps(3, 5, 5).is_ok());
+        assert!(check_number_of_mip_maps(4, 5, 5).is_err());
+    }
+}
@@ -0,0 +1,44 @@
+use crate::context::{consts, Context};
+use crate::core::Error;
+
+///
+/// A transform feedback object which captures the outputs of the vertex shader into [vertex buffers](crate::VertexBuffer)
+/// instead of (or in addition to) rasterizing the primitives.
+/// This makes it possible to for example update particles entirely on the GPU by ping-ponging between two sets of buffers
+/// (see [from_source_with_transform_feedback](crate::Program::from_source_with_transform_feedback)
+/// and [draw_arrays_with_transform_feedback](crate::Program::draw_arrays_with_transform_feedback)).
+///
+pub struct TransformFeedback {
+    context: Context,
+    id: crate::context::TransformFeedback,
+}
+
+impl TransformFeedback {
+    pub fn new(context: &Context) -> Result<TransformFeedback, Error> {
+        let id = context.create_transform_feedback().ok_or_else(|| {
+            Error::FailedToCreateTransformFeedback {
+                message: "Unable to create transform feedback object".to_string(),
+            }
+        })?;
+        Ok(TransformFeedback {
+            context: context.clone(),
+            id,
+        })
+    }
+
+    pub(crate) fn bind(&self) {
+        self.context
+            .bind_transform_feedback(consts::TRANSFORM_FEEDBACK, Some(&self.id));
+    }
+
+    pub(crate) fn unbind(&self) {
+        self.context
+            .bind_transform_feedback(consts::TRANSFORM_FEEDBACK, None);
+    }
+}
+
+impl Drop for TransformFeedback {
+    fn drop(&mut self) {
+        self.context.delete_transform_feedback(&self.id);
+    }
+}
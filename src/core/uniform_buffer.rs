@@ -1,5 +1,5 @@
 use crate::context::{consts, Context};
-use crate::core::Error;
+use crate::core::{check_context, Error};
 
 ///
 /// A buffer for transferring a set of uniform variables to the shader program
@@ -14,6 +14,7 @@ pub struct UniformBuffer {
 
 impl UniformBuffer {
     pub fn new(context: &Context, sizes: &[u32]) -> Result<UniformBuffer, Error> {
+        check_context(context)?;
         let id = context.create_buffer().unwrap();
 
         let mut offsets = Vec::new();
@@ -36,19 +37,29 @@ impl UniformBuffer {
     }
 
     pub fn update(&mut self, index: usize, data: &[f32]) -> Result<(), Error> {
-        let (offset, length) = self.offset_length(index)?;
-        if data.len() != length {
-            return Err(Error::FailedToUpdateBuffer {
-                message: format!(
-                    "The uniform buffer data for index {} has length {} but it must be {}.",
-                    index,
-                    data.len(),
-                    length
-                ),
-            });
+        self.update_multiple(&[(index, data)])
+    }
+
+    ///
+    /// Same as [update](Self::update) for each of the given indices and data,
+    /// except that the buffer is only sent to the GPU once after all of the data is updated.
+    ///
+    pub fn update_multiple(&mut self, updates: &[(usize, &[f32])]) -> Result<(), Error> {
+        for (index, data) in updates {
+            let (offset, length) = self.offset_length(*index)?;
+            if data.len() != length {
+                return Err(Error::FailedToUpdateBuffer {
+                    message: format!(
+                        "The uniform buffer data for index {} has length {} but it must be {}.",
+                        index,
+                        data.len(),
+                        length
+                    ),
+                });
+            }
+            self.data
+                .splice(offset..offset + length, data.iter().cloned());
         }
-        self.data
-            .splice(offset..offset + length, data.iter().cloned());
         self.send();
         //TODO: Send to GPU (contextBufferSubData)
         Ok(())
@@ -59,6 +70,10 @@ impl UniformBuffer {
         Ok(&self.data[offset..offset + length])
     }
 
+    pub(crate) fn data(&self) -> &[f32] {
+        &self.data
+    }
+
     fn offset_length(&self, index: usize) -> Result<(usize, usize), Error> {
         if index >= self.offsets.len() {
             return Err(Error::FailedToUpdateBuffer {
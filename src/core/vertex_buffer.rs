@@ -1,5 +1,35 @@
 use crate::context::{consts, Context};
-use crate::core::Error;
+use crate::core::{check_context, Error};
+
+///
+/// A hint to the graphics driver of how often the data in a [vertex buffer](crate::VertexBuffer) or
+/// [element buffer](crate::ElementBuffer) is updated, so that the data can be placed in the most suitable memory.
+///
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum BufferUsage {
+    ///
+    /// The data is set once and used many times.
+    ///
+    Static,
+    ///
+    /// The data is updated repeatedly and used many times.
+    ///
+    Dynamic,
+    ///
+    /// The data is updated repeatedly, for example every frame, and used at most a few times.
+    ///
+    Stream,
+}
+
+impl BufferUsage {
+    pub(crate) fn to_const(self) -> u32 {
+        match self {
+            BufferUsage::Static => consts::STATIC_DRAW,
+            BufferUsage::Dynamic => consts::DYNAMIC_DRAW,
+            BufferUsage::Stream => consts::STREAM_DRAW,
+        }
+    }
+}
 
 ///
 /// A buffer containing per vertex data, for example positions, normals, uv coordinates or colors
@@ -14,28 +44,30 @@ pub struct VertexBuffer {
 
 impl VertexBuffer {
     pub fn new_with_static_u8(context: &Context, data: &[u8]) -> Result<VertexBuffer, Error> {
-        let id = context.create_buffer().unwrap();
-        let mut buffer = VertexBuffer {
-            context: context.clone(),
-            id,
-            count: 0,
-            data_type: consts::UNSIGNED_BYTE,
-        };
-        if data.len() > 0 {
-            buffer.fill_with_static_u8(data);
-        }
-        Ok(buffer)
+        Self::new_with_usage_u8(context, data, BufferUsage::Static)
     }
 
     pub fn fill_with_static_u8(&mut self, data: &[u8]) {
-        self.bind();
-        self.context
-            .buffer_data_u8(consts::ARRAY_BUFFER, data, consts::STATIC_DRAW);
-        self.context.unbind_buffer(consts::ARRAY_BUFFER);
-        self.count = data.len();
+        self.fill_with_usage_u8(data, BufferUsage::Static);
     }
 
     pub fn new_with_dynamic_u8(context: &Context, data: &[u8]) -> Result<VertexBuffer, Error> {
+        Self::new_with_usage_u8(context, data, BufferUsage::Dynamic)
+    }
+
+    pub fn fill_with_dynamic_u8(&mut self, data: &[u8]) {
+        self.fill_with_usage_u8(data, BufferUsage::Dynamic);
+    }
+
+    ///
+    /// Creates a new vertex buffer with the given data and [usage](crate::BufferUsage) hint.
+    ///
+    pub fn new_with_usage_u8(
+        context: &Context,
+        data: &[u8],
+        usage: BufferUsage,
+    ) -> Result<VertexBuffer, Error> {
+        check_context(context)?;
         let id = context.create_buffer().unwrap();
         let mut buffer = VertexBuffer {
             context: context.clone(),
@@ -44,42 +76,47 @@ impl VertexBuffer {
             data_type: consts::UNSIGNED_BYTE,
         };
         if data.len() > 0 {
-            buffer.fill_with_dynamic_u8(data);
+            buffer.fill_with_usage_u8(data, usage);
         }
         Ok(buffer)
     }
 
-    pub fn fill_with_dynamic_u8(&mut self, data: &[u8]) {
+    ///
+    /// Replaces the data in the buffer with the given data and [usage](crate::BufferUsage) hint.
+    ///
+    pub fn fill_with_usage_u8(&mut self, data: &[u8], usage: BufferUsage) {
         self.bind();
         self.context
-            .buffer_data_u8(consts::ARRAY_BUFFER, data, consts::DYNAMIC_DRAW);
+            .buffer_data_u8(consts::ARRAY_BUFFER, data, usage.to_const());
         self.context.unbind_buffer(consts::ARRAY_BUFFER);
         self.count = data.len();
     }
 
     pub fn new_with_static_f32(context: &Context, data: &[f32]) -> Result<VertexBuffer, Error> {
-        let id = context.create_buffer().unwrap();
-        let mut buffer = VertexBuffer {
-            context: context.clone(),
-            id,
-            count: 0,
-            data_type: consts::FLOAT,
-        };
-        if data.len() > 0 {
-            buffer.fill_with_static_f32(data);
-        }
-        Ok(buffer)
+        Self::new_with_usage_f32(context, data, BufferUsage::Static)
     }
 
     pub fn fill_with_static_f32(&mut self, data: &[f32]) {
-        self.bind();
-        self.context
-            .buffer_data_f32(consts::ARRAY_BUFFER, data, consts::STATIC_DRAW);
-        self.context.unbind_buffer(consts::ARRAY_BUFFER);
-        self.count = data.len();
+        self.fill_with_usage_f32(data, BufferUsage::Static);
     }
 
     pub fn new_with_dynamic_f32(context: &Context, data: &[f32]) -> Result<VertexBuffer, Error> {
+        Self::new_with_usage_f32(context, data, BufferUsage::Dynamic)
+    }
+
+    pub fn fill_with_dynamic_f32(&mut self, data: &[f32]) {
+        self.fill_with_usage_f32(data, BufferUsage::Dynamic);
+    }
+
+    ///
+    /// Creates a new vertex buffer with the given data and [usage](crate::BufferUsage) hint.
+    ///
+    pub fn new_with_usage_f32(
+        context: &Context,
+        data: &[f32],
+        usage: BufferUsage,
+    ) -> Result<VertexBuffer, Error> {
+        check_context(context)?;
         let id = context.create_buffer().unwrap();
         let mut buffer = VertexBuffer {
             context: context.clone(),
@@ -88,15 +125,18 @@ impl VertexBuffer {
             data_type: consts::FLOAT,
         };
         if data.len() > 0 {
-            buffer.fill_with_dynamic_f32(data);
+            buffer.fill_with_usage_f32(data, usage);
         }
         Ok(buffer)
     }
 
-    pub fn fill_with_dynamic_f32(&mut self, data: &[f32]) {
+    ///
+    /// Replaces the data in the buffer with the given data and [usage](crate::BufferUsage) hint.
+    ///
+    pub fn fill_with_usage_f32(&mut self, data: &[f32], usage: BufferUsage) {
         self.bind();
         self.context
-            .buffer_data_f32(consts::ARRAY_BUFFER, data, consts::DYNAMIC_DRAW);
+            .buffer_data_f32(consts::ARRAY_BUFFER, data, usage.to_const());
         self.context.unbind_buffer(consts::ARRAY_BUFFER);
         self.count = data.len();
     }
@@ -109,6 +149,13 @@ impl VertexBuffer {
         self.context.bind_buffer(consts::ARRAY_BUFFER, &self.id);
     }
 
+    pub(crate) fn bind_as_transform_feedback_target(&self, index: u32) {
+        self.context
+            .bind_buffer_base(consts::TRANSFORM_FEEDBACK_BUFFER, index, &self.id);
+        self.context
+            .unbind_buffer(consts::TRANSFORM_FEEDBACK_BUFFER);
+    }
+
     pub(crate) fn data_type(&self) -> u32 {
         self.data_type
     }
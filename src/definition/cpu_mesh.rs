@@ -14,6 +14,12 @@ pub struct CPUMesh {
     pub normals: Option<Vec<f32>>,
     pub uvs: Option<Vec<f32>>,
     pub colors: Option<Vec<u8>>,
+    ///
+    /// Per vertex colors with full float precision, four values between 0 and 1 per vertex, or larger for high dynamic range.
+    /// Use this instead of [colors](Self::colors) when the colors should not be quantized to 8 bits,
+    /// for example when the color encodes a continuous value. Takes precedence over [colors](Self::colors) if both are specified.
+    ///
+    pub colors_f32: Option<Vec<f32>>,
 }
 
 impl CPUMesh {
@@ -36,6 +42,30 @@ impl CPUMesh {
         }
     }
 
+    pub fn cube(size: f32) -> Self {
+        let h = 0.5 * size;
+        let mut positions = Vec::with_capacity(24);
+        for i in 0..8 {
+            positions.push(if i & 1 == 0 { -h } else { h });
+            positions.push(if i & 2 == 0 { -h } else { h });
+            positions.push(if i & 4 == 0 { -h } else { h });
+        }
+        let indices = vec![
+            4, 5, 7, 7, 6, 4, // +z
+            0, 2, 3, 3, 1, 0, // -z
+            1, 3, 7, 7, 5, 1, // +x
+            0, 4, 6, 6, 2, 0, // -x
+            2, 6, 7, 7, 3, 2, // +y
+            0, 1, 5, 5, 4, 0, // -y
+        ];
+        CPUMesh {
+            name: "cube".to_string(),
+            indices: Some(indices),
+            positions,
+            ..Default::default()
+        }
+    }
+
     pub fn circle(radius: f32, angle_subdivisions: u32) -> Self {
         let mut positions = Vec::new();
         let mut indices = Vec::new();
@@ -201,6 +231,51 @@ impl CPUMesh {
         }
     }
 
+    ///
+    /// Transforms the positions and normals of the mesh with the given transformation, for example to change the coordinate system
+    /// of an imported mesh using [convert_z_up_to_y_up](crate::convert_z_up_to_y_up) or [convert_handedness](crate::convert_handedness).
+    /// The normals are transformed with the inverse transpose of the transformation, so they stay perpendicular to the surface,
+    /// and if the transformation mirrors the mesh, the winding order of the triangles is reversed so the front faces stay the front faces.
+    /// If the transformation is not invertible, for example a scale of zero along an axis, the normals are not transformed,
+    /// since the transformed mesh is flat and does not have well defined normals.
+    ///
+    pub fn transform(&mut self, transformation: &Mat4) {
+        for position in self.positions.chunks_exact_mut(3) {
+            let p = transformation * vec4(position[0], position[1], position[2], 1.0);
+            position.copy_from_slice(&[p.x, p.y, p.z]);
+        }
+        if let (Some(normals), Some(inverse)) = (&mut self.normals, transformation.invert()) {
+            let normal_transformation = inverse.transpose();
+            for normal in normals.chunks_exact_mut(3) {
+                let n = (normal_transformation * vec4(normal[0], normal[1], normal[2], 0.0))
+                    .truncate()
+                    .normalize();
+                normal.copy_from_slice(&[n.x, n.y, n.z]);
+            }
+        }
+        if transformation.determinant() < 0.0 {
+            if let Some(ref mut indices) = self.indices {
+                for face in indices.chunks_exact_mut(3) {
+                    face.swap(1, 2);
+                }
+            } else {
+                swap_second_and_third_vertex(&mut self.positions, 3);
+                if let Some(ref mut normals) = self.normals {
+                    swap_second_and_third_vertex(normals, 3);
+                }
+                if let Some(ref mut uvs) = self.uvs {
+                    swap_second_and_third_vertex(uvs, 2);
+                }
+                if let Some(ref mut colors) = self.colors {
+                    swap_second_and_third_vertex(colors, 4);
+                }
+                if let Some(ref mut colors) = self.colors_f32 {
+                    swap_second_and_third_vertex(colors, 4);
+                }
+            }
+        }
+    }
+
     ///
     /// Computes the axis aligned bounding box of the mesh.
     ///
@@ -209,6 +284,14 @@ impl CPUMesh {
     }
 }
 
+// Swaps the second and third vertex of each triangle, where each vertex has the given number of components.
+fn swap_second_and_third_vertex<T>(data: &mut [T], components: usize) {
+    for triangle in data.chunks_exact_mut(3 * components) {
+        let (second, third) = triangle[components..].split_at_mut(components);
+        second.swap_with_slice(third);
+    }
+}
+
 fn compute_normals_with_indices(indices: &[u32], positions: &[f32]) -> Vec<f32> {
     let mut normals = vec![0.0f32; positions.len() * 3];
     for face in 0..indices.len() / 3 {
@@ -16,6 +16,12 @@ pub enum Wrapping {
     Repeat,
     MirroredRepeat,
     ClampToEdge,
+    ///
+    /// Returns the border color of the texture outside the texture (see [TextureParameters::set_border_color](crate::TextureParameters::set_border_color)),
+    /// which is transparent black by default. Only available on desktop.
+    ///
+    #[cfg(not(target_arch = "wasm32"))]
+    ClampToBorder,
 }
 
 ///
@@ -31,11 +37,27 @@ pub enum Format {
     RGBA8,
     SRGBA8,
     RGBA32F,
+    R16F,
+    RG16F,
+    RGBA16F,
+    ///
+    /// One channel containing a 32 bit unsigned integer, for example an object id.
+    /// Only supported by [ColorTargetTexture2D](crate::ColorTargetTexture2D) which is sampled with a `usampler2D` in the shader.
+    ///
+    R32UI,
+    ///
+    /// Two channels each containing a 32 bit unsigned integer.
+    /// Only supported by [ColorTargetTexture2D](crate::ColorTargetTexture2D) which is sampled with a `usampler2D` in the shader.
+    ///
+    RG32UI,
 }
 
 ///
 /// A CPU-side version of a texture (for example [2D texture](crate::Texture2D).
 /// Can be constructed manually or loaded via [io](crate::io).
+/// The first row of the data is the bottom row of the texture, ie. at the uv coordinate v = 0, which is the OpenGL convention.
+/// Images are usually stored with the first row at the top, including the images loaded via [io](crate::io),
+/// so set [flip_y](Self::flip_y) to true to upload them the other way round.
 ///
 pub struct CPUTexture<T> {
     pub data: Vec<T>,
@@ -49,6 +71,21 @@ pub struct CPUTexture<T> {
     pub wrap_s: Wrapping,
     pub wrap_t: Wrapping,
     pub wrap_r: Wrapping,
+    ///
+    /// Whether the rows of the data are flipped when uploaded to the GPU, so that the first row of the data becomes the top row of the texture.
+    /// For a cube map, each face is flipped. Note that the faces of a cube map are usually stored with the first row at the top,
+    /// which is the expected orientation of cube map faces, so they are displayed correctly in a [Skybox](crate::Skybox) without flipping.
+    /// Default is false.
+    ///
+    pub flip_y: bool,
+    ///
+    /// Whether the color channels are multiplied by the alpha channel when uploaded to the GPU, which is needed if the
+    /// data has straight alpha and the texture is rendered with [premultiplied alpha blending](crate::BlendParameters::PREMULTIPLIED_ALPHA).
+    /// Should be false if the data already has premultiplied alpha or if the texture is rendered with
+    /// [straight alpha blending](crate::BlendParameters::TRANSPARENCY). Only has an effect for formats with an alpha channel.
+    /// Default is false.
+    ///
+    pub premultiply_alpha: bool,
 }
 
 impl Default for CPUTexture<u8> {
@@ -65,6 +102,172 @@ impl Default for CPUTexture<u8> {
             wrap_s: Wrapping::Repeat,
             wrap_t: Wrapping::Repeat,
             wrap_r: Wrapping::Repeat,
+            flip_y: false,
+            premultiply_alpha: false,
         }
     }
 }
+
+impl Default for CPUTexture<f32> {
+    fn default() -> Self {
+        Self {
+            data: [1.0f32, 1.0, 0.0, 1.0].into(),
+            width: 1,
+            height: 1,
+            depth: 1,
+            format: Format::RGBA32F,
+            min_filter: Interpolation::Linear,
+            mag_filter: Interpolation::Linear,
+            mip_map_filter: Some(Interpolation::Linear),
+            wrap_s: Wrapping::Repeat,
+            wrap_t: Wrapping::Repeat,
+            wrap_r: Wrapping::Repeat,
+            flip_y: false,
+            premultiply_alpha: false,
+        }
+    }
+}
+
+impl CPUTexture<u8> {
+    // Returns the data as it should be uploaded to the given number of images, ie. flipped and premultiplied if specified.
+    pub(crate) fn upload_data(&self, images: usize) -> std::borrow::Cow<'_, [u8]> {
+        let mut data = std::borrow::Cow::Borrowed(&self.data[..]);
+        if self.flip_y {
+            data = std::borrow::Cow::Owned(flip_rows(&data, self.height, images));
+        }
+        if self.premultiply_alpha && (self.format == Format::RGBA8 || self.format == Format::SRGBA8)
+        {
+            for pixel in data.to_mut().chunks_mut(4) {
+                let alpha = pixel[3] as u32;
+                for value in pixel.iter_mut().take(3) {
+                    *value = ((*value as u32 * alpha + 127) / 255) as u8;
+                }
+            }
+        }
+        data
+    }
+}
+
+impl CPUTexture<f32> {
+    // Returns the data as it should be uploaded to the given number of images, ie. flipped and premultiplied if specified.
+    pub(crate) fn upload_data(&self, images: usize) -> std::borrow::Cow<'_, [f32]> {
+        let mut data = std::borrow::Cow::Borrowed(&self.data[..]);
+        if self.flip_y {
+            data = std::borrow::Cow::Owned(flip_rows(&data, self.height, images));
+        }
+        if self.premultiply_alpha
+            && (self.format == Format::RGBA32F || self.format == Format::RGBA16F)
+        {
+            for pixel in data.to_mut().chunks_mut(4) {
+                let alpha = pixel[3];
+                for value in pixel.iter_mut().take(3) {
+                    *value *= alpha;
+                }
+            }
+        }
+        data
+    }
+}
+
+// Returns the data with the rows of each of the given number of images in reverse order.
+pub(crate) fn flip_rows<T: Copy>(data: &[T], height: usize, images: usize) -> Vec<T> {
+    let rows = height * images;
+    let row_size = data.len().checked_div(rows).unwrap_or(0);
+    if row_size == 0 || row_size * rows != data.len() {
+        return data.to_vec();
+    }
+    let image_size = row_size * height;
+    let mut flipped = Vec::with_capacity(data.len());
+    for image in data.chunks(image_size) {
+        for row in image.chunks(row_size).rev() {
+            flipped.extend_from_slice(row);
+        }
+    }
+    flipped
+}
+
+///
+/// Compares two images, for example an image read from a render target using [RenderTarget::copy_color_to_cpu](crate::RenderTarget::copy_color_to_cpu)
+/// and a stored reference image, and returns the fraction of pixels (between 0 and 1) where at least one channel differs by more than the tolerance.
+/// Together with a headless context (see the `headless` feature), this can be used to test that the rendering has not changed.
+/// Images with a different size or format are completely different, ie. 1 is returned.
+///
+pub fn compare_images(a: &CPUTexture<u8>, b: &CPUTexture<u8>, tolerance: u8) -> f32 {
+    let pixels = a.width * a.height * a.depth;
+    if a.width != b.width
+        || a.height != b.height
+        || a.depth != b.depth
+        || a.format != b.format
+        || a.data.len() != b.data.len()
+    {
+        return 1.0;
+    }
+    if pixels == 0 || a.data.len() < pixels {
+        return if a.data == b.data { 0.0 } else { 1.0 };
+    }
+    let channels = a.data.len() / pixels;
+    let different_pixels = a
+        .data
+        .chunks(channels)
+        .zip(b.data.chunks(channels))
+        .filter(|(pixel_a, pixel_b)| {
+            pixel_a
+                .iter()
+                .zip(pixel_b.iter())
+                .any(|(value_a, value_b)| {
+                    (*value_a as i16 - *value_b as i16).abs() > tolerance as i16
+                })
+        })
+        .count();
+    different_pixels as f32 / pixels as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(width: usize, height: usize, data: Vec<u8>) -> CPUTexture<u8> {
+        CPUTexture {
+            data,
+            width,
+            height,
+            format: Format::RGBA8,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn compare_identical_images() {
+        let a = image(2, 1, vec![10, 20, 30, 255, 40, 50, 60, 255]);
+        assert_eq!(compare_images(&a, &a, 0), 0.0);
+    }
+
+    #[test]
+    fn compare_images_within_tolerance() {
+        let a = image(2, 1, vec![10, 20, 30, 255, 40, 50, 60, 255]);
+        let b = image(2, 1, vec![12, 18, 30, 253, 40, 50, 62, 255]);
+        assert_eq!(compare_images(&a, &b, 2), 0.0);
+        assert_eq!(compare_images(&a, &b, 1), 1.0);
+    }
+
+    #[test]
+    fn compare_images_with_mismatched_sizes() {
+        let a = image(2, 1, vec![0; 8]);
+        let b = image(1, 2, vec![0; 8]);
+        assert_eq!(compare_images(&a, &b, 255), 1.0);
+        let c = image(1, 1, vec![0; 4]);
+        assert_eq!(compare_images(&a, &c, 255), 1.0);
+    }
+
+    #[test]
+    fn compare_images_with_channel_differences() {
+        let a = image(4, 1, vec![0; 16]);
+        let mut b = a.data.clone();
+        // Only the alpha channel of the second pixel and the red channel of the last pixel differ
+        b[7] = 100;
+        b[12] = 100;
+        let b = image(4, 1, b);
+        assert_eq!(compare_images(&a, &b, 10), 0.5);
+        assert_eq!(compare_images(&a, &b, 100), 0.0);
+    }
+}
@@ -7,6 +7,11 @@ pub mod image_effect;
 #[doc(inline)]
 pub use image_effect::*;
 
+#[doc(hidden)]
+pub mod fullscreen_triangle;
+#[doc(inline)]
+pub use crate::fullscreen_triangle::*;
+
 #[doc(hidden)]
 pub mod fog;
 #[doc(inline)]
@@ -16,3 +21,33 @@ pub use crate::fog::*;
 pub mod fxaa;
 #[doc(inline)]
 pub use crate::fxaa::*;
+
+#[doc(hidden)]
+pub mod anaglyph;
+#[doc(inline)]
+pub use crate::anaglyph::*;
+
+#[doc(hidden)]
+pub mod motion_blur;
+#[doc(inline)]
+pub use crate::motion_blur::*;
+
+#[doc(hidden)]
+pub mod volumetric_light;
+#[doc(inline)]
+pub use crate::volumetric_light::*;
+
+#[doc(hidden)]
+pub mod bilateral_upsample;
+#[doc(inline)]
+pub use crate::bilateral_upsample::*;
+
+#[doc(hidden)]
+pub mod linear_depth;
+#[doc(inline)]
+pub use crate::linear_depth::*;
+
+#[doc(hidden)]
+pub mod dynamic_resolution;
+#[doc(inline)]
+pub use crate::dynamic_resolution::*;
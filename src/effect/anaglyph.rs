@@ -0,0 +1,42 @@
+use crate::core::*;
+use crate::effect::*;
+use crate::math::*;
+
+///
+/// Combines a render of the scene from the left eye and a render from the right eye into a red-cyan anaglyph image,
+/// which gives a stereoscopic effect when viewed with red-cyan glasses.
+///
+pub struct AnaglyphEffect {
+    image_effect: ImageEffect,
+}
+
+impl AnaglyphEffect {
+    pub fn new(context: &Context) -> Result<Self, Error> {
+        Ok(Self {
+            image_effect: ImageEffect::new(context, include_str!("shaders/anaglyph.frag"))?,
+        })
+    }
+
+    ///
+    /// Combines the given left and right eye color textures into the pixels inside the given viewport.
+    /// The red channel is taken from the left texture and the green and blue channels from the right texture.
+    /// The entire textures are mapped to the viewport, so they should have the same aspect ratio as the viewport.
+    ///
+    pub fn apply(
+        &self,
+        left_texture: &dyn Texture,
+        right_texture: &dyn Texture,
+        viewport: Viewport,
+    ) -> Result<(), Error> {
+        let render_states = RenderStates {
+            cull: CullType::Back,
+            write_mask: WriteMask::COLOR,
+            depth_test: DepthTestType::Always,
+            ..Default::default()
+        };
+
+        self.image_effect
+            .inputs(&[("leftMap", left_texture), ("rightMap", right_texture)])
+            .apply(render_states, viewport)
+    }
+}
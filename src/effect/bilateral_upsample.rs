@@ -0,0 +1,64 @@
+use crate::camera::*;
+use crate::core::*;
+use crate::effect::*;
+use crate::math::*;
+
+///
+/// Upsamples a texture rendered at a lower resolution, for example the result of an ambient occlusion or
+/// [volumetric light](crate::VolumetricLightEffect) effect applied at half resolution, to the full resolution
+/// without bleeding across depth edges.
+/// Each pixel is interpolated between the nearest low resolution texels, but only the texels with a depth similar to the depth
+/// of the pixel are used, where the depths are given by a full resolution depth texture.
+///
+pub struct BilateralUpsampleEffect {
+    ///
+    /// The maximum difference between the depth of a low resolution texel and the depth of a pixel,
+    /// relative to the distance from the camera to the pixel, for the texel to be used. Default is 0.1.
+    ///
+    pub depth_threshold: f32,
+    image_effect: ImageEffect,
+}
+
+impl BilateralUpsampleEffect {
+    pub fn new(context: &Context) -> Result<Self, Error> {
+        Ok(Self {
+            depth_threshold: 0.1,
+            image_effect: ImageEffect::new(
+                context,
+                include_str!("shaders/bilateral_upsample.frag"),
+            )?,
+        })
+    }
+
+    ///
+    /// Writes the upsampled low resolution texture to the pixels inside the given viewport.
+    /// The depth texture is the full resolution depth rendered with the given camera, for example the
+    /// [geometry pass depth texture](crate::PhongDeferredPipeline::geometry_pass_depth_texture), and it must have the same size
+    /// as the render target, since it is sampled at the same pixel as the fragment.
+    ///
+    pub fn apply(
+        &self,
+        viewport: Viewport,
+        camera: &Camera,
+        low_res_texture: &dyn Texture,
+        full_res_depth_texture: &dyn Texture,
+    ) -> Result<(), Error> {
+        let render_states = RenderStates {
+            cull: CullType::Back,
+            write_mask: WriteMask::COLOR,
+            depth_test: DepthTestType::Always,
+            ..Default::default()
+        };
+
+        self.image_effect
+            .use_uniform_mat4("projectionInverse", &camera.projection().invert().unwrap())?;
+        self.image_effect
+            .use_uniform_float("depthThreshold", &self.depth_threshold)?;
+        self.image_effect
+            .inputs(&[
+                ("lowResTexture", low_res_texture),
+                ("depthMap", full_res_depth_texture),
+            ])
+            .apply(render_states, viewport)
+    }
+}
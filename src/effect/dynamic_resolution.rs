@@ -0,0 +1,133 @@
+use crate::core::*;
+use crate::definition::*;
+use crate::math::*;
+
+///
+/// Renders into an offscreen render target with a resolution which is a fraction of the resolution of the screen
+/// and upscales the result to the screen. The fraction, ie. the [scale](Self::scale), is adjusted in [update](Self::update)
+/// based on the measured frame time, so that a target frame time is kept on slow hardware by trading resolution for speed.
+///
+pub struct DynamicResolution {
+    context: Context,
+    ///
+    /// The frame time in milliseconds to aim for, for example 16.7 for 60 frames per second.
+    ///
+    pub target_frame_time: f64,
+    ///
+    /// The minimum scale of the resolution in each direction, between 0 and 1. Default is 0.5.
+    ///
+    pub min_scale: f32,
+    ///
+    /// The maximum scale of the resolution in each direction, between 0 and 1. Default is 1.
+    ///
+    pub max_scale: f32,
+    scale: f32,
+    average_frame_time: Option<f64>,
+    color_texture: Option<ColorTargetTexture2D>,
+    depth_texture: Option<DepthTargetTexture2D>,
+}
+
+impl DynamicResolution {
+    ///
+    /// Constructs a new dynamic resolution helper with the given target frame time in milliseconds, which starts rendering in full resolution.
+    ///
+    pub fn new(context: &Context, target_frame_time: f64) -> Self {
+        Self {
+            context: context.clone(),
+            target_frame_time,
+            min_scale: 0.5,
+            max_scale: 1.0,
+            scale: 1.0,
+            average_frame_time: None,
+            color_texture: None,
+            depth_texture: None,
+        }
+    }
+
+    ///
+    /// Returns the current scale of the resolution in each direction.
+    ///
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    ///
+    /// Adjusts the [scale](Self::scale) based on the given time in milliseconds it took to render the last frame,
+    /// for example [FrameInput::elapsed_time](crate::FrameInput::elapsed_time).
+    /// The frame time is averaged over a few frames and the scale is only changed gradually to avoid flickering between resolutions.
+    /// Note that with vertical sync enabled, the frame time is never below the refresh interval of the screen,
+    /// so the target frame time should be larger than the refresh interval.
+    ///
+    pub fn update(&mut self, frame_time: f64) {
+        let average_frame_time = match self.average_frame_time {
+            Some(average) => 0.9 * average + 0.1 * frame_time,
+            None => frame_time,
+        };
+        self.average_frame_time = Some(average_frame_time);
+        if average_frame_time <= 0.0 || self.target_frame_time <= 0.0 {
+            return;
+        }
+
+        // The frame time is roughly proportional to the number of pixels, ie. to the square of the scale
+        let ratio = self.target_frame_time / average_frame_time;
+        if !(0.95..=1.1).contains(&ratio) {
+            let change = (ratio.sqrt() as f32).clamp(0.95, 1.02);
+            self.scale = (self.scale * change)
+                .max(self.min_scale)
+                .min(self.max_scale);
+        }
+    }
+
+    ///
+    /// Renders whatever rendered in the **render** closure into an offscreen render target with the size of the given viewport
+    /// multiplied by the current [scale](Self::scale), rounded to a multiple of 0.05, and then copies the color and depth to the given viewport of the screen.
+    /// The render target is cleared based on the given clear state before rendering and the **render** closure gets the viewport
+    /// which covers the whole render target, which must be used for all render calls inside the closure.
+    ///
+    pub fn render<F: FnOnce(Viewport) -> Result<(), Error>>(
+        &mut self,
+        viewport: Viewport,
+        clear_state: &ClearState,
+        render: F,
+    ) -> Result<(), Error> {
+        // Quantized to avoid creating new textures every frame while the scale changes gradually
+        let scale = (self.scale * 20.0).round() / 20.0;
+        let width = ((viewport.width as f32 * scale).round() as usize).max(1);
+        let height = ((viewport.height as f32 * scale).round() as usize).max(1);
+        if self
+            .color_texture
+            .as_ref()
+            .map(|t| t.width() != width || t.height() != height)
+            .unwrap_or(true)
+        {
+            self.color_texture = Some(ColorTargetTexture2D::new(
+                &self.context,
+                width,
+                height,
+                Interpolation::Linear,
+                Interpolation::Linear,
+                None,
+                Wrapping::ClampToEdge,
+                Wrapping::ClampToEdge,
+                Format::RGBA8,
+            )?);
+            self.depth_texture = Some(DepthTargetTexture2D::new(
+                &self.context,
+                width,
+                height,
+                Wrapping::ClampToEdge,
+                Wrapping::ClampToEdge,
+                DepthFormat::Depth32F,
+            )?);
+        }
+        let render_target = RenderTarget::new(
+            &self.context,
+            self.color_texture.as_ref().unwrap(),
+            self.depth_texture.as_ref().unwrap(),
+        )?;
+        render_target.write(clear_state, || {
+            render(Viewport::new_at_origo(width, height))
+        })?;
+        render_target.copy_to_screen(viewport)
+    }
+}
@@ -23,6 +23,10 @@ impl FogEffect {
         })
     }
 
+    ///
+    /// Applies the fog effect to the pixels inside the given viewport.
+    /// The depth texture must have the same size as the render target, since it is sampled at the same pixel as the fragment.
+    ///
     pub fn apply(
         &self,
         viewport: Viewport,
@@ -38,6 +42,13 @@ impl FogEffect {
         };
 
         self.image_effect.use_texture(depth_texture, "depthMap")?;
+        self.image_effect.use_uniform_vec2(
+            "texelSize",
+            &vec2(
+                1.0 / depth_texture.width() as f32,
+                1.0 / depth_texture.height() as f32,
+            ),
+        )?;
         self.image_effect.use_uniform_mat4(
             "viewProjectionInverse",
             &(camera.projection() * camera.view()).invert().unwrap(),
@@ -0,0 +1,53 @@
+use crate::core::*;
+use crate::math::*;
+
+///
+/// Vertex shader source code which can be used together with a [FullscreenTriangle] for writing custom 2D effects.
+/// It outputs the `out vec2 uv;` varying, which goes from (0, 0) in the bottom left corner to (1, 1) in the top right corner of the viewport,
+/// the same convention as used by [ImageEffect](crate::ImageEffect).
+///
+pub const FULLSCREEN_VERTEX_SHADER_SOURCE: &str = "in vec3 position;
+                                                    in vec2 uv_coordinate;
+                                                    out vec2 uv;
+                                                    void main()
+                                                    {
+                                                        uv = uv_coordinate;
+                                                        gl_Position = vec4(position, 1.0);
+                                                    }";
+
+///
+/// A single triangle in clip space which covers the whole viewport, which is used by [ImageEffect](crate::ImageEffect) and
+/// can be used to draw a custom [Program] over all pixels inside a viewport.
+/// The program must have the `in vec3 position;` and `in vec2 uv_coordinate;` attributes,
+/// for example by using [FULLSCREEN_VERTEX_SHADER_SOURCE] as the vertex shader.
+///
+pub struct FullscreenTriangle {
+    positions: VertexBuffer,
+    uvs: VertexBuffer,
+}
+
+impl FullscreenTriangle {
+    pub fn new(context: &Context) -> Result<Self, Error> {
+        let positions = vec![-3.0, -1.0, 0.0, 3.0, -1.0, 0.0, 0.0, 2.0, 0.0];
+        let uvs = vec![-1.0, 0.0, 2.0, 0.0, 0.5, 1.5];
+        Ok(Self {
+            positions: VertexBuffer::new_with_static_f32(context, &positions)?,
+            uvs: VertexBuffer::new_with_static_f32(context, &uvs)?,
+        })
+    }
+
+    ///
+    /// Draws the given program over all pixels inside the given viewport.
+    ///
+    pub fn draw_fullscreen(
+        &self,
+        program: &Program,
+        render_states: RenderStates,
+        viewport: Viewport,
+    ) -> Result<(), Error> {
+        program.use_attribute_vec3(&self.positions, "position")?;
+        program.use_attribute_vec2(&self.uvs, "uv_coordinate")?;
+        program.draw_arrays(render_states, viewport, 3);
+        Ok(())
+    }
+}
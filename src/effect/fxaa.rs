@@ -10,6 +10,7 @@ pub struct FXAAEffect {
     pub color: Vec3,
     pub density: f32,
     pub animation: f32,
+    context: Context,
     image_effect: ImageEffect,
 }
 
@@ -19,10 +20,18 @@ impl FXAAEffect {
             color: vec3(0.8, 0.8, 0.8),
             density: 0.2,
             animation: 0.1,
+            context: gl.clone(),
             image_effect: ImageEffect::new(gl, include_str!("shaders/fxaa.frag"))?,
         })
     }
 
+    ///
+    /// Applies the anti-aliasing effect to the pixels inside the given viewport.
+    /// The color texture must have the same size as the render target, since it is sampled at the same pixel as the fragment.
+    /// Any color texture can be used, for example a [ColorTargetTexture2D](crate::ColorTargetTexture2D) which has been rendered into
+    /// using a [RenderTarget](crate::RenderTarget), but it must not be the color texture of the render target which is currently written to.
+    /// Must be called in a render target render function, for example in the callback function of [Screen::write](crate::Screen::write).
+    ///
     pub fn apply(&self, viewport: Viewport, color_texture: &dyn Texture) -> Result<(), Error> {
         let render_states = RenderStates {
             cull: CullType::Back,
@@ -33,11 +42,28 @@ impl FXAAEffect {
 
         self.image_effect.use_texture(color_texture, "colorMap")?;
         self.image_effect.use_uniform_vec2(
-            "resolution",
-            &vec2(color_texture.width() as f32, color_texture.height() as f32),
+            "texelSize",
+            &vec2(
+                1.0 / color_texture.width() as f32,
+                1.0 / color_texture.height() as f32,
+            ),
         )?;
 
         self.image_effect.apply(render_states, viewport)?;
         Ok(())
     }
+
+    ///
+    /// Applies the anti-aliasing effect to the given color texture and writes the result to the pixels inside the given viewport of the screen
+    /// without clearing the screen first. This is the same as calling [apply](Self::apply) in the callback function of [Screen::write](crate::Screen::write).
+    ///
+    pub fn apply_to_screen(
+        &self,
+        color_texture: &dyn Texture,
+        viewport: Viewport,
+    ) -> Result<(), Error> {
+        Screen::write(&self.context, &ClearState::none(), || {
+            self.apply(viewport, color_texture)
+        })
+    }
 }
@@ -1,4 +1,5 @@
 use crate::core::*;
+use crate::effect::*;
 use crate::math::*;
 
 ///
@@ -6,46 +7,146 @@ use crate::math::*;
 /// Can for example be used for adding an effect on top of the rendered 3D scene, like [fog](crate::FogEffect).
 ///
 pub struct ImageEffect {
+    context: Context,
     program: Program,
-    positions: VertexBuffer,
-    uvs: VertexBuffer,
+    triangle: FullscreenTriangle,
 }
 
 impl ImageEffect {
     pub fn new(context: &Context, fragment_shader: &str) -> Result<Self, Error> {
-        let program = program::Program::from_source(
-            &context,
-            "in vec3 position;
-                                                    in vec2 uv_coordinate;
-                                                    out vec2 uv;
-                                                    void main()
-                                                    {
-                                                        uv = uv_coordinate;
-                                                        gl_Position = vec4(position, 1.0);
-                                                    }",
-            fragment_shader,
-        )?;
+        Self::new_internal(context, fragment_shader, false)
+    }
 
-        let positions = vec![-3.0, -1.0, 0.0, 3.0, -1.0, 0.0, 0.0, 2.0, 0.0];
-        let uvs = vec![-1.0, 0.0, 2.0, 0.0, 0.5, 1.5];
-        let positions = VertexBuffer::new_with_static_f32(&context, &positions).unwrap();
-        let uvs = VertexBuffer::new_with_static_f32(&context, &uvs).unwrap();
+    ///
+    /// Same as [new](Self::new), except that the shader program is compiled in parallel if supported,
+    /// see [Program::from_source_parallel](crate::Program::from_source_parallel).
+    ///
+    pub fn new_parallel(context: &Context, fragment_shader: &str) -> Result<Self, Error> {
+        Self::new_internal(context, fragment_shader, true)
+    }
+
+    fn new_internal(
+        context: &Context,
+        fragment_shader: &str,
+        parallel: bool,
+    ) -> Result<Self, Error> {
+        let program = if parallel {
+            program::Program::from_source_parallel(
+                context,
+                FULLSCREEN_VERTEX_SHADER_SOURCE,
+                fragment_shader,
+            )?
+        } else {
+            program::Program::from_source(
+                context,
+                FULLSCREEN_VERTEX_SHADER_SOURCE,
+                fragment_shader,
+            )?
+        };
 
         Ok(Self {
+            context: context.clone(),
             program,
-            positions,
-            uvs,
+            triangle: FullscreenTriangle::new(context)?,
         })
     }
 
-    pub fn apply(&self, render_states: RenderStates, viewport: Viewport) -> Result<(), Error> {
-        self.program
-            .use_attribute_vec3(&self.positions, "position")?;
-        self.program
-            .use_attribute_vec2(&self.uvs, "uv_coordinate")?;
-        self.program.draw_arrays(render_states, viewport, 3);
+    ///
+    /// Recompiles the effect with the given fragment shader source, for example when the shader source file is changed during development.
+    /// All uniforms and textures must be set again before the next call to [apply](Self::apply).
+    ///
+    /// # Errors
+    /// Will return an error if the new shader program fails to compile or link, in which case the previous shader program is kept.
+    ///
+    pub fn reload(&mut self, fragment_shader: &str) -> Result<(), Error> {
+        self.program = program::Program::from_source(
+            &self.context,
+            FULLSCREEN_VERTEX_SHADER_SOURCE,
+            fragment_shader,
+        )?;
         Ok(())
     }
+
+    ///
+    /// Applies the effect to all pixels inside the given viewport, which can be any part of the render target.
+    /// The `uv` coordinates in the fragment shader goes from 0 to 1 across the viewport,
+    /// so to sample a texture with the same size as the render target at the current pixel,
+    /// use `gl_FragCoord.xy / vec2(textureSize(tex, 0))` instead.
+    ///
+    /// The depth test and depth write are controlled by the given render states like for any other render call.
+    /// To write depth, for example to fill the far plane or to composite a depth map, write `gl_FragDepth` in the fragment shader
+    /// and use a write mask which includes depth, for example [WriteMask::COLOR_AND_DEPTH](crate::WriteMask::COLOR_AND_DEPTH),
+    /// together with a depth test like [DepthTestType::Always](crate::DepthTestType::Always).
+    /// If `gl_FragDepth` is not written, the depth of each pixel is 0.5, the depth of the triangle covering the viewport.
+    ///
+    pub fn apply(&self, render_states: RenderStates, viewport: Viewport) -> Result<(), Error> {
+        self.triangle
+            .draw_fullscreen(&self.program, render_states, viewport)
+    }
+
+    ///
+    /// Returns a builder which binds each of the given textures to the sampler with the given name before applying the effect,
+    /// for example `effect.inputs(&[("colorMap", &color_texture), ("depthMap", &depth_texture)]).apply(render_states, viewport)`.
+    /// This is the same as calling [use_texture](crate::Program::use_texture) for each texture followed by [apply](Self::apply),
+    /// so each sampler keeps the same texture unit between calls.
+    ///
+    pub fn inputs<'a>(
+        &'a self,
+        textures: &'a [(&'a str, &'a dyn Texture)],
+    ) -> ImageEffectInputs<'a> {
+        ImageEffectInputs {
+            effect: self,
+            textures,
+        }
+    }
+
+    ///
+    /// Same as [apply](Self::apply), except that the effect is applied to all pixels of the current render target,
+    /// ie. the viewport covers the textures of the [render target](crate::RenderTarget) or the whole [screen](crate::Screen)
+    /// which is currently written to.
+    ///
+    /// # Errors
+    /// Will return an error if the size of the current render target is unknown, for example when writing to the screen
+    /// of a context which is not created by a window (see [set_screen_size](crate::context::Glstruct::set_screen_size)).
+    ///
+    pub fn apply_fullscreen(&self, render_states: RenderStates) -> Result<(), Error> {
+        let (width, height) = self.context.state_cache().draw_framebuffer_size.ok_or(
+            Error::FailedToWriteToRenderTarget {
+                message: "The size of the current render target is unknown.".to_string(),
+            },
+        )?;
+        self.apply(render_states, Viewport::new_at_origo(width, height))
+    }
+}
+
+///
+/// The input textures of an [ImageEffect], see [ImageEffect::inputs].
+///
+pub struct ImageEffectInputs<'a> {
+    effect: &'a ImageEffect,
+    textures: &'a [(&'a str, &'a dyn Texture)],
+}
+
+impl ImageEffectInputs<'_> {
+    ///
+    /// Binds the input textures and [applies](ImageEffect::apply) the effect to all pixels inside the given viewport.
+    ///
+    pub fn apply(&self, render_states: RenderStates, viewport: Viewport) -> Result<(), Error> {
+        for (name, texture) in self.textures.iter() {
+            self.effect.use_texture(*texture, name)?;
+        }
+        self.effect.apply(render_states, viewport)
+    }
+
+    ///
+    /// Binds the input textures and [applies](ImageEffect::apply_fullscreen) the effect to all pixels of the current render target.
+    ///
+    pub fn apply_fullscreen(&self, render_states: RenderStates) -> Result<(), Error> {
+        for (name, texture) in self.textures.iter() {
+            self.effect.use_texture(*texture, name)?;
+        }
+        self.effect.apply_fullscreen(render_states)
+    }
 }
 
 impl std::ops::Deref for ImageEffect {
@@ -55,3 +156,28 @@ impl std::ops::Deref for ImageEffect {
         &self.program
     }
 }
+
+#[cfg(all(test, feature = "headless", not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::test_util::*;
+
+    #[test]
+    fn apply_inside_viewport() {
+        let test_context = TestContext::new();
+        let context = test_context.gl();
+        let effect = ImageEffect::new(
+            &context,
+            "layout (location = 0) out vec4 color;
+            void main() {
+                color = vec4(0.0, 1.0, 0.0, 1.0);
+            }",
+        )
+        .unwrap();
+        assert_renders_inside_viewport(|width, height, clear_state, viewport| {
+            render_to_cpu(&context, width, height, clear_state, || {
+                effect.apply(RenderStates::default(), viewport)
+            })
+        });
+    }
+}
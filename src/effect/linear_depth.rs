@@ -0,0 +1,107 @@
+use crate::camera::*;
+use crate::core::*;
+use crate::definition::*;
+use crate::effect::*;
+use crate::math::*;
+
+///
+/// GLSL source code with functions which convert a depth value read from a depth texture into the linear distance along the view direction
+/// in world units, ie. the negated view space z coordinate. Include it in a shader and call
+/// `linearize_depth(float depth, float near, float far)` for a depth written with a perspective projection or
+/// `linearize_depth_orthographic(float depth, float near, float far)` for an orthographic projection,
+/// where near and far are the [near](crate::Camera::z_near) and [far](crate::Camera::z_far) plane distances of the camera.
+///
+pub const LINEARIZE_DEPTH_SOURCE: &str = include_str!("shaders/linearize_depth.frag");
+
+///
+/// Converts a depth texture into the linear distance along the view direction of the camera,
+/// so that effects which need a linear depth, for example depth of field, ambient occlusion or fog, can sample it directly.
+/// See also [LINEARIZE_DEPTH_SOURCE] for doing the conversion in a custom shader.
+///
+pub struct LinearDepthEffect {
+    context: Context,
+    image_effect: ImageEffect,
+}
+
+impl LinearDepthEffect {
+    pub fn new(context: &Context) -> Result<Self, Error> {
+        Ok(Self {
+            context: context.clone(),
+            image_effect: ImageEffect::new(
+                context,
+                &format!(
+                    "{}\n{}",
+                    LINEARIZE_DEPTH_SOURCE,
+                    include_str!("shaders/linear_depth.frag")
+                ),
+            )?,
+        })
+    }
+
+    ///
+    /// Writes the linear distance along the view direction of the given camera, given the depth texture written with the same camera,
+    /// to the red channel of the pixels inside the given viewport.
+    /// The depth texture must have the same size as the render target, since it is sampled at the same pixel as the fragment.
+    /// Must be called in a render target render function, for example in the callback function of [Screen::write](crate::Screen::write).
+    ///
+    pub fn apply(
+        &self,
+        viewport: Viewport,
+        camera: &Camera,
+        depth_texture: &dyn Texture,
+    ) -> Result<(), Error> {
+        let render_states = RenderStates {
+            cull: CullType::Back,
+            write_mask: WriteMask::COLOR,
+            depth_test: DepthTestType::Always,
+            ..Default::default()
+        };
+
+        let projection_type = match camera.projection_type() {
+            ProjectionType::Perspective { .. } => 0,
+            ProjectionType::Orthographic { .. } => 1,
+        };
+        self.image_effect
+            .use_uniform_float("zNear", &camera.z_near())?;
+        self.image_effect
+            .use_uniform_float("zFar", &camera.z_far())?;
+        self.image_effect
+            .use_uniform_int("projectionType", &projection_type)?;
+        self.image_effect
+            .inputs(&[("depthMap", depth_texture)])
+            .apply(render_states, viewport)
+    }
+
+    ///
+    /// Returns a new single channel float texture with the same size as the given depth texture,
+    /// which contains the linear distance along the view direction of the given camera, see [apply](Self::apply).
+    ///
+    /// # Errors
+    /// Will return an error if the context does not support rendering to float textures.
+    ///
+    pub fn linearize(
+        &self,
+        camera: &Camera,
+        depth_texture: &DepthTargetTexture2D,
+    ) -> Result<ColorTargetTexture2D, Error> {
+        let texture = ColorTargetTexture2D::new(
+            &self.context,
+            depth_texture.width(),
+            depth_texture.height(),
+            Interpolation::Nearest,
+            Interpolation::Nearest,
+            None,
+            Wrapping::ClampToEdge,
+            Wrapping::ClampToEdge,
+            Format::R32F,
+        )?;
+        RenderTarget::new_color(&self.context, &texture)?.write(&ClearState::none(), || {
+            self.apply(
+                Viewport::new_at_origo(depth_texture.width(), depth_texture.height()),
+                camera,
+                depth_texture,
+            )
+        })?;
+        Ok(texture)
+    }
+}
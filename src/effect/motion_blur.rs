@@ -0,0 +1,63 @@
+use crate::core::*;
+use crate::effect::*;
+use crate::math::*;
+
+///
+/// Blurs each pixel along the direction the geometry at that pixel moved since the previous frame,
+/// which simulates the motion blur of a real camera.
+/// The velocities are written by the [geometry pass](crate::PhongDeferredPipeline::geometry_pass) using the
+/// [previous view-projection](crate::Camera::previous_view_projection) of the camera and, for moving meshes,
+/// the transformation given to [render_geometry_with_motion](crate::PhongMesh::render_geometry_with_motion).
+///
+pub struct MotionBlurEffect {
+    ///
+    /// The number of color samples along the velocity for each pixel.
+    ///
+    pub samples: u32,
+    ///
+    /// Scales the length of the blur, 1 means the blur covers the full movement since the previous frame.
+    ///
+    pub strength: f32,
+    image_effect: ImageEffect,
+}
+
+impl MotionBlurEffect {
+    pub fn new(context: &Context) -> Result<Self, Error> {
+        Ok(Self {
+            samples: 8,
+            strength: 1.0,
+            image_effect: ImageEffect::new(context, include_str!("shaders/motion_blur.frag"))?,
+        })
+    }
+
+    ///
+    /// Applies the motion blur effect to the pixels inside the given viewport.
+    /// The color texture is typically the result of the [light pass](crate::PhongDeferredPipeline::light_pass) and
+    /// the geometry pass texture is the [geometry pass texture](crate::PhongDeferredPipeline::geometry_pass_texture).
+    /// Both textures must have the same size as the render target, since they are sampled at the same pixel as the fragment.
+    ///
+    pub fn apply(
+        &self,
+        viewport: Viewport,
+        color_texture: &dyn Texture,
+        geometry_pass_texture: &dyn Texture,
+    ) -> Result<(), Error> {
+        let render_states = RenderStates {
+            cull: CullType::Back,
+            write_mask: WriteMask::COLOR,
+            depth_test: DepthTestType::Always,
+            ..Default::default()
+        };
+
+        self.image_effect
+            .use_uniform_int("samples", &(self.samples.max(1) as i32))?;
+        self.image_effect
+            .use_uniform_float("strength", &self.strength)?;
+        self.image_effect
+            .inputs(&[
+                ("colorMap", color_texture),
+                ("gbuffer", geometry_pass_texture),
+            ])
+            .apply(render_states, viewport)
+    }
+}
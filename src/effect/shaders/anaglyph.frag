@@ -0,0 +1,14 @@
+
+uniform sampler2D leftMap;
+uniform sampler2D rightMap;
+
+in vec2 uv;
+
+layout (location = 0) out vec4 color;
+
+void main()
+{
+    vec4 left = texture(leftMap, uv);
+    vec4 right = texture(rightMap, uv);
+    color = vec4(left.r, right.g, right.b, max(left.a, right.a));
+}
@@ -0,0 +1,53 @@
+uniform sampler2D lowResTexture;
+uniform sampler2D depthMap;
+uniform mat4 projectionInverse;
+uniform float depthThreshold;
+
+layout (location = 0) out vec4 color;
+
+float view_depth(ivec2 pixel)
+{
+    float depth = texelFetch(depthMap, clamp(pixel, ivec2(0), textureSize(depthMap, 0) - 1), 0).r;
+    vec4 position = projectionInverse * vec4(0.0, 0.0, depth * 2.0 - 1.0, 1.0);
+    return abs(position.z / position.w);
+}
+
+void main()
+{
+    vec2 depthMapSize = vec2(textureSize(depthMap, 0));
+    ivec2 lowResSize = textureSize(lowResTexture, 0);
+    float depth = view_depth(ivec2(gl_FragCoord.xy));
+
+    vec2 p = gl_FragCoord.xy / depthMapSize * vec2(lowResSize) - 0.5;
+    vec2 base = floor(p);
+    vec2 f = p - base;
+
+    vec4 result = vec4(0.0);
+    float totalWeight = 0.0;
+    vec4 closest = vec4(0.0);
+    float closestDifference = 1.0e30;
+    for(int j = 0; j < 2; j++)
+    {
+        for(int i = 0; i < 2; i++)
+        {
+            ivec2 texel = clamp(ivec2(base) + ivec2(i, j), ivec2(0), lowResSize - 1);
+            vec4 value = texelFetch(lowResTexture, texel, 0);
+            // The depth of the low resolution texel is approximated by the full resolution depth at the center of the texel
+            float texelDepth = view_depth(ivec2((vec2(texel) + 0.5) / vec2(lowResSize) * depthMapSize));
+            float difference = abs(texelDepth - depth) / max(depth, 0.0001);
+            if(difference < depthThreshold)
+            {
+                float weight = (i == 0 ? 1.0 - f.x : f.x) * (j == 0 ? 1.0 - f.y : f.y);
+                result += weight * value;
+                totalWeight += weight;
+            }
+            if(difference < closestDifference)
+            {
+                closestDifference = difference;
+                closest = value;
+            }
+        }
+    }
+    // Use the texel with the most similar depth if all of the texels are on the other side of a depth edge
+    color = totalWeight > 0.0001 ? result / totalWeight : closest;
+}
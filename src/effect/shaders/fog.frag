@@ -1,5 +1,7 @@
 
 uniform sampler2D depthMap;
+// One divided by the size of the depth texture
+uniform vec2 texelSize;
 
 uniform mat4 viewProjectionInverse;
 
@@ -135,7 +137,7 @@ vec3 WorldPosFromDepth(float depth, vec2 uv) {
 // factor: 1 == full fog, 0 == no fog
 void main()
 {
-    float depth = texture(depthMap, uv).x;
+    float depth = texture(depthMap, gl_FragCoord.xy * texelSize).x;
     vec3 pos = WorldPosFromDepth(depth, uv);
 
     // Distance
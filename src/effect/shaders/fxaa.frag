@@ -1,9 +1,8 @@
 
 uniform sampler2D colorMap;
 
-uniform vec2 resolution;
-
-in vec2 uv;
+// One divided by the size of the color texture
+uniform vec2 texelSize;
 
 layout (location = 0) out vec4 color;
 
@@ -55,12 +54,12 @@ OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 //optimized version for mobile, where dependent
 //texture reads can be a bottleneck
-vec4 fxaa(sampler2D tex, vec2 fragCoord, vec2 resolution,
+vec4 fxaa(sampler2D tex, vec2 fragCoord, vec2 texelSize,
             vec2 v_rgbNW, vec2 v_rgbNE,
             vec2 v_rgbSW, vec2 v_rgbSE,
             vec2 v_rgbM) {
     vec4 color;
-    mediump vec2 inverseVP = vec2(1.0 / resolution.x, 1.0 / resolution.y);
+    mediump vec2 inverseVP = texelSize;
     vec3 rgbNW = texture(tex, v_rgbNW).xyz;
     vec3 rgbNE = texture(tex, v_rgbNE).xyz;
     vec3 rgbSW = texture(tex, v_rgbSW).xyz;
@@ -103,11 +102,11 @@ vec4 fxaa(sampler2D tex, vec2 fragCoord, vec2 resolution,
     return color;
 }
 
-void texcoords(vec2 fragCoord, vec2 resolution,
+void texcoords(vec2 fragCoord, vec2 texelSize,
 			out vec2 v_rgbNW, out vec2 v_rgbNE,
 			out vec2 v_rgbSW, out vec2 v_rgbSE,
 			out vec2 v_rgbM) {
-	vec2 inverseVP = 1.0 / resolution.xy;
+	vec2 inverseVP = texelSize;
 	v_rgbNW = (fragCoord + vec2(-1.0, -1.0)) * inverseVP;
 	v_rgbNE = (fragCoord + vec2(1.0, -1.0)) * inverseVP;
 	v_rgbSW = (fragCoord + vec2(-1.0, 1.0)) * inverseVP;
@@ -123,8 +122,8 @@ void main() {
 	mediump vec2 v_rgbM;
 
 	//compute the texture coords
-	texcoords(uv * resolution, resolution, v_rgbNW, v_rgbNE, v_rgbSW, v_rgbSE, v_rgbM);
+	texcoords(gl_FragCoord.xy, texelSize, v_rgbNW, v_rgbNE, v_rgbSW, v_rgbSE, v_rgbM);
 
 	//compute FXAA
-	color = fxaa(colorMap, uv * resolution, resolution, v_rgbNW, v_rgbNE, v_rgbSW, v_rgbSE, v_rgbM);
+	color = fxaa(colorMap, gl_FragCoord.xy, texelSize, v_rgbNW, v_rgbNE, v_rgbSW, v_rgbSE, v_rgbM);
 }
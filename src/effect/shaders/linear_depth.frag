@@ -0,0 +1,21 @@
+uniform sampler2D depthMap;
+uniform float zNear;
+uniform float zFar;
+uniform int projectionType;
+
+layout (location = 0) out vec4 color;
+
+void main()
+{
+    float depth = texture(depthMap, gl_FragCoord.xy / vec2(textureSize(depthMap, 0))).r;
+    float distance;
+    if(projectionType == 1)
+    {
+        distance = linearize_depth_orthographic(depth, zNear, zFar);
+    }
+    else
+    {
+        distance = linearize_depth(depth, zNear, zFar);
+    }
+    color = vec4(distance, 0.0, 0.0, 1.0);
+}
@@ -0,0 +1,13 @@
+// Converts a depth value read from a depth texture, which is between 0 and 1, into the linear distance along the view direction,
+// ie. the negated view space z coordinate, given the distances to the near and far plane of the projection.
+float linearize_depth(float depth, float near, float far)
+{
+    float z = depth * 2.0 - 1.0;
+    return 2.0 * near * far / (far + near - z * (far - near));
+}
+
+// Same as linearize_depth, except for a depth written with an orthographic projection.
+float linearize_depth_orthographic(float depth, float near, float far)
+{
+    return near + depth * (far - near);
+}
@@ -0,0 +1,33 @@
+
+uniform sampler2D colorMap;
+uniform sampler2DArray gbuffer;
+
+uniform int samples;
+uniform float strength;
+
+layout (location = 0) out vec4 color;
+
+// Decodes the velocity written in the geometry pass. Pixels without any geometry have zero velocity.
+vec2 velocity_at(vec2 uv)
+{
+    vec4 v = texture(gbuffer, vec3(uv, 2));
+    vec2 velocity = vec2(dot(v.rg, vec2(65280.0, 255.0)), dot(v.ba, vec2(65280.0, 255.0)));
+    if(velocity.x < 0.5)
+    {
+        return vec2(0.0);
+    }
+    return (velocity - 1.0) / 65534.0 * 2.0 - 1.0;
+}
+
+void main()
+{
+    vec2 uv = gl_FragCoord.xy / vec2(textureSize(colorMap, 0));
+    vec2 velocity = strength * velocity_at(gl_FragCoord.xy / vec2(textureSize(gbuffer, 0).xy));
+    vec4 result = vec4(0.0);
+    for(int i = 0; i < samples; i++)
+    {
+        float t = samples > 1 ? float(i) / float(samples - 1) - 0.5 : 0.0;
+        result += texture(colorMap, uv - velocity * t);
+    }
+    color = result / float(samples);
+}
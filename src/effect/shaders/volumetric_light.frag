@@ -0,0 +1,26 @@
+
+uniform sampler2D occlusionMap;
+uniform vec2 lightPosition;
+uniform int samples;
+uniform float density;
+uniform float decay;
+uniform float weight;
+uniform float exposure;
+
+layout (location = 0) out vec4 color;
+
+void main()
+{
+    // The occlusion texture is sampled at the same pixel as the fragment, which works for any viewport
+    vec2 texcoord = gl_FragCoord.xy / vec2(textureSize(occlusionMap, 0));
+    vec2 delta = (texcoord - lightPosition) * density / float(samples);
+    float illumination = 1.0;
+    vec3 scattered = vec3(0.0);
+    for (int i = 0; i < samples; i++)
+    {
+        texcoord -= delta;
+        scattered += texture(occlusionMap, texcoord).rgb * illumination * weight;
+        illumination *= decay;
+    }
+    color = vec4(scattered * exposure, 1.0);
+}
@@ -0,0 +1,63 @@
+
+uniform sampler2D depthMap;
+uniform sampler2DShadow shadowMap;
+uniform mat4 viewProjectionInverse;
+uniform int samples;
+uniform float scattering;
+uniform float anisotropy;
+uniform float maxDistance;
+
+layout (std140) uniform DirectionalLightUniform
+{
+    DirectionalLight light;
+};
+
+in vec2 uv;
+
+layout (location = 0) out vec4 color;
+
+vec3 WorldPosFromDepth(float depth, vec2 uv) {
+    vec4 clipSpacePosition = vec4(uv * 2.0 - 1.0, depth * 2.0 - 1.0, 1.0);
+    vec4 position = viewProjectionInverse * clipSpacePosition;
+    return position.xyz / position.w;
+}
+
+float is_lit(vec3 position)
+{
+    if(light.shadowEnabled < 0.5)
+    {
+        return 1.0;
+    }
+    vec4 shadow_coord = light.shadowMVP * vec4(position, 1.0);
+    vec2 shadow_uv = shadow_coord.xy / shadow_coord.w;
+    if(shadow_uv.x < 0.0 || shadow_uv.x > 1.0 || shadow_uv.y < 0.0 || shadow_uv.y > 1.0)
+    {
+        return 1.0;
+    }
+    return textureProj(shadowMap, vec4(shadow_coord.xy, shadow_coord.z - 0.005, shadow_coord.w));
+}
+
+void main()
+{
+    float depth = texture(depthMap, gl_FragCoord.xy / vec2(textureSize(depthMap, 0))).r;
+    vec3 ray = WorldPosFromDepth(depth, uv) - eyePosition;
+    float distance = min(length(ray), maxDistance);
+    ray = normalize(ray);
+
+    // Offset the samples differently for neighbouring pixels to hide the banding
+    float offset = fract(sin(dot(gl_FragCoord.xy, vec2(12.9898, 78.233))) * 43758.5453);
+    float step_length = distance / float(samples);
+    float lit = 0.0;
+    for (int i = 0; i < samples; i++)
+    {
+        lit += is_lit(eyePosition + ray * (float(i) + offset) * step_length);
+    }
+
+    // Henyey-Greenstein phase function, relative to isotropic scattering, which scatters more light towards the camera when looking towards the light
+    float cos_theta = dot(ray, -light.direction);
+    float g2 = anisotropy * anisotropy;
+    float phase = (1.0 - g2) / pow(1.0 + g2 - 2.0 * anisotropy * cos_theta, 1.5);
+
+    float amount = (lit / float(samples)) * (1.0 - exp(-scattering * distance)) * phase;
+    color = vec4(light.base.color * light.base.intensity * amount, 1.0);
+}
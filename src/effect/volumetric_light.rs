@@ -0,0 +1,180 @@
+use crate::camera::*;
+use crate::core::*;
+use crate::effect::*;
+use crate::light::*;
+use crate::math::*;
+
+///
+/// Simulates light scattered in the air, for example shafts of light (god rays) from the sun shining through trees or windows.
+/// The scattered light is added to the pixels of the render target, either by a cheap radial blur in screen space
+/// towards the position of the light on the screen (see [apply](Self::apply)) or by ray marching the shadow map
+/// of a directional light (see [apply_with_shadow_map](Self::apply_with_shadow_map)).
+///
+pub struct VolumetricLightEffect {
+    ///
+    /// The number of samples for each pixel, more samples give smoother light shafts but are more expensive.
+    ///
+    pub samples: u32,
+    ///
+    /// The fraction of the distance from each pixel towards the light which is blurred in [apply](Self::apply).
+    ///
+    pub density: f32,
+    ///
+    /// How much the contribution of each sample decreases with the distance from the pixel in [apply](Self::apply), between 0 and 1.
+    ///
+    pub decay: f32,
+    ///
+    /// The contribution of each sample in [apply](Self::apply).
+    ///
+    pub weight: f32,
+    ///
+    /// Scales the scattered light in [apply](Self::apply).
+    ///
+    pub exposure: f32,
+    ///
+    /// How much light is scattered per unit distance in [apply_with_shadow_map](Self::apply_with_shadow_map).
+    ///
+    pub scattering: f32,
+    ///
+    /// How much the light is scattered forward in [apply_with_shadow_map](Self::apply_with_shadow_map), between -1 and 1,
+    /// where 0 scatters the light equally in all directions and values close to 1 give bright light shafts when looking towards the light.
+    ///
+    pub anisotropy: f32,
+    ///
+    /// The maximum distance from the camera which is ray marched in [apply_with_shadow_map](Self::apply_with_shadow_map).
+    ///
+    pub max_distance: f32,
+    context: Context,
+    image_effect: ImageEffect,
+    shadow_map_effect: Option<ImageEffect>,
+}
+
+impl VolumetricLightEffect {
+    pub fn new(context: &Context) -> Result<Self, Error> {
+        Ok(Self {
+            samples: 64,
+            density: 0.9,
+            decay: 0.96,
+            weight: 0.05,
+            exposure: 1.0,
+            scattering: 0.05,
+            anisotropy: 0.6,
+            max_distance: 100.0,
+            context: context.clone(),
+            image_effect: ImageEffect::new(context, include_str!("shaders/volumetric_light.frag"))?,
+            shadow_map_effect: None,
+        })
+    }
+
+    ///
+    /// Returns the position of a directional light with the given direction on the screen, where (0, 0) is the bottom left corner
+    /// and (1, 1) is the top right corner of the viewport, or `None` if the light is behind the camera.
+    /// The position can be outside the viewport.
+    ///
+    pub fn light_screen_position(camera: &Camera, light_direction: &Vec3) -> Option<Vec2> {
+        let clip = camera.projection() * camera.view() * (-*light_direction).extend(0.0);
+        if clip.w <= 0.0 {
+            None
+        } else {
+            Some(vec2(
+                0.5 * clip.x / clip.w + 0.5,
+                0.5 * clip.y / clip.w + 0.5,
+            ))
+        }
+    }
+
+    ///
+    /// Adds the scattered light to the pixels inside the given viewport by blurring the occlusion texture radially towards the light screen position
+    /// (see [light_screen_position](Self::light_screen_position)).
+    /// The occlusion texture contains the color of the light where the light source, for example the sky around the sun, is visible and black elsewhere,
+    /// but it can also be the rendered color texture for a cheaper, but less precise, effect.
+    /// The occlusion texture must have the same size as the render target, since it is sampled at the same pixel as the fragment.
+    /// Must be called in a render target render function, for example in the callback function of [Screen::write](crate::Screen::write).
+    ///
+    pub fn apply(
+        &self,
+        viewport: Viewport,
+        occlusion_texture: &dyn Texture,
+        light_screen_position: Vec2,
+    ) -> Result<(), Error> {
+        let width = occlusion_texture.width() as f32;
+        let height = occlusion_texture.height() as f32;
+        self.image_effect
+            .use_texture(occlusion_texture, "occlusionMap")?;
+        self.image_effect.use_uniform_vec2(
+            "lightPosition",
+            &vec2(
+                (viewport.x as f32 + light_screen_position.x * viewport.width as f32) / width,
+                (viewport.y as f32 + light_screen_position.y * viewport.height as f32) / height,
+            ),
+        )?;
+        self.image_effect
+            .use_uniform_int("samples", &(self.samples.max(1) as i32))?;
+        self.image_effect
+            .use_uniform_float("density", &self.density)?;
+        self.image_effect.use_uniform_float("decay", &self.decay)?;
+        self.image_effect
+            .use_uniform_float("weight", &self.weight)?;
+        self.image_effect
+            .use_uniform_float("exposure", &self.exposure)?;
+        self.image_effect.apply(Self::render_states(), viewport)?;
+        Ok(())
+    }
+
+    ///
+    /// Adds the light scattered in the air between the camera and the surface at each pixel inside the given viewport, by ray marching
+    /// the [shadow map](crate::DirectionalLight::generate_shadow_map) of the given directional light, so that the shadowed parts of the air are dark.
+    /// This gives correct light shafts also when the light source is not visible on the screen, but it is more expensive than [apply](Self::apply).
+    /// The depth texture must have the same size as the render target, since it is sampled at the same pixel as the fragment.
+    /// Must be called in a render target render function, for example in the callback function of [Screen::write](crate::Screen::write).
+    ///
+    pub fn apply_with_shadow_map(
+        &mut self,
+        viewport: Viewport,
+        camera: &Camera,
+        depth_texture: &dyn Texture,
+        light: &DirectionalLight,
+    ) -> Result<(), Error> {
+        if self.shadow_map_effect.is_none() {
+            self.shadow_map_effect = Some(ImageEffect::new(
+                &self.context,
+                &format!(
+                    "{}\n{}",
+                    include_str!("../phong/shaders/light_shared.frag"),
+                    include_str!("shaders/volumetric_shadow.frag")
+                ),
+            )?);
+        }
+        let effect = self.shadow_map_effect.as_ref().unwrap();
+        effect.use_texture(depth_texture, "depthMap")?;
+        effect.use_texture(light.shadow_map(), "shadowMap")?;
+        effect.use_uniform_block(light.buffer(), "DirectionalLightUniform");
+        effect.use_uniform_mat4(
+            "viewProjectionInverse",
+            &(camera.projection() * camera.view()).invert().unwrap(),
+        )?;
+        effect.use_uniform_vec3("eyePosition", camera.position())?;
+        effect.use_uniform_int("samples", &(self.samples.max(1) as i32))?;
+        effect.use_uniform_float("scattering", &self.scattering)?;
+        effect.use_uniform_float("anisotropy", &self.anisotropy.clamp(-0.99, 0.99))?;
+        effect.use_uniform_float("maxDistance", &self.max_distance)?;
+        effect.apply(Self::render_states(), viewport)?;
+        Ok(())
+    }
+
+    fn render_states() -> RenderStates {
+        RenderStates {
+            cull: CullType::Back,
+            write_mask: WriteMask {
+                red: true,
+                green: true,
+                blue: true,
+                alpha: false,
+                depth: false,
+            },
+            depth_test: DepthTestType::Always,
+            blend: Some(BlendParameters::ADD),
+            ..Default::default()
+        }
+    }
+}
@@ -83,6 +83,11 @@ pub enum Event {
         modifiers: Modifiers,
     },
     Text(String),
+    /// The graphics context is lost and all GPU resources are invalid (only on web).
+    ContextLost,
+    /// The graphics context is restored after being lost (only on web).
+    /// All GPU resources, for example meshes, textures and programs, must be recreated.
+    ContextRestored,
 }
 
 /// Keyboard key input.
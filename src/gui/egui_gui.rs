@@ -120,17 +120,28 @@ impl GUI {
             self.texture_version = egui_texture.version;
         };
 
-        for egui::ClippedMesh(clip_rect, mesh) in clipped_meshes {
-            self.paint_mesh(
-                self.width,
-                self.height,
-                self.egui_context.pixels_per_point(),
-                clip_rect,
-                &mesh,
-                self.texture.as_ref().unwrap(),
-            )?;
+        // The colors are already converted to sRGB in the fragment shader
+        let framebuffer_srgb = self.context.is_framebuffer_srgb();
+        if framebuffer_srgb {
+            self.context.set_framebuffer_srgb(false);
         }
-        Ok(())
+        let result =
+            clipped_meshes
+                .into_iter()
+                .try_for_each(|egui::ClippedMesh(clip_rect, mesh)| {
+                    self.paint_mesh(
+                        self.width,
+                        self.height,
+                        self.egui_context.pixels_per_point(),
+                        clip_rect,
+                        &mesh,
+                        self.texture.as_ref().unwrap(),
+                    )
+                });
+        if framebuffer_srgb {
+            self.context.set_framebuffer_srgb(true);
+        }
+        result
     }
 
     fn paint_mesh(
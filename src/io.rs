@@ -37,7 +37,7 @@ pub mod obj;
 pub use obj::*;
 
 ///
-/// Error message from the [core](crate::io) module.
+/// Error message from the [io](crate::io) module.
 ///
 #[derive(Debug)]
 pub enum IOError {
@@ -57,6 +57,40 @@ pub enum IOError {
     },
 }
 
+impl std::fmt::Display for IOError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            #[cfg(feature = "image-io")]
+            IOError::Image(e) => write!(f, "Image error: {}", e),
+            #[cfg(feature = "3d-io")]
+            IOError::Bincode(e) => write!(f, "Bincode error: {}", e),
+            #[cfg(feature = "obj-io")]
+            IOError::Obj(e) => write!(f, "Obj error: {}", e),
+            #[cfg(not(target_arch = "wasm32"))]
+            IOError::IO(e) => write!(f, "IO error: {}", e),
+            IOError::FailedToLoad { message } | IOError::FailedToSave { message } => {
+                write!(f, "{}", message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for IOError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "image-io")]
+            IOError::Image(e) => Some(e),
+            #[cfg(feature = "3d-io")]
+            IOError::Bincode(e) => Some(e),
+            #[cfg(feature = "obj-io")]
+            IOError::Obj(e) => Some(e),
+            #[cfg(not(target_arch = "wasm32"))]
+            IOError::IO(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
 #[cfg(feature = "image-io")]
 impl From<image::ImageError> for IOError {
     fn from(other: image::ImageError) -> Self {
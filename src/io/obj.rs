@@ -154,6 +154,7 @@ impl<'a> Loaded<'a> {
                     normals: Some(normals),
                     uvs: Some(uvs),
                     colors: None,
+                    colors_f32: None,
                 });
             }
         }
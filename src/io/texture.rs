@@ -71,6 +71,8 @@ impl<'a> Loaded<'a> {
 impl Saver {
     ///
     /// Saves the given RGB pixels as an image.
+    /// The first row of the pixels is the bottom row of the image, ie. the order returned by [Screen::read_color](crate::Screen::read_color)
+    /// when it is not flipped.
     ///
     /// # Feature
     /// Only available when the `image-io` feature is enabled.
@@ -43,6 +43,7 @@ impl<'a> Loaded<'a> {
                 normals: mesh.normals,
                 uvs: mesh.uvs,
                 colors: None,
+                colors_f32: None,
             });
         }
 
@@ -55,3 +55,6 @@ pub use window::*;
 pub mod gui;
 #[doc(inline)]
 pub use gui::*;
+
+#[cfg(all(test, feature = "headless", not(target_arch = "wasm32")))]
+pub(crate) mod test_util;
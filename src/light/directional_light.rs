@@ -24,7 +24,7 @@ impl DirectionalLight {
         let mut light = DirectionalLight {
             context: context.clone(),
             light_buffer: UniformBuffer::new(context, &[3u32, 1, 3, 1, 16])?,
-            shadow_texture: DepthTargetTexture2D::new(
+            shadow_texture: DepthTargetTexture2D::new_with_comparison(
                 context,
                 1,
                 1,
@@ -62,7 +62,7 @@ impl DirectionalLight {
 
     pub fn clear_shadow_map(&mut self) {
         self.shadow_camera = None;
-        self.shadow_texture = DepthTargetTexture2D::new(
+        self.shadow_texture = DepthTargetTexture2D::new_with_comparison(
             &self.context,
             1,
             1,
@@ -101,7 +101,7 @@ impl DirectionalLight {
             &shadow_matrix(self.shadow_camera.as_ref().unwrap()).to_slice(),
         )?;
 
-        self.shadow_texture = DepthTargetTexture2D::new(
+        self.shadow_texture = DepthTargetTexture2D::new_with_comparison(
             &self.context,
             texture_width,
             texture_height,
@@ -51,7 +51,60 @@ impl PointLight {
         self.light_buffer.update(6, &position.to_slice()).unwrap();
     }
 
+    pub fn color(&self) -> Vec3 {
+        let c = self.light_buffer.get(0).unwrap();
+        vec3(c[0], c[1], c[2])
+    }
+
+    pub fn intensity(&self) -> f32 {
+        self.light_buffer.get(1).unwrap()[0]
+    }
+
+    pub fn attenuation(&self) -> (f32, f32, f32) {
+        (
+            self.light_buffer.get(2).unwrap()[0],
+            self.light_buffer.get(3).unwrap()[0],
+            self.light_buffer.get(4).unwrap()[0],
+        )
+    }
+
+    pub fn position(&self) -> Vec3 {
+        let p = self.light_buffer.get(6).unwrap();
+        vec3(p[0], p[1], p[2])
+    }
+
+    ///
+    /// Returns the distance from the light position where the contribution of the light becomes insignificant (less than 1/256),
+    /// or `None` if the light is not attenuated by distance and therefore affects everything.
+    ///
+    pub fn radius(&self) -> Option<f32> {
+        attenuation_radius(self.intensity(), &self.color(), self.attenuation())
+    }
+
     pub fn buffer(&self) -> &UniformBuffer {
         &self.light_buffer
     }
 }
+
+///
+/// Returns the distance where the contribution of a light with the given intensity, color and attenuation becomes insignificant,
+/// see [PointLight::radius].
+///
+pub(crate) fn attenuation_radius(
+    intensity: f32,
+    color: &Vec3,
+    attenuation: (f32, f32, f32),
+) -> Option<f32> {
+    let (constant, linear, exponential) = attenuation;
+    let max_contribution = 2.0 * intensity * color.x.max(color.y).max(color.z);
+    let c = constant - 256.0 * max_contribution;
+    if c >= 0.0 {
+        Some(0.0)
+    } else if exponential > 0.0 {
+        Some((-linear + (linear * linear - 4.0 * exponential * c).sqrt()) / (2.0 * exponential))
+    } else if linear > 0.0 {
+        Some(-c / linear)
+    } else {
+        None
+    }
+}
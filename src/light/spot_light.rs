@@ -2,16 +2,21 @@ use crate::camera::*;
 use crate::core::*;
 use crate::definition::*;
 use crate::math::*;
+use std::rc::Rc;
 
 ///
 /// A light which shines from the given position and in the given direction.
-/// The light will cast shadows if you [generate a shadow map](SpotLight::generate_shadow_map).
+/// The light is inside a cone around the direction and fades out smoothly between an inner and outer angle (see [set_cone](SpotLight::set_cone)).
+/// The light will cast shadows if you [generate a shadow map](SpotLight::generate_shadow_map)
+/// and it can project a texture, for example the pattern of a flashlight, see [set_projection_texture](SpotLight::set_projection_texture).
 ///
 pub struct SpotLight {
     context: Context,
     light_buffer: UniformBuffer,
     shadow_texture: DepthTargetTexture2D,
     shadow_camera: Option<Camera>,
+    projection_texture: Rc<Texture2D>,
+    has_projection_texture: bool,
 }
 
 impl SpotLight {
@@ -26,11 +31,11 @@ impl SpotLight {
         attenuation_linear: f32,
         attenuation_exponential: f32,
     ) -> Result<SpotLight, Error> {
-        let uniform_sizes = [3u32, 1, 1, 1, 1, 1, 3, 1, 3, 1, 16];
+        let uniform_sizes = [3u32, 1, 1, 1, 1, 1, 3, 1, 3, 1, 16, 16];
         let mut light = SpotLight {
             context: context.clone(),
             light_buffer: UniformBuffer::new(context, &uniform_sizes)?,
-            shadow_texture: DepthTargetTexture2D::new(
+            shadow_texture: DepthTargetTexture2D::new_with_comparison(
                 context,
                 1,
                 1,
@@ -39,6 +44,8 @@ impl SpotLight {
                 DepthFormat::Depth32F,
             )?,
             shadow_camera: None,
+            projection_texture: Rc::new(Self::white_texture(context)?),
+            has_projection_texture: false,
         };
         light.set_intensity(intensity);
         light.set_color(color);
@@ -50,6 +57,10 @@ impl SpotLight {
             attenuation_linear,
             attenuation_exponential,
         );
+        // Any finite texture coordinate samples the white texture
+        light
+            .light_buffer
+            .update(11, &Mat4::identity().to_slice())?;
         Ok(light)
     }
 
@@ -57,18 +68,47 @@ impl SpotLight {
         self.light_buffer.update(0, &color.to_slice()).unwrap();
     }
 
+    pub fn color(&self) -> Vec3 {
+        let c = self.light_buffer.get(0).unwrap();
+        vec3(c[0], c[1], c[2])
+    }
+
     pub fn set_intensity(&mut self, intensity: f32) {
         self.light_buffer.update(1, &[intensity]).unwrap();
     }
 
+    pub fn intensity(&self) -> f32 {
+        self.light_buffer.get(1).unwrap()[0]
+    }
+
     pub fn set_attenuation(&mut self, constant: f32, linear: f32, exponential: f32) {
         self.light_buffer.update(2, &[constant]).unwrap();
         self.light_buffer.update(3, &[linear]).unwrap();
         self.light_buffer.update(4, &[exponential]).unwrap();
     }
 
+    pub fn attenuation(&self) -> (f32, f32, f32) {
+        (
+            self.light_buffer.get(2).unwrap()[0],
+            self.light_buffer.get(3).unwrap()[0],
+            self.light_buffer.get(4).unwrap()[0],
+        )
+    }
+
+    ///
+    /// Returns the distance from the light position where the contribution of the light becomes insignificant (less than 1/256),
+    /// or `None` if the light is not attenuated by distance and therefore affects everything inside the cone.
+    ///
+    pub fn radius(&self) -> Option<f32> {
+        crate::point_light::attenuation_radius(self.intensity(), &self.color(), self.attenuation())
+    }
+
+    ///
+    /// Moves the light to the given position.
+    ///
     pub fn set_position(&mut self, position: &Vec3) {
         self.light_buffer.update(6, &position.to_slice()).unwrap();
+        self.update_projection();
     }
 
     pub fn position(&self) -> Vec3 {
@@ -76,14 +116,44 @@ impl SpotLight {
         vec3(p[0], p[1], p[2])
     }
 
+    ///
+    /// Sets the outer angle of the cone in degrees and the inner angle to three quarters of the outer angle, see [set_cone](Self::set_cone).
+    ///
     pub fn set_cutoff(&mut self, cutoff: f32) {
-        self.light_buffer.update(7, &[cutoff]).unwrap();
+        self.set_cone(0.75 * cutoff, cutoff);
     }
 
+    ///
+    /// Sets the angles in degrees between the direction of the light and the edges of the inner and outer cone.
+    /// The light has full intensity inside the inner cone and fades out smoothly towards the outer cone, outside which there is no light.
+    /// Use the same angle for both to get a hard edge. The inner angle is clamped to be between 0 and the outer angle.
+    ///
+    pub fn set_cone(&mut self, inner_degrees: f32, outer_degrees: f32) {
+        self.light_buffer
+            .update(5, &[inner_degrees.clamp(0.0, outer_degrees.max(0.0))])
+            .unwrap();
+        self.light_buffer.update(7, &[outer_degrees]).unwrap();
+        self.update_projection();
+    }
+
+    ///
+    /// Returns the inner and outer angle of the cone in degrees, see [set_cone](Self::set_cone).
+    ///
+    pub fn cone(&self) -> (f32, f32) {
+        (
+            self.light_buffer.get(5).unwrap()[0],
+            self.light_buffer.get(7).unwrap()[0],
+        )
+    }
+
+    ///
+    /// Points the light in the given direction.
+    ///
     pub fn set_direction(&mut self, direction: &Vec3) {
         self.light_buffer
             .update(8, &direction.normalize().to_slice())
             .unwrap();
+        self.update_projection();
     }
 
     pub fn direction(&self) -> Vec3 {
@@ -93,7 +163,7 @@ impl SpotLight {
 
     pub fn clear_shadow_map(&mut self) {
         self.shadow_camera = None;
-        self.shadow_texture = DepthTargetTexture2D::new(
+        self.shadow_texture = DepthTargetTexture2D::new_with_comparison(
             &self.context,
             1,
             1,
@@ -114,14 +184,13 @@ impl SpotLight {
         let position = self.position();
         let direction = self.direction();
         let up = compute_up_direction(direction);
-        let cutoff = self.light_buffer.get(7).unwrap()[0];
 
         self.shadow_camera = Some(Camera::new_perspective(
             &self.context,
             position,
             position + direction,
             up,
-            degrees(cutoff),
+            self.field_of_view(),
             1.0,
             0.1,
             frustrum_depth,
@@ -131,7 +200,7 @@ impl SpotLight {
             &shadow_matrix(self.shadow_camera.as_ref().unwrap()).to_slice(),
         )?;
 
-        self.shadow_texture = DepthTargetTexture2D::new(
+        self.shadow_texture = DepthTargetTexture2D::new_with_comparison(
             &self.context,
             texture_size,
             texture_size,
@@ -157,16 +226,85 @@ impl SpotLight {
         &self.shadow_texture
     }
 
+    ///
+    /// Projects the given texture from the position of the light in the direction of the light, like a projector or a gobo,
+    /// so that the color of the light is multiplied by the color of the texture.
+    /// The texture is stretched to the square containing the outer [cone](Self::set_cone) and it is oriented
+    /// like the [shadow map](Self::generate_shadow_map), ie. the up direction is perpendicular to the direction of the light.
+    /// The field of view of the projection, ie. twice the outer angle, is clamped to be between 1 and 179 degrees,
+    /// so the texture does not cover cones with an outer angle of 90 degrees or more.
+    ///
+    pub fn set_projection_texture(&mut self, texture: Rc<Texture2D>) {
+        self.projection_texture = texture;
+        self.has_projection_texture = true;
+        self.update_projection();
+    }
+
+    ///
+    /// Removes the [projection texture](Self::set_projection_texture), so that the light has the same color in the entire cone.
+    ///
+    pub fn clear_projection_texture(&mut self) -> Result<(), Error> {
+        self.projection_texture = Rc::new(Self::white_texture(&self.context)?);
+        self.has_projection_texture = false;
+        self.light_buffer.update(11, &Mat4::identity().to_slice())?;
+        Ok(())
+    }
+
+    ///
+    /// Returns the texture projected by the light, which is a white texture if no [projection texture](Self::set_projection_texture) is set.
+    ///
+    pub fn projection_texture(&self) -> &dyn Texture {
+        self.projection_texture.as_ref()
+    }
+
     pub fn buffer(&self) -> &UniformBuffer {
         &self.light_buffer
     }
+
+    // The field of view of the shadow map and the projection texture, which contains the outer cone.
+    fn field_of_view(&self) -> Degrees {
+        degrees((2.0 * self.cone().1).clamp(1.0, 179.0))
+    }
+
+    fn update_projection(&mut self) {
+        if !self.has_projection_texture {
+            return;
+        }
+        let position = self.position();
+        let direction = self.direction();
+        let view = Mat4::look_at(
+            Point::from_vec(position),
+            Point::from_vec(position + direction),
+            compute_up_direction(direction),
+        );
+        let projection = perspective(self.field_of_view(), 1.0, 0.1, 1.0);
+        self.light_buffer
+            .update(11, &(bias_matrix() * projection * view).to_slice())
+            .unwrap();
+    }
+
+    fn white_texture(context: &Context) -> Result<Texture2D, Error> {
+        Texture2D::new_with_u8(
+            context,
+            &CPUTexture {
+                data: vec![255; 4],
+                mip_map_filter: None,
+                wrap_s: Wrapping::ClampToEdge,
+                wrap_t: Wrapping::ClampToEdge,
+                ..Default::default()
+            },
+        )
+    }
 }
 
 fn shadow_matrix(camera: &Camera) -> Mat4 {
-    let bias_matrix = crate::Mat4::new(
+    bias_matrix() * camera.projection() * camera.view()
+}
+
+fn bias_matrix() -> Mat4 {
+    crate::Mat4::new(
         0.5, 0.0, 0.0, 0.0, 0.0, 0.5, 0.0, 0.0, 0.0, 0.0, 0.5, 0.0, 0.5, 0.5, 0.5, 1.0,
-    );
-    bias_matrix * camera.projection() * camera.view()
+    )
 }
 
 fn compute_up_direction(direction: Vec3) -> Vec3 {
@@ -1,5 +1,6 @@
 //!
 //! Math functionality.
+//! The coordinate system is right-handed, like in OpenGL, and which axis points up is defined by the [camera](crate::Camera).
 //!
 
 #[doc(hidden)]
@@ -63,6 +63,28 @@ impl AxisAlignedBoundingBox {
         self
     }
 
+    ///
+    /// Returns the center of the bounding box.
+    ///
+    pub fn center(&self) -> Vec3 {
+        0.5 * (self.min + self.max)
+    }
+
+    ///
+    /// Returns the axis aligned bounding box which contains this bounding box after it is transformed with the given transformation.
+    ///
+    pub fn transform(&self, transformation: &Mat4) -> Self {
+        let mut corners = Vec::with_capacity(24);
+        for x in &[self.min.x, self.max.x] {
+            for y in &[self.min.y, self.max.y] {
+                for z in &[self.min.z, self.max.z] {
+                    corners.extend(&[*x, *y, *z]);
+                }
+            }
+        }
+        Self::new().expand_with_transformation(&corners, transformation)
+    }
+
     pub fn add(mut self, other: &AxisAlignedBoundingBox) -> Self {
         self.min = vec3(
             f32::min(self.min.x, other.min.x),
@@ -131,3 +131,29 @@ pub fn rotation_matrix_from_dir_to_dir(source_dir: Vec3, target_dir: Vec3) -> Ma
     )
     .transpose();
 }
+
+///
+/// Returns the transformation which converts from a coordinate system where the y-axis points up to a coordinate system where the z-axis points up,
+/// ie. a rotation of 90 degrees around the x-axis which maps the y-axis to the z-axis and the z-axis to the negative y-axis.
+/// Both coordinate systems are right-handed, like the coordinate system used in this crate.
+///
+pub fn convert_y_up_to_z_up() -> Mat4 {
+    Mat4::from_angle_x(degrees(90.0))
+}
+
+///
+/// Returns the transformation which converts from a coordinate system where the z-axis points up to a coordinate system where the y-axis points up,
+/// for example when importing a model from a tool which uses z up into a scene which uses y up. This is the inverse of [convert_y_up_to_z_up].
+///
+pub fn convert_z_up_to_y_up() -> Mat4 {
+    Mat4::from_angle_x(degrees(-90.0))
+}
+
+///
+/// Returns the transformation which converts between a left-handed and a right-handed coordinate system by mirroring the z-axis.
+/// This crate uses a right-handed coordinate system, like OpenGL, so use this when importing geometry from a left-handed tool.
+/// Note that mirroring also changes the winding order of the triangles, which is handled by [CPUMesh::transform](crate::CPUMesh::transform).
+///
+pub fn convert_handedness() -> Mat4 {
+    Mat4::from_nonuniform_scale(1.0, 1.0, -1.0)
+}
@@ -7,6 +7,11 @@ pub mod mesh;
 #[doc(inline)]
 pub use crate::mesh::*;
 
+#[doc(hidden)]
+pub mod mesh_buffer;
+#[doc(inline)]
+pub use crate::mesh_buffer::*;
+
 #[doc(hidden)]
 pub mod instanced_mesh;
 #[doc(inline)]
@@ -31,3 +36,28 @@ pub use crate::particles::*;
 pub mod axes;
 #[doc(inline)]
 pub use crate::axes::*;
+
+#[doc(hidden)]
+pub mod render_queue;
+#[doc(inline)]
+pub use crate::render_queue::*;
+
+#[doc(hidden)]
+pub mod model;
+#[doc(inline)]
+pub use crate::model::*;
+
+#[doc(hidden)]
+pub mod lines;
+#[doc(inline)]
+pub use crate::lines::*;
+
+#[doc(hidden)]
+pub mod bounding_box;
+#[doc(inline)]
+pub use crate::bounding_box::*;
+
+#[doc(hidden)]
+pub mod points;
+#[doc(inline)]
+pub use crate::points::*;
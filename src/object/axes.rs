@@ -3,6 +3,7 @@ use crate::core::*;
 use crate::definition::*;
 use crate::math::*;
 use crate::object::*;
+use std::cell::RefCell;
 
 ///
 /// Three arrows indicating the three main axes; the x-axis (red), the y-axis (green) and the z-axis (blue).
@@ -12,14 +13,35 @@ pub struct Axes {
     x: Mesh,
     y: Mesh,
     z: Mesh,
+    context: Context,
+    cpu_mesh: CPUMesh,
+    // Created the first time the axes are rendered instanced
+    instanced: RefCell<Option<[InstancedMesh; 3]>>,
 }
 
 impl Axes {
     pub fn new(context: &Context, radius: f32, length: f32) -> Result<Self, Error> {
+        Self::new_with_detail(context, radius, length, 16)
+    }
+
+    ///
+    /// Same as [new](Self::new), except that the arrows are tessellated with the given number of subdivisions around their axis (the default is 16),
+    /// for example more for smooth arrows in high resolution renders or fewer for a minimal overhead.
+    ///
+    pub fn new_with_detail(
+        context: &Context,
+        radius: f32,
+        length: f32,
+        angle_subdivisions: u32,
+    ) -> Result<Self, Error> {
+        let cpu_mesh = CPUMesh::arrow(radius, length, angle_subdivisions);
         Ok(Self {
-            x: Mesh::new(context, &CPUMesh::arrow(radius, length, 16))?,
-            y: Mesh::new(context, &CPUMesh::arrow(radius, length, 16))?,
-            z: Mesh::new(context, &CPUMesh::arrow(radius, length, 16))?,
+            x: Mesh::new(context, &cpu_mesh)?,
+            y: Mesh::new(context, &cpu_mesh)?,
+            z: Mesh::new(context, &cpu_mesh)?,
+            context: context.clone(),
+            cpu_mesh,
+            instanced: RefCell::new(None),
         })
     }
 
@@ -59,4 +81,54 @@ impl Axes {
 
         Ok(())
     }
+
+    ///
+    /// Render one set of axes for each of the given transformations using instanced rendering,
+    /// ie. only one draw call per axis regardless of the number of transformations.
+    /// The instanced meshes are created the first time this function is called.
+    /// Must be called in a render target render function,
+    /// for example in the callback function of [Screen::write](crate::Screen::write).
+    /// Each transformation is used to position, orientate and scale one set of axes.
+    ///
+    pub fn render_instanced(
+        &self,
+        transformations: &[Mat4],
+        viewport: Viewport,
+        camera: &camera::Camera,
+    ) -> Result<(), Error> {
+        let colors = [
+            vec4(1.0, 0.0, 0.0, 1.0),
+            vec4(0.0, 1.0, 0.0, 1.0),
+            vec4(0.0, 0.0, 1.0, 1.0),
+        ];
+        let rotations = [
+            Mat4::identity(),
+            Mat4::from_angle_z(degrees(90.0)),
+            Mat4::from_angle_y(degrees(-90.0)),
+        ];
+        let mut instanced = self.instanced.borrow_mut();
+        if instanced.is_none() {
+            *instanced = Some([
+                InstancedMesh::new(&self.context, &[], &self.cpu_mesh)?,
+                InstancedMesh::new(&self.context, &[], &self.cpu_mesh)?,
+                InstancedMesh::new(&self.context, &[], &self.cpu_mesh)?,
+            ]);
+        }
+        let instanced = instanced.as_mut().unwrap();
+        for i in 0..3 {
+            let axis_transformations: Vec<Mat4> = transformations
+                .iter()
+                .map(|transformation| transformation * rotations[i])
+                .collect();
+            instanced[i].update_transformations(&axis_transformations);
+            instanced[i].render_with_color(
+                &colors[i],
+                RenderStates::default(),
+                viewport,
+                &Mat4::identity(),
+                camera,
+            )?;
+        }
+        Ok(())
+    }
 }
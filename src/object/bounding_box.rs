@@ -0,0 +1,99 @@
+use crate::camera::*;
+use crate::core::*;
+use crate::math::*;
+use crate::object::*;
+
+///
+/// The twelve edges of a box rendered as [lines](crate::Lines) with the given color.
+/// Used for debugging, for example to visualize the [bounding box](crate::AxisAlignedBoundingBox) of an object
+/// and verify that it is culled correctly.
+///
+pub struct BoundingBox {
+    lines: Lines,
+    ///
+    /// The color of the edges.
+    ///
+    pub color: Vec4,
+}
+
+impl BoundingBox {
+    ///
+    /// Constructs a new box with the given minimum and maximum corners. The edges are two pixels wide.
+    ///
+    pub fn new(context: &Context, min: Vec3, max: Vec3, color: Vec4) -> Result<Self, Error> {
+        Ok(Self {
+            lines: Lines::new(context, &edges(min, max), 2.0)?,
+            color,
+        })
+    }
+
+    ///
+    /// Same as [new](Self::new), except that the box is the given axis aligned bounding box,
+    /// for example the bounding box of a mesh given by [Mesh::aabb](crate::Mesh::aabb). Use the transformation of the mesh
+    /// when rendering, since the bounding box is in the local space of the mesh.
+    ///
+    pub fn new_from_aabb(
+        context: &Context,
+        aabb: &AxisAlignedBoundingBox,
+        color: Vec4,
+    ) -> Result<Self, Error> {
+        Self::new(context, aabb.min, aabb.max, color)
+    }
+
+    ///
+    /// Updates the minimum and maximum corners of the box.
+    ///
+    pub fn update(&mut self, min: Vec3, max: Vec3) {
+        self.lines.update_positions(&edges(min, max));
+    }
+
+    ///
+    /// Render the edges of the box.
+    /// Must be called in a render target render function,
+    /// for example in the callback function of [Screen::write](crate::Screen::write).
+    /// The transformation can be used to position, orientate and scale the box.
+    ///
+    pub fn render(
+        &self,
+        viewport: Viewport,
+        transformation: &Mat4,
+        camera: &Camera,
+    ) -> Result<(), Error> {
+        self.lines.render(
+            &self.color,
+            RenderStates {
+                write_mask: WriteMask {
+                    depth: false,
+                    ..WriteMask::default()
+                },
+                blend: Some(BlendParameters::TRANSPARENCY),
+                ..Default::default()
+            },
+            viewport,
+            transformation,
+            camera,
+        )
+    }
+}
+
+fn edges(min: Vec3, max: Vec3) -> Vec<f32> {
+    let corner = |i: usize| {
+        vec3(
+            if i & 1 == 0 { min.x } else { max.x },
+            if i & 2 == 0 { min.y } else { max.y },
+            if i & 4 == 0 { min.z } else { max.z },
+        )
+    };
+    let mut positions = Vec::with_capacity(72);
+    for i in 0..8 {
+        // Each edge goes from a corner to the corner where one of the coordinates is changed from min to max
+        for bit in [1, 2, 4].iter() {
+            if i & bit == 0 {
+                let start = corner(i);
+                let end = corner(i | bit);
+                positions.extend(&[start.x, start.y, start.z, end.x, end.y, end.z]);
+            }
+        }
+    }
+    positions
+}
@@ -3,6 +3,7 @@ use crate::core::*;
 use crate::definition::*;
 use crate::math::*;
 use crate::object::mesh::*;
+use std::cell::RefCell;
 
 ///
 /// A shader program used for rendering one or more instances of a [InstancedMesh](InstancedMesh). It has a fixed vertex shader and
@@ -17,11 +18,50 @@ impl InstancedMeshProgram {
     /// Constructs a new shader program for rendering instanced meshes. The fragment shader can use the fragments position by adding `in vec3 pos;`,
     /// its normal by `in vec3 nor;`, its uv coordinates by `in vec2 uvs;` and its per vertex color by `in vec4 col;` to the shader source code.
     ///
+    /// # Errors
+    /// Will return an error if the fragment shader uses any other `in` values, since instanced meshes do not have
+    /// [custom attributes](crate::Mesh::new_with_attributes).
+    ///
     pub fn new(context: &Context, fragment_shader_source: &str) -> Result<Self, Error> {
         Ok(Self {
-            mesh_program: MeshProgram::new_internal(context, fragment_shader_source, true)?,
+            mesh_program: MeshProgram::new_internal(
+                context,
+                fragment_shader_source,
+                MeshProgramOptions {
+                    instanced: true,
+                    ..Default::default()
+                },
+            )?,
         })
     }
+
+    ///
+    /// Same as [new](Self::new), except that the depth is written using a logarithmic distribution,
+    /// see [MeshProgram::new_with_logarithmic_depth](crate::MeshProgram::new_with_logarithmic_depth).
+    ///
+    pub fn new_with_logarithmic_depth(
+        context: &Context,
+        fragment_shader_source: &str,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            mesh_program: MeshProgram::new_internal(
+                context,
+                fragment_shader_source,
+                MeshProgramOptions {
+                    instanced: true,
+                    logarithmic_depth: true,
+                    ..Default::default()
+                },
+            )?,
+        })
+    }
+
+    ///
+    /// Recompiles the program with the given fragment shader source, see [MeshProgram::reload](crate::MeshProgram::reload).
+    ///
+    pub fn reload(&mut self, fragment_shader_source: &str) -> Result<(), Error> {
+        self.mesh_program.reload(fragment_shader_source)
+    }
 }
 
 impl std::ops::Deref for InstancedMeshProgram {
@@ -41,10 +81,11 @@ pub struct InstancedMesh {
     normal_buffer: Option<VertexBuffer>,
     index_buffer: Option<ElementBuffer>,
     uv_buffer: Option<VertexBuffer>,
-    instance_count: u32,
-    instance_buffer1: VertexBuffer,
-    instance_buffer2: VertexBuffer,
-    instance_buffer3: VertexBuffer,
+    instances: InstanceBuffers,
+    // Used for the visible instances in render_culled and the selected instances in render_subset.
+    culled_instances: RefCell<InstanceBuffers>,
+    transformations: Vec<Mat4>,
+    aabb: AxisAlignedBoundingBox,
 }
 
 impl InstancedMesh {
@@ -53,6 +94,7 @@ impl InstancedMesh {
     /// in as many instances as there are transformation matrices in the transformations parameter.
     /// Each instance is transformed with the given transformation before it is rendered.
     /// The transformations can be updated by the [update_transformations](Self::update_transformations) function.
+    /// If the CPU mesh has no indices, or an empty list of indices, each three consecutive positions form a triangle.
     ///
     pub fn new(
         context: &Context,
@@ -65,11 +107,12 @@ impl InstancedMesh {
         } else {
             None
         };
-        let index_buffer = if let Some(ref ind) = cpu_mesh.indices {
-            Some(ElementBuffer::new_with_u32(context, ind)?)
-        } else {
-            None
-        };
+        let index_buffer =
+            if let Some(ind) = cpu_mesh.indices.as_ref().filter(|ind| !ind.is_empty()) {
+                Some(ElementBuffer::new_with_u32(context, ind)?)
+            } else {
+                None
+            };
         let uv_buffer = if let Some(ref uvs) = cpu_mesh.uvs {
             Some(VertexBuffer::new_with_static_f32(context, uvs)?)
         } else {
@@ -78,14 +121,14 @@ impl InstancedMesh {
 
         let mut mesh = Self {
             context: context.clone(),
-            instance_count: 0,
             position_buffer,
             normal_buffer,
             index_buffer,
             uv_buffer,
-            instance_buffer1: VertexBuffer::new_with_dynamic_f32(context, &[])?,
-            instance_buffer2: VertexBuffer::new_with_dynamic_f32(context, &[])?,
-            instance_buffer3: VertexBuffer::new_with_dynamic_f32(context, &[])?,
+            instances: InstanceBuffers::new(context)?,
+            culled_instances: RefCell::new(InstanceBuffers::new(context)?),
+            transformations: Vec::new(),
+            aabb: cpu_mesh.compute_aabb(),
         };
         mesh.update_transformations(transformations);
         unsafe {
@@ -220,12 +263,126 @@ impl InstancedMesh {
         transformation: &Mat4,
         camera: &camera::Camera,
     ) -> Result<(), Error> {
-        program.use_attribute_vec4_divisor(&self.instance_buffer1, "row1", 1)?;
-        program.use_attribute_vec4_divisor(&self.instance_buffer2, "row2", 1)?;
-        program.use_attribute_vec4_divisor(&self.instance_buffer3, "row3", 1)?;
+        self.render_instances(
+            &self.instances,
+            program,
+            render_states,
+            viewport,
+            transformation,
+            camera,
+        )
+    }
+
+    ///
+    /// Same as [render](Self::render), except that only the instances which are inside the frustum of the camera are rendered.
+    /// The bounding box of each instance, ie. the bounding box of the mesh transformed by the given transformation and the transformation
+    /// of the instance, is tested against the frustum on the CPU and only the transformations of the visible instances are uploaded,
+    /// which saves GPU work when most of the instances are outside the view, for example a forest in an open world.
+    /// Returns the number of rendered instances.
+    ///
+    pub fn render_culled(
+        &self,
+        program: &InstancedMeshProgram,
+        render_states: RenderStates,
+        viewport: Viewport,
+        transformation: &Mat4,
+        camera: &camera::Camera,
+    ) -> Result<u32, Error> {
+        let visible_transformations = self
+            .transformations
+            .iter()
+            .filter(|t| camera.in_frustum(&self.aabb.transform(&(transformation * *t))))
+            .cloned()
+            .collect::<Vec<_>>();
+        let mut culled_instances = self.culled_instances.borrow_mut();
+        culled_instances.fill(&visible_transformations);
+        if culled_instances.count > 0 {
+            self.render_instances(
+                &culled_instances,
+                program,
+                render_states,
+                viewport,
+                transformation,
+                camera,
+            )?;
+        }
+        Ok(culled_instances.count)
+    }
+
+    ///
+    /// Same as [render](Self::render), except that only the instances with the given indices are rendered,
+    /// where an index refers to the transformation at that position in the list given at construction or in
+    /// [update_transformations](Self::update_transformations).
+    /// Only the transformations of the selected instances are uploaded to a separate compacted instance buffer,
+    /// so instances can be hidden and shown every frame without re-uploading all of the instances.
+    ///
+    /// # Errors
+    /// Will return an error if one of the indices is not less than the number of instances.
+    ///
+    pub fn render_subset(
+        &self,
+        instance_indices: &[u32],
+        program: &InstancedMeshProgram,
+        render_states: RenderStates,
+        viewport: Viewport,
+        transformation: &Mat4,
+        camera: &camera::Camera,
+    ) -> Result<(), Error> {
+        let mut subset_transformations = Vec::with_capacity(instance_indices.len());
+        for index in instance_indices {
+            let t = self
+                .transformations
+                .get(*index as usize)
+                .ok_or(Error::FailedToCreateMesh {
+                    message: format!(
+                        "The instance index {} is out of range, the mesh only has {} instances.",
+                        index,
+                        self.transformations.len()
+                    ),
+                })?;
+            subset_transformations.push(*t);
+        }
+        let mut subset_instances = self.culled_instances.borrow_mut();
+        subset_instances.fill(&subset_transformations);
+        if subset_instances.count > 0 {
+            self.render_instances(
+                &subset_instances,
+                program,
+                render_states,
+                viewport,
+                transformation,
+                camera,
+            )?;
+        }
+        Ok(())
+    }
+
+    ///
+    /// Returns the axis aligned bounding box of the mesh before it is transformed by the instance transformations.
+    ///
+    pub fn aabb(&self) -> &AxisAlignedBoundingBox {
+        &self.aabb
+    }
+
+    fn render_instances(
+        &self,
+        instances: &InstanceBuffers,
+        program: &InstancedMeshProgram,
+        render_states: RenderStates,
+        viewport: Viewport,
+        transformation: &Mat4,
+        camera: &camera::Camera,
+    ) -> Result<(), Error> {
+        program.use_attribute_vec4_divisor(&instances.row1, "row1", 1)?;
+        program.use_attribute_vec4_divisor(&instances.row2, "row2", 1)?;
+        program.use_attribute_vec4_divisor(&instances.row3, "row3", 1)?;
 
         program.use_uniform_mat4("modelMatrix", &transformation)?;
         program.use_uniform_block(camera.matrix_buffer(), "Camera");
+        program
+            .mesh_program
+            .use_clip_planes(&render_states.clip_planes)?;
+        program.mesh_program.use_logarithmic_depth(camera)?;
 
         program.use_attribute_vec3(&self.position_buffer, "position")?;
         if program.mesh_program.use_uvs {
@@ -247,18 +404,13 @@ impl InstancedMesh {
         }
 
         if let Some(ref index_buffer) = self.index_buffer {
-            program.draw_elements_instanced(
-                render_states,
-                viewport,
-                index_buffer,
-                self.instance_count,
-            );
+            program.draw_elements_instanced(render_states, viewport, index_buffer, instances.count);
         } else {
             program.draw_arrays_instanced(
                 render_states,
                 viewport,
                 self.position_buffer.count() as u32 / 3,
-                self.instance_count,
+                instances.count,
             );
         }
         Ok(())
@@ -269,7 +421,31 @@ impl InstancedMesh {
     /// The mesh is rendered in as many instances as there are transformation matrices.
     ///
     pub fn update_transformations(&mut self, transformations: &[Mat4]) {
-        self.instance_count = transformations.len() as u32;
+        self.transformations = transformations.to_vec();
+        self.instances.fill(transformations);
+    }
+}
+
+// The first three rows of the transformation of each instance, the last row is always (0, 0, 0, 1).
+struct InstanceBuffers {
+    row1: VertexBuffer,
+    row2: VertexBuffer,
+    row3: VertexBuffer,
+    count: u32,
+}
+
+impl InstanceBuffers {
+    fn new(context: &Context) -> Result<Self, Error> {
+        Ok(Self {
+            row1: VertexBuffer::new_with_dynamic_f32(context, &[])?,
+            row2: VertexBuffer::new_with_dynamic_f32(context, &[])?,
+            row3: VertexBuffer::new_with_dynamic_f32(context, &[])?,
+            count: 0,
+        })
+    }
+
+    fn fill(&mut self, transformations: &[Mat4]) {
+        self.count = transformations.len() as u32;
         let mut row1 = Vec::new();
         let mut row2 = Vec::new();
         let mut row3 = Vec::new();
@@ -289,9 +465,9 @@ impl InstancedMesh {
             row3.push(transform.z.z);
             row3.push(transform.w.z);
         }
-        self.instance_buffer1.fill_with_dynamic_f32(&row1);
-        self.instance_buffer2.fill_with_dynamic_f32(&row2);
-        self.instance_buffer3.fill_with_dynamic_f32(&row3);
+        self.row1.fill_with_dynamic_f32(&row1);
+        self.row2.fill_with_dynamic_f32(&row2);
+        self.row3.fill_with_dynamic_f32(&row3);
     }
 }
 
@@ -314,3 +490,59 @@ static mut PROGRAM_TEXTURE: Option<InstancedMeshProgram> = None;
 static mut PROGRAM_DEPTH: Option<InstancedMeshProgram> = None;
 static mut PROGRAM_PER_VERTEX_COLOR: Option<InstancedMeshProgram> = None;
 static mut MESH_COUNT: u32 = 0;
+
+#[cfg(all(test, feature = "headless", not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::test_util::*;
+
+    #[test]
+    fn render_non_indexed_instanced_mesh() {
+        let test_context = TestContext::new();
+        let context = test_context.gl();
+        let camera = orthographic_camera(&context);
+        let viewport = Viewport::new_at_origo(8, 8);
+        // Each instance covers one half of the view
+        let transformations = [
+            Mat4::identity(),
+            Mat4::from_translation(vec3(2.0, 0.0, 0.0)),
+        ];
+        let mesh =
+            InstancedMesh::new(&context, &transformations, &triangle_soup(-2.0, 0.0, 0.0)).unwrap();
+
+        let image = render_to_cpu(&context, 8, 8, &ClearState::default(), || {
+            mesh.render_with_color(
+                &vec4(0.0, 1.0, 0.0, 1.0),
+                RenderStates::default(),
+                viewport,
+                &Mat4::identity(),
+                &camera,
+            )
+        })
+        .unwrap();
+        assert_images_match(&image, &uniform_image(8, 8, [0, 255, 0, 255]), 1, 0.0);
+
+        // The depth of both instances hides everything behind them
+        let background = Mesh::new(&context, &triangle_soup(-2.0, 2.0, -1.0)).unwrap();
+        let image = render_to_cpu(&context, 8, 8, &ClearState::default(), || {
+            mesh.render_depth(
+                RenderStates {
+                    write_mask: WriteMask::DEPTH,
+                    ..Default::default()
+                },
+                viewport,
+                &Mat4::identity(),
+                &camera,
+            )?;
+            background.render_with_color(
+                &vec4(0.0, 1.0, 0.0, 1.0),
+                RenderStates::default(),
+                viewport,
+                &Mat4::identity(),
+                &camera,
+            )
+        })
+        .unwrap();
+        assert_images_match(&image, &uniform_image(8, 8, [0, 0, 0, 255]), 0, 0.0);
+    }
+}
@@ -0,0 +1,128 @@
+use crate::camera::*;
+use crate::core::*;
+use crate::math::*;
+
+///
+/// A set of line segments which are rendered with a constant width in pixels regardless of the distance to the camera.
+/// Each line segment is rendered as a screen aligned quad with anti-aliased edges,
+/// so unlike lines rendered by OpenGL, the width is not limited to one pixel.
+///
+pub struct Lines {
+    context: Context,
+    start_buffer: VertexBuffer,
+    end_buffer: VertexBuffer,
+    corner_buffer: VertexBuffer,
+    instance_count: u32,
+    ///
+    /// The width of the lines in pixels.
+    ///
+    pub line_width: f32,
+    ///
+    /// The distance in camera space the depth of the lines is moved towards the camera,
+    /// which makes it possible to render lines on top of a surface without z-fighting, for example a wireframe or annotations.
+    /// The lines are rendered at the same position on the screen regardless of the bias. The default is zero.
+    ///
+    pub depth_bias: f32,
+}
+
+impl Lines {
+    ///
+    /// Constructs new lines from the given positions, where each line segment is defined by its two end points,
+    /// ie. six consecutive values in the positions.
+    ///
+    pub fn new(context: &Context, positions: &[f32], line_width: f32) -> Result<Self, Error> {
+        let corners = [
+            0.0, -1.0, 1.0, -1.0, 1.0, 1.0, 1.0, 1.0, 0.0, 1.0, 0.0, -1.0,
+        ];
+        let mut lines = Self {
+            context: context.clone(),
+            start_buffer: VertexBuffer::new_with_dynamic_f32(context, &[])?,
+            end_buffer: VertexBuffer::new_with_dynamic_f32(context, &[])?,
+            corner_buffer: VertexBuffer::new_with_static_f32(context, &corners)?,
+            instance_count: 0,
+            line_width,
+            depth_bias: 0.0,
+        };
+        lines.update_positions(positions);
+        unsafe {
+            LINES_COUNT += 1;
+        }
+        Ok(lines)
+    }
+
+    ///
+    /// Updates the positions of the line segments, see [new](Self::new).
+    ///
+    pub fn update_positions(&mut self, positions: &[f32]) {
+        let mut start = Vec::with_capacity(positions.len() / 2);
+        let mut end = Vec::with_capacity(positions.len() / 2);
+        for segment in positions.chunks_exact(6) {
+            start.extend(&segment[0..3]);
+            end.extend(&segment[3..6]);
+        }
+        self.instance_count = start.len() as u32 / 3;
+        self.start_buffer.fill_with_dynamic_f32(&start);
+        self.end_buffer.fill_with_dynamic_f32(&end);
+    }
+
+    ///
+    /// Render the lines with the given color.
+    /// Must be called in a render target render function,
+    /// for example in the callback function of [Screen::write](crate::Screen::write).
+    /// The transformation can be used to position, orientate and scale the lines.
+    /// Since the edges of the lines are anti-aliased, the render states should normally enable
+    /// [transparency blending](crate::BlendParameters::TRANSPARENCY) and disable writing to the depth buffer.
+    /// Line segments are clipped against the near plane of the camera, so lines crossing the camera plane are rendered correctly.
+    ///
+    pub fn render(
+        &self,
+        color: &Vec4,
+        render_states: RenderStates,
+        viewport: Viewport,
+        transformation: &Mat4,
+        camera: &Camera,
+    ) -> Result<(), Error> {
+        if self.instance_count == 0 {
+            return Ok(());
+        }
+        let program = unsafe {
+            if PROGRAM.is_none() {
+                PROGRAM = Some(Program::from_source(
+                    &self.context,
+                    include_str!("shaders/lines.vert"),
+                    include_str!("shaders/lines.frag"),
+                )?);
+            }
+            PROGRAM.as_ref().unwrap()
+        };
+        program.use_uniform_vec4("color", color)?;
+        program.use_uniform_float("lineWidth", &self.line_width)?;
+        program.use_uniform_float("depthBias", &self.depth_bias)?;
+        program.use_uniform_float("zNear", &camera.z_near())?;
+        program.use_uniform_vec2(
+            "viewportSize",
+            &vec2(viewport.width as f32, viewport.height as f32),
+        )?;
+        program.use_uniform_mat4("modelMatrix", transformation)?;
+        program.use_uniform_block(camera.matrix_buffer(), "Camera");
+        program.use_attribute_vec3_divisor(&self.start_buffer, "start", 1)?;
+        program.use_attribute_vec3_divisor(&self.end_buffer, "end", 1)?;
+        program.use_attribute_vec2(&self.corner_buffer, "corner")?;
+        program.draw_arrays_instanced(render_states, viewport, 6, self.instance_count);
+        Ok(())
+    }
+}
+
+impl Drop for Lines {
+    fn drop(&mut self) {
+        unsafe {
+            LINES_COUNT -= 1;
+            if LINES_COUNT == 0 {
+                PROGRAM = None;
+            }
+        }
+    }
+}
+
+static mut PROGRAM: Option<Program> = None;
+static mut LINES_COUNT: u32 = 0;
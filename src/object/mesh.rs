@@ -3,68 +3,219 @@ use crate::core::*;
 use crate::definition::*;
 #[doc(hidden)]
 use crate::math::*;
+use crate::object::*;
+use std::collections::HashMap;
+use std::rc::Rc;
 
 ///
 /// A shader program used for rendering one or more instances of a [Mesh](Mesh). It has a fixed vertex shader and
 /// customizable fragment shader for custom lighting. Use this in combination with [render](Mesh::render).
 ///
 pub struct MeshProgram {
+    context: Context,
     program: Program,
     pub(in crate::object) use_normals: bool,
     pub(in crate::object) use_uvs: bool,
     pub(in crate::object) use_colors: bool,
+    pub(in crate::object) custom_attributes: Vec<(String, usize)>,
+    pub(in crate::object) options: MeshProgramOptions,
+}
+
+///
+/// The variations of the vertex shader of a [MeshProgram], which are all disabled by default.
+///
+#[derive(Copy, Clone, Debug, Default)]
+pub(in crate::object) struct MeshProgramOptions {
+    pub instanced: bool,
+    pub logarithmic_depth: bool,
+    pub screen_space: bool,
+    pub displacement: bool,
 }
 
 impl MeshProgram {
     ///
     /// Constructs a new shader program for rendering meshes. The fragment shader can use the fragments position by adding `in vec3 pos;`,
     /// its normal by `in vec3 nor;`, its uv coordinates by `in vec2 uvs;` and its per vertex color by `in vec4 col;` to the shader source code.
+    /// The per vertex color is in the range 0-255, also when the colors are specified as floats.
+    /// Any other `in float`, `in vec2`, `in vec3` or `in vec4` declaration in the fragment shader is the interpolated value of the
+    /// [custom attribute](Mesh::new_with_attributes) with the same name and number of components.
+    /// The declarations can have interpolation qualifiers, for example `flat in float id;`, and a precision qualifier.
+    ///
+    /// # Errors
+    /// Will return an error if the shader fails to compile or if an `in` declaration cannot be passed on from the vertex shader,
+    /// for example an array, an integer type or a declaration with a layout qualifier.
     ///
     pub fn new(context: &Context, fragment_shader_source: &str) -> Result<Self, Error> {
-        Self::new_internal(context, fragment_shader_source, false)
+        Self::new_internal(
+            context,
+            fragment_shader_source,
+            MeshProgramOptions::default(),
+        )
+    }
+
+    ///
+    /// Same as [new](Self::new), except that the depth is written using a logarithmic distribution based on the far plane of the camera,
+    /// which gives a lot more precision across huge view ranges, for example when rendering both a cockpit and a distant planet.
+    /// The fragment shader source must contain `void main()` which is wrapped in a main function that writes `gl_FragDepth`.
+    /// The depth goes from 0 at the camera to 1 at the far plane, so the standard [depth test](crate::DepthTestType::Less) should be used.
+    /// If the far plane is at infinity (see [new_perspective_infinite](crate::Camera::new_perspective_infinite)),
+    /// the depth goes from 0 at the camera to 1 at the largest finite distance instead.
+    /// Note that writing the depth in the fragment shader disables early depth testing, so this is slower than rendering with the standard depth.
+    ///
+    pub fn new_with_logarithmic_depth(
+        context: &Context,
+        fragment_shader_source: &str,
+    ) -> Result<Self, Error> {
+        Self::new_internal(
+            context,
+            fragment_shader_source,
+            MeshProgramOptions {
+                logarithmic_depth: true,
+                ..Default::default()
+            },
+        )
+    }
+
+    ///
+    /// Same as [new](Self::new), except that the transformed vertex positions are used directly as clip space coordinates
+    /// and no camera is needed, which is useful for UI quads and custom fullscreen passes.
+    /// Use this in combination with [Mesh::render_screen_space], for example a fullscreen quad is given by positions
+    /// between -1 and 1 in the x and y direction and an identity transformation.
+    /// The fragment position `in vec3 pos;` is then also in clip space.
+    ///
+    pub fn new_screen_space(
+        context: &Context,
+        fragment_shader_source: &str,
+    ) -> Result<Self, Error> {
+        Self::new_internal(
+            context,
+            fragment_shader_source,
+            MeshProgramOptions {
+                screen_space: true,
+                ..Default::default()
+            },
+        )
+    }
+
+    ///
+    /// Same as [new](Self::new), except that each vertex is displaced along its normal by the value in the red channel of a displacement texture
+    /// at the uv coordinates of the vertex multiplied by a scale, which adds surface detail, for example to a terrain, without storing dense geometry.
+    /// Use this in combination with [Mesh::render_displaced], which also sets the displacement texture and scale,
+    /// and a mesh with normals, uv coordinates and enough vertices to represent the details of the displacement texture.
+    /// Note that the normal `in vec3 nor;` is the normal before the displacement, so use a normal map to get lighting which matches the displaced surface,
+    /// and that the [bounding box](Mesh::aabb) of the mesh does not include the displacement.
+    ///
+    pub fn new_with_displacement(
+        context: &Context,
+        fragment_shader_source: &str,
+    ) -> Result<Self, Error> {
+        Self::new_internal(
+            context,
+            fragment_shader_source,
+            MeshProgramOptions {
+                displacement: true,
+                ..Default::default()
+            },
+        )
     }
 
     pub(in crate::object) fn new_internal(
         context: &Context,
         fragment_shader_source: &str,
-        instanced: bool,
+        options: MeshProgramOptions,
     ) -> Result<Self, Error> {
-        let use_positions = fragment_shader_source.find("in vec3 pos;").is_some();
-        let use_normals = fragment_shader_source.find("in vec3 nor;").is_some();
-        let use_uvs = fragment_shader_source.find("in vec2 uvs;").is_some();
-        let use_colors = fragment_shader_source.find("in vec4 col;").is_some();
+        let MeshProgramOptions {
+            instanced,
+            logarithmic_depth,
+            screen_space,
+            displacement,
+        } = options;
+        let inputs = find_inputs(fragment_shader_source)?;
+        let use_input = |name: &str, components: usize| -> Result<bool, Error> {
+            match inputs.iter().find(|input| input.name == name) {
+                Some(input)
+                    if input.components != components || !input.interpolation.is_empty() =>
+                {
+                    Err(Error::FailedToCreateShader {
+                        shader_type: "Fragment shader".to_string(),
+                        message: format!(
+                            "The input {} must be declared as `in {} {};`",
+                            name,
+                            glsl_type(components),
+                            name
+                        ),
+                    })
+                }
+                input => Ok(input.is_some()),
+            }
+        };
+        let use_positions = use_input("pos", 3)?;
+        let use_normals = use_input("nor", 3)?;
+        let use_uvs = use_input("uvs", 2)?;
+        let use_colors = use_input("col", 4)?;
+        let custom_attributes: Vec<&InputDeclaration> = inputs
+            .iter()
+            .filter(|input| !["pos", "nor", "uvs", "col"].contains(&input.name.as_str()))
+            .collect();
+        let mut custom_attributes_in_out = String::new();
+        let mut custom_attributes_assignment = String::new();
+        for input in custom_attributes.iter() {
+            let glsl_type = glsl_type(input.components);
+            custom_attributes_in_out.push_str(&format!(
+                "in {} {}_attribute;\n{}out {} {};\n",
+                glsl_type, input.name, input.interpolation, glsl_type, input.name
+            ));
+            custom_attributes_assignment
+                .push_str(&format!("{} = {}_attribute;\n", input.name, input.name));
+        }
         let vertex_shader_source = &format!(
             "
-                layout (std140) uniform Camera
-                {{
-                    mat4 viewProjection;
-                    mat4 view;
-                    mat4 projection;
-                    vec3 position;
-                    float padding;
-                }} camera;
+                {} // Camera
 
                 uniform mat4 modelMatrix;
                 in vec3 position;
 
+                {} // Clip planes
+
                 {} // Instancing
                 {} // Positions out
                 {} // Normals in/out
                 {} // UV coordinates in/out
                 {} // Colors in/out
+                {} // Custom attributes in/out
+                {} // Logarithmic depth out
+                {} // Displacement
 
                 void main()
                 {{
                     mat4 local2World = modelMatrix;
                     {} // Instancing
-                    vec4 worldPosition = local2World * vec4(position, 1.);
-                    gl_Position = camera.viewProjection * worldPosition;
+                    vec3 localPosition = position;
+                    {} // Displacement
+                    vec4 worldPosition = local2World * vec4(localPosition, 1.);
+                    gl_Position = {}worldPosition;
+                    {} // Clip distances
                     {} // Position
                     {} // Normal
                     {} // UV coordinates
                     {} // Colors
+                    {} // Custom attributes
+                    {} // Logarithmic depth
                 }}
             ",
+            if screen_space {
+                ""
+            } else {
+                "layout (std140) uniform Camera
+                {
+                    mat4 viewProjection;
+                    mat4 view;
+                    mat4 projection;
+                    vec3 position;
+                    float padding;
+                } camera;"
+            },
+            CLIP_PLANES_SOURCE,
             if instanced {
                 "in vec4 row1;
                 in vec4 row2;
@@ -77,12 +228,16 @@ impl MeshProgram {
                 "uniform mat4 normalMatrix;
                 in vec3 normal;
                 out vec3 nor;"
+            } else if displacement {
+                "in vec3 normal;"
             } else {
                 ""
             },
             if use_uvs {
                 "in vec2 uv_coordinates;
                 out vec2 uvs;"
+            } else if displacement {
+                "in vec2 uv_coordinates;"
             } else {
                 ""
             },
@@ -92,6 +247,18 @@ impl MeshProgram {
             } else {
                 ""
             },
+            &custom_attributes_in_out,
+            if logarithmic_depth {
+                "out float logDepth;"
+            } else {
+                ""
+            },
+            if displacement {
+                "uniform sampler2D displacementMap;
+                uniform float displacementScale;"
+            } else {
+                ""
+            },
             if instanced {
                 "
                     mat4 transform;
@@ -103,6 +270,17 @@ impl MeshProgram {
             } else {
                 ""
             },
+            if displacement {
+                "localPosition += displacementScale * textureLod(displacementMap, uv_coordinates, 0.0).r * normal;"
+            } else {
+                ""
+            },
+            if screen_space {
+                ""
+            } else {
+                "camera.viewProjection * "
+            },
+            CLIP_DISTANCES_SOURCE,
             if use_positions {
                 "pos = worldPosition.xyz;"
             } else {
@@ -114,19 +292,306 @@ impl MeshProgram {
                 ""
             },
             if use_uvs { "uvs = uv_coordinates;" } else { "" },
-            if use_colors { "col = color;" } else { "" }
+            if use_colors { "col = color;" } else { "" },
+            &custom_attributes_assignment,
+            if logarithmic_depth {
+                "logDepth = 1.0 + gl_Position.w;"
+            } else {
+                ""
+            }
         );
 
-        let program = Program::from_source(context, vertex_shader_source, fragment_shader_source)?;
+        let program = if logarithmic_depth {
+            if !fragment_shader_source.contains("void main()") {
+                Err(Error::FailedToCreateShader {
+                    shader_type: "Fragment shader".to_string(),
+                    message: "A fragment shader with logarithmic depth must contain void main()"
+                        .to_string(),
+                })?;
+            }
+            let fragment_shader_source = format!(
+                "uniform float logDepthCoefficient;
+                in float logDepth;
+                {}
+                void main()
+                {{
+                    mainWithoutLogarithmicDepth();
+                    gl_FragDepth = log2(logDepth) * logDepthCoefficient;
+                }}",
+                fragment_shader_source.replacen(
+                    "void main()",
+                    "void mainWithoutLogarithmicDepth()",
+                    1
+                )
+            );
+            Program::from_source(context, vertex_shader_source, &fragment_shader_source)?
+        } else {
+            Program::from_source(context, vertex_shader_source, fragment_shader_source)?
+        };
+
+        // Only the declarations which are used in the fragment shader result in active attributes in the linked program
+        let mut active_custom_attributes = Vec::new();
+        for input in custom_attributes {
+            if program.has_attribute(&format!("{}_attribute", input.name))? {
+                active_custom_attributes.push((input.name.clone(), input.components));
+            }
+        }
+        if instanced {
+            if let Some((name, _)) = active_custom_attributes.first() {
+                Err(Error::FailedToCreateShader {
+                    shader_type: "Fragment shader".to_string(),
+                    message: format!(
+                        "The fragment shader uses the custom attribute {}, but custom attributes are not supported when rendering instanced meshes.",
+                        name
+                    ),
+                })?;
+            }
+        }
         Ok(Self {
+            context: context.clone(),
             program,
             use_normals,
             use_uvs,
             use_colors,
+            custom_attributes: active_custom_attributes,
+            options,
         })
     }
+
+    ///
+    /// Recompiles the program with the given fragment shader source, for example when the shader source file is changed during development.
+    /// The program is otherwise constructed in the same way as before, for example with [logarithmic depth](Self::new_with_logarithmic_depth).
+    /// All uniforms and textures must be set again before the next render call.
+    ///
+    /// # Errors
+    /// Will return an error if the new shader program fails to compile or link, in which case the previous shader program is kept.
+    ///
+    pub fn reload(&mut self, fragment_shader_source: &str) -> Result<(), Error> {
+        *self = Self::new_internal(&self.context, fragment_shader_source, self.options)?;
+        Ok(())
+    }
 }
 
+impl MeshProgram {
+    pub(in crate::object) fn use_clip_planes(
+        &self,
+        clip_planes: &[Option<Vec4>; MAX_CLIP_PLANES],
+    ) -> Result<(), Error> {
+        if cfg!(not(target_arch = "wasm32")) {
+            for (i, plane) in clip_planes.iter().enumerate() {
+                if let Some(plane) = plane {
+                    self.program
+                        .use_uniform_vec4(&format!("clipPlane{}", i), plane)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub(in crate::object) fn use_logarithmic_depth(
+        &self,
+        camera: &camera::Camera,
+    ) -> Result<(), Error> {
+        if self.options.logarithmic_depth {
+            // An infinite far plane is replaced by the largest finite distance, so every finite distance gets a depth between 0 and 1
+            self.program.use_uniform_float(
+                "logDepthCoefficient",
+                &(1.0 / (camera.z_far().min(f32::MAX) + 1.0).log2()),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+// A per vertex value declared with `in` in the fragment shader.
+#[derive(Debug, PartialEq)]
+struct InputDeclaration {
+    name: String,
+    components: usize,
+    // The interpolation qualifiers followed by a space, for example `flat `, or empty for the default interpolation.
+    interpolation: String,
+}
+
+fn glsl_type(components: usize) -> String {
+    match components {
+        1 => "float".to_string(),
+        _ => format!("vec{}", components),
+    }
+}
+
+// Returns the per vertex values declared with `in` in the global scope of the fragment shader, including the position,
+// normal, uv coordinates and color. Declarations which are not used are also returned, so the custom attributes must be
+// filtered by the active attributes of the linked program.
+// Returns an error for an `in` declaration which cannot be passed through from the vertex shader,
+// for example one with an integer type, an array or a layout qualifier.
+fn find_inputs(fragment_shader_source: &str) -> Result<Vec<InputDeclaration>, Error> {
+    let mut inputs: Vec<InputDeclaration> = Vec::new();
+    let mut statement: Vec<String> = Vec::new();
+    let mut depth = 0;
+    for token in tokenize(&remove_comments(fragment_shader_source)) {
+        match token.as_str() {
+            "{" => {
+                depth += 1;
+                statement.clear();
+            }
+            "}" => {
+                depth -= 1;
+                statement.clear();
+            }
+            ";" if depth == 0 => {
+                for input in parse_input_declaration(&statement)? {
+                    if !inputs.iter().any(|i| i.name == input.name) {
+                        inputs.push(input);
+                    }
+                }
+                statement.clear();
+            }
+            _ if depth == 0 => statement.push(token),
+            _ => {}
+        }
+    }
+    Ok(inputs)
+}
+
+// Returns the inputs declared by the given global statement, which is empty if it is not an `in` declaration.
+fn parse_input_declaration(statement: &[String]) -> Result<Vec<InputDeclaration>, Error> {
+    let in_index = match statement.iter().position(|token| token == "in") {
+        Some(in_index) => in_index,
+        None => return Ok(Vec::new()),
+    };
+    let unsupported = |reason: &str| {
+        Err(Error::FailedToCreateShader {
+            shader_type: "Fragment shader".to_string(),
+            message: format!(
+                "The input declaration `{};` is not supported, {}.",
+                statement.join(" "),
+                reason
+            ),
+        })
+    };
+    if statement.iter().any(|token| token == "(") {
+        if statement[0] == "layout" {
+            return unsupported("layout qualifiers cannot be used");
+        }
+        // A function prototype with an `in` parameter
+        return Ok(Vec::new());
+    }
+    let mut interpolation = String::new();
+    for qualifier in statement[..in_index].iter() {
+        match qualifier.as_str() {
+            "flat" | "smooth" | "noperspective" | "centroid" => {
+                interpolation.push_str(qualifier);
+                interpolation.push(' ');
+            }
+            _ => return unsupported("only interpolation qualifiers can be used before `in`"),
+        }
+    }
+    let mut rest = statement[in_index + 1..].iter().peekable();
+    if let Some(&precision) = rest.peek() {
+        if ["highp", "mediump", "lowp"].contains(&precision.as_str()) {
+            rest.next();
+        }
+    }
+    let components = match rest.next().map(|t| t.as_str()) {
+        Some("float") => 1,
+        Some("vec2") => 2,
+        Some("vec3") => 3,
+        Some("vec4") => 4,
+        _ => return unsupported("the type must be float, vec2, vec3 or vec4"),
+    };
+    let mut inputs = Vec::new();
+    loop {
+        match rest.next() {
+            Some(name) if is_identifier(name) => inputs.push(InputDeclaration {
+                name: name.clone(),
+                components,
+                interpolation: interpolation.clone(),
+            }),
+            _ => return unsupported("expected a name"),
+        }
+        match rest.next().map(|t| t.as_str()) {
+            None => return Ok(inputs),
+            Some(",") => {}
+            Some("[") => return unsupported("arrays cannot be used"),
+            _ => return unsupported("expected `,` or `;` after the name"),
+        }
+    }
+}
+
+fn is_identifier(token: &str) -> bool {
+    token
+        .chars()
+        .next()
+        .map(|c| c.is_ascii_alphabetic() || c == '_')
+        .unwrap_or(false)
+}
+
+// Splits the shader source, without comments, into identifiers, numbers and single punctuation characters,
+// where preprocessor directives are skipped.
+fn tokenize(source: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    for line in source
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+    {
+        let mut word = String::new();
+        for c in line.chars() {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '.' {
+                word.push(c);
+                continue;
+            }
+            if !word.is_empty() {
+                tokens.push(std::mem::take(&mut word));
+            }
+            if !c.is_whitespace() {
+                tokens.push(c.to_string());
+            }
+        }
+        if !word.is_empty() {
+            tokens.push(word);
+        }
+    }
+    tokens
+}
+
+// Returns the shader source where each comment is replaced by a space.
+fn remove_comments(source: &str) -> String {
+    let mut result = String::with_capacity(source.len());
+    let mut rest = source;
+    while let Some(start) = rest.find('/') {
+        let (code, comment) = rest.split_at(start);
+        result.push_str(code);
+        if comment.starts_with("//") {
+            rest = comment.find('\n').map(|end| &comment[end..]).unwrap_or("");
+            result.push(' ');
+        } else if let Some(block) = comment.strip_prefix("/*") {
+            rest = block.find("*/").map(|end| &block[end + 2..]).unwrap_or("");
+            result.push(' ');
+        } else {
+            result.push('/');
+            rest = &comment[1..];
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+const CLIP_PLANES_SOURCE: &str = "uniform vec4 clipPlane0;
+                uniform vec4 clipPlane1;
+                uniform vec4 clipPlane2;
+                uniform vec4 clipPlane3;";
+#[cfg(target_arch = "wasm32")]
+const CLIP_PLANES_SOURCE: &str = "";
+
+#[cfg(not(target_arch = "wasm32"))]
+const CLIP_DISTANCES_SOURCE: &str = "gl_ClipDistance[0] = dot(clipPlane0, worldPosition);
+                    gl_ClipDistance[1] = dot(clipPlane1, worldPosition);
+                    gl_ClipDistance[2] = dot(clipPlane2, worldPosition);
+                    gl_ClipDistance[3] = dot(clipPlane3, worldPosition);";
+#[cfg(target_arch = "wasm32")]
+const CLIP_DISTANCES_SOURCE: &str = "";
+
 impl std::ops::Deref for MeshProgram {
     type Target = Program;
 
@@ -135,43 +600,176 @@ impl std::ops::Deref for MeshProgram {
     }
 }
 
+///
+/// The parameters used when rendering a mesh with [refraction](Mesh::render_refraction).
+///
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Refraction {
+    /// The ratio between the index of refraction of the mesh material and the surrounding medium (usually air).
+    /// For example 1.33 for water and 1.5 for glass, while 1.0 gives no distortion.
+    pub index_of_refraction: f32,
+    /// The distance in world space the refracted light travels through the mesh, which controls the amount of distortion.
+    pub strength: f32,
+}
+
+impl Default for Refraction {
+    fn default() -> Self {
+        Self {
+            index_of_refraction: 1.5,
+            strength: 0.5,
+        }
+    }
+}
+
 ///
 /// A triangle mesh which can be rendered with one of the default render functions or with a custom [MeshProgram](MeshProgram).
 /// See also [PhongMesh](crate::PhongMesh) for rendering a mesh with lighting.
 ///
 pub struct Mesh {
     context: Context,
-    position_buffer: VertexBuffer,
-    normal_buffer: Option<VertexBuffer>,
-    index_buffer: Option<ElementBuffer>,
-    uv_buffer: Option<VertexBuffer>,
-    color_buffer: Option<VertexBuffer>,
+    buffers: MeshBuffers,
+    attribute_buffers: HashMap<String, (VertexBuffer, usize)>,
+    aabb: AxisAlignedBoundingBox,
+}
+
+enum MeshBuffers {
+    Separate {
+        position_buffer: VertexBuffer,
+        normal_buffer: Option<VertexBuffer>,
+        index_buffer: Option<ElementBuffer>,
+        uv_buffer: Option<VertexBuffer>,
+        color_buffer: Option<VertexBuffer>,
+    },
+    // A range of the indices in interleaved buffers, which are shared with other meshes if they belong to a MeshBuffer
+    Shared {
+        buffers: Rc<InterleavedBuffers>,
+        first: u32,
+        count: u32,
+    },
 }
 
 impl Mesh {
     ///
     /// Copies the per vertex data defined in the given [CPUMesh](crate::CPUMesh) to the GPU, thereby
     /// making it possible to render the mesh.
+    /// If the CPU mesh has no indices, or an empty list of indices, each three consecutive positions form a triangle.
     ///
     pub fn new(context: &Context, cpu_mesh: &CPUMesh) -> Result<Self, Error> {
-        let position_buffer = VertexBuffer::new_with_static_f32(context, &cpu_mesh.positions)?;
+        Self::new_with_usage(context, cpu_mesh, BufferUsage::Static)
+    }
+
+    ///
+    /// Same as [new](Self::new), except that the vertex and element buffers are created with the given [usage](crate::BufferUsage) hint,
+    /// for example [Dynamic](crate::BufferUsage::Dynamic) or [Stream](crate::BufferUsage::Stream) for a mesh which is updated often.
+    ///
+    pub fn new_with_usage(
+        context: &Context,
+        cpu_mesh: &CPUMesh,
+        usage: BufferUsage,
+    ) -> Result<Self, Error> {
+        let position_buffer =
+            VertexBuffer::new_with_usage_f32(context, &cpu_mesh.positions, usage)?;
         let normal_buffer = if let Some(ref normals) = cpu_mesh.normals {
-            Some(VertexBuffer::new_with_static_f32(context, normals)?)
+            Some(VertexBuffer::new_with_usage_f32(context, normals, usage)?)
         } else {
             None
         };
-        let index_buffer = if let Some(ref ind) = cpu_mesh.indices {
-            Some(ElementBuffer::new_with_u32(context, ind)?)
+        let index_buffer =
+            if let Some(ind) = cpu_mesh.indices.as_ref().filter(|ind| !ind.is_empty()) {
+                Some(ElementBuffer::new_with_usage_u32(context, ind, usage)?)
+            } else {
+                None
+            };
+        let uv_buffer = if let Some(ref uvs) = cpu_mesh.uvs {
+            Some(VertexBuffer::new_with_usage_f32(context, uvs, usage)?)
         } else {
             None
         };
-        let uv_buffer = if let Some(ref uvs) = cpu_mesh.uvs {
-            Some(VertexBuffer::new_with_static_f32(context, uvs)?)
+        let color_buffer = if let Some(ref colors) = cpu_mesh.colors_f32 {
+            // Scaled to the same range as the 8 bit colors, so shaders can treat both the same way.
+            let colors = colors.iter().map(|c| c * 255.0).collect::<Vec<_>>();
+            Some(VertexBuffer::new_with_usage_f32(context, &colors, usage)?)
+        } else if let Some(ref colors) = cpu_mesh.colors {
+            Some(VertexBuffer::new_with_usage_u8(context, colors, usage)?)
         } else {
             None
         };
-        let color_buffer = if let Some(ref colors) = cpu_mesh.colors {
-            Some(VertexBuffer::new_with_static_u8(context, colors)?)
+        unsafe {
+            MESH_COUNT += 1;
+        }
+        Ok(Mesh {
+            context: context.clone(),
+            buffers: MeshBuffers::Separate {
+                position_buffer,
+                normal_buffer,
+                index_buffer,
+                uv_buffer,
+                color_buffer,
+            },
+            attribute_buffers: HashMap::new(),
+            aabb: cpu_mesh.compute_aabb(),
+        })
+    }
+
+    ///
+    /// Same as [new](Self::new), except that the positions, normals, uv coordinates and colors are interleaved in one vertex buffer
+    /// instead of being stored in a buffer each, which can improve the vertex fetch performance for large meshes.
+    /// If the CPU mesh has no indices, an element buffer with an index for each vertex is created, since interleaved meshes are always drawn with indices.
+    ///
+    /// # Errors
+    /// Will return an error if the length of the normals, uv coordinates or colors does not match the number of vertices.
+    ///
+    pub fn new_interleaved(context: &Context, cpu_mesh: &CPUMesh) -> Result<Self, Error> {
+        let (buffers, ranges) = InterleavedBuffers::new(context, &[cpu_mesh])?;
+        let (first, count, aabb) = ranges[0];
+        Ok(Self::new_shared(
+            context,
+            Rc::new(buffers),
+            first,
+            count,
+            aabb,
+        ))
+    }
+
+    ///
+    /// Constructs a mesh with the given positions, optional indices and custom per vertex attributes instead of the attributes
+    /// of a [CPUMesh](crate::CPUMesh), for example a scalar field or a second set of uv coordinates.
+    /// Each attribute is given by its name, the data and the number of components per vertex between 1 and 4,
+    /// and it is available in the fragment shader of a [MeshProgram] which declares it with the same name,
+    /// for example `in float temperature;` for an attribute with one component.
+    ///
+    /// # Errors
+    /// Will return an error if the number of components of an attribute is not between 1 and 4 or if the length of the data
+    /// does not match the number of vertices.
+    ///
+    pub fn new_with_attributes(
+        context: &Context,
+        positions: &[f32],
+        indices: Option<&[u32]>,
+        attributes: HashMap<String, (Vec<f32>, usize)>,
+    ) -> Result<Self, Error> {
+        let vertex_count = positions.len() / 3;
+        let mut attribute_buffers = HashMap::new();
+        for (name, (data, components)) in attributes.iter() {
+            if *components < 1 || *components > 4 || data.len() != vertex_count * components {
+                Err(Error::FailedToCreateMesh {
+                    message: format!(
+                        "The attribute {} must have between 1 and 4 components per vertex for {} vertices, but {} values with {} components was given.",
+                        name, vertex_count, data.len(), components
+                    ),
+                })?;
+            }
+            attribute_buffers.insert(
+                name.clone(),
+                (
+                    VertexBuffer::new_with_static_f32(context, data)?,
+                    *components,
+                ),
+            );
+        }
+        let position_buffer = VertexBuffer::new_with_static_f32(context, positions)?;
+        let index_buffer = if let Some(ind) = indices.filter(|ind| !ind.is_empty()) {
+            Some(ElementBuffer::new_with_u32(context, ind)?)
         } else {
             None
         };
@@ -180,14 +778,51 @@ impl Mesh {
         }
         Ok(Mesh {
             context: context.clone(),
-            position_buffer,
-            normal_buffer,
-            index_buffer,
-            uv_buffer,
-            color_buffer,
+            buffers: MeshBuffers::Separate {
+                position_buffer,
+                normal_buffer: None,
+                index_buffer,
+                uv_buffer: None,
+                color_buffer: None,
+            },
+            attribute_buffers,
+            aabb: AxisAlignedBoundingBox::new().expand(positions),
         })
     }
 
+    ///
+    /// Constructs a mesh which draws the `count` indices starting at `first` in the given interleaved buffers,
+    /// for example the buffers of a [MeshBuffer].
+    ///
+    pub(in crate::object) fn new_shared(
+        context: &Context,
+        buffers: Rc<InterleavedBuffers>,
+        first: u32,
+        count: u32,
+        aabb: AxisAlignedBoundingBox,
+    ) -> Self {
+        unsafe {
+            MESH_COUNT += 1;
+        }
+        Mesh {
+            context: context.clone(),
+            buffers: MeshBuffers::Shared {
+                buffers,
+                first,
+                count,
+            },
+            attribute_buffers: HashMap::new(),
+            aabb,
+        }
+    }
+
+    ///
+    /// Returns the axis aligned bounding box of the mesh before it is transformed.
+    ///
+    pub fn aabb(&self) -> &AxisAlignedBoundingBox {
+        &self.aabb
+    }
+
     ///
     /// Render only the depth of the mesh into the current depth render target which is useful for shadow maps or depth pre-pass.
     /// Must be called in a render target render function,
@@ -211,7 +846,8 @@ impl Mesh {
     }
 
     ///
-    /// Render the mesh with a color per triangle vertex. The colors are defined when constructing the mesh.
+    /// Render the mesh with a color per triangle vertex. The colors are defined when constructing the mesh,
+    /// either as 8 bit colors or with full float precision, see [CPUMesh::colors_f32](crate::CPUMesh::colors_f32).
     /// Must be called in a render target render function,
     /// for example in the callback function of [Screen::write](crate::Screen::write).
     /// The transformation can be used to position, orientate and scale the mesh.
@@ -295,6 +931,138 @@ impl Mesh {
         self.render(program, render_states, viewport, transformation, camera)
     }
 
+    ///
+    /// Render the uv coordinates of the mesh, the u coordinate in the red channel and the v coordinate in the green channel,
+    /// which is useful for debugging texture mapping.
+    /// Must be called in a render target render function,
+    /// for example in the callback function of [Screen::write](crate::Screen::write).
+    /// The transformation can be used to position, orientate and scale the mesh.
+    ///
+    /// # Errors
+    /// Will return an error if the mesh has no uv coordinates.
+    ///
+    pub fn render_uvs(
+        &self,
+        render_states: RenderStates,
+        viewport: Viewport,
+        transformation: &Mat4,
+        camera: &camera::Camera,
+    ) -> Result<(), Error> {
+        let program = unsafe {
+            if PROGRAM_UVS.is_none() {
+                PROGRAM_UVS = Some(MeshProgram::new(
+                    &self.context,
+                    include_str!("shaders/mesh_uvs.frag"),
+                )?);
+            }
+            PROGRAM_UVS.as_ref().unwrap()
+        };
+        self.render(program, render_states, viewport, transformation, camera)
+    }
+
+    ///
+    /// Render the world space normals of the mesh, mapped from the range `[-1, 1]` to the color range `[0, 1]`,
+    /// which is useful for detecting missing or flipped normals.
+    /// Must be called in a render target render function,
+    /// for example in the callback function of [Screen::write](crate::Screen::write).
+    /// The transformation can be used to position, orientate and scale the mesh.
+    ///
+    /// # Errors
+    /// Will return an error if the mesh has no normals.
+    ///
+    pub fn render_normals(
+        &self,
+        render_states: RenderStates,
+        viewport: Viewport,
+        transformation: &Mat4,
+        camera: &camera::Camera,
+    ) -> Result<(), Error> {
+        let program = unsafe {
+            if PROGRAM_NORMALS.is_none() {
+                PROGRAM_NORMALS = Some(MeshProgram::new(
+                    &self.context,
+                    include_str!("shaders/mesh_normals.frag"),
+                )?);
+            }
+            PROGRAM_NORMALS.as_ref().unwrap()
+        };
+        self.render(program, render_states, viewport, transformation, camera)
+    }
+
+    ///
+    /// Render the mesh as a refracting surface, for example glass, water or heat haze, which distorts whatever is behind it.
+    /// The scene behind the mesh is sampled from the given scene color texture, offset by the refraction at each fragment
+    /// given by the normal of the mesh and the [refraction parameters](Refraction).
+    /// The scene color texture is usually captured by rendering everything else into a render target and using
+    /// [copy_color_to_texture](crate::RenderTarget::copy_color_to_texture) or [render_to_texture](crate::render_to_texture),
+    /// and it must have the same size as the render target written to, since it is sampled at the pixel of each fragment.
+    /// Must be called in a render target render function,
+    /// for example in the callback function of [Screen::write](crate::Screen::write), and the scene color texture must not be
+    /// a texture in the render target written to.
+    /// The transformation can be used to position, orientate and scale the mesh.
+    ///
+    /// # Errors
+    /// Will return an error if the mesh has no normals.
+    ///
+    pub fn render_refraction(
+        &self,
+        scene_color_texture: &dyn Texture,
+        refraction: &Refraction,
+        render_states: RenderStates,
+        viewport: Viewport,
+        transformation: &Mat4,
+        camera: &camera::Camera,
+    ) -> Result<(), Error> {
+        let program = unsafe {
+            if PROGRAM_REFRACTION.is_none() {
+                PROGRAM_REFRACTION = Some(MeshProgram::new(
+                    &self.context,
+                    include_str!("shaders/mesh_refraction.frag"),
+                )?);
+            }
+            PROGRAM_REFRACTION.as_ref().unwrap()
+        };
+        program.use_texture(scene_color_texture, "sceneColor")?;
+        program.use_uniform_vec3("eyePosition", camera.position())?;
+        program.use_uniform_mat4(
+            "viewProjectionMatrix",
+            &(camera.projection() * camera.view()),
+        )?;
+        program.use_uniform_float("indexOfRefraction", &refraction.index_of_refraction)?;
+        program.use_uniform_float("strength", &refraction.strength)?;
+        self.render(program, render_states, viewport, transformation, camera)
+    }
+
+    ///
+    /// Render the mesh with the given [material](crate::DrawMaterial), ie. by calling the render function corresponding to the material.
+    /// Must be called in a render target render function,
+    /// for example in the callback function of [Screen::write](crate::Screen::write).
+    /// The transformation can be used to position, orientate and scale the mesh.
+    ///
+    pub fn render_with_material(
+        &self,
+        material: &DrawMaterial,
+        render_states: RenderStates,
+        viewport: Viewport,
+        transformation: &Mat4,
+        camera: &camera::Camera,
+    ) -> Result<(), Error> {
+        match material {
+            DrawMaterial::Color(color) => {
+                self.render_with_color(color, render_states, viewport, transformation, camera)
+            }
+            DrawMaterial::VertexColor => {
+                self.render_color(render_states, viewport, transformation, camera)
+            }
+            DrawMaterial::Texture(texture) => {
+                self.render_with_texture(*texture, render_states, viewport, transformation, camera)
+            }
+            DrawMaterial::Program(program) => {
+                self.render(program, render_states, viewport, transformation, camera)
+            }
+        }
+    }
+
     ///
     /// Render the mesh with the given [MeshProgram](MeshProgram).
     /// Must be called in a render target render function,
@@ -314,41 +1082,178 @@ impl Mesh {
         transformation: &Mat4,
         camera: &camera::Camera,
     ) -> Result<(), Error> {
-        program.use_uniform_mat4("modelMatrix", &transformation)?;
-        program.use_uniform_block(camera.matrix_buffer(), "Camera");
+        if !program.options.screen_space {
+            program.use_uniform_block(camera.matrix_buffer(), "Camera");
+        }
+        program.use_logarithmic_depth(camera)?;
+        self.draw(program, render_states, viewport, transformation)
+    }
 
-        program.use_attribute_vec3(&self.position_buffer, "position")?;
-        if program.use_uvs {
-            let uv_buffer = self.uv_buffer.as_ref().ok_or(Error::FailedToCreateMesh {
-                message:
-                    "The mesh shader program needs uv coordinates, but the mesh does not have any."
-                        .to_string(),
+    ///
+    /// Render the mesh without a camera with the given [screen space program](MeshProgram::new_screen_space),
+    /// ie. the transformed vertex positions are used directly as clip space coordinates.
+    /// Must be called in a render target render function,
+    /// for example in the callback function of [Screen::write](crate::Screen::write).
+    ///
+    /// # Errors
+    /// Will return an error if the program is not a screen space program or if the program requires a certain attribute
+    /// and the mesh does not have that attribute.
+    ///
+    pub fn render_screen_space(
+        &self,
+        program: &MeshProgram,
+        render_states: RenderStates,
+        viewport: Viewport,
+        transformation: &Mat4,
+    ) -> Result<(), Error> {
+        if !program.options.screen_space {
+            Err(Error::FailedToCreateMesh {
+                message: "The mesh shader program must be constructed with MeshProgram::new_screen_space to render in screen space.".to_string(),
             })?;
-            program.use_attribute_vec2(uv_buffer, "uv_coordinates")?;
+        }
+        self.draw(program, render_states, viewport, transformation)
+    }
+
+    ///
+    /// Render the mesh with the given [displacement program](MeshProgram::new_with_displacement), where each vertex is displaced along its normal
+    /// by the value in the red channel of the displacement texture at the uv coordinates of the vertex multiplied by the given scale.
+    /// Must be called in a render target render function,
+    /// for example in the callback function of [Screen::write](crate::Screen::write).
+    /// The transformation can be used to position, orientate and scale the mesh.
+    ///
+    /// # Errors
+    /// Will return an error if the program is not a displacement program or if the mesh does not have normals and uv coordinates.
+    ///
+    pub fn render_displaced(
+        &self,
+        program: &MeshProgram,
+        displacement_texture: &dyn Texture,
+        scale: f32,
+        render_states: RenderStates,
+        viewport: Viewport,
+        transformation: &Mat4,
+        camera: &camera::Camera,
+    ) -> Result<(), Error> {
+        if !program.options.displacement {
+            Err(Error::FailedToCreateMesh {
+                message: "The mesh shader program must be constructed with MeshProgram::new_with_displacement to render with displacement.".to_string(),
+            })?;
+        }
+        program.use_texture(displacement_texture, "displacementMap")?;
+        program.use_uniform_float("displacementScale", &scale)?;
+        self.render(program, render_states, viewport, transformation, camera)
+    }
+
+    fn draw(
+        &self,
+        program: &MeshProgram,
+        render_states: RenderStates,
+        viewport: Viewport,
+        transformation: &Mat4,
+    ) -> Result<(), Error> {
+        program.use_uniform_mat4("modelMatrix", &transformation)?;
+        program.use_clip_planes(&render_states.clip_planes)?;
+
+        let missing_uvs = || Error::FailedToCreateMesh {
+            message:
+                "The mesh shader program needs uv coordinates, but the mesh does not have any."
+                    .to_string(),
+        };
+        let missing_normals = || {
+            Error::FailedToCreateMesh {message: "The mesh shader program needs normals, but the mesh does not have any. Consider calculating the normals on the CPUMesh.".to_string()}
+        };
+        let missing_colors = || Error::FailedToCreateMesh {
+            message:
+                "The mesh shader program needs per vertex colors, but the mesh does not have any."
+                    .to_string(),
+        };
+        match self.buffers {
+            MeshBuffers::Separate {
+                ref position_buffer,
+                ref normal_buffer,
+                ref uv_buffer,
+                ref color_buffer,
+                ..
+            } => {
+                program.use_attribute_vec3(position_buffer, "position")?;
+                if program.use_uvs || program.options.displacement {
+                    let uv_buffer = uv_buffer.as_ref().ok_or_else(missing_uvs)?;
+                    program.use_attribute_vec2(uv_buffer, "uv_coordinates")?;
+                }
+                if program.use_normals || program.options.displacement {
+                    let normal_buffer = normal_buffer.as_ref().ok_or_else(missing_normals)?;
+                    program.use_attribute_vec3(normal_buffer, "normal")?;
+                }
+                if program.use_colors {
+                    let color_buffer = color_buffer.as_ref().ok_or_else(missing_colors)?;
+                    program.use_attribute_vec4(color_buffer, "color")?;
+                }
+            }
+            MeshBuffers::Shared { ref buffers, .. } => {
+                let buffer = &buffers.vertex_buffer;
+                let stride = buffers.stride;
+                program.use_attribute_interleaved(buffer, "position", 3, stride, 0)?;
+                if program.use_uvs || program.options.displacement {
+                    let offset = buffers.uv_offset.ok_or_else(missing_uvs)?;
+                    program.use_attribute_interleaved(
+                        buffer,
+                        "uv_coordinates",
+                        2,
+                        stride,
+                        offset,
+                    )?;
+                }
+                if program.use_normals || program.options.displacement {
+                    let offset = buffers.normal_offset.ok_or_else(missing_normals)?;
+                    program.use_attribute_interleaved(buffer, "normal", 3, stride, offset)?;
+                }
+                if program.use_colors {
+                    let offset = buffers.color_offset.ok_or_else(missing_colors)?;
+                    program.use_attribute_interleaved(buffer, "color", 4, stride, offset)?;
+                }
+            }
         }
         if program.use_normals {
-            let normal_buffer = self.normal_buffer.as_ref().ok_or(
-                Error::FailedToCreateMesh {message: "The mesh shader program needs normals, but the mesh does not have any. Consider calculating the normals on the CPUMesh.".to_string()})?;
             program.use_uniform_mat4(
                 "normalMatrix",
                 &transformation.invert().unwrap().transpose(),
             )?;
-            program.use_attribute_vec3(normal_buffer, "normal")?;
         }
-        if program.use_colors {
-            let color_buffer = self.color_buffer.as_ref().ok_or(
-                Error::FailedToCreateMesh {message: "The mesh shader program needs per vertex colors, but the mesh does not have any.".to_string()})?;
-            program.use_attribute_vec4(color_buffer, "color")?;
+        for (name, components) in program.custom_attributes.iter() {
+            let (buffer, buffer_components) = self.attribute_buffers.get(name).ok_or(
+                Error::FailedToCreateMesh {message: format!("The mesh shader program needs the attribute {}, but the mesh does not have it.", name)})?;
+            if buffer_components != components {
+                Err(Error::FailedToCreateMesh {message: format!("The mesh shader program needs {} components of the attribute {}, but the mesh has {}.", components, name, buffer_components)})?;
+            }
+            let attribute_name = format!("{}_attribute", name);
+            match components {
+                1 => program.use_attribute(buffer, &attribute_name)?,
+                2 => program.use_attribute_vec2(buffer, &attribute_name)?,
+                3 => program.use_attribute_vec3(buffer, &attribute_name)?,
+                _ => program.use_attribute_vec4(buffer, &attribute_name)?,
+            }
         }
 
-        if let Some(ref index_buffer) = self.index_buffer {
-            program.draw_elements(render_states, viewport, index_buffer);
-        } else {
-            program.draw_arrays(
+        match self.buffers {
+            MeshBuffers::Separate {
+                index_buffer: Some(ref index_buffer),
+                ..
+            } => program.draw_elements(render_states, viewport, index_buffer),
+            MeshBuffers::Separate {
+                ref position_buffer,
+                ..
+            } => program.draw_arrays(render_states, viewport, position_buffer.count() as u32 / 3),
+            MeshBuffers::Shared {
+                ref buffers,
+                first,
+                count,
+            } => program.draw_subset_of_elements(
                 render_states,
                 viewport,
-                self.position_buffer.count() as u32 / 3,
-            );
+                &buffers.index_buffer,
+                first,
+                count,
+            ),
         }
         Ok(())
     }
@@ -363,6 +1268,9 @@ impl Drop for Mesh {
                 PROGRAM_COLOR = None;
                 PROGRAM_TEXTURE = None;
                 PROGRAM_PER_VERTEX_COLOR = None;
+                PROGRAM_UVS = None;
+                PROGRAM_NORMALS = None;
+                PROGRAM_REFRACTION = None;
             }
         }
     }
@@ -372,4 +1280,213 @@ static mut PROGRAM_COLOR: Option<MeshProgram> = None;
 static mut PROGRAM_TEXTURE: Option<MeshProgram> = None;
 static mut PROGRAM_DEPTH: Option<MeshProgram> = None;
 static mut PROGRAM_PER_VERTEX_COLOR: Option<MeshProgram> = None;
+static mut PROGRAM_UVS: Option<MeshProgram> = None;
+static mut PROGRAM_NORMALS: Option<MeshProgram> = None;
+static mut PROGRAM_REFRACTION: Option<MeshProgram> = None;
 static mut MESH_COUNT: u32 = 0;
+
+#[cfg(all(test, feature = "headless", not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::test_util::*;
+
+    #[test]
+    fn render_non_indexed_mesh() {
+        let test_context = TestContext::new();
+        let context = test_context.gl();
+        let camera = orthographic_camera(&context);
+        let viewport = Viewport::new_at_origo(8, 8);
+        let mut cpu_mesh = triangle_soup(-2.0, 2.0, 0.0);
+        cpu_mesh.colors = Some([0, 0, 255, 255].repeat(6));
+        let mesh = Mesh::new(&context, &cpu_mesh).unwrap();
+
+        let image = render_to_cpu(&context, 8, 8, &ClearState::default(), || {
+            mesh.render_with_color(
+                &vec4(0.0, 1.0, 0.0, 1.0),
+                RenderStates::default(),
+                viewport,
+                &Mat4::identity(),
+                &camera,
+            )
+        })
+        .unwrap();
+        assert_images_match(&image, &uniform_image(8, 8, [0, 255, 0, 255]), 1, 0.0);
+
+        let image = render_to_cpu(&context, 8, 8, &ClearState::default(), || {
+            mesh.render_color(
+                RenderStates::default(),
+                viewport,
+                &Mat4::identity(),
+                &camera,
+            )
+        })
+        .unwrap();
+        assert_images_match(&image, &uniform_image(8, 8, [0, 0, 255, 255]), 1, 0.0);
+
+        // The depth of the whole mesh hides everything behind it
+        let background = Mesh::new(&context, &triangle_soup(-2.0, 2.0, -1.0)).unwrap();
+        let image = render_to_cpu(&context, 8, 8, &ClearState::default(), || {
+            mesh.render_depth(
+                RenderStates {
+                    write_mask: WriteMask::DEPTH,
+                    ..Default::default()
+                },
+                viewport,
+                &Mat4::identity(),
+                &camera,
+            )?;
+            background.render_with_color(
+                &vec4(0.0, 1.0, 0.0, 1.0),
+                RenderStates::default(),
+                viewport,
+                &Mat4::identity(),
+                &camera,
+            )
+        })
+        .unwrap();
+        assert_images_match(&image, &uniform_image(8, 8, [0, 0, 0, 255]), 0, 0.0);
+    }
+
+    #[cfg(feature = "image-io")]
+    #[test]
+    fn render_mesh_matches_reference_image() {
+        let test_context = TestContext::new();
+        let context = test_context.gl();
+        let camera = orthographic_camera(&context);
+        let mesh = Mesh::new(&context, &triangle_soup(-1.0, 0.0, 0.0)).unwrap();
+        let image = render_to_cpu(
+            &context,
+            8,
+            8,
+            &ClearState::color_and_depth(0.0, 0.0, 1.0, 1.0, 1.0),
+            || {
+                mesh.render_with_color(
+                    &vec4(1.0, 0.0, 0.0, 1.0),
+                    RenderStates::default(),
+                    Viewport::new_at_origo(8, 8),
+                    &Mat4::identity(),
+                    &camera,
+                )
+            },
+        )
+        .unwrap();
+        assert_matches_reference_image(&image, "mesh_left_half", 1, 0.0);
+    }
+
+    #[test]
+    fn render_logarithmic_depth_with_infinite_far_plane() {
+        let test_context = TestContext::new();
+        let context = test_context.gl();
+        let camera = Camera::new_perspective_infinite(
+            &context,
+            vec3(0.0, 0.0, 1.0),
+            vec3(0.0, 0.0, 0.0),
+            vec3(0.0, 1.0, 0.0),
+            degrees(90.0),
+            1.0,
+            0.1,
+        )
+        .unwrap();
+        let program = MeshProgram::new_with_logarithmic_depth(
+            &context,
+            "uniform vec4 surfaceColor;
+            layout (location = 0) out vec4 outColor;
+            void main() { outColor = surfaceColor; }",
+        )
+        .unwrap();
+        let near = Mesh::new(&context, &triangle_soup(-2.0, 2.0, 0.0)).unwrap();
+        let far = Mesh::new(&context, &triangle_soup(-4.0, 4.0, -1.0)).unwrap();
+
+        // The near mesh is in front of the far mesh, also when the far mesh is rendered first
+        let image = render_to_cpu(&context, 8, 8, &ClearState::default(), || {
+            program.use_uniform_vec4("surfaceColor", &vec4(0.0, 1.0, 0.0, 1.0))?;
+            far.render(
+                &program,
+                RenderStates::default(),
+                Viewport::new_at_origo(8, 8),
+                &Mat4::identity(),
+                &camera,
+            )?;
+            program.use_uniform_vec4("surfaceColor", &vec4(1.0, 0.0, 0.0, 1.0))?;
+            near.render(
+                &program,
+                RenderStates::default(),
+                Viewport::new_at_origo(8, 8),
+                &Mat4::identity(),
+                &camera,
+            )
+        })
+        .unwrap();
+        assert_images_match(&image, &uniform_image(8, 8, [255, 0, 0, 255]), 1, 0.0);
+    }
+}
+
+#[cfg(test)]
+mod parse_tests {
+    use super::*;
+
+    #[test]
+    fn find_inputs_outside_comments() {
+        let source = "
+            // in vec3 commented; in float alsoCommented;
+            in float temperature; /* in vec2 blockCommented; */ in vec2 offset;
+            /* uniform float a; */
+            in vec4 weights; in vec3 nor;
+            void main() { float half = temperature / 2.0; }";
+        let names: Vec<(String, usize)> = find_inputs(source)
+            .unwrap()
+            .into_iter()
+            .map(|input| (input.name, input.components))
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                ("temperature".to_string(), 1),
+                ("offset".to_string(), 2),
+                ("weights".to_string(), 4),
+                ("nor".to_string(), 3)
+            ]
+        );
+    }
+
+    #[test]
+    fn find_inputs_after_functions_and_directives() {
+        let source = "
+            #define SCALE 2.0
+            float scaled(in float value) { return SCALE * value; }
+            flat in float id;
+            #ifdef SOMETHING
+            #endif
+            in highp vec2 second_uvs, third_uvs;
+            float unused(in vec3 v);
+            void main() {}";
+        assert_eq!(
+            find_inputs(source).unwrap(),
+            vec![
+                InputDeclaration {
+                    name: "id".to_string(),
+                    components: 1,
+                    interpolation: "flat ".to_string(),
+                },
+                InputDeclaration {
+                    name: "second_uvs".to_string(),
+                    components: 2,
+                    interpolation: String::new(),
+                },
+                InputDeclaration {
+                    name: "third_uvs".to_string(),
+                    components: 2,
+                    interpolation: String::new(),
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn find_unsupported_inputs() {
+        assert!(find_inputs("in int id;").is_err());
+        assert!(find_inputs("in float values[2];").is_err());
+        assert!(find_inputs("layout (location = 1) in vec2 offset;").is_err());
+        assert!(find_inputs("uniform in vec2 offset;").is_err());
+    }
+}
@@ -0,0 +1,202 @@
+use crate::core::*;
+use crate::definition::*;
+use crate::math::*;
+use crate::object::*;
+use std::rc::Rc;
+
+///
+/// The vertex data of several meshes interleaved in one vertex buffer together with the indices of all of the meshes in one element buffer.
+/// The offsets and the stride is given in number of values.
+///
+pub(crate) struct InterleavedBuffers {
+    pub vertex_buffer: VertexBuffer,
+    pub index_buffer: ElementBuffer,
+    pub stride: u32,
+    pub normal_offset: Option<u32>,
+    pub uv_offset: Option<u32>,
+    pub color_offset: Option<u32>,
+}
+
+// The first index, the number of indices and the bounding box of a mesh in interleaved buffers
+pub(crate) type MeshRange = (u32, u32, AxisAlignedBoundingBox);
+
+impl InterleavedBuffers {
+    ///
+    /// Returns the buffers containing the given CPU meshes and the range of each of them.
+    ///
+    pub fn new(
+        context: &Context,
+        cpu_meshes: &[&CPUMesh],
+    ) -> Result<(Self, Vec<MeshRange>), Error> {
+        let has_attributes = |cpu_mesh: &CPUMesh| {
+            (
+                cpu_mesh.normals.is_some(),
+                cpu_mesh.uvs.is_some(),
+                cpu_mesh.colors.is_some() || cpu_mesh.colors_f32.is_some(),
+            )
+        };
+        let (has_normals, has_uvs, has_colors) = has_attributes(
+            cpu_meshes
+                .first()
+                .copied()
+                .ok_or(Error::FailedToCreateMesh {
+                    message: "A mesh buffer must contain at least one mesh.".to_string(),
+                })?,
+        );
+
+        let mut stride = 3;
+        let mut offset = |components: u32, enabled: bool| {
+            if enabled {
+                stride += components;
+                Some(stride - components)
+            } else {
+                None
+            }
+        };
+        let normal_offset = offset(3, has_normals);
+        let uv_offset = offset(2, has_uvs);
+        let color_offset = offset(4, has_colors);
+
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        let mut ranges = Vec::new();
+        for cpu_mesh in cpu_meshes.iter() {
+            if has_attributes(cpu_mesh) != (has_normals, has_uvs, has_colors) {
+                Err(Error::FailedToCreateMesh {
+                        message: format!(
+                            "The mesh {} does not have the same attributes as the first mesh in the mesh buffer.",
+                            cpu_mesh.name
+                        ),
+                    })?;
+            }
+            let vertex_count = cpu_mesh.positions.len() / 3;
+            // Scaled to the same range as the 8 bit colors, like in Mesh::new.
+            let colors = if let Some(ref colors) = cpu_mesh.colors_f32 {
+                Some(colors.iter().map(|c| c * 255.0).collect::<Vec<_>>())
+            } else {
+                cpu_mesh
+                    .colors
+                    .as_ref()
+                    .map(|colors| colors.iter().map(|c| *c as f32).collect::<Vec<_>>())
+            };
+            let check_length = |name: &str, data: Option<&Vec<f32>>, components: usize| {
+                match data {
+                        Some(data) if data.len() != vertex_count * components => {
+                            Err(Error::FailedToCreateMesh {
+                                message: format!(
+                                    "The mesh {} has {} values in its {}, but {} was expected for {} vertices.",
+                                    cpu_mesh.name,
+                                    data.len(),
+                                    name,
+                                    vertex_count * components,
+                                    vertex_count
+                                ),
+                            })
+                        }
+                        _ => Ok(()),
+                    }
+            };
+            check_length("normals", cpu_mesh.normals.as_ref(), 3)?;
+            check_length("uv coordinates", cpu_mesh.uvs.as_ref(), 2)?;
+            check_length("colors", colors.as_ref(), 4)?;
+
+            let first_vertex = (vertices.len() / stride as usize) as u32;
+            for i in 0..vertex_count {
+                vertices.extend_from_slice(&cpu_mesh.positions[i * 3..i * 3 + 3]);
+                if let Some(ref normals) = cpu_mesh.normals {
+                    vertices.extend_from_slice(&normals[i * 3..i * 3 + 3]);
+                }
+                if let Some(ref uvs) = cpu_mesh.uvs {
+                    vertices.extend_from_slice(&uvs[i * 2..i * 2 + 2]);
+                }
+                if let Some(ref colors) = colors {
+                    vertices.extend_from_slice(&colors[i * 4..i * 4 + 4]);
+                }
+            }
+
+            // The indices point directly into the shared vertex buffer, since drawing with a base vertex is not supported on web
+            let first = indices.len() as u32;
+            if let Some(ind) = cpu_mesh.indices.as_ref().filter(|ind| !ind.is_empty()) {
+                indices.extend(ind.iter().map(|i| first_vertex + i));
+            } else {
+                indices.extend(first_vertex..first_vertex + vertex_count as u32);
+            }
+            ranges.push((first, indices.len() as u32 - first, cpu_mesh.compute_aabb()));
+        }
+        Ok((
+            Self {
+                vertex_buffer: VertexBuffer::new_with_static_f32(context, &vertices)?,
+                index_buffer: ElementBuffer::new_with_u32(context, &indices)?,
+                stride,
+                normal_offset,
+                uv_offset,
+                color_offset,
+            },
+            ranges,
+        ))
+    }
+}
+
+///
+/// The data of several [CPUMesh](crate::CPUMesh)es, for example all of the primitives of an imported model,
+/// copied to the GPU in one vertex buffer, where the positions, normals, uv coordinates and colors are interleaved, and one element buffer.
+/// Use [mesh](Self::mesh) to get a [Mesh] for each of them which draws its range of the shared buffers,
+/// which results in fewer buffers and buffer binds than constructing a [Mesh] for each CPU mesh.
+///
+pub struct MeshBuffer {
+    context: Context,
+    buffers: Rc<InterleavedBuffers>,
+    ranges: Vec<MeshRange>,
+}
+
+impl MeshBuffer {
+    ///
+    /// Copies the per vertex data of the given CPU meshes to the GPU.
+    /// If a CPU mesh has no indices, or an empty list of indices, each three consecutive positions form a triangle.
+    ///
+    /// # Errors
+    /// Will return an error if no CPU meshes are given, if the CPU meshes do not have the same attributes,
+    /// ie. they must all have normals, uv coordinates and colors or not, or if the length of an attribute does not match the number of vertices.
+    ///
+    pub fn new(context: &Context, cpu_meshes: &[CPUMesh]) -> Result<Self, Error> {
+        let (buffers, ranges) =
+            InterleavedBuffers::new(context, &cpu_meshes.iter().collect::<Vec<_>>())?;
+        Ok(Self {
+            context: context.clone(),
+            buffers: Rc::new(buffers),
+            ranges,
+        })
+    }
+
+    ///
+    /// Returns the number of meshes in this buffer, ie. the number of CPU meshes given to [new](Self::new).
+    ///
+    pub fn count(&self) -> usize {
+        self.ranges.len()
+    }
+
+    ///
+    /// Returns a [Mesh] which draws the mesh with the given index, ie. the CPU mesh with the same index in the list given to [new](Self::new).
+    /// The mesh shares the buffers of this mesh buffer, so it can be rendered with all of the render functions of a [Mesh],
+    /// except that it does not have any custom attributes (see [Mesh::new_with_attributes]).
+    ///
+    /// # Errors
+    /// Will return an error if the index is not smaller than the [count](Self::count).
+    ///
+    pub fn mesh(&self, index: usize) -> Result<Mesh, Error> {
+        let (first, count, aabb) = self.ranges.get(index).ok_or(Error::FailedToCreateMesh {
+            message: format!(
+                "The mesh buffer contains {} meshes, so there is no mesh with index {}.",
+                self.ranges.len(),
+                index
+            ),
+        })?;
+        Ok(Mesh::new_shared(
+            &self.context,
+            self.buffers.clone(),
+            *first,
+            *count,
+            *aabb,
+        ))
+    }
+}
@@ -0,0 +1,146 @@
+use crate::camera::*;
+use crate::core::*;
+use crate::definition::*;
+use crate::math::*;
+use crate::object::*;
+
+///
+/// A [mesh](crate::Mesh) together with its transformation and [material](crate::DrawMaterial),
+/// which is only rendered if it is inside the camera frustum.
+/// The bounding box of the transformed mesh is cached and only updated when the transformation changes.
+///
+pub struct Model<'a> {
+    mesh: Mesh,
+    transformation: Mat4,
+    aabb: AxisAlignedBoundingBox,
+    ///
+    /// Defines how the model is shaded.
+    ///
+    pub material: DrawMaterial<'a>,
+    ///
+    /// The render states used when rendering the model.
+    ///
+    pub render_states: RenderStates,
+}
+
+impl<'a> Model<'a> {
+    ///
+    /// Constructs a new model from the given [CPUMesh](crate::CPUMesh) which is shaded with the given material.
+    /// The transformation is initially the identity.
+    ///
+    pub fn new(
+        context: &Context,
+        cpu_mesh: &CPUMesh,
+        material: DrawMaterial<'a>,
+    ) -> Result<Self, Error> {
+        Ok(Self::new_with_mesh(Mesh::new(context, cpu_mesh)?, material))
+    }
+
+    ///
+    /// Constructs a new model from the given mesh which is shaded with the given material.
+    /// The transformation is initially the identity.
+    ///
+    pub fn new_with_mesh(mesh: Mesh, material: DrawMaterial<'a>) -> Self {
+        let aabb = *mesh.aabb();
+        Self {
+            mesh,
+            transformation: Mat4::identity(),
+            aabb,
+            material,
+            render_states: RenderStates::default(),
+        }
+    }
+
+    ///
+    /// Sets the transformation used to position, orientate and scale the model.
+    ///
+    pub fn set_transformation(&mut self, transformation: &Mat4) {
+        self.transformation = *transformation;
+        self.aabb = self.mesh.aabb().transform(transformation);
+    }
+
+    pub fn transformation(&self) -> &Mat4 {
+        &self.transformation
+    }
+
+    ///
+    /// Returns the bounding box of the model after it is transformed.
+    ///
+    pub fn aabb(&self) -> &AxisAlignedBoundingBox {
+        &self.aabb
+    }
+
+    pub fn mesh(&self) -> &Mesh {
+        &self.mesh
+    }
+
+    ///
+    /// Returns whether or not the model is inside or intersects the frustum of the given camera.
+    ///
+    pub fn is_visible(&self, camera: &Camera) -> bool {
+        camera.in_frustum(&self.aabb)
+    }
+
+    ///
+    /// Renders the model if it is inside the camera frustum and returns whether or not it was rendered.
+    /// Must be called in a render target render function,
+    /// for example in the callback function of [Screen::write](crate::Screen::write).
+    ///
+    pub fn render_if_visible(&self, camera: &Camera, viewport: Viewport) -> Result<bool, Error> {
+        if !self.is_visible(camera) {
+            return Ok(false);
+        }
+        self.mesh.render_with_material(
+            &self.material,
+            self.render_states,
+            viewport,
+            &self.transformation,
+            camera,
+        )?;
+        Ok(true)
+    }
+}
+
+///
+/// A collection of [models](crate::Model) which can be rendered with one call.
+/// The models outside the camera frustum are skipped and the rest are rendered in the order
+/// defined by a [RenderQueue](crate::RenderQueue).
+///
+pub struct Scene<'a> {
+    pub models: Vec<Model<'a>>,
+}
+
+impl<'a> Scene<'a> {
+    ///
+    /// Constructs a new empty scene.
+    ///
+    pub fn new() -> Self {
+        Self { models: Vec::new() }
+    }
+
+    ///
+    /// Renders all of the models which are inside the camera frustum and returns the number of rendered models.
+    /// Must be called in a render target render function,
+    /// for example in the callback function of [Screen::write](crate::Screen::write).
+    ///
+    pub fn render(&self, camera: &Camera, viewport: Viewport) -> Result<usize, Error> {
+        let mut queue = RenderQueue::new();
+        for model in self.models.iter().filter(|model| model.is_visible(camera)) {
+            queue.push(
+                &model.mesh,
+                &model.transformation,
+                model.material,
+                model.render_states,
+            );
+        }
+        let count = queue.len();
+        queue.render(camera, viewport)?;
+        Ok(count)
+    }
+}
+
+impl<'a> Default for Scene<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -116,11 +116,12 @@ impl Particles {
         } else {
             None
         };
-        let index_buffer = if let Some(ref ind) = cpu_mesh.indices {
-            Some(ElementBuffer::new_with_u32(context, ind)?)
-        } else {
-            None
-        };
+        let index_buffer =
+            if let Some(ind) = cpu_mesh.indices.as_ref().filter(|ind| !ind.is_empty()) {
+                Some(ElementBuffer::new_with_u32(context, ind)?)
+            } else {
+                None
+            };
         let uv_buffer = if let Some(ref uvs) = cpu_mesh.uvs {
             Some(VertexBuffer::new_with_static_f32(context, uvs)?)
         } else {
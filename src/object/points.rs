@@ -0,0 +1,121 @@
+use crate::camera::*;
+use crate::core::*;
+use crate::math::*;
+use std::rc::Rc;
+
+///
+/// A set of points, for example a point cloud, where each point is rendered as a round point sprite with anti-aliased edges
+/// or as a sprite texture facing the camera.
+///
+pub struct Points {
+    context: Context,
+    position_buffer: VertexBuffer,
+    count: u32,
+    ///
+    /// The size of the points in pixels or, if [size_attenuation](Self::size_attenuation) is enabled, in world units.
+    ///
+    pub point_size: f32,
+    ///
+    /// Whether the size of the points decreases with the distance to the camera, so that the points have a constant size in the world
+    /// instead of a constant size on the screen. The default is false.
+    ///
+    pub size_attenuation: bool,
+    ///
+    /// A texture which is multiplied with the color of each point, where the uv coordinates go from (0, 0) in the top left corner
+    /// to (1, 1) in the bottom right corner of the point. If `None`, which is the default, the points are rendered as circles.
+    ///
+    pub sprite: Option<Rc<Texture2D>>,
+}
+
+impl Points {
+    ///
+    /// Constructs new points at the given positions, ie. three consecutive values in the positions define one point.
+    ///
+    pub fn new(context: &Context, positions: &[f32], point_size: f32) -> Result<Self, Error> {
+        let mut points = Self {
+            context: context.clone(),
+            position_buffer: VertexBuffer::new_with_dynamic_f32(context, &[])?,
+            count: 0,
+            point_size,
+            size_attenuation: false,
+            sprite: None,
+        };
+        points.update_positions(positions);
+        unsafe {
+            POINTS_COUNT += 1;
+        }
+        Ok(points)
+    }
+
+    ///
+    /// Updates the positions of the points, see [new](Self::new).
+    ///
+    pub fn update_positions(&mut self, positions: &[f32]) {
+        self.count = positions.len() as u32 / 3;
+        self.position_buffer.fill_with_dynamic_f32(positions);
+    }
+
+    ///
+    /// Render the points with the given color, which is multiplied with the [sprite](Self::sprite) if there is one.
+    /// Must be called in a render target render function,
+    /// for example in the callback function of [Screen::write](crate::Screen::write).
+    /// The transformation can be used to position, orientate and scale the points.
+    ///
+    pub fn render(
+        &self,
+        color: &Vec4,
+        viewport: Viewport,
+        transformation: &Mat4,
+        camera: &Camera,
+    ) -> Result<(), Error> {
+        if self.count == 0 {
+            return Ok(());
+        }
+        let program = unsafe {
+            if PROGRAM.is_none() {
+                PROGRAM = Some(Program::from_source(
+                    &self.context,
+                    include_str!("shaders/points.vert"),
+                    include_str!("shaders/points.frag"),
+                )?);
+            }
+            PROGRAM.as_ref().unwrap()
+        };
+        program.use_uniform_vec4("color", color)?;
+        if let Some(ref sprite) = self.sprite {
+            program.use_texture(sprite.as_ref(), "sprite")?;
+            program.use_uniform_int("useSprite", &1)?;
+        } else {
+            program.use_uniform_int("useSprite", &0)?;
+        }
+        program.use_uniform_float("pointSize", &self.point_size)?;
+        program.use_uniform_int("sizeAttenuation", &(self.size_attenuation as i32))?;
+        program.use_uniform_float("viewportHeight", &(viewport.height as f32))?;
+        program.use_uniform_mat4("modelMatrix", transformation)?;
+        program.use_uniform_block(camera.matrix_buffer(), "Camera");
+        program.use_attribute_vec3(&self.position_buffer, "position")?;
+        program.draw_points(
+            RenderStates {
+                blend: Some(BlendParameters::TRANSPARENCY),
+                ..Default::default()
+            },
+            viewport,
+            self.count,
+        );
+        Ok(())
+    }
+}
+
+impl Drop for Points {
+    fn drop(&mut self) {
+        unsafe {
+            POINTS_COUNT -= 1;
+            if POINTS_COUNT == 0 {
+                PROGRAM = None;
+            }
+        }
+    }
+}
+
+static mut PROGRAM: Option<Program> = None;
+static mut POINTS_COUNT: u32 = 0;
@@ -0,0 +1,145 @@
+use crate::camera::*;
+use crate::core::*;
+use crate::math::*;
+use crate::object::*;
+
+///
+/// Defines how a mesh is shaded, see [Mesh::render_with_material](crate::Mesh::render_with_material).
+///
+#[derive(Clone, Copy)]
+pub enum DrawMaterial<'a> {
+    ///
+    /// See [Mesh::render_with_color](crate::Mesh::render_with_color).
+    ///
+    Color(Vec4),
+    ///
+    /// See [Mesh::render_color](crate::Mesh::render_color).
+    ///
+    VertexColor,
+    ///
+    /// See [Mesh::render_with_texture](crate::Mesh::render_with_texture).
+    ///
+    Texture(&'a dyn Texture),
+    ///
+    /// See [Mesh::render](crate::Mesh::render).
+    /// The uniforms of the program are not stored in the queue, so they must be the same for all draws using the program.
+    ///
+    Program(&'a MeshProgram),
+}
+
+struct DrawCommand<'a> {
+    mesh: &'a Mesh,
+    transformation: Mat4,
+    material: DrawMaterial<'a>,
+    render_states: RenderStates,
+}
+
+///
+/// Collects draw commands and renders them in an order which gives correct transparency and reduces overdraw.
+/// Meshes with blending enabled in their [render states](crate::RenderStates) are considered transparent
+/// and are rendered after the opaque meshes, sorted from back to front.
+/// The opaque meshes are sorted from front to back.
+/// The distance to the camera is measured from the center of the transformed [bounding box](crate::Mesh::aabb) of each mesh,
+/// and a mesh with an undefined distance, for example because its bounding box is empty, is sorted as infinitely far away.
+///
+pub struct RenderQueue<'a> {
+    commands: Vec<DrawCommand<'a>>,
+}
+
+impl<'a> RenderQueue<'a> {
+    ///
+    /// Constructs a new empty render queue.
+    ///
+    pub fn new() -> Self {
+        Self {
+            commands: Vec::new(),
+        }
+    }
+
+    ///
+    /// Adds a draw command to the queue. The mesh is not rendered until [render](Self::render) is called.
+    ///
+    pub fn push(
+        &mut self,
+        mesh: &'a Mesh,
+        transformation: &Mat4,
+        material: DrawMaterial<'a>,
+        render_states: RenderStates,
+    ) {
+        self.commands.push(DrawCommand {
+            mesh,
+            transformation: *transformation,
+            material,
+            render_states,
+        });
+    }
+
+    ///
+    /// Returns the number of draw commands in the queue.
+    ///
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    ///
+    /// Returns whether or not the queue is empty.
+    ///
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    ///
+    /// Removes all draw commands from the queue without rendering them.
+    ///
+    pub fn clear(&mut self) {
+        self.commands.clear();
+    }
+
+    ///
+    /// Sorts and renders all draw commands in the queue, after which the queue is empty.
+    /// Must be called in a render target render function,
+    /// for example in the callback function of [Screen::write](crate::Screen::write).
+    ///
+    /// # Errors
+    /// Will return an error if one of the meshes does not have the attributes required by its material,
+    /// for example uv coordinates when using a texture.
+    ///
+    pub fn render(&mut self, camera: &Camera, viewport: Viewport) -> Result<(), Error> {
+        let position = *camera.position();
+        let distance = |command: &DrawCommand| {
+            let center = command.mesh.aabb().center();
+            let world_center = command.transformation * vec4(center.x, center.y, center.z, 1.0);
+            let distance = world_center.truncate().distance2(position);
+            // An undefined distance, for example because of an empty bounding box, is sorted as infinitely far away
+            if distance.is_nan() {
+                f32::INFINITY
+            } else {
+                distance
+            }
+        };
+        let (mut opaque, mut transparent): (Vec<_>, Vec<_>) = self
+            .commands
+            .drain(..)
+            .map(|command| (distance(&command), command))
+            .partition(|(_, command)| command.render_states.blend.is_none());
+        opaque.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+        transparent.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+
+        for (_, command) in opaque.iter().chain(transparent.iter()) {
+            let DrawCommand {
+                mesh,
+                transformation,
+                material,
+                render_states,
+            } = command;
+            mesh.render_with_material(material, *render_states, viewport, transformation, camera)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Default for RenderQueue<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,13 @@
+
+uniform vec4 color;
+uniform float lineWidth;
+
+in float dist;
+
+layout (location = 0) out vec4 outColor;
+
+void main()
+{
+    float coverage = clamp(0.5 * lineWidth + 0.5 - abs(dist), 0.0, 1.0);
+    outColor = vec4(color.rgb, color.a * coverage);
+}
@@ -0,0 +1,60 @@
+
+layout (std140) uniform Camera
+{
+    mat4 viewProjection;
+    mat4 view;
+    mat4 projection;
+    vec3 position;
+    float padding;
+} camera;
+
+uniform mat4 modelMatrix;
+uniform vec2 viewportSize;
+uniform float lineWidth;
+uniform float depthBias;
+uniform float zNear;
+
+in vec3 start;
+in vec3 end;
+in vec2 corner;
+
+out float dist;
+
+void main()
+{
+    vec4 viewStart = camera.view * modelMatrix * vec4(start, 1.0);
+    vec4 viewEnd = camera.view * modelMatrix * vec4(end, 1.0);
+
+    // Clip the segment against the near plane before it is expanded, since the projection of a point behind the camera is mirrored
+    float nearZ = -zNear;
+    if(viewStart.z > nearZ && viewEnd.z > nearZ) {
+        // The whole segment is behind the near plane, so all corners are collapsed into a point outside the view
+        dist = 0.0;
+        gl_Position = vec4(0.0, 0.0, 2.0, 1.0);
+        return;
+    }
+    if(viewStart.z > nearZ) {
+        viewStart = mix(viewStart, viewEnd, (viewStart.z - nearZ) / (viewStart.z - viewEnd.z));
+    } else if(viewEnd.z > nearZ) {
+        viewEnd = mix(viewEnd, viewStart, (viewEnd.z - nearZ) / (viewEnd.z - viewStart.z));
+    }
+
+    vec4 clipStart = camera.projection * viewStart;
+    vec4 clipEnd = camera.projection * viewEnd;
+    vec2 screenStart = 0.5 * viewportSize * clipStart.xy / clipStart.w;
+    vec2 screenEnd = 0.5 * viewportSize * clipEnd.xy / clipEnd.w;
+    vec2 direction = screenEnd - screenStart;
+    direction = length(direction) > 0.0 ? normalize(direction) : vec2(1.0, 0.0);
+    vec2 normal = vec2(-direction.y, direction.x);
+
+    // Expand the line with one pixel on each side, which is used for anti-aliasing
+    float halfWidth = 0.5 * lineWidth + 1.0;
+    vec4 clip = mix(clipStart, clipEnd, corner.x);
+    clip.xy += (normal * corner.y * halfWidth) / (0.5 * viewportSize) * clip.w;
+    dist = corner.y * halfWidth;
+
+    // Use the depth of the position moved towards the camera while keeping the position on the screen
+    vec4 biasedClip = camera.projection * (mix(viewStart, viewEnd, corner.x) + vec4(0.0, 0.0, depthBias, 0.0));
+    clip.z = biasedClip.z / biasedClip.w * clip.w;
+    gl_Position = clip;
+}
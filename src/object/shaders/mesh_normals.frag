@@ -0,0 +1,9 @@
+
+in vec3 nor;
+
+layout (location = 0) out vec4 outColor;
+
+void main()
+{
+    outColor = vec4(0.5 * normalize(nor) + 0.5, 1.0);
+}
@@ -0,0 +1,26 @@
+uniform sampler2D sceneColor;
+uniform vec3 eyePosition;
+uniform mat4 viewProjectionMatrix;
+uniform float indexOfRefraction;
+uniform float strength;
+
+in vec3 pos;
+in vec3 nor;
+
+layout (location = 0) out vec4 outColor;
+
+vec2 screen_uv(vec3 position)
+{
+    vec4 clipPosition = viewProjectionMatrix * vec4(position, 1.0);
+    return 0.5 * clipPosition.xy / max(clipPosition.w, 0.0001) + 0.5;
+}
+
+void main()
+{
+    vec3 normal = normalize(gl_FrontFacing ? nor : -nor);
+    vec3 incident = normalize(pos - eyePosition);
+    vec3 refracted = refract(incident, normal, 1.0 / indexOfRefraction);
+    vec2 offset = screen_uv(pos + strength * refracted) - screen_uv(pos + strength * incident);
+    vec2 uv = gl_FragCoord.xy / vec2(textureSize(sceneColor, 0));
+    outColor = vec4(texture(sceneColor, clamp(uv + offset, 0.0, 1.0)).rgb, 1.0);
+}
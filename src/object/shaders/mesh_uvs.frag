@@ -0,0 +1,9 @@
+
+in vec2 uvs;
+
+layout (location = 0) out vec4 outColor;
+
+void main()
+{
+    outColor = vec4(uvs.x, uvs.y, 0.0, 1.0);
+}
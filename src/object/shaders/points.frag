@@ -0,0 +1,21 @@
+
+uniform vec4 color;
+uniform int useSprite;
+uniform sampler2D sprite;
+
+layout (location = 0) out vec4 outColor;
+
+void main()
+{
+    if (useSprite == 1) {
+        outColor = color * texture(sprite, gl_PointCoord);
+    } else {
+        // A circle with anti-aliased edges
+        float r = length(2.0 * gl_PointCoord - 1.0);
+        float coverage = 1.0 - smoothstep(1.0 - fwidth(r), 1.0, r);
+        if (coverage <= 0.0) {
+            discard;
+        }
+        outColor = vec4(color.rgb, color.a * coverage);
+    }
+}
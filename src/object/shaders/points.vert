@@ -0,0 +1,28 @@
+
+layout (std140) uniform Camera
+{
+    mat4 viewProjection;
+    mat4 view;
+    mat4 projection;
+    vec3 position;
+    float padding;
+} camera;
+
+uniform mat4 modelMatrix;
+uniform float pointSize;
+uniform int sizeAttenuation;
+uniform float viewportHeight;
+
+in vec3 position;
+
+void main()
+{
+    gl_Position = camera.projection * camera.view * modelMatrix * vec4(position, 1.0);
+
+    // With size attenuation, the point size is in world units, ie. the size in pixels of a point at the distance w from the camera
+    float size = pointSize;
+    if (sizeAttenuation == 1) {
+        size = pointSize * camera.projection[1][1] * 0.5 * viewportHeight / max(gl_Position.w, 0.0001);
+    }
+    gl_PointSize = max(size, 1.0);
+}
@@ -0,0 +1,13 @@
+uniform sampler2D texture0;
+
+in vec3 coords;
+
+layout (location = 0) out vec4 color;
+
+const float PI = 3.14159265359;
+
+void main() {
+    vec3 direction = normalize(coords);
+    vec2 uv = vec2(atan(direction.z, direction.x) / (2.0 * PI) + 0.5, asin(clamp(direction.y, -1.0, 1.0)) / PI + 0.5);
+    color = vec4(texture(texture0, vec2(uv.x, 1.0 - uv.y)).rgb, 1.0);
+}
@@ -4,16 +4,28 @@ use crate::definition::*;
 use crate::math::*;
 
 ///
-/// An illusion of a sky.
+/// An illusion of a sky, where the texture is either a [TextureCubeMap](crate::TextureCubeMap), which is the default,
+/// or a [Texture2D](crate::Texture2D) with an equirectangular image (see [new_equirectangular_direct](Skybox::new_equirectangular_direct)).
 ///
-pub struct Skybox {
+pub struct Skybox<T: Texture = TextureCubeMap> {
     program: program::Program,
     vertex_buffer: VertexBuffer,
-    texture: texture::TextureCubeMap,
+    texture: T,
 }
 
 impl Skybox {
+    ///
+    /// Creates a new skybox from the given cpu texture which contains either
+    /// the 6 square faces in the order right, left, top, bottom, front and back (see [Loaded::cube_image](crate::Loaded::cube_image)),
+    /// a single image with the 6 faces in the same order from top to bottom (a vertical strip with the aspect ratio 1:6)
+    /// or a single image with the faces laid out as a horizontal cross (the aspect ratio 4:3) with top above and bottom below front.
+    /// A single image is split into faces which replaces the data, width and height of the given cpu texture.
+    ///
+    /// # Errors
+    /// Will return an error if the size of the cpu texture does not match any of the layouts above.
+    ///
     pub fn new(context: &Context, cpu_texture: &mut CPUTexture<u8>) -> Result<Skybox, Error> {
+        extract_cube_map_faces(cpu_texture)?;
         cpu_texture.wrap_t = Wrapping::ClampToEdge;
         cpu_texture.wrap_s = Wrapping::ClampToEdge;
         cpu_texture.wrap_r = Wrapping::ClampToEdge;
@@ -26,10 +38,44 @@ impl Skybox {
         context: &Context,
         texture: texture::TextureCubeMap,
     ) -> Result<Skybox, Error> {
+        Self::new_internal(context, texture, include_str!("shaders/skybox.frag"))
+    }
+}
+
+impl Skybox<Texture2D> {
+    ///
+    /// Creates a new skybox from the given cpu texture which contains an equirectangular image,
+    /// ie. the longitude is mapped to the width and the latitude to the height of the image (the aspect ratio is usually 2:1).
+    /// The image is stored in a 2D texture and sampled directly in the view direction,
+    /// which avoids the memory cost of converting the image to the six faces of a cube map.
+    ///
+    pub fn new_equirectangular_direct(
+        context: &Context,
+        cpu_texture: &mut CPUTexture<u8>,
+    ) -> Result<Skybox<Texture2D>, Error> {
+        cpu_texture.wrap_s = Wrapping::Repeat;
+        cpu_texture.wrap_t = Wrapping::ClampToEdge;
+        // Mip mapping gives a visible seam where the longitude wraps around
+        cpu_texture.mip_map_filter = None;
+        let texture = Texture2D::new_with_u8(context, cpu_texture)?;
+        Self::new_internal(
+            context,
+            texture,
+            include_str!("shaders/skybox_equirectangular.frag"),
+        )
+    }
+}
+
+impl<T: Texture> Skybox<T> {
+    fn new_internal(
+        context: &Context,
+        texture: T,
+        fragment_shader_source: &str,
+    ) -> Result<Self, Error> {
         let program = program::Program::from_source(
             context,
             include_str!("shaders/skybox.vert"),
-            include_str!("shaders/skybox.frag"),
+            fragment_shader_source,
         )?;
 
         let vertex_buffer = VertexBuffer::new_with_static_f32(context, &get_positions())?;
@@ -42,17 +88,34 @@ impl Skybox {
     }
 
     ///
-    /// Render the skybox.
+    /// Render the skybox at the far plane of the camera with front face culling and a depth test which passes at the far plane,
+    /// ie. [LessOrEqual](crate::DepthTestType::LessOrEqual).
     /// Must be called in a render target render function,
     /// for example in the callback function of [Screen::write](crate::Screen::write).
     ///
     pub fn render(&self, viewport: Viewport, camera: &Camera) -> Result<(), Error> {
-        let render_states = RenderStates {
-            cull: CullType::Front,
-            depth_test: DepthTestType::LessOrEqual,
-            ..Default::default()
-        };
+        self.render_with_states(
+            RenderStates {
+                cull: CullType::Front,
+                depth_test: DepthTestType::LessOrEqual,
+                ..Default::default()
+            },
+            viewport,
+            camera,
+        )
+    }
 
+    ///
+    /// Same as [render](Self::render), except that the render states are given, for example to cull the back faces instead of the front faces
+    /// when the winding order is reversed by a [reflected camera](crate::Camera::reflected_across) or to use a custom depth test.
+    /// The skybox is always rendered at the far plane of the camera.
+    ///
+    pub fn render_with_states(
+        &self,
+        render_states: RenderStates,
+        viewport: Viewport,
+        camera: &Camera,
+    ) -> Result<(), Error> {
         self.program.use_texture(&self.texture, "texture0")?;
         self.program
             .use_uniform_block(camera.matrix_buffer(), "Camera");
@@ -64,11 +127,80 @@ impl Skybox {
         Ok(())
     }
 
-    pub fn get_texture(&self) -> &texture::TextureCubeMap {
+    ///
+    /// Returns the texture of the skybox, ie. a [TextureCubeMap](crate::TextureCubeMap) or
+    /// a [Texture2D](crate::Texture2D) if constructed with [new_equirectangular_direct](Skybox::new_equirectangular_direct).
+    ///
+    pub fn get_texture(&self) -> &T {
         &self.texture
     }
 }
 
+fn extract_cube_map_faces(cpu_texture: &mut CPUTexture<u8>) -> Result<(), Error> {
+    let channels = match cpu_texture.format {
+        Format::R8 => 1,
+        Format::RGB8 | Format::SRGB8 => 3,
+        Format::RGBA8 | Format::SRGBA8 => 4,
+        _ => Err(Error::FailedToCreateTexture {
+            message: format!(
+                "A skybox texture must have an 8 bit format, got {:?}",
+                cpu_texture.format
+            ),
+        })?,
+    };
+    let width = cpu_texture.width;
+    let height = cpu_texture.height;
+    let image_size = width * height * channels;
+    if cpu_texture.data.len() == 6 * image_size {
+        if width != height {
+            Err(Error::FailedToCreateTexture {
+                message: format!(
+                    "The faces of a skybox texture must be square, got {}x{} pixels",
+                    width, height
+                ),
+            })?;
+        }
+        return Ok(());
+    }
+    if cpu_texture.data.len() != image_size {
+        Err(Error::FailedToCreateTexture {
+            message: format!(
+                "Wrong size of data for the skybox texture (got {} bytes but expected {} bytes for a single image or {} bytes for 6 faces)",
+                cpu_texture.data.len(),
+                image_size,
+                6 * image_size
+            ),
+        })?;
+    }
+
+    // The position (column, row) of the right, left, top, bottom, front and back face in units of faces
+    let (face_size, positions) = if width > 0 && height == 6 * width {
+        (width, [(0, 0), (0, 1), (0, 2), (0, 3), (0, 4), (0, 5)])
+    } else if width > 0 && 3 * width == 4 * height {
+        (width / 4, [(2, 1), (0, 1), (1, 0), (1, 2), (1, 1), (3, 1)])
+    } else {
+        Err(Error::FailedToCreateTexture {
+            message: format!(
+                "A skybox texture of {}x{} pixels is neither a vertical strip with the aspect ratio 1:6 nor a horizontal cross with the aspect ratio 4:3",
+                width, height
+            ),
+        })?
+    };
+
+    let row_size = face_size * channels;
+    let mut data = Vec::with_capacity(6 * face_size * row_size);
+    for (column, row) in positions.iter() {
+        for y in 0..face_size {
+            let start = ((row * face_size + y) * width + column * face_size) * channels;
+            data.extend_from_slice(&cpu_texture.data[start..start + row_size]);
+        }
+    }
+    cpu_texture.data = data;
+    cpu_texture.width = face_size;
+    cpu_texture.height = face_size;
+    Ok(())
+}
+
 fn get_positions() -> Vec<f32> {
     vec![
         1.0, 1.0, -1.0, -1.0, 1.0, -1.0, 1.0, 1.0, 1.0, -1.0, 1.0, 1.0, 1.0, 1.0, 1.0, -1.0, 1.0,
@@ -80,3 +212,44 @@ fn get_positions() -> Vec<f32> {
         -1.0, -1.0, 1.0, -1.0, 1.0, 1.0, -1.0, -1.0, -1.0,
     ]
 }
+
+#[cfg(all(test, feature = "headless", not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::test_util::*;
+
+    #[test]
+    fn render_inside_viewport() {
+        let test_context = TestContext::new();
+        let context = test_context.gl();
+        let texture = TextureCubeMap::new_with_u8(
+            &context,
+            &CPUTexture {
+                data: vec![255; 6 * 4],
+                width: 1,
+                height: 1,
+                format: Format::RGBA8,
+                mip_map_filter: None,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let skybox = Skybox::new_with_texture(&context, texture).unwrap();
+        let camera = Camera::new_perspective(
+            &context,
+            vec3(0.0, 0.0, 0.0),
+            vec3(0.0, 0.0, -1.0),
+            vec3(0.0, 1.0, 0.0),
+            degrees(90.0),
+            1.0,
+            0.1,
+            10.0,
+        )
+        .unwrap();
+        assert_renders_inside_viewport(|width, height, clear_state, viewport| {
+            render_to_cpu(&context, width, height, clear_state, || {
+                skybox.render(viewport, &camera)
+            })
+        });
+    }
+}
@@ -17,6 +17,16 @@ pub mod deferred_pipeline;
 #[doc(inline)]
 pub use crate::deferred_pipeline::*;
 
+#[doc(hidden)]
+pub mod tiled_forward_pipeline;
+#[doc(inline)]
+pub use crate::tiled_forward_pipeline::*;
+
+#[doc(hidden)]
+pub mod decal;
+#[doc(inline)]
+pub use crate::decal::*;
+
 #[doc(hidden)]
 pub mod phong_mesh;
 #[doc(inline)]
@@ -32,13 +42,29 @@ fn phong_fragment_shader(
     directional_lights: usize,
     spot_lights: usize,
     point_lights: usize,
+) -> String {
+    phong_fragment_shader_internal(
+        surface_functionality,
+        directional_lights,
+        spot_lights,
+        point_lights,
+        false,
+    )
+}
+
+fn phong_fragment_shader_internal(
+    surface_functionality: &str,
+    directional_lights: usize,
+    spot_lights: usize,
+    point_lights: usize,
+    tiled_point_lights: bool,
 ) -> String {
     let mut dir_uniform = String::new();
     let mut dir_fun = String::new();
     for i in 0..directional_lights {
         dir_uniform.push_str(&format!(
             "
-                uniform sampler2D directionalShadowMap{};
+                uniform sampler2DShadow directionalShadowMap{};
                 layout (std140) uniform DirectionalLightUniform{}
                 {{
                     DirectionalLight directionalLight{};
@@ -54,16 +80,17 @@ fn phong_fragment_shader(
     for i in 0..spot_lights {
         spot_uniform.push_str(&format!(
             "
-                uniform sampler2D spotShadowMap{};
+                uniform sampler2DShadow spotShadowMap{};
+                uniform sampler2D spotProjectionTexture{};
                 layout (std140) uniform SpotLightUniform{}
                 {{
                     SpotLight spotLight{};
                 }};",
-            i, i, i
+            i, i, i, i
         ));
         spot_fun.push_str(&format!("
                     color.rgb += calculate_spot_light(spotLight{}, surface.color.rgb, surface.position, surface.normal,
-                        surface.diffuse_intensity, surface.specular_intensity, surface.specular_power, spotShadowMap{});", i, i));
+                        surface.diffuse_intensity, surface.specular_intensity, surface.specular_power, spotShadowMap{}, spotProjectionTexture{});", i, i, i));
     }
     let mut point_uniform = String::new();
     let mut point_fun = String::new();
@@ -81,6 +108,17 @@ fn phong_fragment_shader(
                         surface.diffuse_intensity, surface.specular_intensity, surface.specular_power);", i));
     }
 
+    let (tiled_uniform, tiled_fun) = if tiled_point_lights {
+        (
+            include_str!("phong/shaders/tiled_lights.frag"),
+            "
+                    color.rgb += calculate_tiled_point_lights(surface.color.rgb, surface.position, surface.normal,
+                        surface.diffuse_intensity, surface.specular_intensity, surface.specular_power);",
+        )
+    } else {
+        ("", "")
+    };
+
     format!(
         "{}\n{}\n{}",
         &include_str!("phong/shaders/light_shared.frag"),
@@ -93,34 +131,94 @@ fn phong_fragment_shader(
                 {} // Directional lights
                 {} // Spot lights
                 {} // Point lights
+                {} // Tiled point lights
 
                 void main()
                 {{
                     {} // Surface parameters
-                    color = vec4(ambientColor * surfaceColor.rgb, surfaceColor.a);
+                    color = vec4(get_surface_ambient_occlusion() * ambientColor * surfaceColor.rgb, surfaceColor.a);
                     {} // Directional lights
                     {} // Spot lights
                     {} // Point lights
+                    {} // Tiled point lights
+                    color.rgb += get_surface_fresnel();
                 }}
                 ",
             &dir_uniform,
             &spot_uniform,
             &point_uniform,
-            if directional_lights > 0 || spot_lights > 0 || point_lights > 0 {
+            tiled_uniform,
+            if directional_lights > 0 || spot_lights > 0 || point_lights > 0 || tiled_point_lights {
                 "Surface surface = get_surface(); vec4 surfaceColor = surface.color;"
             } else {
                 "vec4 surfaceColor = get_surface_color();"
             },
             &dir_fun,
             &spot_fun,
-            &point_fun
+            &point_fun,
+            tiled_fun
         )
     )
 }
 
+use crate::camera::*;
 use crate::core::*;
 use crate::light::*;
 use crate::math::*;
+
+///
+/// Returns the fragment shader source code which makes it possible to render custom geometry, for example with a custom
+/// [MeshProgram](crate::MeshProgram), into the gbuffer in the [geometry pass](crate::PhongDeferredPipeline::geometry_pass)
+/// of the deferred pipeline, so that it is shaded together with the [Phong meshes](crate::PhongMesh).
+///
+/// Include the returned source code in the fragment shader and call
+/// `write(vec3 normal, vec3 color, float diffuse_intensity, float specular_intensity, float specular_power)` exactly once in `main`,
+/// where the normal is the normalized world space normal and the intensities are between 0 and 1.
+/// Alternatively, call `write` with an additional `float ambient_occlusion` argument between 0 and 1 which is multiplied with the ambient light.
+/// The surface is written to the following outputs which must not be written to elsewhere in the shader:
+/// - `layout (location = 0) out vec4 out_color`: The color and the packed diffuse intensity and ambient occlusion.
+/// - `layout (location = 1) out vec4 out_normal`: The encoded normal and the packed specular intensity and power.
+/// - `layout (location = 2) out vec4 out_velocity`: The encoded screen space velocity.
+///
+/// The source code declares `in vec3 pos;` and the uniforms needed for calculating the velocity,
+/// which must be set with [bind_gbuffer_uniforms] before each render call.
+///
+pub fn gbuffer_output_snippet() -> String {
+    format!(
+        "{}\n{}",
+        include_str!("phong/shaders/normal_encoding.frag"),
+        include_str!("phong/shaders/deferred_objects_shared.frag")
+    )
+}
+
+///
+/// Sets the uniforms needed by the [gbuffer output source code](gbuffer_output_snippet) on the given program.
+/// The previous transformation is the transformation of the geometry in the previous frame, which is used to calculate the velocity,
+/// use the same as the current transformation if the geometry is not moving.
+/// If the current transformation is not invertible, for example when it scales the geometry to zero,
+/// the geometry is treated as not moving, so only the camera motion contributes to the velocity.
+///
+pub fn bind_gbuffer_uniforms(
+    program: &Program,
+    camera: &Camera,
+    transformation: &Mat4,
+    previous_transformation: &Mat4,
+) -> Result<(), Error> {
+    program.use_uniform_mat4(
+        "currentViewProjection",
+        &(camera.projection() * camera.view()),
+    )?;
+    program.use_uniform_mat4("previousViewProjection", camera.previous_view_projection())?;
+    program.use_uniform_mat4(
+        "motionTransformation",
+        &transformation
+            .invert()
+            .map(|inverse| previous_transformation * inverse)
+            .unwrap_or_else(Mat4::identity),
+    )?;
+    Ok(())
+}
+
 fn bind_lights(
     effect: &Program,
     ambient_light: Option<&AmbientLight>,
@@ -151,6 +249,10 @@ fn bind_lights(
     // Spot light
     for i in 0..spot_lights.len() {
         effect.use_texture(spot_lights[i].shadow_map(), &format!("spotShadowMap{}", i))?;
+        effect.use_texture(
+            spot_lights[i].projection_texture(),
+            &format!("spotProjectionTexture{}", i),
+        )?;
         effect.use_uniform_block(spot_lights[i].buffer(), &format!("SpotLightUniform{}", i));
     }
 
@@ -0,0 +1,137 @@
+use crate::camera::*;
+use crate::core::*;
+use crate::definition::*;
+use crate::math::*;
+use crate::object::*;
+use crate::phong::*;
+use std::rc::Rc;
+
+///
+/// A deferred decal, for example a bullet hole, a puddle or a sign, which is projected onto the surfaces written in the
+/// [geometry pass](crate::PhongDeferredPipeline::geometry_pass) of a [PhongDeferredPipeline](crate::PhongDeferredPipeline).
+/// The decal is a unit box centered at the origin which is positioned, orientated and scaled by the transformation given when rendering.
+/// The texture is projected along the negative y-axis of the box onto the surfaces inside the box and blended into their color
+/// using the alpha channel of the texture.
+///
+pub struct Decal {
+    context: Context,
+    mesh: Mesh,
+    ///
+    /// The texture projected onto the surfaces inside the decal box.
+    ///
+    pub texture: Rc<Texture2D>,
+    ///
+    /// The distance in world space the surfaces are moved towards the camera before testing if they are inside the decal box,
+    /// which makes it possible to project the decal onto a surface lying exactly on a face of the box without flickering
+    /// due to the precision of the depth. The default is zero.
+    ///
+    pub depth_bias: f32,
+}
+
+impl Decal {
+    ///
+    /// Constructs a new decal which projects the given texture.
+    ///
+    pub fn new(context: &Context, texture: Rc<Texture2D>) -> Result<Self, Error> {
+        let mesh = Mesh::new(context, &CPUMesh::cube(1.0))?;
+        unsafe {
+            DECAL_COUNT += 1;
+        }
+        Ok(Self {
+            context: context.clone(),
+            mesh,
+            texture,
+            depth_bias: 0.0,
+        })
+    }
+
+    ///
+    /// Render the decal into the color of the surfaces written in the last geometry pass of the given pipeline.
+    /// Must be called in the render closure of [decal_pass](crate::PhongDeferredPipeline::decal_pass),
+    /// and the viewport and camera must be the same as the ones used when rendering the geometry.
+    /// The transformation can be used to position, orientate and scale the decal box.
+    /// Nothing is rendered if the transformation is not invertible, for example if the scale is zero, since the box has no volume.
+    ///
+    pub fn render(
+        &self,
+        pipeline: &PhongDeferredPipeline,
+        viewport: Viewport,
+        transformation: &Mat4,
+        camera: &Camera,
+    ) -> Result<(), Error> {
+        let decal_inverse = match transformation.invert() {
+            Some(decal_inverse) => decal_inverse,
+            None => return Ok(()),
+        };
+        let program = unsafe {
+            if PROGRAM.is_none() {
+                PROGRAM = Some(MeshProgram::new(
+                    &self.context,
+                    &format!(
+                        "{}\n{}",
+                        include_str!("shaders/normal_encoding.frag"),
+                        include_str!("shaders/decal.frag")
+                    ),
+                )?);
+            }
+            PROGRAM.as_ref().unwrap()
+        };
+        program.use_texture(pipeline.decal_normal_texture()?, "normalMap")?;
+        program.use_texture(pipeline.geometry_pass_depth_texture_array(), "depthMap")?;
+        program.use_texture(self.texture.as_ref(), "decalTexture")?;
+        program.use_uniform_mat4(
+            "viewProjectionInverse",
+            &(camera.projection() * camera.view()).invert().unwrap(),
+        )?;
+        program.use_uniform_mat4("decalInverse", &decal_inverse)?;
+        program.use_uniform_vec3("eyePosition", camera.position())?;
+        program.use_uniform_float("depthBias", &self.depth_bias)?;
+        program.use_uniform_vec3(
+            "projectionDirection",
+            &(transformation * vec4(0.0, -1.0, 0.0, 0.0))
+                .truncate()
+                .normalize(),
+        )?;
+        program.use_uniform_vec4(
+            "screenViewport",
+            &vec4(
+                viewport.x as f32,
+                viewport.y as f32,
+                viewport.width as f32,
+                viewport.height as f32,
+            ),
+        )?;
+
+        // Render the back faces of the box without depth test, which also works when the camera is inside the box.
+        // The alpha channel of the gbuffer color contains other surface parameters, so only the color is written.
+        let render_states = RenderStates {
+            cull: CullType::Front,
+            depth_test: DepthTestType::Always,
+            write_mask: WriteMask {
+                red: true,
+                green: true,
+                blue: true,
+                alpha: false,
+                depth: false,
+            },
+            blend: Some(BlendParameters::TRANSPARENCY),
+            ..Default::default()
+        };
+        self.mesh
+            .render(program, render_states, viewport, transformation, camera)
+    }
+}
+
+impl Drop for Decal {
+    fn drop(&mut self) {
+        unsafe {
+            DECAL_COUNT -= 1;
+            if DECAL_COUNT == 0 {
+                PROGRAM = None;
+            }
+        }
+    }
+}
+
+static mut PROGRAM: Option<MeshProgram> = None;
+static mut DECAL_COUNT: u32 = 0;
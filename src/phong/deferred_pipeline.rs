@@ -4,7 +4,38 @@ use crate::definition::*;
 use crate::effect::*;
 use crate::light::*;
 use crate::math::*;
-use std::collections::HashMap;
+use crate::object::*;
+use std::cell::RefCell;
+
+///
+/// The maximum number of directional lights in one [light pass](PhongDeferredPipeline::light_pass), more lights are applied in additional passes.
+///
+pub const MAX_DEFERRED_DIRECTIONAL_LIGHTS: usize = 4;
+
+///
+/// The maximum number of spot lights in one [light pass](PhongDeferredPipeline::light_pass), more lights are applied in additional passes.
+///
+pub const MAX_DEFERRED_SPOT_LIGHTS: usize = 4;
+
+///
+/// The maximum number of point lights in one [light pass](PhongDeferredPipeline::light_pass), more lights are applied in additional passes.
+/// Use [light_pass_with_volumes](PhongDeferredPipeline::light_pass_with_volumes) for scenes with more attenuated point lights.
+///
+pub const MAX_DEFERRED_POINT_LIGHTS: usize = 64;
+
+///
+/// Spot lights with an outer angle in degrees of at least this angle use a sphere instead of a cone as light volume
+/// in [light_pass_with_volumes](PhongDeferredPipeline::light_pass_with_volumes), since the cone becomes too wide.
+///
+pub const MAX_LIGHT_VOLUME_CONE_ANGLE: f32 = 75.0;
+
+const LIGHT_VOLUME_CONE_SUBDIVISIONS: u32 = 16;
+const LIGHT_VOLUME_STENCIL_BIT: u8 = 0x80;
+
+// The number of floats in the std140 layout of each type of light, which is the same as in the uniform buffer of the light.
+const DIRECTIONAL_LIGHT_SIZE: usize = 24;
+const SPOT_LIGHT_SIZE: usize = 48;
+const POINT_LIGHT_SIZE: usize = 12;
 
 ///
 /// Used for debug purposes.
@@ -18,40 +49,171 @@ pub enum DebugType {
     DIFFUSE,
     SPECULAR,
     POWER,
+    VELOCITY,
     NONE,
 }
 
+///
+/// The number of layers and the depth format of the textures written in the [geometry pass](PhongDeferredPipeline::geometry_pass),
+/// see [PhongDeferredPipeline::new_with_config].
+/// The color texture array always has the [RGBA8](Format::RGBA8) format, since the pipeline packs the surface parameters,
+/// the normal and the velocity into bytes, so a float format would not give any additional precision.
+///
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct GBufferConfig {
+    ///
+    /// The number of layers in the color texture array. The first three layers are used by the pipeline (color, normal and velocity),
+    /// any additional layers can be written to by custom geometry shaders at the following output locations.
+    ///
+    pub layers: usize,
+    ///
+    /// The format of the depth texture.
+    ///
+    pub depth_format: DepthFormat,
+}
+
+impl Default for GBufferConfig {
+    fn default() -> Self {
+        Self {
+            layers: 3,
+            depth_format: DepthFormat::Depth32F,
+        }
+    }
+}
+
+///
+/// The parameters of the [screen space reflections pass](PhongDeferredPipeline::ssr_pass).
+///
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SSRConfig {
+    ///
+    /// The maximum number of steps along each reflected ray before giving up on finding a reflected surface.
+    ///
+    pub max_steps: u32,
+    ///
+    /// The maximum distance in world space along each reflected ray, ie. the length of each step is the maximum distance divided by the maximum number of steps.
+    ///
+    pub max_distance: f32,
+    ///
+    /// How far in world space a reflected ray can be behind a surface in the depth buffer and still hit it.
+    ///
+    pub thickness: f32,
+    ///
+    /// The fraction of the screen size at each edge of the screen where the reflections are faded out,
+    /// since the reflected surfaces outside the screen are unknown.
+    ///
+    pub edge_fade: f32,
+    ///
+    /// The radius in pixels at which the reflections of the roughest surfaces, ie. surfaces with the lowest specular power, are blurred.
+    ///
+    pub max_blur_radius: f32,
+}
+
+impl Default for SSRConfig {
+    fn default() -> Self {
+        Self {
+            max_steps: 64,
+            max_distance: 10.0,
+            thickness: 0.5,
+            edge_fade: 0.1,
+            max_blur_radius: 4.0,
+        }
+    }
+}
+
 ///
 /// Deferred pipeline based on the Phong reflection model supporting a performance-limited
 /// amount of directional, point and spot lights with shadows. Supports colored, textured and instanced meshes.
 ///
 pub struct PhongDeferredPipeline {
     context: Context,
-    program_map: HashMap<String, ImageEffect>,
+    light_pass_effect: Option<ImageEffect>,
+    ambient_light_pass_effect: Option<ImageEffect>,
+    light_buffer: UniformBuffer,
+    empty_shadow_map: DepthTargetTexture2D,
+    empty_projection_texture: Texture2D,
     debug_effect: Option<ImageEffect>,
     ///
     /// Set this to visualize the positions, normals etc. for debug purposes.
     ///
     pub debug_type: DebugType,
+    gbuffer_config: GBufferConfig,
     geometry_pass_texture: Option<ColorTargetTexture2DArray>,
     geometry_pass_depth_texture: Option<DepthTargetTexture2DArray>,
+    geometry_pass_depth_texture_2d: Option<DepthTargetTexture2D>,
+    decal_normal_texture: Option<ColorTargetTexture2D>,
+    light_volume_program: Option<MeshProgram>,
+    light_volume_spot_program: Option<MeshProgram>,
+    light_volume_mesh: Option<Mesh>,
+    light_volume_cone_mesh: Option<Mesh>,
+    light_pass_texture: Option<ColorTargetTexture2D>,
+    ssr_effect: RefCell<Option<ImageEffect>>,
 }
 
 impl PhongDeferredPipeline {
     ///
-    /// Constructor.
+    /// Constructor using the [default](GBufferConfig::default) number of geometry pass layers and depth format.
     ///
     pub fn new(context: &Context) -> Result<Self, Error> {
+        Self::new_with_config(context, GBufferConfig::default())
+    }
+
+    ///
+    /// Constructor where the number of layers and the depth format of the geometry pass textures are given by the config.
+    ///
+    /// # Errors
+    /// Will return an error if there are less than three layers or if the number of layers exceeds the capabilities of the context.
+    ///
+    pub fn new_with_config(
+        context: &Context,
+        gbuffer_config: GBufferConfig,
+    ) -> Result<Self, Error> {
+        let capabilities = context.capabilities();
+        let max_layers = (capabilities.max_color_attachments as usize)
+            .min(capabilities.max_array_texture_layers as usize);
+        if gbuffer_config.layers < 3 || gbuffer_config.layers > max_layers {
+            Err(Error::FailedToCreateTexture {
+                message: format!(
+                    "The number of geometry pass layers must be between 3 and {}, but {} was given.",
+                    max_layers, gbuffer_config.layers
+                ),
+            })?;
+        }
         let renderer = Self {
             context: context.clone(),
-            program_map: HashMap::new(),
+            light_pass_effect: None,
+            ambient_light_pass_effect: None,
+            light_buffer: UniformBuffer::new(
+                context,
+                &[
+                    (DIRECTIONAL_LIGHT_SIZE * MAX_DEFERRED_DIRECTIONAL_LIGHTS) as u32,
+                    (SPOT_LIGHT_SIZE * MAX_DEFERRED_SPOT_LIGHTS) as u32,
+                    (POINT_LIGHT_SIZE * MAX_DEFERRED_POINT_LIGHTS) as u32,
+                ],
+            )?,
+            empty_shadow_map: DepthTargetTexture2D::new_with_comparison(
+                context,
+                1,
+                1,
+                Wrapping::ClampToEdge,
+                Wrapping::ClampToEdge,
+                DepthFormat::Depth32F,
+            )?,
+            empty_projection_texture: Texture2D::new_with_u8(
+                context,
+                &CPUTexture {
+                    data: vec![255; 4],
+                    ..Default::default()
+                },
+            )?,
             debug_effect: None,
             debug_type: DebugType::NONE,
+            gbuffer_config,
             geometry_pass_texture: Some(ColorTargetTexture2DArray::new(
                 context,
                 1,
                 1,
-                2,
+                gbuffer_config.layers,
                 Interpolation::Nearest,
                 Interpolation::Nearest,
                 None,
@@ -66,8 +228,16 @@ impl PhongDeferredPipeline {
                 1,
                 Wrapping::ClampToEdge,
                 Wrapping::ClampToEdge,
-                DepthFormat::Depth32F,
+                gbuffer_config.depth_format,
             )?),
+            geometry_pass_depth_texture_2d: None,
+            decal_normal_texture: None,
+            light_volume_program: None,
+            light_volume_spot_program: None,
+            light_volume_mesh: None,
+            light_volume_cone_mesh: None,
+            light_pass_texture: None,
+            ssr_effect: RefCell::new(None),
         };
         Ok(renderer)
     }
@@ -78,6 +248,9 @@ impl PhongDeferredPipeline {
     /// either type of mesh inside the **render** closure.
     /// This function must not be called in a render target render function, but needs to be followed
     /// by a call to [light_pass](Self::light_pass) which must be inside a render target render function.
+    /// Besides the surface parameters, the velocity of each pixel is written to the third layer of the
+    /// [geometry pass texture](Self::geometry_pass_texture), which can be used by the [motion blur effect](crate::MotionBlurEffect).
+    /// Custom geometry can also be rendered into the gbuffer using a shader program which includes the [gbuffer output source code](crate::gbuffer_output_snippet).
     ///
     pub fn geometry_pass<F: FnOnce() -> Result<(), Error>>(
         &mut self,
@@ -85,42 +258,129 @@ impl PhongDeferredPipeline {
         height: usize,
         render: F,
     ) -> Result<(), Error> {
-        self.geometry_pass_texture = Some(ColorTargetTexture2DArray::new(
-            &self.context,
-            width,
-            height,
-            2,
-            Interpolation::Nearest,
-            Interpolation::Nearest,
-            None,
-            Wrapping::ClampToEdge,
-            Wrapping::ClampToEdge,
-            Format::RGBA8,
-        )?);
-        self.geometry_pass_depth_texture = Some(DepthTargetTexture2DArray::new(
-            &self.context,
-            width,
-            height,
-            1,
-            Wrapping::ClampToEdge,
-            Wrapping::ClampToEdge,
-            DepthFormat::Depth32F,
-        )?);
+        self.geometry_pass_with_clear_state(width, height, &ClearState::default(), render)
+    }
+
+    ///
+    /// Same as [geometry_pass](Self::geometry_pass), except that all layers of the geometry pass texture and the depth are cleared
+    /// based on the given clear state instead of being cleared to zero and a depth of one.
+    /// The geometry pass textures are kept as long as the width and height are the same as in the previous geometry pass,
+    /// so for example [ClearState::none] renders on top of the result of the previous geometry pass.
+    ///
+    pub fn geometry_pass_with_clear_state<F: FnOnce() -> Result<(), Error>>(
+        &mut self,
+        width: usize,
+        height: usize,
+        clear_state: &ClearState,
+        render: F,
+    ) -> Result<(), Error> {
+        if self
+            .geometry_pass_texture
+            .as_ref()
+            .map(|t| t.width() != width || t.height() != height)
+            .unwrap_or(true)
+        {
+            self.geometry_pass_texture = Some(ColorTargetTexture2DArray::new(
+                &self.context,
+                width,
+                height,
+                self.gbuffer_config.layers,
+                Interpolation::Nearest,
+                Interpolation::Nearest,
+                None,
+                Wrapping::ClampToEdge,
+                Wrapping::ClampToEdge,
+                Format::RGBA8,
+            )?);
+            self.geometry_pass_depth_texture = Some(DepthTargetTexture2DArray::new(
+                &self.context,
+                width,
+                height,
+                1,
+                Wrapping::ClampToEdge,
+                Wrapping::ClampToEdge,
+                self.gbuffer_config.depth_format,
+            )?);
+        }
         RenderTargetArray::new(
             &self.context,
             self.geometry_pass_texture.as_ref().unwrap(),
             self.geometry_pass_depth_texture.as_ref().unwrap(),
         )?
-        .write(&ClearState::default(), &[0, 1], 0, render)?;
+        .write(
+            clear_state,
+            &(0..self.gbuffer_config.layers).collect::<Vec<usize>>(),
+            0,
+            render,
+        )?;
         Ok(())
     }
 
+    ///
+    /// Render [decals](crate::Decal) into the color of the surfaces written in the last [geometry_pass](Self::geometry_pass) call
+    /// by calling [Decal::render](crate::Decal::render) inside the **render** closure.
+    /// This function must not be called in a render target render function, and must be called after the geometry pass and before
+    /// the [light_pass](Self::light_pass), so that the decals are lit in the same way as the surfaces they are projected onto.
+    /// The normals are copied to a separate texture before the decals are rendered, since the decals cannot sample
+    /// the geometry pass texture while writing to it. This texture is allocated the first time this function is called
+    /// and again when the size of the geometry pass changes.
+    ///
+    pub fn decal_pass<F: FnOnce() -> Result<(), Error>>(&mut self, render: F) -> Result<(), Error> {
+        let geometry_pass_texture = self.geometry_pass_texture.as_ref().unwrap();
+        let (width, height) = (
+            geometry_pass_texture.width(),
+            geometry_pass_texture.height(),
+        );
+        if self
+            .decal_normal_texture
+            .as_ref()
+            .map(|t| t.width() != width || t.height() != height)
+            .unwrap_or(true)
+        {
+            self.decal_normal_texture = Some(ColorTargetTexture2D::new(
+                &self.context,
+                width,
+                height,
+                Interpolation::Nearest,
+                Interpolation::Nearest,
+                None,
+                Wrapping::ClampToEdge,
+                Wrapping::ClampToEdge,
+                Format::RGBA8,
+            )?);
+        }
+        let render_target = RenderTargetArray::new_color(&self.context, geometry_pass_texture)?;
+        render_target.copy_color(
+            1,
+            &RenderTarget::new_color(&self.context, self.decal_normal_texture.as_ref().unwrap())?,
+            Viewport::new_at_origo(width, height),
+        )?;
+        render_target.write(&ClearState::none(), &[0], 0, render)
+    }
+
+    pub(crate) fn decal_normal_texture(&self) -> Result<&ColorTargetTexture2D, Error> {
+        self.decal_normal_texture
+            .as_ref()
+            .ok_or_else(|| Error::FailedToCopyFromRenderTarget {
+                message: "A decal must be rendered in the render closure of a decal pass."
+                    .to_owned(),
+            })
+    }
+
     ///
     /// Uses the geometry and surface material parameters written in the last [geometry_pass](Self::geometry_pass) call
     /// and all of the given lights
     /// to shade the Phong [meshes](crate::PhongMesh) or [instanced meshes](crate::PhongInstancedMesh).
     /// Must be called in a render target render function,
     /// for example in the callback function of [Screen::write](crate::Screen::write).
+    /// The viewport must be the same as the one used when rendering the geometry, but it does not need to cover the entire
+    /// render target, so it is possible to render for example split-screen by calling the geometry and light pass once per viewport.
+    /// The lights are uploaded to a uniform buffer, so the same shader program is used for any number of lights.
+    /// Each pass applies up to [MAX_DEFERRED_DIRECTIONAL_LIGHTS], [MAX_DEFERRED_SPOT_LIGHTS] and [MAX_DEFERRED_POINT_LIGHTS]
+    /// lights of each type, any additional lights are applied in additional passes which are added to the result of the first pass.
+    /// The program is compiled the first time it is needed (see also [precompile](Self::precompile)). If the context supports
+    /// [parallel shader compilation](crate::Capabilities::parallel_shader_compile), only the ambient light is applied
+    /// until the program is ready, instead of stalling the render loop.
     ///
     pub fn light_pass(
         &mut self,
@@ -140,7 +400,15 @@ impl PhongDeferredPipeline {
         if self.debug_type != DebugType::NONE {
             if self.debug_effect.is_none() {
                 self.debug_effect = Some(
-                    ImageEffect::new(&self.context, include_str!("shaders/debug.frag")).unwrap(),
+                    ImageEffect::new(
+                        &self.context,
+                        &format!(
+                            "{}\n{}",
+                            include_str!("shaders/normal_encoding.frag"),
+                            include_str!("shaders/debug.frag")
+                        ),
+                    )
+                    .unwrap(),
                 );
             }
             self.debug_effect.as_ref().unwrap().use_uniform_mat4(
@@ -166,50 +434,631 @@ impl PhongDeferredPipeline {
             return Ok(());
         }
 
-        let key = format!(
-            "{},{},{},{}",
-            ambient_light.is_some(),
-            directional_lights.len(),
-            spot_lights.len(),
-            point_lights.len()
-        );
-        if !self.program_map.contains_key(&key) {
-            self.program_map.insert(
-                key.clone(),
-                ImageEffect::new(
+        // The lights which exceed the maximum number of lights in one pass are applied in additional passes,
+        // where the color of the surface is added without the ambient light and the alpha is kept.
+        // usize::div_ceil is not available in the oldest compilers supported by this crate
+        #[allow(clippy::manual_div_ceil)]
+        let passes = [
+            (directional_lights.len(), MAX_DEFERRED_DIRECTIONAL_LIGHTS),
+            (spot_lights.len(), MAX_DEFERRED_SPOT_LIGHTS),
+            (point_lights.len(), MAX_DEFERRED_POINT_LIGHTS),
+        ]
+        .iter()
+        .map(|(count, max)| (count + max - 1) / max)
+        .max()
+        .unwrap()
+        .max(1);
+        let batch = |lights_len: usize, max: usize, pass: usize| {
+            (pass * max).min(lights_len)..((pass + 1) * max).min(lights_len)
+        };
+        for pass in 0..passes {
+            let (ambient_light, render_states) = if pass == 0 {
+                (ambient_light, render_states)
+            } else {
+                (
+                    None,
+                    RenderStates {
+                        write_mask: WriteMask {
+                            red: true,
+                            green: true,
+                            blue: true,
+                            alpha: false,
+                            depth: false,
+                        },
+                        blend: Some(BlendParameters::ADD),
+                        ..render_states
+                    },
+                )
+            };
+            if !self.light_pass_batch(
+                viewport,
+                camera,
+                ambient_light,
+                &directional_lights[batch(
+                    directional_lights.len(),
+                    MAX_DEFERRED_DIRECTIONAL_LIGHTS,
+                    pass,
+                )],
+                &spot_lights[batch(spot_lights.len(), MAX_DEFERRED_SPOT_LIGHTS, pass)],
+                &point_lights[batch(point_lights.len(), MAX_DEFERRED_POINT_LIGHTS, pass)],
+                render_states,
+            )? {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    ///
+    /// Applies the given lights, which must not exceed the maximum number of lights of each type.
+    /// Returns false if only the ambient light is applied, since the light pass program is not ready yet.
+    ///
+    fn light_pass_batch(
+        &mut self,
+        viewport: Viewport,
+        camera: &Camera,
+        ambient_light: Option<&AmbientLight>,
+        directional_lights: &[&DirectionalLight],
+        spot_lights: &[&SpotLight],
+        point_lights: &[&PointLight],
+        render_states: RenderStates,
+    ) -> Result<bool, Error> {
+        if self.light_pass_effect.is_none() {
+            self.light_pass_effect = Some(Self::light_pass_effect(&self.context)?);
+        }
+        if !self.light_pass_effect.as_ref().unwrap().is_ready()? {
+            // Only apply the ambient light until the light pass program is compiled to avoid a stall
+            if self.ambient_light_pass_effect.is_none() {
+                self.ambient_light_pass_effect = Some(ImageEffect::new(
                     &self.context,
                     &crate::phong::phong_fragment_shader(
-                        &include_str!("shaders/deferred_surface.frag"),
-                        directional_lights.len(),
-                        spot_lights.len(),
-                        point_lights.len(),
+                        &Self::light_pass_surface_functionality(),
+                        0,
+                        0,
+                        0,
                     ),
-                )?,
-            );
+                )?);
+            }
+            let effect = self.ambient_light_pass_effect.as_ref().unwrap();
+            crate::phong::bind_lights(effect, ambient_light, &[], &[], &[])?;
+            effect.use_texture(self.geometry_pass_texture(), "gbuffer")?;
+            effect.use_texture(self.geometry_pass_depth_texture_array(), "depthMap")?;
+            effect.apply(render_states, viewport)?;
+            return Ok(false);
+        }
+
+        let mut directional_data =
+            vec![0.0; DIRECTIONAL_LIGHT_SIZE * MAX_DEFERRED_DIRECTIONAL_LIGHTS];
+        for (i, light) in directional_lights.iter().enumerate() {
+            directional_data[i * DIRECTIONAL_LIGHT_SIZE..(i + 1) * DIRECTIONAL_LIGHT_SIZE]
+                .copy_from_slice(light.buffer().data());
+        }
+        let mut spot_data = vec![0.0; SPOT_LIGHT_SIZE * MAX_DEFERRED_SPOT_LIGHTS];
+        for (i, light) in spot_lights.iter().enumerate() {
+            spot_data[i * SPOT_LIGHT_SIZE..(i + 1) * SPOT_LIGHT_SIZE]
+                .copy_from_slice(light.buffer().data());
+        }
+        let mut point_data = vec![0.0; POINT_LIGHT_SIZE * MAX_DEFERRED_POINT_LIGHTS];
+        for (i, light) in point_lights.iter().enumerate() {
+            point_data[i * POINT_LIGHT_SIZE..(i + 1) * POINT_LIGHT_SIZE]
+                .copy_from_slice(light.buffer().data());
+        }
+        self.light_buffer.update(0, &directional_data)?;
+        self.light_buffer.update(1, &spot_data)?;
+        self.light_buffer.update(2, &point_data)?;
+
+        let effect = self.light_pass_effect.as_ref().unwrap();
+        effect.use_uniform_vec3(
+            "ambientColor",
+            &ambient_light
+                .map(|light| light.color * light.intensity)
+                .unwrap_or(vec3(0.0, 0.0, 0.0)),
+        )?;
+        effect.use_uniform_block(&self.light_buffer, "Lights");
+        effect.use_uniform_int("directionalLightCount", &(directional_lights.len() as i32))?;
+        effect.use_uniform_int("spotLightCount", &(spot_lights.len() as i32))?;
+        effect.use_uniform_int("pointLightCount", &(point_lights.len() as i32))?;
+        // All shadow maps must be bound, also those of the unused lights, since they are all sampled with depth comparison
+        for i in 0..MAX_DEFERRED_DIRECTIONAL_LIGHTS {
+            effect.use_texture(
+                directional_lights
+                    .get(i)
+                    .map(|light| light.shadow_map())
+                    .unwrap_or(&self.empty_shadow_map),
+                &format!("directionalShadowMap{}", i),
+            )?;
+        }
+        for i in 0..MAX_DEFERRED_SPOT_LIGHTS {
+            effect.use_texture(
+                spot_lights
+                    .get(i)
+                    .map(|light| light.shadow_map())
+                    .unwrap_or(&self.empty_shadow_map),
+                &format!("spotShadowMap{}", i),
+            )?;
+            effect.use_texture(
+                spot_lights
+                    .get(i)
+                    .map(|light| light.projection_texture())
+                    .unwrap_or(&self.empty_projection_texture),
+                &format!("spotProjectionTexture{}", i),
+            )?;
+        }
+
+        effect.use_texture(self.geometry_pass_texture(), "gbuffer")?;
+        effect.use_texture(self.geometry_pass_depth_texture_array(), "depthMap")?;
+        effect.use_uniform_vec3("eyePosition", camera.position())?;
+        effect.use_uniform_mat4(
+            "viewProjectionInverse",
+            &(camera.projection() * camera.view()).invert().unwrap(),
+        )?;
+        effect.apply(render_states, viewport)?;
+        Ok(true)
+    }
+
+    ///
+    /// Same as [light_pass](Self::light_pass), except that the lit result is rendered into a color texture with the given size,
+    /// which must be the same as the size used in the [geometry pass](Self::geometry_pass), instead of into the current render target.
+    /// The texture has a high dynamic range format, ie. [RGBA16F](crate::Format::RGBA16F), if rendering into float textures is supported
+    /// (see [Capabilities::color_buffer_float](crate::Capabilities::color_buffer_float)) and [RGBA8](crate::Format::RGBA8) otherwise,
+    /// and is cleared to transparent black before the light pass.
+    /// The same texture is used each time this function is called, so it is only allocated the first time and again when the size changes.
+    /// The texture can then be used as input to post effects, for example [screen space reflections](Self::ssr_pass)
+    /// by using [light_pass_texture](Self::light_pass_texture), before it is copied to the screen.
+    /// This function must not be called in a render target render function.
+    ///
+    pub fn light_pass_to_texture(
+        &mut self,
+        width: usize,
+        height: usize,
+        camera: &Camera,
+        ambient_light: Option<&AmbientLight>,
+        directional_lights: &[&DirectionalLight],
+        spot_lights: &[&SpotLight],
+        point_lights: &[&PointLight],
+    ) -> Result<&ColorTargetTexture2D, Error> {
+        let output = match self
+            .light_pass_texture
+            .take()
+            .filter(|t| t.width() == width && t.height() == height)
+        {
+            Some(texture) => texture,
+            None => {
+                let format = if self.context.capabilities().color_buffer_float {
+                    Format::RGBA16F
+                } else {
+                    Format::RGBA8
+                };
+                ColorTargetTexture2D::new(
+                    &self.context,
+                    width,
+                    height,
+                    Interpolation::Linear,
+                    Interpolation::Linear,
+                    None,
+                    Wrapping::ClampToEdge,
+                    Wrapping::ClampToEdge,
+                    format,
+                )?
+            }
         };
-        let effect = self.program_map.get(&key).unwrap();
+        // The texture is taken out of the pipeline while rendering, since the light pass needs a mutable reference to the pipeline
+        let result = RenderTarget::new_color(&self.context, &output).and_then(|render_target| {
+            render_target.write(&ClearState::color(0.0, 0.0, 0.0, 0.0), || {
+                self.light_pass(
+                    Viewport::new_at_origo(width, height),
+                    camera,
+                    ambient_light,
+                    directional_lights,
+                    spot_lights,
+                    point_lights,
+                )
+            })
+        });
+        self.light_pass_texture = Some(output);
+        result?;
+        Ok(self.light_pass_texture.as_ref().unwrap())
+    }
+
+    ///
+    /// Returns the texture written in the last [light_pass_to_texture](Self::light_pass_to_texture) call,
+    /// or `None` if that function has not been called.
+    ///
+    pub fn light_pass_texture(&self) -> Option<&ColorTargetTexture2D> {
+        self.light_pass_texture.as_ref()
+    }
+
+    ///
+    /// Compiles the [light pass](Self::light_pass) shader program up front, for example while loading,
+    /// so that no compilation happens when the first light pass is rendered.
+    /// The same program is used for any number of lights, so this function only needs to be called once.
+    ///
+    pub fn precompile(&mut self) -> Result<(), Error> {
+        if self.light_pass_effect.is_none() {
+            self.light_pass_effect = Some(Self::light_pass_effect(&self.context)?);
+        }
+        self.light_pass_effect.as_ref().unwrap().finish_link()
+    }
+
+    fn light_pass_surface_functionality() -> String {
+        format!(
+            "in vec2 uv;\n{}\n{}",
+            include_str!("shaders/normal_encoding.frag"),
+            include_str!("shaders/deferred_surface.frag")
+        )
+    }
+
+    fn light_pass_effect(context: &Context) -> Result<ImageEffect, Error> {
+        let mut shadow_maps = String::new();
+        let mut directional_and_spot_lights = String::new();
+        for i in 0..MAX_DEFERRED_DIRECTIONAL_LIGHTS {
+            shadow_maps.push_str(&format!(
+                "uniform sampler2DShadow directionalShadowMap{};\n",
+                i
+            ));
+            // Samplers can only be indexed with constant expressions, so the loop is unrolled
+            directional_and_spot_lights.push_str(&format!("
+                    if(directionalLightCount > {}) {{
+                        color.rgb += calculate_directional_light(directionalLights[{}], surface.color.rgb, surface.position, surface.normal,
+                            surface.diffuse_intensity, surface.specular_intensity, surface.specular_power, directionalShadowMap{});
+                    }}", i, i, i));
+        }
+        for i in 0..MAX_DEFERRED_SPOT_LIGHTS {
+            shadow_maps.push_str(&format!(
+                "uniform sampler2DShadow spotShadowMap{};\nuniform sampler2D spotProjectionTexture{};\n",
+                i, i
+            ));
+            directional_and_spot_lights.push_str(&format!("
+                    if(spotLightCount > {}) {{
+                        color.rgb += calculate_spot_light(spotLights[{}], surface.color.rgb, surface.position, surface.normal,
+                            surface.diffuse_intensity, surface.specular_intensity, surface.specular_power, spotShadowMap{}, spotProjectionTexture{});
+                    }}", i, i, i, i));
+        }
+        let fragment_shader = format!(
+            "{}\n{}\n{}",
+            include_str!("shaders/light_shared.frag"),
+            Self::light_pass_surface_functionality(),
+            &format!(
+                "
+                uniform vec3 ambientColor;
+                layout (location = 0) out vec4 color;
 
-        crate::phong::bind_lights(
-            effect,
+                layout (std140) uniform Lights
+                {{
+                    DirectionalLight directionalLights[{}];
+                    SpotLight spotLights[{}];
+                    PointLight pointLights[{}];
+                }};
+                uniform int directionalLightCount;
+                uniform int spotLightCount;
+                uniform int pointLightCount;
+                {}
+
+                void main()
+                {{
+                    Surface surface = get_surface();
+                    color = vec4(get_surface_ambient_occlusion() * ambientColor * surface.color.rgb, surface.color.a);
+                    {}
+                    for(int i = 0; i < pointLightCount; i++)
+                    {{
+                        color.rgb += calculate_point_light(pointLights[i], surface.color.rgb, surface.position, surface.normal,
+                            surface.diffuse_intensity, surface.specular_intensity, surface.specular_power);
+                    }}
+                }}
+                ",
+                MAX_DEFERRED_DIRECTIONAL_LIGHTS,
+                MAX_DEFERRED_SPOT_LIGHTS,
+                MAX_DEFERRED_POINT_LIGHTS,
+                &shadow_maps,
+                &directional_and_spot_lights
+            )
+        );
+        ImageEffect::new_parallel(context, &fragment_shader)
+    }
+
+    ///
+    /// Same as [light_pass](Self::light_pass), except that the attenuated point and spot lights are shaded by rendering a volume
+    /// bounding the part of the scene affected by each light with additive blending, ie. a sphere with the light radius
+    /// (see [PointLight::radius](crate::PointLight::radius)) for point lights and a cone with the light radius as length
+    /// and the outer angle of the light as angle (see [SpotLight::radius](crate::SpotLight::radius)) for spot lights.
+    /// A spot light with an outer angle of [MAX_LIGHT_VOLUME_CONE_ANGLE] degrees or more uses a sphere instead.
+    /// Only the pixels inside the light volumes are therefore shaded by these lights,
+    /// which is a lot faster than shading every pixel when there are many small lights in the scene.
+    /// The depth test uses the depth written by the full screen pass, so the render target must have a depth buffer.
+    /// If the render target also has a stencil buffer, the pixels where the surface is in front of a light volume are masked out
+    /// using the highest bit of the stencil buffer before the volume is shaded, which is reset to zero afterwards.
+    /// In any case, the fragment shader discards the pixels where the surface, reconstructed from the depth written by the full screen pass,
+    /// is outside the sphere bounding the light volume. Without a stencil buffer, this is what rejects the surfaces in front of a light volume,
+    /// so a few more pixels are shaded, but the lights do not affect the surfaces outside their radius or cone.
+    ///
+    pub fn light_pass_with_volumes(
+        &mut self,
+        viewport: Viewport,
+        camera: &Camera,
+        ambient_light: Option<&AmbientLight>,
+        directional_lights: &[&DirectionalLight],
+        spot_lights: &[&SpotLight],
+        point_lights: &[&PointLight],
+    ) -> Result<(), Error> {
+        let (volume_point_lights, full_screen_point_lights): (Vec<&PointLight>, Vec<&PointLight>) =
+            point_lights
+                .iter()
+                .partition(|light| light.radius().is_some());
+        let (volume_spot_lights, full_screen_spot_lights): (Vec<&SpotLight>, Vec<&SpotLight>) =
+            spot_lights
+                .iter()
+                .partition(|light| light.radius().is_some());
+        self.light_pass(
+            viewport,
+            camera,
             ambient_light,
             directional_lights,
-            spot_lights,
-            point_lights,
+            &full_screen_spot_lights,
+            &full_screen_point_lights,
         )?;
+        if self.debug_type != DebugType::NONE
+            || (volume_point_lights.is_empty() && volume_spot_lights.is_empty())
+        {
+            return Ok(());
+        }
 
-        effect.use_texture(self.geometry_pass_texture(), "gbuffer")?;
-        effect.use_texture(self.geometry_pass_depth_texture_array(), "depthMap")?;
-        if !directional_lights.is_empty() || !spot_lights.is_empty() || !point_lights.is_empty() {
-            effect.use_uniform_vec3("eyePosition", &camera.position())?;
-            effect.use_uniform_mat4(
+        if self.light_volume_program.is_none() {
+            self.light_volume_program = Some(Self::light_volume_program(&self.context, 0, 1)?);
+            self.light_volume_spot_program = Some(Self::light_volume_program(&self.context, 1, 0)?);
+            self.light_volume_mesh = Some(Mesh::new(&self.context, &CPUMesh::sphere(1.0))?);
+            self.light_volume_cone_mesh = Some(Mesh::new(
+                &self.context,
+                &light_volume_cone(LIGHT_VOLUME_CONE_SUBDIVISIONS),
+            )?);
+        }
+        for program in [
+            self.light_volume_program.as_ref().unwrap(),
+            self.light_volume_spot_program.as_ref().unwrap(),
+        ]
+        .iter()
+        {
+            program.use_texture(self.geometry_pass_texture(), "gbuffer")?;
+            program.use_texture(self.geometry_pass_depth_texture_array(), "depthMap")?;
+            program.use_uniform_vec3("eyePosition", camera.position())?;
+            program.use_uniform_mat4(
                 "viewProjectionInverse",
                 &(camera.projection() * camera.view()).invert().unwrap(),
             )?;
+            program.use_uniform_vec4(
+                "screenViewport",
+                &vec4(
+                    viewport.x as f32,
+                    viewport.y as f32,
+                    viewport.width as f32,
+                    viewport.height as f32,
+                ),
+            )?;
+        }
+
+        for light in volume_point_lights {
+            let program = self.light_volume_program.as_ref().unwrap();
+            crate::phong::bind_lights(program, None, &[], &[], &[light])?;
+            // The sphere mesh is an icosahedron, so it is scaled to contain the sphere with the light radius.
+            let radius = 1.26 * light.radius().unwrap();
+            self.render_light_volume(
+                program,
+                self.light_volume_mesh.as_ref().unwrap(),
+                viewport,
+                camera,
+                &(Mat4::from_translation(light.position()) * Mat4::from_scale(radius)),
+                (light.position(), radius),
+            )?;
+        }
+        for light in volume_spot_lights {
+            let program = self.light_volume_spot_program.as_ref().unwrap();
+            crate::phong::bind_lights(program, None, &[], &[light], &[])?;
+            let radius = light.radius().unwrap();
+            let (_, outer_angle) = light.cone();
+            if outer_angle < MAX_LIGHT_VOLUME_CONE_ANGLE {
+                // The cone mesh is a polygon around the axis, so it is scaled to contain the circular cone.
+                let base_radius = radius * degrees(outer_angle.max(0.0)).tan()
+                    / (std::f32::consts::PI / LIGHT_VOLUME_CONE_SUBDIVISIONS as f32).cos();
+                self.render_light_volume(
+                    program,
+                    self.light_volume_cone_mesh.as_ref().unwrap(),
+                    viewport,
+                    camera,
+                    &(light_volume_cone_orientation(light.position(), light.direction())
+                        * Mat4::from_nonuniform_scale(base_radius, base_radius, radius)),
+                    (
+                        light.position(),
+                        (radius * radius + base_radius * base_radius).sqrt(),
+                    ),
+                )?;
+            } else {
+                let radius = 1.26 * radius;
+                self.render_light_volume(
+                    program,
+                    self.light_volume_mesh.as_ref().unwrap(),
+                    viewport,
+                    camera,
+                    &(Mat4::from_translation(light.position()) * Mat4::from_scale(radius)),
+                    (light.position(), radius),
+                )?;
+            }
         }
-        effect.apply(render_states, viewport)?;
         Ok(())
     }
 
+    fn light_volume_program(
+        context: &Context,
+        spot_lights: usize,
+        point_lights: usize,
+    ) -> Result<MeshProgram, Error> {
+        MeshProgram::new(
+            context,
+            &crate::phong::phong_fragment_shader(
+                &format!(
+                    "#define KEEP_FRAGMENT_DEPTH\n#define LIGHT_VOLUME\nuniform vec4 screenViewport;\n#define uv ((gl_FragCoord.xy - screenViewport.xy) / screenViewport.zw)\n{}\n{}",
+                    include_str!("shaders/normal_encoding.frag"),
+                    include_str!("shaders/deferred_surface.frag")
+                ),
+                0,
+                spot_lights,
+                point_lights,
+            ),
+        )
+    }
+
+    ///
+    /// Renders the given light volume, which is contained in the given bounding sphere (center and radius), with additive blending.
+    ///
+    fn render_light_volume(
+        &self,
+        program: &MeshProgram,
+        mesh: &Mesh,
+        viewport: Viewport,
+        camera: &Camera,
+        transformation: &Mat4,
+        bounding_sphere: (Vec3, f32),
+    ) -> Result<(), Error> {
+        let (center, radius) = bounding_sphere;
+        program.use_uniform_vec3("lightVolumeCenter", &center)?;
+        program.use_uniform_float("lightVolumeRadius", &radius)?;
+        // The back faces of the light volume which are behind the surface written in the full screen pass are shaded.
+        // This also works when the camera is inside the light volume.
+        let render_states = RenderStates {
+            cull: CullType::Front,
+            depth_test: DepthTestType::GreaterOrEqual,
+            write_mask: WriteMask {
+                red: true,
+                green: true,
+                blue: true,
+                alpha: false,
+                depth: false,
+            },
+            blend: Some(BlendParameters::ADD),
+            ..Default::default()
+        };
+
+        // The front faces are clipped by the near plane when the camera is close to or inside the light volume,
+        // in which case they cannot be used to mask out the surfaces in front of the volume.
+        let depth = -(camera.view() * center.extend(1.0)).z;
+        if depth - radius <= 1.01 * camera.z_near() {
+            return mesh.render(program, render_states, viewport, transformation, camera);
+        }
+
+        // Mark the pixels where the front faces of the light volume are in front of the surface
+        mesh.render(
+            program,
+            RenderStates {
+                cull: CullType::Back,
+                depth_test: DepthTestType::LessOrEqual,
+                write_mask: WriteMask::NONE,
+                stencil: Some(StencilParameters {
+                    reference: LIGHT_VOLUME_STENCIL_BIT,
+                    write_mask: LIGHT_VOLUME_STENCIL_BIT,
+                    pass: StencilOperation::Replace,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            viewport,
+            transformation,
+            camera,
+        )?;
+        // Only shade the marked pixels and reset the mark, the back faces cover the same pixels as the front faces
+        mesh.render(
+            program,
+            RenderStates {
+                stencil: Some(StencilParameters {
+                    test: StencilTestType::Equal,
+                    reference: LIGHT_VOLUME_STENCIL_BIT,
+                    read_mask: LIGHT_VOLUME_STENCIL_BIT,
+                    write_mask: LIGHT_VOLUME_STENCIL_BIT,
+                    depth_fail: StencilOperation::Zero,
+                    pass: StencilOperation::Zero,
+                    ..Default::default()
+                }),
+                ..render_states
+            },
+            viewport,
+            transformation,
+            camera,
+        )
+    }
+
+    ///
+    /// Adds screen space reflections to the given lit color, which is the result of the light pass
+    /// rendered into a color texture with the same size as the geometry pass, for example the [light_pass_texture](Self::light_pass_texture)
+    /// written by [light_pass_to_texture](Self::light_pass_to_texture), and returns the result in a new texture with the same format.
+    /// The reflections are found by marching along the reflection of the view direction at each pixel
+    /// against the depth written in the last [geometry_pass](Self::geometry_pass) call, and the reflected color is sampled from the lit color.
+    /// The reflections are scaled by the specular intensity of each surface and blurred based on its specular power,
+    /// see [SSRConfig] for the parameters.
+    /// This function must not be called in a render target render function, and the camera must be the same as the one used
+    /// when rendering the geometry.
+    ///
+    pub fn ssr_pass(
+        &self,
+        lit_color: &ColorTargetTexture2D,
+        camera: &Camera,
+        config: &SSRConfig,
+    ) -> Result<ColorTargetTexture2D, Error> {
+        if self.ssr_effect.borrow().is_none() {
+            *self.ssr_effect.borrow_mut() = Some(ImageEffect::new(
+                &self.context,
+                &format!(
+                    "{}\n{}",
+                    include_str!("shaders/normal_encoding.frag"),
+                    include_str!("shaders/ssr.frag")
+                ),
+            )?);
+        }
+        let ssr_effect = self.ssr_effect.borrow();
+        let effect = ssr_effect.as_ref().unwrap();
+        let output = ColorTargetTexture2D::new(
+            &self.context,
+            lit_color.width(),
+            lit_color.height(),
+            Interpolation::Linear,
+            Interpolation::Linear,
+            None,
+            Wrapping::ClampToEdge,
+            Wrapping::ClampToEdge,
+            lit_color.format(),
+        )?;
+        RenderTarget::new_color(&self.context, &output)?.write(&ClearState::none(), || {
+            let view_projection = camera.projection() * camera.view();
+            effect.use_uniform_mat4("viewProjection", &view_projection)?;
+            effect.use_uniform_mat4("viewProjectionInverse", &view_projection.invert().unwrap())?;
+            effect.use_uniform_vec3("eyePosition", camera.position())?;
+            effect.use_uniform_int("maxSteps", &(config.max_steps.max(1) as i32))?;
+            effect.use_uniform_float("maxDistance", &config.max_distance)?;
+            effect.use_uniform_float("thickness", &config.thickness)?;
+            effect.use_uniform_float("edgeFade", &config.edge_fade)?;
+            effect.use_uniform_float("maxBlurRadius", &config.max_blur_radius)?;
+            effect
+                .inputs(&[
+                    ("gbuffer", self.geometry_pass_texture()),
+                    ("depthMap", self.geometry_pass_depth_texture_array()),
+                    ("litColor", lit_color),
+                ])
+                .apply(
+                    RenderStates {
+                        cull: CullType::Back,
+                        write_mask: WriteMask::COLOR,
+                        depth_test: DepthTestType::Always,
+                        ..Default::default()
+                    },
+                    Viewport::new_at_origo(lit_color.width(), lit_color.height()),
+                )
+        })?;
+        Ok(output)
+    }
+
+    ///
+    /// Returns the formats and number of layers of the geometry pass textures.
+    ///
+    pub fn gbuffer_config(&self) -> &GBufferConfig {
+        &self.gbuffer_config
+    }
+
     pub fn geometry_pass_texture(&self) -> &dyn Texture {
         self.geometry_pass_texture.as_ref().unwrap()
     }
@@ -217,26 +1066,118 @@ impl PhongDeferredPipeline {
         self.geometry_pass_depth_texture.as_ref().unwrap()
     }
 
-    pub fn geometry_pass_depth_texture(&self) -> DepthTargetTexture2D {
+    ///
+    /// Returns the depth written in the last [geometry_pass](Self::geometry_pass) call as a 2D texture.
+    /// The depth is copied to the same texture each time this function is called, so the returned texture is only
+    /// allocated the first time this function is called and again when the size of the geometry pass changes.
+    ///
+    pub fn geometry_pass_depth_texture(&mut self) -> Result<&DepthTargetTexture2D, Error> {
         let depth_array = self.geometry_pass_depth_texture.as_ref().unwrap();
-        let depth_texture = DepthTargetTexture2D::new(
-            &self.context,
-            depth_array.width(),
-            depth_array.height(),
-            Wrapping::ClampToEdge,
-            Wrapping::ClampToEdge,
-            DepthFormat::Depth32F,
+        let (width, height) = (depth_array.width(), depth_array.height());
+        if self
+            .geometry_pass_depth_texture_2d
+            .as_ref()
+            .map(|t| t.width() != width || t.height() != height)
+            .unwrap_or(true)
+        {
+            self.geometry_pass_depth_texture_2d = Some(DepthTargetTexture2D::new(
+                &self.context,
+                width,
+                height,
+                Wrapping::ClampToEdge,
+                Wrapping::ClampToEdge,
+                self.gbuffer_config.depth_format,
+            )?);
+        }
+        let depth_texture = self.geometry_pass_depth_texture_2d.as_ref().unwrap();
+        RenderTargetArray::new_depth(&self.context, depth_array)?.copy_depth_layer_to(
+            0,
+            depth_texture,
+            Viewport::new_at_origo(width, height),
+        )?;
+        Ok(depth_texture)
+    }
+}
+
+///
+/// Returns a closed cone with the apex at the origin and a base with radius one at one along the z-axis.
+///
+fn light_volume_cone(angle_subdivisions: u32) -> CPUMesh {
+    let mut positions = vec![0.0, 0.0, 0.0, 0.0, 0.0, 1.0];
+    let mut indices = Vec::new();
+    for j in 0..angle_subdivisions {
+        let angle = 2.0 * std::f32::consts::PI * j as f32 / angle_subdivisions as f32;
+        positions.extend_from_slice(&[angle.cos(), angle.sin(), 1.0]);
+        let current = 2 + j;
+        let next = 2 + (j + 1) % angle_subdivisions;
+        indices.extend_from_slice(&[0, next, current, 1, current, next]);
+    }
+    CPUMesh {
+        name: "light volume cone".to_string(),
+        positions,
+        indices: Some(indices),
+        ..Default::default()
+    }
+}
+
+///
+/// Returns the transformation which places the apex of the [light volume cone](light_volume_cone) at the given position
+/// and points its axis in the given direction.
+///
+fn light_volume_cone_orientation(position: Vec3, direction: Vec3) -> Mat4 {
+    let z = direction.normalize();
+    let up = if z.y.abs() < 0.99 {
+        vec3(0.0, 1.0, 0.0)
+    } else {
+        vec3(1.0, 0.0, 0.0)
+    };
+    let x = up.cross(z).normalize();
+    let y = z.cross(x);
+    Mat4::from_cols(
+        x.extend(0.0),
+        y.extend(0.0),
+        z.extend(0.0),
+        position.extend(1.0),
+    )
+}
+
+#[cfg(all(test, feature = "headless", not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::phong::*;
+    use crate::test_util::*;
+
+    #[test]
+    fn light_pass_inside_viewport() {
+        let test_context = TestContext::new();
+        let context = test_context.gl();
+        let mut pipeline = PhongDeferredPipeline::new(&context).unwrap();
+        let mesh =
+            PhongMesh::new(&context, &CPUMesh::square(4.0), &PhongMaterial::default()).unwrap();
+        let camera = Camera::new_perspective(
+            &context,
+            vec3(0.0, 0.0, 1.0),
+            vec3(0.0, 0.0, 0.0),
+            vec3(0.0, 1.0, 0.0),
+            degrees(90.0),
+            1.0,
+            0.1,
+            10.0,
         )
         .unwrap();
-
-        RenderTargetArray::new_depth(&self.context, depth_array)
-            .unwrap()
-            .copy_depth(
-                0,
-                &RenderTarget::new_depth(&self.context, &depth_texture).unwrap(),
-                Viewport::new_at_origo(depth_array.width(), depth_array.height()),
-            )
-            .unwrap();
-        depth_texture
+        let ambient_light = AmbientLight::default();
+        assert_renders_inside_viewport(|width, height, clear_state, viewport| {
+            pipeline.geometry_pass(width, height, || {
+                mesh.render_geometry(
+                    RenderStates::default(),
+                    viewport,
+                    &Mat4::identity(),
+                    &camera,
+                )
+            })?;
+            render_to_cpu(&context, width, height, clear_state, || {
+                pipeline.light_pass(viewport, &camera, Some(&ambient_light), &[], &[], &[])
+            })
+        });
     }
 }
@@ -1,3 +1,4 @@
+use crate::camera::*;
 use crate::core::*;
 use crate::definition::*;
 use crate::math::*;
@@ -27,14 +28,58 @@ impl std::fmt::Display for ColorSource {
 
 ///
 /// A material used for shading an object based on the Phong shading model.
+/// The material is held by a [PhongMesh](crate::PhongMesh) or [PhongInstancedMesh](crate::PhongInstancedMesh)
+/// and all of its parameters are bound in each render call.
+/// Use [new](Self::new) to construct it from a loaded [CPUMaterial](crate::CPUMaterial) or change the [default](Self::default) material.
 ///
 #[derive(Clone)]
 pub struct PhongMaterial {
     pub name: String,
+    ///
+    /// The albedo of the surface, either a fixed color or a texture. Default is white.
+    ///
     pub color_source: ColorSource,
+    ///
+    /// How much of the light is reflected diffusely, between 0 and 1. Default is 0.5.
+    ///
     pub diffuse_intensity: f32,
+    ///
+    /// How much of the light is reflected specularly, between 0 and 1. Default is 0.2.
+    ///
     pub specular_intensity: f32,
+    ///
+    /// The shininess of the surface, ie. the exponent of the specular term, between 0 and 30.
+    /// A higher value gives smaller and sharper highlights. Default is 6.
+    ///
     pub specular_power: f32,
+    ///
+    /// Fragments where the alpha value of the albedo is below this value are discarded,
+    /// which is useful for cutout materials like foliage and fences. This also applies to the depth when rendered with
+    /// [PhongMesh::render_depth](crate::PhongMesh::render_depth), so the shadows match the cutout. Default is 0, ie. nothing is discarded.
+    /// Combine a low cutoff with [alpha to coverage](crate::RenderStates::alpha_to_coverage) on a multisample render target
+    /// to get smooth edges in the forward renderer.
+    ///
+    pub alpha_cutoff: f32,
+    ///
+    /// A texture with the ambient occlusion of the surface in the red channel, where 0 is fully occluded and 1 is not occluded.
+    /// The ambient occlusion is multiplied with the [ambient light](crate::AmbientLight) and does not affect the other lights.
+    /// The texture is sampled using the uv coordinates of the mesh. Default is `None`, ie. no occlusion.
+    ///
+    pub ao_texture: Option<Rc<Texture2D>>,
+    ///
+    /// The color of the Fresnel (rim) term `pow(1 - dot(N, V), fresnel_power) * fresnel_color` which is added to the shaded color
+    /// independently of the lights, where N is the surface normal and V the direction towards the camera.
+    /// This gives a glow at the silhouette of the object, which is useful for stylized looks.
+    /// Only applied when rendering with forward lighting, for example [PhongMesh::render_with_lighting](crate::PhongMesh::render_with_lighting),
+    /// since the gbuffer of the [deferred pipeline](crate::PhongDeferredPipeline) has no room for it.
+    /// Default is black, ie. no Fresnel term.
+    ///
+    pub fresnel_color: Vec3,
+    ///
+    /// The exponent of the Fresnel term (see [fresnel_color](Self::fresnel_color)).
+    /// A higher value gives a thinner rim. Default is 5.
+    ///
+    pub fresnel_power: f32,
 }
 
 impl PhongMaterial {
@@ -61,6 +106,10 @@ impl PhongMaterial {
             diffuse_intensity: cpu_material.diffuse_intensity.unwrap_or(0.5),
             specular_intensity: cpu_material.specular_intensity.unwrap_or(0.2),
             specular_power: cpu_material.specular_power.unwrap_or(6.0),
+            alpha_cutoff: 0.0,
+            ao_texture: None,
+            fresnel_color: vec3(0.0, 0.0, 0.0),
+            fresnel_power: 5.0,
         })
     }
 
@@ -68,7 +117,11 @@ impl PhongMaterial {
         program.use_uniform_float("diffuse_intensity", &self.diffuse_intensity)?;
         program.use_uniform_float("specular_intensity", &self.specular_intensity)?;
         program.use_uniform_float("specular_power", &self.specular_power)?;
+        self.bind_color(program)
+    }
 
+    pub(crate) fn bind_color(&self, program: &Program) -> Result<(), Error> {
+        program.use_uniform_float("alphaCutoff", &self.alpha_cutoff)?;
         match self.color_source {
             ColorSource::Color(ref color) => {
                 program.use_uniform_vec4("surfaceColor", color)?;
@@ -77,10 +130,101 @@ impl PhongMaterial {
                 program.use_texture(texture.as_ref(), "tex")?;
             }
         }
+        if let Some(ref texture) = self.ao_texture {
+            program.use_texture(texture.as_ref(), "aoTexture")?;
+        }
         Ok(())
     }
+
+    ///
+    /// Binds the uniforms of the Fresnel term used by the [forward surface functionality](Self::forward_surface_functionality).
+    ///
+    pub(crate) fn bind_fresnel(&self, program: &Program, camera: &Camera) -> Result<(), Error> {
+        if self.has_fresnel() {
+            program.use_uniform_vec3("fresnelColor", &self.fresnel_color)?;
+            program.use_uniform_float("fresnelPower", &self.fresnel_power)?;
+            program.use_uniform_vec3("eyePosition", camera.position())?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn shader_key(&self) -> String {
+        format!(
+            "{}{}{}",
+            self.color_source,
+            if self.ao_texture.is_some() { "AO" } else { "" },
+            if self.has_fresnel() { "Fresnel" } else { "" }
+        )
+    }
+
+    pub(crate) fn forward_surface_functionality(&self) -> String {
+        format!(
+            "{}\n{}\n{}",
+            match self.color_source {
+                ColorSource::Color(_) => include_str!("shaders/forward_color_surface.frag"),
+                ColorSource::Texture(_) => include_str!("shaders/forward_texture_surface.frag"),
+            },
+            self.ambient_occlusion_functionality(),
+            if self.has_fresnel() {
+                "uniform vec3 fresnelColor;
+                uniform float fresnelPower;
+
+                vec3 get_surface_fresnel()
+                {
+                    vec3 normal = normalize(gl_FrontFacing ? nor : -nor);
+                    float cos_angle = clamp(dot(normal, normalize(eyePosition - pos)), 0.0, 1.0);
+                    return pow(1.0 - cos_angle, fresnelPower) * fresnelColor;
+                }"
+            } else {
+                NO_FRESNEL_FUNCTIONALITY
+            }
+        )
+    }
+
+    fn has_fresnel(&self) -> bool {
+        self.fresnel_color != vec3(0.0, 0.0, 0.0)
+    }
+
+    pub(crate) fn geometry_pass_functionality(&self) -> String {
+        format!(
+            "{}\n{}",
+            match self.color_source {
+                ColorSource::Color(_) => include_str!("shaders/deferred_color.frag"),
+                ColorSource::Texture(_) => include_str!("shaders/deferred_texture.frag"),
+            },
+            self.ambient_occlusion_functionality()
+        )
+    }
+
+    fn ambient_occlusion_functionality(&self) -> String {
+        if self.ao_texture.is_some() {
+            format!(
+                "{}uniform sampler2D aoTexture;
+
+                float get_surface_ambient_occlusion()
+                {{
+                    return texture(aoTexture, vec2(uvs.x, 1.0 - uvs.y)).r;
+                }}",
+                match self.color_source {
+                    ColorSource::Color(_) => "in vec2 uvs;\n",
+                    ColorSource::Texture(_) => "",
+                }
+            )
+        } else {
+            "float get_surface_ambient_occlusion()
+            {
+                return 1.0;
+            }"
+            .to_string()
+        }
+    }
 }
 
+const NO_FRESNEL_FUNCTIONALITY: &str = "vec3 get_surface_fresnel()
+{
+    return vec3(0.0);
+}";
+
 impl Default for PhongMaterial {
     fn default() -> Self {
         Self {
@@ -89,6 +233,10 @@ impl Default for PhongMaterial {
             diffuse_intensity: 0.5,
             specular_intensity: 0.2,
             specular_power: 6.0,
+            alpha_cutoff: 0.0,
+            ao_texture: None,
+            fresnel_color: vec3(0.0, 0.0, 0.0),
+            fresnel_power: 5.0,
         }
     }
 }
@@ -35,6 +35,50 @@ impl PhongInstancedMesh {
         })
     }
 
+    ///
+    /// Render only the depth of the instanced mesh into the current depth render target which is useful for shadow maps or depth pre-pass.
+    /// Same as [InstancedMesh::render_depth](crate::InstancedMesh::render_depth), except that the [alpha cutoff](crate::PhongMaterial::alpha_cutoff)
+    /// of the material is respected, so cutout materials cast the correct shadows.
+    /// Must be called in a render target render function,
+    /// for example in the callback function of [Screen::write](crate::Screen::write).
+    ///
+    pub fn render_depth(
+        &self,
+        render_states: RenderStates,
+        viewport: Viewport,
+        transformation: &Mat4,
+        camera: &Camera,
+    ) -> Result<(), Error> {
+        match self.material.color_source {
+            ColorSource::Texture(ref texture) if self.material.alpha_cutoff > 0.0 => {
+                let program = unsafe {
+                    if PROGRAMS.is_none() {
+                        PROGRAMS = Some(std::collections::HashMap::new());
+                    }
+                    let key = "TextureDepthCutout";
+                    if !PROGRAMS.as_ref().unwrap().contains_key(key) {
+                        PROGRAMS.as_mut().unwrap().insert(
+                            key.to_string(),
+                            InstancedMeshProgram::new(
+                                &self.context,
+                                include_str!("shaders/depth_cutout.frag"),
+                            )?,
+                        );
+                    };
+                    PROGRAMS.as_ref().unwrap().get(key).unwrap()
+                };
+                program.use_texture(texture.as_ref(), "tex")?;
+                program.use_uniform_float("alphaCutoff", &self.material.alpha_cutoff)?;
+                self.mesh
+                    .render(program, render_states, viewport, transformation, camera)
+            }
+            ColorSource::Color(ref color) if color.w < self.material.alpha_cutoff => Ok(()),
+            _ => self
+                .mesh
+                .render_depth(render_states, viewport, transformation, camera),
+        }
+    }
+
     ///
     /// Render the geometry and surface material parameters of the instanced mesh, ie. the first part of a deferred render pass.
     /// Must be called inside the **render** closure given to [PhongDeferredPipeline::geometry_pass](crate::PhongDeferredPipeline::geometry_pass).
@@ -50,36 +94,24 @@ impl PhongInstancedMesh {
             if PROGRAMS.is_none() {
                 PROGRAMS = Some(std::collections::HashMap::new());
             }
-            let key = match self.material.color_source {
-                ColorSource::Color(_) => "ColorDeferred",
-                ColorSource::Texture(_) => "TextureDeferred",
-            };
-            if !PROGRAMS.as_ref().unwrap().contains_key(key) {
+            let key = format!("{}Deferred", self.material.shader_key());
+            if !PROGRAMS.as_ref().unwrap().contains_key(&key) {
                 PROGRAMS.as_mut().unwrap().insert(
-                    key.to_string(),
-                    match self.material.color_source {
-                        ColorSource::Color(_) => InstancedMeshProgram::new(
-                            &self.context,
-                            &format!(
-                                "{}\n{}",
-                                include_str!("shaders/deferred_objects_shared.frag"),
-                                include_str!("shaders/deferred_color.frag")
-                            ),
-                        )?,
-                        ColorSource::Texture(_) => InstancedMeshProgram::new(
-                            &self.context,
-                            &format!(
-                                "{}\n{}",
-                                include_str!("shaders/deferred_objects_shared.frag"),
-                                include_str!("shaders/deferred_texture.frag")
-                            ),
-                        )?,
-                    },
+                    key.clone(),
+                    InstancedMeshProgram::new(
+                        &self.context,
+                        &format!(
+                            "{}\n{}",
+                            gbuffer_output_snippet(),
+                            self.material.geometry_pass_functionality()
+                        ),
+                    )?,
                 );
             };
-            PROGRAMS.as_ref().unwrap().get(key).unwrap()
+            PROGRAMS.as_ref().unwrap().get(&key).unwrap()
         };
         self.material.bind(program)?;
+        bind_gbuffer_uniforms(program, camera, transformation, transformation)?;
         self.mesh
             .render(program, render_states, viewport, transformation, camera)
     }
@@ -102,7 +134,7 @@ impl PhongInstancedMesh {
     ) -> Result<(), Error> {
         let key = format!(
             "{},{},{},{}",
-            self.material.color_source,
+            self.material.shader_key(),
             directional_lights.len(),
             spot_lights.len(),
             point_lights.len()
@@ -112,14 +144,7 @@ impl PhongInstancedMesh {
                 PROGRAMS = Some(std::collections::HashMap::new());
             }
             if !PROGRAMS.as_ref().unwrap().contains_key(&key) {
-                let surface_functionality = match self.material.color_source {
-                    ColorSource::Color(_) => {
-                        include_str!("shaders/forward_color_surface.frag")
-                    }
-                    ColorSource::Texture(_) => {
-                        include_str!("shaders/forward_texture_surface.frag")
-                    }
-                };
+                let surface_functionality = self.material.forward_surface_functionality();
                 let fragment_shader_source = phong_fragment_shader(
                     &surface_functionality,
                     directional_lights.len(),
@@ -143,18 +168,65 @@ impl PhongInstancedMesh {
         )?;
 
         if !directional_lights.is_empty() || !spot_lights.is_empty() || !point_lights.is_empty() {
-            program.use_uniform_vec3("eyePosition", &camera.position())?;
+            program.use_uniform_vec3("eyePosition", camera.position())?;
             self.material.bind(program)?;
         } else {
-            match self.material.color_source {
-                ColorSource::Color(ref color) => {
-                    program.use_uniform_vec4("surfaceColor", color)?;
-                }
-                ColorSource::Texture(ref texture) => {
-                    program.use_texture(texture.as_ref(), "tex")?;
-                }
-            }
+            self.material.bind_color(program)?;
         }
+        self.material.bind_fresnel(program, camera)?;
+        self.mesh
+            .render(program, render_states, viewport, transformation, camera)?;
+        Ok(())
+    }
+
+    ///
+    /// Render the triangle mesh shaded with the given lights and the point lights binned into screen tiles
+    /// in the last [light culling](crate::PhongTiledForwardPipeline::light_culling) step of the given tiled forward pipeline.
+    /// Must be called inside the **render** closure given to [PhongTiledForwardPipeline::shading_pass](crate::PhongTiledForwardPipeline::shading_pass).
+    ///
+    pub fn render_with_tiled_lighting(
+        &self,
+        render_states: RenderStates,
+        viewport: Viewport,
+        transformation: &Mat4,
+        camera: &Camera,
+        pipeline: &PhongTiledForwardPipeline,
+        ambient_light: Option<&AmbientLight>,
+        directional_lights: &[&DirectionalLight],
+        spot_lights: &[&SpotLight],
+    ) -> Result<(), Error> {
+        let key = format!(
+            "{},{},{},tiled",
+            self.material.shader_key(),
+            directional_lights.len(),
+            spot_lights.len()
+        );
+        let program = unsafe {
+            if PROGRAMS.is_none() {
+                PROGRAMS = Some(std::collections::HashMap::new());
+            }
+            if !PROGRAMS.as_ref().unwrap().contains_key(&key) {
+                let surface_functionality = self.material.forward_surface_functionality();
+                let fragment_shader_source = phong_fragment_shader_internal(
+                    &surface_functionality,
+                    directional_lights.len(),
+                    spot_lights.len(),
+                    0,
+                    true,
+                );
+                PROGRAMS.as_mut().unwrap().insert(
+                    key.clone(),
+                    InstancedMeshProgram::new(&self.context, &fragment_shader_source)?,
+                );
+            };
+            PROGRAMS.as_ref().unwrap().get(&key).unwrap()
+        };
+
+        crate::phong::bind_lights(program, ambient_light, directional_lights, spot_lights, &[])?;
+        pipeline.bind(program)?;
+        program.use_uniform_vec3("eyePosition", camera.position())?;
+        self.material.bind(program)?;
+        self.material.bind_fresnel(program, camera)?;
         self.mesh
             .render(program, render_states, viewport, transformation, camera)?;
         Ok(())
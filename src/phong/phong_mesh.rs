@@ -34,9 +34,70 @@ impl PhongMesh {
         })
     }
 
+    ///
+    /// Constructs a Phong mesh from an existing [Mesh], for example a mesh from a [MeshBuffer](crate::MeshBuffer) which shares its buffers with other meshes.
+    /// The [name](Self::name) is empty.
+    ///
+    pub fn new_with_mesh(context: &Context, mesh: Mesh, material: &PhongMaterial) -> Self {
+        unsafe {
+            MESH_COUNT += 1;
+        }
+        Self {
+            context: context.clone(),
+            name: String::new(),
+            mesh,
+            material: material.clone(),
+        }
+    }
+
+    ///
+    /// Render only the depth of the mesh into the current depth render target which is useful for shadow maps or depth pre-pass.
+    /// Same as [Mesh::render_depth](crate::Mesh::render_depth), except that the [alpha cutoff](crate::PhongMaterial::alpha_cutoff)
+    /// of the material is respected, so cutout materials cast the correct shadows.
+    /// Must be called in a render target render function,
+    /// for example in the callback function of [Screen::write](crate::Screen::write).
+    ///
+    pub fn render_depth(
+        &self,
+        render_states: RenderStates,
+        viewport: Viewport,
+        transformation: &Mat4,
+        camera: &Camera,
+    ) -> Result<(), Error> {
+        match self.material.color_source {
+            ColorSource::Texture(ref texture) if self.material.alpha_cutoff > 0.0 => {
+                let program = unsafe {
+                    if PROGRAMS.is_none() {
+                        PROGRAMS = Some(std::collections::HashMap::new());
+                    }
+                    let key = "TextureDepthCutout";
+                    if !PROGRAMS.as_ref().unwrap().contains_key(key) {
+                        PROGRAMS.as_mut().unwrap().insert(
+                            key.to_string(),
+                            MeshProgram::new(
+                                &self.context,
+                                include_str!("shaders/depth_cutout.frag"),
+                            )?,
+                        );
+                    };
+                    PROGRAMS.as_ref().unwrap().get(key).unwrap()
+                };
+                program.use_texture(texture.as_ref(), "tex")?;
+                program.use_uniform_float("alphaCutoff", &self.material.alpha_cutoff)?;
+                self.mesh
+                    .render(program, render_states, viewport, transformation, camera)
+            }
+            ColorSource::Color(ref color) if color.w < self.material.alpha_cutoff => Ok(()),
+            _ => self
+                .mesh
+                .render_depth(render_states, viewport, transformation, camera),
+        }
+    }
+
     ///
     /// Render the geometry and surface material parameters of the mesh, ie. the first part of a deferred render pass.
     /// Must be called inside the **render** closure given to [PhongDeferredPipeline::geometry_pass](crate::PhongDeferredPipeline::geometry_pass).
+    /// The mesh is assumed not to move, use [render_geometry_with_motion](Self::render_geometry_with_motion) for moving meshes.
     ///
     pub fn render_geometry(
         &self,
@@ -44,41 +105,50 @@ impl PhongMesh {
         viewport: Viewport,
         transformation: &Mat4,
         camera: &Camera,
+    ) -> Result<(), Error> {
+        self.render_geometry_with_motion(
+            render_states,
+            viewport,
+            transformation,
+            transformation,
+            camera,
+        )
+    }
+
+    ///
+    /// Same as [render_geometry](Self::render_geometry), except that the transformation of the mesh in the previous frame is also given,
+    /// which is used to calculate the velocity needed by the [motion blur effect](crate::MotionBlurEffect).
+    ///
+    pub fn render_geometry_with_motion(
+        &self,
+        render_states: RenderStates,
+        viewport: Viewport,
+        transformation: &Mat4,
+        previous_transformation: &Mat4,
+        camera: &Camera,
     ) -> Result<(), Error> {
         let program = unsafe {
             if PROGRAMS.is_none() {
                 PROGRAMS = Some(std::collections::HashMap::new());
             }
-            let key = match self.material.color_source {
-                ColorSource::Color(_) => "ColorDeferred",
-                ColorSource::Texture(_) => "TextureDeferred",
-            };
-            if !PROGRAMS.as_ref().unwrap().contains_key(key) {
+            let key = format!("{}Deferred", self.material.shader_key());
+            if !PROGRAMS.as_ref().unwrap().contains_key(&key) {
                 PROGRAMS.as_mut().unwrap().insert(
-                    key.to_string(),
-                    match self.material.color_source {
-                        ColorSource::Color(_) => MeshProgram::new(
-                            &self.context,
-                            &format!(
-                                "{}\n{}",
-                                include_str!("shaders/deferred_objects_shared.frag"),
-                                include_str!("shaders/deferred_color.frag")
-                            ),
-                        )?,
-                        ColorSource::Texture(_) => MeshProgram::new(
-                            &self.context,
-                            &format!(
-                                "{}\n{}",
-                                include_str!("shaders/deferred_objects_shared.frag"),
-                                include_str!("shaders/deferred_texture.frag")
-                            ),
-                        )?,
-                    },
+                    key.clone(),
+                    MeshProgram::new(
+                        &self.context,
+                        &format!(
+                            "{}\n{}",
+                            gbuffer_output_snippet(),
+                            self.material.geometry_pass_functionality()
+                        ),
+                    )?,
                 );
             };
-            PROGRAMS.as_ref().unwrap().get(key).unwrap()
+            PROGRAMS.as_ref().unwrap().get(&key).unwrap()
         };
         self.material.bind(program)?;
+        bind_gbuffer_uniforms(program, camera, transformation, previous_transformation)?;
         self.mesh
             .render(program, render_states, viewport, transformation, camera)
     }
@@ -101,7 +171,7 @@ impl PhongMesh {
     ) -> Result<(), Error> {
         let key = format!(
             "{},{},{},{}",
-            self.material.color_source,
+            self.material.shader_key(),
             directional_lights.len(),
             spot_lights.len(),
             point_lights.len()
@@ -111,14 +181,7 @@ impl PhongMesh {
                 PROGRAMS = Some(std::collections::HashMap::new());
             }
             if !PROGRAMS.as_ref().unwrap().contains_key(&key) {
-                let surface_functionality = match self.material.color_source {
-                    ColorSource::Color(_) => {
-                        include_str!("shaders/forward_color_surface.frag")
-                    }
-                    ColorSource::Texture(_) => {
-                        include_str!("shaders/forward_texture_surface.frag")
-                    }
-                };
+                let surface_functionality = self.material.forward_surface_functionality();
                 let fragment_shader_source = phong_fragment_shader(
                     &surface_functionality,
                     directional_lights.len(),
@@ -142,18 +205,65 @@ impl PhongMesh {
         )?;
 
         if !directional_lights.is_empty() || !spot_lights.is_empty() || !point_lights.is_empty() {
-            program.use_uniform_vec3("eyePosition", &camera.position())?;
+            program.use_uniform_vec3("eyePosition", camera.position())?;
             self.material.bind(program)?;
         } else {
-            match self.material.color_source {
-                ColorSource::Color(ref color) => {
-                    program.use_uniform_vec4("surfaceColor", color)?;
-                }
-                ColorSource::Texture(ref texture) => {
-                    program.use_texture(texture.as_ref(), "tex")?;
-                }
-            }
+            self.material.bind_color(program)?;
         }
+        self.material.bind_fresnel(program, camera)?;
+        self.mesh
+            .render(program, render_states, viewport, transformation, camera)?;
+        Ok(())
+    }
+
+    ///
+    /// Render the triangle mesh shaded with the given lights and the point lights binned into screen tiles
+    /// in the last [light culling](crate::PhongTiledForwardPipeline::light_culling) step of the given tiled forward pipeline.
+    /// Must be called inside the **render** closure given to [PhongTiledForwardPipeline::shading_pass](crate::PhongTiledForwardPipeline::shading_pass).
+    ///
+    pub fn render_with_tiled_lighting(
+        &self,
+        render_states: RenderStates,
+        viewport: Viewport,
+        transformation: &Mat4,
+        camera: &Camera,
+        pipeline: &PhongTiledForwardPipeline,
+        ambient_light: Option<&AmbientLight>,
+        directional_lights: &[&DirectionalLight],
+        spot_lights: &[&SpotLight],
+    ) -> Result<(), Error> {
+        let key = format!(
+            "{},{},{},tiled",
+            self.material.shader_key(),
+            directional_lights.len(),
+            spot_lights.len()
+        );
+        let program = unsafe {
+            if PROGRAMS.is_none() {
+                PROGRAMS = Some(std::collections::HashMap::new());
+            }
+            if !PROGRAMS.as_ref().unwrap().contains_key(&key) {
+                let surface_functionality = self.material.forward_surface_functionality();
+                let fragment_shader_source = phong_fragment_shader_internal(
+                    &surface_functionality,
+                    directional_lights.len(),
+                    spot_lights.len(),
+                    0,
+                    true,
+                );
+                PROGRAMS.as_mut().unwrap().insert(
+                    key.clone(),
+                    MeshProgram::new(&self.context, &fragment_shader_source)?,
+                );
+            };
+            PROGRAMS.as_ref().unwrap().get(&key).unwrap()
+        };
+
+        crate::phong::bind_lights(program, ambient_light, directional_lights, spot_lights, &[])?;
+        pipeline.bind(program)?;
+        program.use_uniform_vec3("eyePosition", camera.position())?;
+        self.material.bind(program)?;
+        self.material.bind_fresnel(program, camera)?;
         self.mesh
             .render(program, render_states, viewport, transformation, camera)?;
         Ok(())
@@ -20,6 +20,11 @@ float linear_depth(float z)
     return (2.0 * n) / (f + n - z * (f - n));
 }
 
+vec2 gbuffer_uv()
+{
+    return gl_FragCoord.xy / vec2(textureSize(depthMap, 0).xy);
+}
+
 vec3 WorldPosFromDepth(float depth, vec2 uv) {
     vec4 clipSpacePosition = vec4(uv * 2.0 - 1.0, depth * 2.0 - 1.0, 1.0);
     vec4 position = viewProjectionInverse * clipSpacePosition;
@@ -30,43 +35,52 @@ void main()
 {
     if(type == 0) // Position
     {
-        float depth = texture(depthMap, vec3(uv, 0)).x;
+        float depth = texture(depthMap, vec3(gbuffer_uv(), 0)).x;
         vec3 pos = WorldPosFromDepth(depth, uv);
         color = vec4(pos, 1.);
     }
     else if(type == 1) // Normal
     {
-        color = vec4(texture(gbuffer, vec3(uv, 1)).xyz, 1.);
+        vec3 normal = decode_normal(texture(gbuffer, vec3(gbuffer_uv(), 1)).xyz);
+        color = vec4(0.5 * normal + 0.5, 1.);
     }
     else if(type == 2) // Color
     {
-        color = vec4(texture(gbuffer, vec3(uv, 0)).xyz, 1.);
+        color = vec4(texture(gbuffer, vec3(gbuffer_uv(), 0)).xyz, 1.);
     }
     else if(type == 3) // Depth
     {
-        float depth = linear_depth(texture(depthMap, vec3(uv, 0)).x);
+        float depth = linear_depth(texture(depthMap, vec3(gbuffer_uv(), 0)).x);
         color = vec4(depth, depth, depth, 1.);
     }
     else if(type == 4) // Diffuse
     {
-        float val = texture(gbuffer, vec3(uv, 0)).w;
+        float w = texture(gbuffer, vec3(gbuffer_uv(), 0)).w;
+        float val = float(int(floor(w*255.0+0.5)) & 15) / 15.0;
         color = vec4(val, val, val, 1.);
     }
     else if(type == 5) // Specular
     {
-        float nw = texture(gbuffer, vec3(uv, 1)).w;
+        float nw = texture(gbuffer, vec3(gbuffer_uv(), 1)).w;
         int t = int(floor(nw*255.0));
         float val = float(t & 15) / 15.0;
         color = vec4(val, val, val, 1.);
     }
     else if(type == 6) // Specular power
     {
-        float nw = texture(gbuffer, vec3(uv, 1)).w;
+        float nw = texture(gbuffer, vec3(gbuffer_uv(), 1)).w;
         int t = int(floor(nw*255.0));
         float val = 2.0 * float((t & 240) >> 4);
         val /= 30.0;
         color = vec4(val, val, val, 1.);
     }
+    else if(type == 7) // Velocity
+    {
+        vec4 v = texture(gbuffer, vec3(gbuffer_uv(), 2));
+        vec2 velocity = vec2(dot(v.rg, vec2(65280.0, 255.0)), dot(v.ba, vec2(65280.0, 255.0)));
+        velocity = velocity.x < 0.5 ? vec2(0.0) : (velocity - 1.0) / 65534.0 * 2.0 - 1.0;
+        color = vec4(0.5 + 10.0 * velocity, 0.5, 1.);
+    }
     else {
         color = vec4(0., 0., 0., 0.);
     }
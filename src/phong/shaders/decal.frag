@@ -0,0 +1,40 @@
+uniform sampler2D normalMap;
+uniform sampler2DArray depthMap;
+uniform sampler2D decalTexture;
+uniform mat4 viewProjectionInverse;
+uniform mat4 decalInverse;
+uniform vec3 eyePosition;
+uniform float depthBias;
+uniform vec3 projectionDirection;
+uniform vec4 screenViewport;
+
+layout (location = 0) out vec4 outColor;
+
+void main()
+{
+    vec2 gbuffer_uv = gl_FragCoord.xy / vec2(textureSize(depthMap, 0).xy);
+    float depth = texture(depthMap, vec3(gbuffer_uv, 0)).r;
+    if(depth > 0.99999)
+    {
+        discard;
+    }
+
+    // Reconstruct the world position of the surface and find its position inside the decal box
+    vec2 viewport_uv = (gl_FragCoord.xy - screenViewport.xy) / screenViewport.zw;
+    vec4 clipSpacePosition = vec4(viewport_uv * 2.0 - 1.0, depth * 2.0 - 1.0, 1.0);
+    vec4 position = viewProjectionInverse * clipSpacePosition;
+    vec3 world = position.xyz / position.w;
+    world += depthBias * normalize(eyePosition - world);
+    vec3 local = (decalInverse * vec4(world, 1.0)).xyz;
+    if(any(greaterThan(abs(local), vec3(0.5))))
+    {
+        discard;
+    }
+
+    // Fade out the decal on surfaces which are almost parallel to the projection direction to avoid stretching
+    vec3 normal = decode_normal(texture(normalMap, gbuffer_uv).xyz);
+    float facing = smoothstep(0.0, 0.3, dot(normal, -projectionDirection));
+
+    vec4 color = texture(decalTexture, vec2(local.x + 0.5, 0.5 - local.z));
+    outColor = vec4(color.rgb, color.a * facing);
+}
@@ -1,14 +1,20 @@
 
 uniform vec4 surfaceColor;
+uniform float alphaCutoff;
 uniform float diffuse_intensity;
 uniform float specular_intensity;
 uniform float specular_power;
 
 in vec3 nor;
-in vec3 pos;
+
+float get_surface_ambient_occlusion();
 
 void main()
 {
+    if(surfaceColor.a < alphaCutoff)
+    {
+        discard;
+    }
 	vec3 normal = normalize(gl_FrontFacing ? nor : -nor);
-	write(normal, surfaceColor.rgb, diffuse_intensity, specular_intensity, specular_power);
+	write(normal, surfaceColor.rgb, diffuse_intensity, specular_intensity, specular_power, get_surface_ambient_occlusion());
 }
\ No newline at end of file
@@ -1,11 +1,38 @@
+uniform mat4 currentViewProjection;
+uniform mat4 previousViewProjection;
+uniform mat4 motionTransformation;
+
+in vec3 pos;
 
 layout (location = 0) out vec4 out_color;
 layout (location = 1) out vec4 out_normal;
+layout (location = 2) out vec4 out_velocity;
 
-void write(vec3 normal, vec3 color, float diffuse_intensity, float specular_intensity, float specular_power)
+// Encodes a value between -1 and 1 into two bytes. Zero is reserved for pixels without any geometry.
+vec2 encode_velocity(float v)
+{
+    float x = 1.0 + floor((clamp(v, -1.0, 1.0) * 0.5 + 0.5) * 65534.0 + 0.5);
+    float hi = floor(x / 256.0);
+    return vec2(hi, x - hi * 256.0) / 255.0;
+}
+
+// The diffuse intensity and the ambient occlusion are packed into four bits each.
+void write(vec3 normal, vec3 color, float diffuse_intensity, float specular_intensity, float specular_power, float ambient_occlusion)
 {
-    out_color = vec4(color, diffuse_intensity);
+    int diffuse = int(floor(clamp(diffuse_intensity, 0.0, 1.0) * 15.0 + 0.5));
+    int occlusion = int(floor(clamp(ambient_occlusion, 0.0, 1.0) * 15.0 + 0.5));
+    out_color = vec4(color, float(occlusion << 4 | diffuse)/255.0);
 	int intensity = int(floor(specular_intensity * 15.0));
 	int power = int(floor(clamp(specular_power, 0.0, 30.0)*0.5));
-    out_normal = vec4(0.5 * normal + 0.5, float(power << 4 | intensity)/255.0);
-}
\ No newline at end of file
+    out_normal = vec4(encode_normal(normal), float(power << 4 | intensity)/255.0);
+
+    vec4 current = currentViewProjection * vec4(pos, 1.0);
+    vec4 previous = previousViewProjection * motionTransformation * vec4(pos, 1.0);
+    vec2 velocity = 0.5 * (current.xy / current.w - previous.xy / previous.w);
+    out_velocity = vec4(encode_velocity(velocity.x), encode_velocity(velocity.y));
+}
+
+void write(vec3 normal, vec3 color, float diffuse_intensity, float specular_intensity, float specular_power)
+{
+    write(normal, color, diffuse_intensity, specular_intensity, specular_power, 1.0);
+}
@@ -3,43 +3,73 @@ uniform sampler2DArray gbuffer;
 uniform sampler2DArray depthMap;
 uniform mat4 viewProjectionInverse;
 
-in vec2 uv;
+// The gbuffer is sampled at the same pixel as the fragment, which works for any viewport
+vec2 gbuffer_uv()
+{
+    return gl_FragCoord.xy / vec2(textureSize(depthMap, 0).xy);
+}
 
-vec3 WorldPosFromDepth(float depth, vec2 uv) {
-    vec4 clipSpacePosition = vec4(uv * 2.0 - 1.0, depth * 2.0 - 1.0, 1.0);
+vec3 WorldPosFromDepth(float depth, vec2 viewport_uv) {
+    vec4 clipSpacePosition = vec4(viewport_uv * 2.0 - 1.0, depth * 2.0 - 1.0, 1.0);
     vec4 position = viewProjectionInverse * clipSpacePosition;
     return position.xyz / position.w;
 }
 
+#ifdef LIGHT_VOLUME
+uniform vec3 lightVolumeCenter;
+uniform float lightVolumeRadius;
+#endif
+
 float get_surface_depth()
 {
-    float depth = texture(depthMap, vec3(uv,0)).r;
+    float depth = texture(depthMap, vec3(gbuffer_uv(), 0)).r;
     if(depth > 0.99999)
     {
         discard;
     }
+#ifndef KEEP_FRAGMENT_DEPTH
     gl_FragDepth = depth;
+#endif
    	return depth;
 }
 
 vec4 get_surface_color()
 {
     get_surface_depth();
-   	return vec4(texture(gbuffer, vec3(uv, 0)).rgb, 1.0);
+   	return vec4(texture(gbuffer, vec3(gbuffer_uv(), 0)).rgb, 1.0);
 }
 
 Surface get_surface()
 {
     float depth = get_surface_depth();
-   	vec4 c = texture(gbuffer, vec3(uv, 0));
+   	vec4 c = texture(gbuffer, vec3(gbuffer_uv(), 0));
     vec4 surface_color = vec4(c.rgb, 1.0);
     vec3 position = WorldPosFromDepth(depth, uv);
-    vec4 n = texture(gbuffer, vec3(uv, 1));
-    vec3 normal = normalize(n.xyz*2.0 - 1.0);
-    float diffuse_intensity = c.w;
+#ifdef LIGHT_VOLUME
+    // Reject the surfaces outside the light volume, also when they are in front of it
+    if(distance(position, lightVolumeCenter) > lightVolumeRadius)
+    {
+        discard;
+    }
+#endif
+    vec4 n = texture(gbuffer, vec3(gbuffer_uv(), 1));
+    vec3 normal = decode_normal(n.xyz);
+    float diffuse_intensity = float(int(floor(c.w*255.0+0.5)) & 15) / 15.0;
     int t = int(floor(n.w*255.0));
     float specular_intensity = float(t & 15) / 15.0;
     float specular_power = 2.0 * float((t & 240) >> 4);
 
     return Surface(position, normal, surface_color, diffuse_intensity, specular_intensity, specular_power);
 }
+
+float get_surface_ambient_occlusion()
+{
+    float w = texture(gbuffer, vec3(gbuffer_uv(), 0)).w;
+    return float((int(floor(w*255.0+0.5)) & 240) >> 4) / 15.0;
+}
+
+// The Fresnel term of the material is not stored in the gbuffer
+vec3 get_surface_fresnel()
+{
+    return vec3(0.0);
+}
@@ -1,16 +1,22 @@
 
 uniform sampler2D tex;
+uniform float alphaCutoff;
 uniform float diffuse_intensity;
 uniform float specular_intensity;
 uniform float specular_power;
 
-in vec3 pos;
 in vec3 nor;
 in vec2 uvs;
 
+float get_surface_ambient_occlusion();
+
 void main()
 {
+    vec4 color = texture(tex, vec2(uvs.x, 1.0 - uvs.y));
+    if(color.a < alphaCutoff)
+    {
+        discard;
+    }
 	vec3 normal = normalize(gl_FrontFacing ? nor : -nor);
-    vec3 color = texture(tex, vec2(uvs.x, 1.0 - uvs.y)).rgb;
-	write(normal, color, diffuse_intensity, specular_intensity, specular_power);
+	write(normal, color.rgb, diffuse_intensity, specular_intensity, specular_power, get_surface_ambient_occlusion());
 }
\ No newline at end of file
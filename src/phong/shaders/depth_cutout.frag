@@ -0,0 +1,12 @@
+uniform sampler2D tex;
+uniform float alphaCutoff;
+
+in vec2 uvs;
+
+void main()
+{
+    if(texture(tex, vec2(uvs.x, 1.0 - uvs.y)).a < alphaCutoff)
+    {
+        discard;
+    }
+}
@@ -3,12 +3,17 @@ uniform float diffuse_intensity;
 uniform float specular_intensity;
 uniform float specular_power;
 uniform vec4 surfaceColor;
+uniform float alphaCutoff;
 
 in vec3 pos;
 in vec3 nor;
 
 vec4 get_surface_color()
 {
+    if(surfaceColor.a < alphaCutoff)
+    {
+        discard;
+    }
     return surfaceColor;
 }
 
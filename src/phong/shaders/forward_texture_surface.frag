@@ -3,6 +3,7 @@ uniform float diffuse_intensity;
 uniform float specular_intensity;
 uniform float specular_power;
 uniform sampler2D tex;
+uniform float alphaCutoff;
 
 in vec3 pos;
 in vec3 nor;
@@ -10,7 +11,12 @@ in vec2 uvs;
 
 vec4 get_surface_color()
 {
-    return texture(tex, vec2(uvs.x, 1.0 - uvs.y));
+    vec4 color = texture(tex, vec2(uvs.x, 1.0 - uvs.y));
+    if(color.a < alphaCutoff)
+    {
+        discard;
+    }
+    return color;
 }
 
 Surface get_surface()
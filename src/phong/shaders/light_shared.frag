@@ -44,12 +44,16 @@ struct PointLight
 struct SpotLight
 {
     BaseLight base;
-    Attenuation attenuation;
+    float attenuationConstant;
+    float attenuationLinear;
+    float attenuationExp;
+    float innerCutoff;
     vec3 position;
     float cutoff;
     vec3 direction;
     float shadowEnabled;
     mat4 shadowMVP;
+    mat4 projectionMVP;
 };
 
 vec3 calculate_light(BaseLight light, vec3 lightDirection, vec3 position, vec3 normal,
@@ -94,15 +98,18 @@ vec3 calculate_attenuated_light(BaseLight light, Attenuation attenuation, vec3 l
     return color / max(1.0, att);
 }
 
-float is_visible(sampler2D shadowMap, vec4 shadow_coord, vec2 offset)
+float is_visible(sampler2DShadow shadowMap, vec4 shadow_coord, vec2 offset)
 {
     vec2 uv = (shadow_coord.xy + offset)/shadow_coord.w;
-    float true_distance = (shadow_coord.z - 0.005)/shadow_coord.w;
-    float shadow_cast_distance = texture(shadowMap, uv).x;
-    return uv.x < 0.0 || uv.x > 1.0 || uv.y < 0.0 || uv.y > 1.0 || shadow_cast_distance > true_distance ? 1.0 : 0.0;
+    if(uv.x < 0.0 || uv.x > 1.0 || uv.y < 0.0 || uv.y > 1.0)
+    {
+        return 1.0;
+    }
+    // The comparison with the biased depth is done, and filtered, by the hardware
+    return textureProj(shadowMap, vec4(shadow_coord.xy + offset, shadow_coord.z - 0.005, shadow_coord.w));
 }
 
-float calculate_shadow(sampler2D shadowMap, mat4 shadowMVP, vec3 position)
+float calculate_shadow(sampler2DShadow shadowMap, mat4 shadowMVP, vec3 position)
 {
     if(shadowMVP[3][3] < 0.1) // Shadow disabled
     {
@@ -124,7 +131,7 @@ float calculate_shadow(sampler2D shadowMap, mat4 shadowMVP, vec3 position)
 }
 
 vec3 calculate_directional_light(DirectionalLight directionalLight, vec3 surface_color, vec3 position, vec3 normal,
-    float diffuse_intensity, float specular_intensity, float specular_power, sampler2D shadowMap)
+    float diffuse_intensity, float specular_intensity, float specular_power, sampler2DShadow shadowMap)
 {
     vec3 light = calculate_light(directionalLight.base, directionalLight.direction, position, normal,
         diffuse_intensity, specular_intensity, specular_power);
@@ -142,19 +149,25 @@ vec3 calculate_point_light(PointLight pointLight, vec3 surface_color, vec3 posit
 }
 
 vec3 calculate_spot_light(SpotLight spotLight, vec3 surface_color, vec3 position, vec3 normal,
-    float diffuse_intensity, float specular_intensity, float specular_power, sampler2D shadowMap)
+    float diffuse_intensity, float specular_intensity, float specular_power, sampler2DShadow shadowMap, sampler2D projectionTexture)
 {
     vec3 light_direction = normalize(position - spotLight.position);
     float angle = acos(dot(light_direction, normalize(spotLight.direction)));
     float cutoff = 3.14 * spotLight.cutoff / 180.0;
+    float innerCutoff = 3.14 * spotLight.innerCutoff / 180.0;
 
     vec3 light = vec3(0.0);
     if (angle < cutoff) {
-        light = calculate_attenuated_light(spotLight.base, spotLight.attenuation, spotLight.position, position, normal,
-            diffuse_intensity, specular_intensity, specular_power) * (1.0 - smoothstep(0.75 * cutoff, cutoff, angle));
+        Attenuation attenuation = Attenuation(spotLight.attenuationConstant, spotLight.attenuationLinear, spotLight.attenuationExp, 0.0);
+        float falloff = innerCutoff < cutoff ? 1.0 - smoothstep(innerCutoff, cutoff, angle) : 1.0;
+        light = calculate_attenuated_light(spotLight.base, attenuation, spotLight.position, position, normal,
+            diffuse_intensity, specular_intensity, specular_power) * falloff;
         if(spotLight.shadowEnabled > 0.5) {
             light *= calculate_shadow(shadowMap, spotLight.shadowMVP, position);
         }
+        vec4 projection_coord = spotLight.projectionMVP * vec4(position, 1.0);
+        vec2 projection_uv = projection_coord.xy / projection_coord.w;
+        light *= texture(projectionTexture, vec2(projection_uv.x, 1.0 - projection_uv.y)).rgb;
     }
     return surface_color * light;
 }
\ No newline at end of file
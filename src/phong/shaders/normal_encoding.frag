@@ -0,0 +1,31 @@
+
+// Normals are stored in the gbuffer using octahedral encoding with 12 bits per component packed into three bytes,
+// which gives a much higher precision than storing each of the three components in one byte.
+vec2 sign_not_zero(vec2 v)
+{
+    return vec2(v.x >= 0.0 ? 1.0 : -1.0, v.y >= 0.0 ? 1.0 : -1.0);
+}
+
+vec3 encode_normal(vec3 normal)
+{
+    vec3 n = normal / (abs(normal.x) + abs(normal.y) + abs(normal.z));
+    vec2 e = n.z >= 0.0 ? n.xy : (1.0 - abs(n.yx)) * sign_not_zero(n.xy);
+    e = floor((0.5 * e + 0.5) * 4095.0 + 0.5);
+    vec2 hi = floor(e / 16.0);
+    vec2 lo = e - hi * 16.0;
+    return vec3(hi, lo.x * 16.0 + lo.y) / 255.0;
+}
+
+vec3 decode_normal(vec3 encoded)
+{
+    vec3 bytes = floor(encoded * 255.0 + 0.5);
+    float lo_x = floor(bytes.z / 16.0);
+    float lo_y = bytes.z - lo_x * 16.0;
+    vec2 e = vec2(bytes.x * 16.0 + lo_x, bytes.y * 16.0 + lo_y) / 4095.0 * 2.0 - 1.0;
+    vec3 n = vec3(e, 1.0 - abs(e.x) - abs(e.y));
+    if(n.z < 0.0)
+    {
+        n.xy = (1.0 - abs(n.yx)) * sign_not_zero(n.xy);
+    }
+    return normalize(n);
+}
@@ -0,0 +1,119 @@
+uniform sampler2DArray gbuffer;
+uniform sampler2DArray depthMap;
+uniform sampler2D litColor;
+uniform mat4 viewProjection;
+uniform mat4 viewProjectionInverse;
+uniform vec3 eyePosition;
+uniform int maxSteps;
+uniform float thickness;
+uniform float maxDistance;
+uniform float edgeFade;
+uniform float maxBlurRadius;
+
+layout (location = 0) out vec4 outColor;
+
+vec3 world_position(vec2 uv)
+{
+    float depth = texture(depthMap, vec3(uv, 0)).r;
+    vec4 position = viewProjectionInverse * vec4(uv * 2.0 - 1.0, depth * 2.0 - 1.0, 1.0);
+    return position.xyz / position.w;
+}
+
+// Returns the screen uv coordinate of the given world position or a negative value if it is outside the screen
+vec2 screen_uv(vec3 position)
+{
+    vec4 clip = viewProjection * vec4(position, 1.0);
+    if(clip.w <= 0.0)
+    {
+        return vec2(-1.0);
+    }
+    vec2 uv = 0.5 * clip.xy / clip.w + 0.5;
+    return any(lessThan(uv, vec2(0.0))) || any(greaterThan(uv, vec2(1.0))) ? vec2(-1.0) : uv;
+}
+
+// Returns how far the ray position is behind the surface seen in the same pixel
+float depth_difference(vec3 position, vec2 uv)
+{
+    return distance(position, eyePosition) - distance(world_position(uv), eyePosition);
+}
+
+void main()
+{
+    vec2 uv = gl_FragCoord.xy / vec2(textureSize(depthMap, 0).xy);
+    vec4 color = texture(litColor, uv);
+    outColor = color;
+    float depth = texture(depthMap, vec3(uv, 0)).r;
+    vec4 n = texture(gbuffer, vec3(uv, 1));
+    int t = int(floor(n.w*255.0));
+    float specular_intensity = float(t & 15) / 15.0;
+    if(depth > 0.99999 || specular_intensity <= 0.0)
+    {
+        return;
+    }
+    float specular_power = 2.0 * float((t & 240) >> 4);
+    vec3 position = world_position(uv);
+    vec3 normal = decode_normal(n.xyz);
+    vec3 reflect_dir = reflect(normalize(position - eyePosition), normal);
+
+    // March along the reflected ray until it is behind the surface in the depth buffer, but not more than the thickness
+    float step_length = maxDistance / float(maxSteps);
+    float hit = -1.0;
+    vec2 hit_uv;
+    for(int i = 1; i <= maxSteps; i++)
+    {
+        vec3 p = position + float(i) * step_length * reflect_dir;
+        vec2 p_uv = screen_uv(p);
+        if(p_uv.x < 0.0)
+        {
+            break;
+        }
+        float difference = depth_difference(p, p_uv);
+        if(difference > 0.0)
+        {
+            if(difference < thickness + step_length)
+            {
+                // Refine the hit by a binary search between the last two steps
+                float a = float(i - 1);
+                float b = float(i);
+                for(int j = 0; j < 4; j++)
+                {
+                    float m = 0.5 * (a + b);
+                    vec2 m_uv = screen_uv(position + m * step_length * reflect_dir);
+                    if(m_uv.x >= 0.0 && depth_difference(position + m * step_length * reflect_dir, m_uv) > 0.0)
+                    {
+                        b = m;
+                    }
+                    else
+                    {
+                        a = m;
+                    }
+                }
+                hit_uv = screen_uv(position + b * step_length * reflect_dir);
+                if(hit_uv.x >= 0.0 && abs(depth_difference(position + b * step_length * reflect_dir, hit_uv)) < thickness)
+                {
+                    hit = b / float(maxSteps);
+                }
+            }
+            break;
+        }
+    }
+    if(hit < 0.0)
+    {
+        return;
+    }
+
+    // Fade out the reflection near the edges of the screen and at the maximum distance
+    vec2 edge_distance = min(hit_uv, 1.0 - hit_uv);
+    float fade = smoothstep(0.0, edgeFade, min(edge_distance.x, edge_distance.y)) * (1.0 - hit * hit);
+
+    // Blur the reflection of rough surfaces, ie. surfaces with a low specular power
+    float roughness = 1.0 - clamp(specular_power / 30.0, 0.0, 1.0);
+    vec2 radius = roughness * maxBlurRadius / vec2(textureSize(litColor, 0));
+    vec3 reflection = 0.2 * (texture(litColor, hit_uv).rgb
+        + texture(litColor, hit_uv + vec2(radius.x, radius.y)).rgb
+        + texture(litColor, hit_uv + vec2(-radius.x, radius.y)).rgb
+        + texture(litColor, hit_uv + vec2(radius.x, -radius.y)).rgb
+        + texture(litColor, hit_uv + vec2(-radius.x, -radius.y)).rgb);
+
+    outColor = vec4(color.rgb + specular_intensity * fade * reflection, color.a);
+}
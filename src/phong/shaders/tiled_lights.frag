@@ -0,0 +1,36 @@
+
+uniform sampler2D tiledLights;
+uniform sampler2D tiledLightTiles;
+uniform sampler2D tiledLightIndices;
+uniform vec2 tileOrigin;
+uniform float tileSize;
+
+// Each light is stored in three texels; the position and radius, the color and intensity and the attenuation.
+PointLight get_tiled_point_light(int index)
+{
+    ivec2 texel = ivec2(3 * (index % 256), index / 256);
+    vec4 color = texelFetch(tiledLights, texel + ivec2(1, 0), 0);
+    vec4 attenuation = texelFetch(tiledLights, texel + ivec2(2, 0), 0);
+    return PointLight(BaseLight(color.rgb, color.a), Attenuation(attenuation.x, attenuation.y, attenuation.z, 0.0),
+        texelFetch(tiledLights, texel, 0).xyz, 0.0);
+}
+
+vec3 calculate_tiled_point_lights(vec3 surface_color, vec3 position, vec3 normal,
+    float diffuse_intensity, float specular_intensity, float specular_power)
+{
+    ivec2 tile = ivec2((gl_FragCoord.xy - tileOrigin) / tileSize);
+    tile = clamp(tile, ivec2(0), textureSize(tiledLightTiles, 0) - 1);
+    vec4 tileLights = texelFetch(tiledLightTiles, tile, 0);
+    int offset = int(tileLights.x);
+    int count = int(tileLights.y);
+
+    vec3 color = vec3(0.0);
+    for (int i = 0; i < count; i++)
+    {
+        int index = offset + i;
+        int light = int(texelFetch(tiledLightIndices, ivec2(index % 1024, index / 1024), 0).r);
+        color += calculate_point_light(get_tiled_point_light(light), surface_color, position, normal,
+            diffuse_intensity, specular_intensity, specular_power);
+    }
+    return color;
+}
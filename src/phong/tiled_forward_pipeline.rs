@@ -0,0 +1,375 @@
+use crate::camera::*;
+use crate::core::*;
+use crate::definition::*;
+use crate::effect::*;
+use crate::light::*;
+use crate::math::*;
+
+// The number of lights in each row of the light texture and the number of indices in each row of the index texture.
+const LIGHTS_PER_ROW: usize = 256;
+const INDICES_PER_ROW: usize = 1024;
+
+///
+/// Tiled forward (Forward+) pipeline based on the Phong reflection model supporting hundreds of point lights.
+/// Supports colored, transparent, textured and instanced meshes.
+///
+/// Each frame consists of three steps:
+/// - A [depth pre-pass](Self::depth_pass) where the depth of the meshes is rendered using for example [PhongMesh::render_depth](crate::PhongMesh::render_depth).
+/// - A [light culling](Self::light_culling) step where the point lights are binned into screen tiles of [tile_size](Self::tile_size) pixels,
+///   based on the volume affected by each light (see [PointLight::radius](crate::PointLight::radius)).
+/// - A [shading pass](Self::shading_pass) where the meshes are shaded by calling for example [PhongMesh::render_with_tiled_lighting](crate::PhongMesh::render_with_tiled_lighting)
+///   which only iterates the point lights in the tile of each pixel.
+///
+/// In contrast to the [deferred pipeline](crate::PhongDeferredPipeline) and [forward rendering](crate::PhongMesh::render_with_lighting),
+/// the shader programs do not depend on the number of point lights, so changing the number of point lights does not compile new programs.
+/// Directional and spot lights are applied to all pixels like in forward rendering.
+///
+pub struct PhongTiledForwardPipeline {
+    context: Context,
+    ///
+    /// The width and height in pixels of the screen tiles which the point lights are binned into in [light_culling](Self::light_culling).
+    /// Smaller tiles give more precise culling but more work on the CPU, the default is 16.
+    /// A change takes effect in the next light culling, the shading pass uses the tile size of the last light culling.
+    ///
+    pub tile_size: usize,
+    depth_texture: Option<DepthTargetTexture2D>,
+    light_texture: Option<Texture2D>,
+    tile_texture: Option<Texture2D>,
+    index_texture: Option<Texture2D>,
+    tile_origin: Vec2,
+    culled_tile_size: usize,
+    depth_copy_effect: Option<ImageEffect>,
+}
+
+impl PhongTiledForwardPipeline {
+    pub fn new(context: &Context) -> Result<Self, Error> {
+        Self::new_with_tile_size(context, 16)
+    }
+
+    ///
+    /// Constructor where the width and height in pixels of the screen tiles are given, see [tile_size](Self::tile_size).
+    ///
+    pub fn new_with_tile_size(context: &Context, tile_size: usize) -> Result<Self, Error> {
+        Ok(Self {
+            context: context.clone(),
+            tile_size,
+            depth_texture: None,
+            light_texture: None,
+            tile_texture: None,
+            index_texture: None,
+            tile_origin: vec2(0.0, 0.0),
+            culled_tile_size: tile_size.max(1),
+            depth_copy_effect: None,
+        })
+    }
+
+    ///
+    /// Render the depth of the meshes, for example by calling [PhongMesh::render_depth](crate::PhongMesh::render_depth), inside the **render** closure.
+    /// The depth is used in the [shading pass](Self::shading_pass), so that each pixel is only shaded once.
+    /// This function must not be called in a render target render function.
+    ///
+    pub fn depth_pass<F: FnOnce() -> Result<(), Error>>(
+        &mut self,
+        width: usize,
+        height: usize,
+        render: F,
+    ) -> Result<(), Error> {
+        if self
+            .depth_texture
+            .as_ref()
+            .map(|t| t.width() != width || t.height() != height)
+            .unwrap_or(true)
+        {
+            self.depth_texture = Some(DepthTargetTexture2D::new(
+                &self.context,
+                width,
+                height,
+                Wrapping::ClampToEdge,
+                Wrapping::ClampToEdge,
+                DepthFormat::Depth32F,
+            )?);
+        }
+        RenderTarget::new_depth(&self.context, self.depth_texture.as_ref().unwrap())?
+            .write(&ClearState::depth(1.0), render)?;
+        Ok(())
+    }
+
+    ///
+    /// Bins the given point lights into the screen tiles of the given viewport, which must be the same as the one used in the [shading pass](Self::shading_pass).
+    /// A light is added to all tiles overlapped by the projection of the box bounding the volume affected by the light,
+    /// or to all tiles if the light is not attenuated by distance and therefore affects everything.
+    ///
+    // usize::div_ceil is not available in the oldest compilers supported by this crate
+    #[allow(clippy::manual_div_ceil)]
+    pub fn light_culling(
+        &mut self,
+        viewport: Viewport,
+        camera: &Camera,
+        point_lights: &[&PointLight],
+    ) -> Result<(), Error> {
+        let tile_size = self.tile_size.max(1);
+        let tiles_x = ((viewport.width + tile_size - 1) / tile_size).max(1);
+        let tiles_y = ((viewport.height + tile_size - 1) / tile_size).max(1);
+        let view_projection = camera.projection() * camera.view();
+
+        let mut tiles = vec![Vec::new(); tiles_x * tiles_y];
+        for (index, light) in point_lights.iter().enumerate() {
+            let tile_range = if let Some(radius) = light.radius() {
+                let range = Self::tile_range(
+                    &view_projection,
+                    light.position(),
+                    radius,
+                    viewport,
+                    tile_size,
+                    tiles_x,
+                    tiles_y,
+                );
+                if let Some(range) = range {
+                    range
+                } else {
+                    continue;
+                }
+            } else {
+                (0, tiles_x - 1, 0, tiles_y - 1)
+            };
+            let (min_x, max_x, min_y, max_y) = tile_range;
+            for y in min_y..=max_y {
+                for x in min_x..=max_x {
+                    tiles[y * tiles_x + x].push(index as f32);
+                }
+            }
+        }
+
+        let mut tile_data = Vec::with_capacity(4 * tiles.len());
+        let mut index_data = Vec::new();
+        for tile in tiles.iter() {
+            tile_data.extend_from_slice(&[index_data.len() as f32, tile.len() as f32, 0.0, 0.0]);
+            index_data.extend_from_slice(tile);
+        }
+        Self::update_data_texture(
+            &self.context,
+            &mut self.tile_texture,
+            tile_data,
+            tiles_x,
+            tiles_y,
+            Format::RGBA32F,
+        )?;
+
+        let index_rows = (index_data.len() + INDICES_PER_ROW - 1) / INDICES_PER_ROW;
+        let index_width = index_data.len().clamp(1, INDICES_PER_ROW);
+        let index_height = index_rows.max(1);
+        index_data.resize(index_width * index_height, 0.0);
+        Self::update_data_texture(
+            &self.context,
+            &mut self.index_texture,
+            index_data,
+            index_width,
+            index_height,
+            Format::R32F,
+        )?;
+
+        let light_rows = (point_lights.len() + LIGHTS_PER_ROW - 1) / LIGHTS_PER_ROW;
+        let light_width = 3 * point_lights.len().clamp(1, LIGHTS_PER_ROW);
+        let light_height = light_rows.max(1);
+        let mut light_data = Vec::with_capacity(4 * light_width * light_height);
+        for light in point_lights.iter() {
+            let position = light.position();
+            let color = light.color();
+            let (constant, linear, exponential) = light.attenuation();
+            light_data.extend_from_slice(&[
+                position.x,
+                position.y,
+                position.z,
+                light.radius().unwrap_or(f32::INFINITY),
+            ]);
+            light_data.extend_from_slice(&[color.x, color.y, color.z, light.intensity()]);
+            light_data.extend_from_slice(&[constant, linear, exponential, 0.0]);
+        }
+        light_data.resize(4 * light_width * light_height, 0.0);
+        Self::update_data_texture(
+            &self.context,
+            &mut self.light_texture,
+            light_data,
+            light_width,
+            light_height,
+            Format::RGBA32F,
+        )?;
+
+        self.tile_origin = vec2(viewport.x as f32, viewport.y as f32);
+        self.culled_tile_size = tile_size;
+        Ok(())
+    }
+
+    ///
+    /// Copies the depth written in the last [depth_pass](Self::depth_pass) call to the current render target and then
+    /// renders the meshes shaded with the point lights given in the last [light_culling](Self::light_culling) call
+    /// by calling for example [PhongMesh::render_with_tiled_lighting](crate::PhongMesh::render_with_tiled_lighting) inside the **render** closure.
+    /// Use the [LessOrEqual](crate::DepthTestType::LessOrEqual) depth test when rendering the meshes, so that
+    /// only the visible surface is shaded.
+    /// Must be called in a render target render function, for example in the callback function of [Screen::write](crate::Screen::write).
+    ///
+    /// # Errors
+    /// Will return an error if no depth pass has been made yet.
+    ///
+    pub fn shading_pass<F: FnOnce() -> Result<(), Error>>(
+        &mut self,
+        viewport: Viewport,
+        render: F,
+    ) -> Result<(), Error> {
+        let depth_texture =
+            self.depth_texture
+                .as_ref()
+                .ok_or_else(|| Error::FailedToCopyFromRenderTarget {
+                    message: "Cannot copy the depth before a depth pass has been made.".to_owned(),
+                })?;
+        if self.depth_copy_effect.is_none() {
+            self.depth_copy_effect = Some(ImageEffect::new(
+                &self.context,
+                "
+                uniform sampler2D depthMap;
+                in vec2 uv;
+                void main()
+                {
+                    gl_FragDepth = texture(depthMap, uv).r;
+                }",
+            )?);
+        }
+        let effect = self.depth_copy_effect.as_ref().unwrap();
+        effect.use_texture(depth_texture, "depthMap")?;
+        effect.apply(
+            RenderStates {
+                cull: CullType::Back,
+                depth_test: DepthTestType::Always,
+                write_mask: WriteMask::DEPTH,
+                ..Default::default()
+            },
+            viewport,
+        )?;
+        render()
+    }
+
+    ///
+    /// Returns the depth written in the last [depth_pass](Self::depth_pass) call or `None` if no depth pass has been made yet.
+    ///
+    pub fn depth_texture(&self) -> Option<&dyn Texture> {
+        self.depth_texture.as_ref().map(|t| t as &dyn Texture)
+    }
+
+    pub(crate) fn bind(&self, program: &Program) -> Result<(), Error> {
+        let (light_texture, tile_texture, index_texture) = match (
+            self.light_texture.as_ref(),
+            self.tile_texture.as_ref(),
+            self.index_texture.as_ref(),
+        ) {
+            (Some(light_texture), Some(tile_texture), Some(index_texture)) => {
+                (light_texture, tile_texture, index_texture)
+            }
+            _ => Err(Error::FailedToBindTexture {
+                message:
+                    "Cannot use the tiled point lights before the light culling has been made."
+                        .to_owned(),
+            })?,
+        };
+        program.use_texture(light_texture, "tiledLights")?;
+        program.use_texture(tile_texture, "tiledLightTiles")?;
+        program.use_texture(index_texture, "tiledLightIndices")?;
+        program.use_uniform_vec2("tileOrigin", &self.tile_origin)?;
+        // The tile size of the light culling, since the tile size can be changed before the shading pass
+        program.use_uniform_float("tileSize", &(self.culled_tile_size as f32))?;
+        Ok(())
+    }
+
+    fn tile_range(
+        view_projection: &Mat4,
+        position: Vec3,
+        radius: f32,
+        viewport: Viewport,
+        tile_size: usize,
+        tiles_x: usize,
+        tiles_y: usize,
+    ) -> Option<(usize, usize, usize, usize)> {
+        let mut min = vec2(f32::MAX, f32::MAX);
+        let mut max = vec2(f32::MIN, f32::MIN);
+        let mut corners_behind = 0;
+        for i in 0..8 {
+            let corner = position
+                + radius
+                    * vec3(
+                        if i & 1 == 0 { -1.0 } else { 1.0 },
+                        if i & 2 == 0 { -1.0 } else { 1.0 },
+                        if i & 4 == 0 { -1.0 } else { 1.0 },
+                    );
+            let clip = view_projection * corner.extend(1.0);
+            if clip.w <= 0.0001 {
+                corners_behind += 1;
+                continue;
+            }
+            let screen = vec2(
+                (0.5 * clip.x / clip.w + 0.5) * viewport.width as f32,
+                (0.5 * clip.y / clip.w + 0.5) * viewport.height as f32,
+            );
+            min = vec2(min.x.min(screen.x), min.y.min(screen.y));
+            max = vec2(max.x.max(screen.x), max.y.max(screen.y));
+        }
+        if corners_behind == 8 {
+            // The light volume is behind the camera
+            return None;
+        }
+        if corners_behind > 0 {
+            // The light volume intersects the plane of the camera, so the projection is unbounded
+            return Some((0, tiles_x - 1, 0, tiles_y - 1));
+        }
+        if max.x < 0.0
+            || max.y < 0.0
+            || min.x > viewport.width as f32
+            || min.y > viewport.height as f32
+        {
+            return None;
+        }
+        let tile = |value: f32, tiles: usize| {
+            ((value.max(0.0) / tile_size as f32) as usize).min(tiles - 1)
+        };
+        Some((
+            tile(min.x, tiles_x),
+            tile(max.x, tiles_x),
+            tile(min.y, tiles_y),
+            tile(max.y, tiles_y),
+        ))
+    }
+
+    // Fills the texture with the data, where the texture is only created if there is none or if it does not have the given size.
+    fn update_data_texture(
+        context: &Context,
+        texture: &mut Option<Texture2D>,
+        data: Vec<f32>,
+        width: usize,
+        height: usize,
+        format: Format,
+    ) -> Result<(), Error> {
+        if let Some(texture) = texture
+            .as_mut()
+            .filter(|t| t.width() == width && t.height() == height)
+        {
+            return texture.fill_with_f32(&data);
+        }
+        *texture = Some(Texture2D::new_with_f32(
+            context,
+            &CPUTexture {
+                data,
+                width,
+                height,
+                depth: 1,
+                format,
+                min_filter: Interpolation::Nearest,
+                mag_filter: Interpolation::Nearest,
+                mip_map_filter: None,
+                wrap_s: Wrapping::ClampToEdge,
+                wrap_t: Wrapping::ClampToEdge,
+                wrap_r: Wrapping::ClampToEdge,
+                flip_y: false,
+                premultiply_alpha: false,
+            },
+        )?);
+        Ok(())
+    }
+}
@@ -0,0 +1,277 @@
+//!
+//! Helpers for tests which render into a render target using a [HeadlessContext](crate::HeadlessContext),
+//! read the result back and compare it with a reference image (see [compare_images](crate::compare_images)).
+//! The tests fail if a headless context cannot be created, so OSMesa must be installed when testing with the `headless` feature.
+//! The stored reference images are in the `test_data/reference_images` folder and are written again by the tests
+//! when the `THREE_D_UPDATE_REFERENCE_IMAGES` environment variable is set.
+//!
+
+use crate::*;
+use std::sync::{Mutex, MutexGuard};
+
+// The programs of the objects are stored in statics which are shared by all contexts, so only one test can render at a time.
+static RENDER_LOCK: Mutex<()> = Mutex::new(());
+
+///
+/// A headless context which is the only context used for rendering until it is dropped.
+///
+pub(crate) struct TestContext {
+    headless: HeadlessContext,
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl TestContext {
+    ///
+    /// Returns a headless context.
+    /// Panics if it is not possible to create one on this machine, for example because OSMesa is not installed,
+    /// since the `headless` feature is explicitly enabled when running these tests.
+    ///
+    pub fn new() -> Self {
+        let lock = RENDER_LOCK
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        match HeadlessContext::new() {
+            Ok(headless) => Self {
+                headless,
+                _lock: lock,
+            },
+            Err(e) => panic!(
+                "A headless context is required for testing with the headless feature: {:?}",
+                e
+            ),
+        }
+    }
+
+    pub fn gl(&self) -> Context {
+        self.headless.gl()
+    }
+}
+
+///
+/// Renders whatever rendered in the **render** closure into a color texture of the given size with a depth texture,
+/// which are first cleared based on the given clear state, and returns the content of the color texture.
+///
+pub(crate) fn render_to_cpu<F: FnOnce() -> Result<(), Error>>(
+    context: &Context,
+    width: usize,
+    height: usize,
+    clear_state: &ClearState,
+    render: F,
+) -> Result<CPUTexture<u8>, Error> {
+    let color_texture = ColorTargetTexture2D::new(
+        context,
+        width,
+        height,
+        Interpolation::Nearest,
+        Interpolation::Nearest,
+        None,
+        Wrapping::ClampToEdge,
+        Wrapping::ClampToEdge,
+        Format::RGBA8,
+    )?;
+    let depth_texture = DepthTargetTexture2D::new(
+        context,
+        width,
+        height,
+        Wrapping::ClampToEdge,
+        Wrapping::ClampToEdge,
+        DepthFormat::Depth32F,
+    )?;
+    let render_target = RenderTarget::new(context, &color_texture, &depth_texture)?;
+    render_target.write(clear_state, render)?;
+    render_target.copy_color_to_cpu(Viewport::new_at_origo(width, height))
+}
+
+///
+/// Returns the part of the RGBA8 image inside the given viewport.
+///
+pub(crate) fn sub_image(image: &CPUTexture<u8>, viewport: Viewport) -> CPUTexture<u8> {
+    let mut data = Vec::with_capacity(viewport.width * viewport.height * 4);
+    for row in viewport.y as usize..viewport.y as usize + viewport.height {
+        let start = (row * image.width + viewport.x as usize) * 4;
+        data.extend_from_slice(&image.data[start..start + viewport.width * 4]);
+    }
+    CPUTexture {
+        data,
+        width: viewport.width,
+        height: viewport.height,
+        format: image.format,
+        ..Default::default()
+    }
+}
+
+///
+/// Returns an RGBA8 image of the given size where all pixels have the given color.
+///
+pub(crate) fn uniform_image(width: usize, height: usize, color: [u8; 4]) -> CPUTexture<u8> {
+    CPUTexture {
+        data: color.repeat(width * height),
+        width,
+        height,
+        format: Format::RGBA8,
+        ..Default::default()
+    }
+}
+
+///
+/// Asserts that at most the given fraction of the pixels differ by more than the tolerance between the two images.
+///
+pub(crate) fn assert_images_match(
+    image: &CPUTexture<u8>,
+    reference: &CPUTexture<u8>,
+    tolerance: u8,
+    max_difference: f32,
+) {
+    let difference = compare_images(image, reference, tolerance);
+    assert!(
+        difference <= max_difference,
+        "{}% of the pixels differ from the reference image, but at most {}% may differ",
+        difference * 100.0,
+        max_difference * 100.0
+    );
+}
+
+///
+/// Asserts that at most the given fraction of the pixels differ by more than the tolerance between the image
+/// and the stored reference image with the given name in the `test_data/reference_images` folder.
+/// If the `THREE_D_UPDATE_REFERENCE_IMAGES` environment variable is set, or if the reference image does not exist,
+/// the image is stored as the reference image instead. In the latter case the test fails, so that the new reference image is reviewed.
+///
+#[cfg(feature = "image-io")]
+pub(crate) fn assert_matches_reference_image(
+    image: &CPUTexture<u8>,
+    name: &str,
+    tolerance: u8,
+    max_difference: f32,
+) {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("test_data")
+        .join("reference_images")
+        .join(format!("{}.png", name));
+    let update = std::env::var_os("THREE_D_UPDATE_REFERENCE_IMAGES").is_some();
+    if update || !path.exists() {
+        assert_eq!(image.format, Format::RGBA8);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        image::save_buffer(
+            &path,
+            &flip_rows(&image.data, image.height, 1),
+            image.width as u32,
+            image.height as u32,
+            image::ColorType::Rgba8,
+        )
+        .unwrap();
+        assert!(
+            update,
+            "The reference image {:?} did not exist and has been written, check it and run the test again",
+            path
+        );
+        return;
+    }
+    let stored = image::open(&path)
+        .unwrap_or_else(|e| panic!("Could not load the reference image {:?}: {}", path, e))
+        .to_rgba8();
+    let (width, height) = (stored.width() as usize, stored.height() as usize);
+    let reference = CPUTexture {
+        data: flip_rows(&stored.into_raw(), height, 1),
+        width,
+        height,
+        format: Format::RGBA8,
+        ..Default::default()
+    };
+    assert_images_match(image, &reference, tolerance, max_difference);
+}
+
+///
+/// Calls **render** once with the left half and once with the right half of a render target as the viewport,
+/// where **render** must render into a render target of the given width and height cleared with the given clear state
+/// (see [render_to_cpu]) and return the result.
+/// Asserts that all pixels inside the viewport are changed and that the other half keeps the clear color.
+///
+pub(crate) fn assert_renders_inside_viewport<
+    F: FnMut(usize, usize, &ClearState, Viewport) -> Result<CPUTexture<u8>, Error>,
+>(
+    mut render: F,
+) {
+    let (width, height) = (16, 8);
+    let left = Viewport::new_at_origo(width / 2, height);
+    let right = Viewport {
+        x: (width / 2) as i32,
+        ..left
+    };
+    let clear_state = ClearState::color_and_depth(1.0, 0.0, 1.0, 1.0, 1.0);
+    let clear_color = uniform_image(width / 2, height, [255, 0, 255, 255]);
+    for (inside, outside) in [(left, right), (right, left)].iter() {
+        let image = render(width, height, &clear_state, *inside).unwrap();
+        assert_eq!(
+            compare_images(&sub_image(&image, *inside), &clear_color, 0),
+            1.0,
+            "Some pixels inside the viewport {:?} are not rendered",
+            inside
+        );
+        assert_eq!(
+            compare_images(&sub_image(&image, *outside), &clear_color, 0),
+            0.0,
+            "Some pixels outside the viewport {:?} are rendered",
+            inside
+        );
+    }
+}
+
+///
+/// Returns a mesh without indices, ie. each three consecutive positions form a triangle,
+/// which covers the rectangle from the left to the right x coordinate and from -1 to 1 in y at the given depth.
+///
+pub(crate) fn triangle_soup(left: f32, right: f32, z: f32) -> CPUMesh {
+    CPUMesh {
+        positions: vec![
+            left, -1.0, z, right, -1.0, z, right, 1.0, z, right, 1.0, z, left, 1.0, z, left, -1.0,
+            z,
+        ],
+        ..Default::default()
+    }
+}
+
+///
+/// Returns an orthographic camera at (0, 0, 1) looking down the negative z axis, which sees from -1 to 1 in x and y.
+///
+pub(crate) fn orthographic_camera(context: &Context) -> Camera {
+    Camera::new_orthographic(
+        context,
+        vec3(0.0, 0.0, 1.0),
+        vec3(0.0, 0.0, 0.0),
+        vec3(0.0, 1.0, 0.0),
+        2.0,
+        2.0,
+        10.0,
+    )
+    .unwrap()
+}
+
+#[test]
+fn render_clear_color_to_cpu() {
+    let test_context = TestContext::new();
+    let context = test_context.gl();
+    let image = render_to_cpu(
+        &context,
+        8,
+        4,
+        &ClearState::color(1.0, 0.0, 0.0, 1.0),
+        || Ok(()),
+    )
+    .unwrap();
+    assert_images_match(&image, &uniform_image(8, 4, [255, 0, 0, 255]), 0, 0.0);
+    assert_images_match(
+        &sub_image(
+            &image,
+            Viewport {
+                x: 4,
+                y: 0,
+                width: 4,
+                height: 4,
+            },
+        ),
+        &uniform_image(4, 4, [255, 0, 0, 255]),
+        0,
+        0.0,
+    );
+}
@@ -17,6 +17,13 @@ pub mod glutin_window;
 #[cfg(all(feature = "glutin-window", not(target_arch = "wasm32")))]
 pub use crate::glutin_window::*;
 
+#[doc(hidden)]
+#[cfg(all(feature = "headless", not(target_arch = "wasm32")))]
+pub mod headless;
+#[doc(inline)]
+#[cfg(all(feature = "headless", not(target_arch = "wasm32")))]
+pub use crate::headless::*;
+
 #[doc(hidden)]
 #[cfg(all(feature = "canvas", target_arch = "wasm32"))]
 pub mod canvas;
@@ -15,6 +15,19 @@ pub enum WindowError {
     EventListenerError { message: String },
 }
 
+impl std::fmt::Display for WindowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WindowError::WindowCreationError { message }
+            | WindowError::ContextError { message }
+            | WindowError::PerformanceError { message }
+            | WindowError::EventListenerError { message } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for WindowError {}
+
 struct FrameRenderer {
     pub render_loop_closure: Rc<RefCell<Option<Closure<dyn FnMut()>>>>,
     pub render_requested: bool,
@@ -47,6 +60,7 @@ pub struct Window {
 #[derive(Serialize)]
 struct ContextOptions {
     antialias: bool,
+    stencil: bool,
 }
 
 impl Window {
@@ -84,6 +98,7 @@ impl Window {
 
         let context_options = ContextOptions {
             antialias: settings.multisamples > 0,
+            stencil: true,
         };
         let context = canvas
             .get_context_with_context_options("webgl2", &JsValue::from_serde(&context_options).unwrap())
@@ -132,6 +147,8 @@ impl Window {
         let modifiers = Rc::new(RefCell::new(Modifiers::default()));
 
         self.add_resize_event_listener(self.frame_renderer.clone())?;
+        self.add_context_lost_event_listener(events.clone(), self.frame_renderer.clone())?;
+        self.add_context_restored_event_listener(events.clone(), self.frame_renderer.clone())?;
         self.add_mouseenter_event_listener(events.clone(), self.frame_renderer.clone())?;
         self.add_mouseleave_event_listener(events.clone(), self.frame_renderer.clone())?;
         self.add_mousedown_event_listener(
@@ -197,6 +214,8 @@ impl Window {
             self.set_canvas_size();
             let (width, height) = self.get_canvas_size();
             let device_pixel_ratio = self.pixels_per_point();
+            self.gl
+                .set_screen_size(device_pixel_ratio * width, device_pixel_ratio * height);
             let frame_input = crate::FrameInput {
                 events: (*events).borrow().clone(),
                 elapsed_time,
@@ -306,6 +325,53 @@ impl Window {
         Ok(())
     }
 
+    fn add_context_lost_event_listener(
+        &self,
+        events: Rc<RefCell<Vec<Event>>>,
+        frame_renderer: Rc<RefCell<FrameRenderer>>,
+    ) -> Result<(), WindowError> {
+        let closure = Closure::wrap(Box::new(move |event: web_sys::Event| {
+            // Preventing the default behaviour signals that the context should be restored
+            event.prevent_default();
+            events.borrow_mut().push(Event::ContextLost);
+            frame_renderer.borrow_mut().render_frame();
+        }) as Box<dyn FnMut(_)>);
+        self.canvas
+            .add_event_listener_with_callback("webglcontextlost", closure.as_ref().unchecked_ref())
+            .map_err(|e| WindowError::EventListenerError {
+                message: format!(
+                    "Unable to add context lost event listener. Error code: {:?}",
+                    e
+                ),
+            })?;
+        closure.forget();
+        Ok(())
+    }
+
+    fn add_context_restored_event_listener(
+        &self,
+        events: Rc<RefCell<Vec<Event>>>,
+        frame_renderer: Rc<RefCell<FrameRenderer>>,
+    ) -> Result<(), WindowError> {
+        let closure = Closure::wrap(Box::new(move |_: web_sys::Event| {
+            events.borrow_mut().push(Event::ContextRestored);
+            frame_renderer.borrow_mut().render_frame();
+        }) as Box<dyn FnMut(_)>);
+        self.canvas
+            .add_event_listener_with_callback(
+                "webglcontextrestored",
+                closure.as_ref().unchecked_ref(),
+            )
+            .map_err(|e| WindowError::EventListenerError {
+                message: format!(
+                    "Unable to add context restored event listener. Error code: {:?}",
+                    e
+                ),
+            })?;
+        closure.forget();
+        Ok(())
+    }
+
     fn add_mouseleave_event_listener(
         &self,
         events: Rc<RefCell<Vec<Event>>>,
@@ -14,10 +14,38 @@ use glutin::*;
 pub enum WindowError {
     WindowCreationError(glutin::CreationError),
     ContextError(glutin::ContextError),
+    /// Failed to create a [headless context](crate::HeadlessContext), for example because OSMesa is not installed on Linux.
+    HeadlessContextCreationError(glutin::CreationError),
     /// The number of samples must be a power of two.
     InvalidNumberOfSamples,
 }
 
+impl std::fmt::Display for WindowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WindowError::WindowCreationError(e) => write!(f, "Failed to create window: {}", e),
+            WindowError::ContextError(e) => write!(f, "Context error: {}", e),
+            WindowError::HeadlessContextCreationError(e) => {
+                write!(f, "Failed to create headless context: {}", e)
+            }
+            WindowError::InvalidNumberOfSamples => {
+                write!(f, "The number of samples must be a power of two")
+            }
+        }
+    }
+}
+
+impl std::error::Error for WindowError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            WindowError::WindowCreationError(e) => Some(e),
+            WindowError::ContextError(e) => Some(e),
+            WindowError::HeadlessContextCreationError(e) => Some(e),
+            WindowError::InvalidNumberOfSamples => None,
+        }
+    }
+}
+
 impl From<glutin::CreationError> for WindowError {
     fn from(other: glutin::CreationError) -> Self {
         WindowError::WindowCreationError(other)
@@ -98,6 +126,8 @@ impl Window {
         Ok(ContextBuilder::new()
             .with_multisampling(settings.multisamples as u16)
             .with_vsync(settings.vsync)
+            .with_srgb(settings.srgb)
+            .with_stencil_buffer(8)
             .build_windowed(window_builder, event_loop)?)
     }
 
@@ -140,6 +170,7 @@ impl Window {
                         .inner_size()
                         .to_logical::<f64>(device_pixel_ratio)
                         .into();
+                    context.set_screen_size(physical_width as usize, physical_height as usize);
                     let frame_input = FrameInput {
                         events: events.clone(),
                         elapsed_time,
@@ -175,6 +206,7 @@ impl Window {
                                 physical_width as usize,
                                 physical_height as usize,
                             ),
+                            false,
                         )
                         .unwrap();
                         crate::Saver::save_pixels(
@@ -0,0 +1,94 @@
+use crate::window::WindowError;
+use glutin::*;
+
+///
+/// A graphics context without a window, for example for rendering thumbnails on a server or image comparison tests in CI.
+/// Since there is no screen, render into a [RenderTarget](crate::RenderTarget) and read the result
+/// using for example [RenderTarget::copy_color_to_cpu](crate::RenderTarget::copy_color_to_cpu).
+///
+/// On Linux and BSD, the context is created using [OSMesa](https://docs.mesa3d.org/osmesa.html) which must be installed,
+/// but does not require a display. On Windows and macOS, a hidden context is created by the platform.
+///
+pub struct HeadlessContext {
+    _context: glutin::Context<PossiblyCurrent>,
+    #[cfg(not(any(
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    )))]
+    _event_loop: event_loop::EventLoop<()>,
+    gl: crate::Context,
+}
+
+impl HeadlessContext {
+    ///
+    /// Constructs a new headless graphics context.
+    ///
+    /// # Errors
+    /// Will return an error if the platform is unable to create an OpenGL 3.3 context without a window,
+    /// for example if OSMesa is not installed on Linux.
+    ///
+    pub fn new() -> Result<Self, WindowError> {
+        let builder = ContextBuilder::new()
+            .with_gl(GlRequest::Specific(Api::OpenGl, (3, 3)))
+            .with_gl_profile(GlProfile::Core);
+
+        #[cfg(any(
+            target_os = "linux",
+            target_os = "dragonfly",
+            target_os = "freebsd",
+            target_os = "netbsd",
+            target_os = "openbsd"
+        ))]
+        let context = {
+            use glutin::platform::unix::HeadlessContextExt;
+            builder
+                .build_osmesa(dpi::PhysicalSize::new(1, 1))
+                .map_err(WindowError::HeadlessContextCreationError)?
+        };
+        #[cfg(not(any(
+            target_os = "linux",
+            target_os = "dragonfly",
+            target_os = "freebsd",
+            target_os = "netbsd",
+            target_os = "openbsd"
+        )))]
+        let event_loop = event_loop::EventLoop::new();
+        #[cfg(not(any(
+            target_os = "linux",
+            target_os = "dragonfly",
+            target_os = "freebsd",
+            target_os = "netbsd",
+            target_os = "openbsd"
+        )))]
+        let context = builder
+            .build_headless(&event_loop, dpi::PhysicalSize::new(1, 1))
+            .map_err(WindowError::HeadlessContextCreationError)?;
+
+        let context = unsafe { context.make_current().map_err(|(_, e)| e)? };
+        let gl = crate::context::Glstruct::load_with(|s| {
+            context.get_proc_address(s) as *const std::os::raw::c_void
+        });
+        Ok(Self {
+            _context: context,
+            #[cfg(not(any(
+                target_os = "linux",
+                target_os = "dragonfly",
+                target_os = "freebsd",
+                target_os = "netbsd",
+                target_os = "openbsd"
+            )))]
+            _event_loop: event_loop,
+            gl,
+        })
+    }
+
+    ///
+    /// Returns the graphics context.
+    ///
+    pub fn gl(&self) -> crate::Context {
+        self.gl.clone()
+    }
+}
@@ -12,12 +12,18 @@ pub struct WindowSettings {
     /// On web, this can only be off (0) or on (>0).
     /// The actual number of samples depends on browser settings.
     pub multisamples: u8,
+    /// Whether to request a default framebuffer which supports conversion from linear to sRGB color space on write,
+    /// which is needed for [set_framebuffer_srgb](crate::context::Glstruct::set_framebuffer_srgb).
+    ///
+    /// On web this has no effect since the default framebuffer never supports sRGB conversion.
+    pub srgb: bool,
 }
 impl Default for WindowSettings {
     fn default() -> Self {
         Self {
             vsync: true,
             multisamples: 4,
+            srgb: true,
         }
     }
 }